@@ -4,7 +4,8 @@ use meshing::delaunay_refinement::delaunay_refinement;
 use meshing::marching_cubes::marching_cubes;
 use meshing::octree::octree_mesh;
 use meshing::voxel_mesh::voxel_mesh;
-use meshing::{bowyer_watson_3d, Face, Point3D};
+use meshing::{bowyer_watson, bowyer_watson_3d, Face, Point2D, Point3D};
+use rand::{Rng, SeedableRng};
 
 fn cube_points(n: usize) -> Vec<Point3D> {
     let mut points = Vec::new();
@@ -33,6 +34,43 @@ fn bench_bowyer_watson_3d(c: &mut Criterion) {
     });
 }
 
+fn random_points_2d(n: usize) -> Vec<Point2D> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    (0..n)
+        .map(|i| Point2D {
+            index: i as i64,
+            x: rng.gen(),
+            y: rng.gen(),
+        })
+        .collect()
+}
+
+fn random_points_3d(n: usize) -> Vec<Point3D> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    (0..n)
+        .map(|i| Point3D {
+            index: i as i64,
+            x: rng.gen(),
+            y: rng.gen(),
+            z: rng.gen(),
+        })
+        .collect()
+}
+
+fn bench_bowyer_watson(c: &mut Criterion) {
+    let points = random_points_2d(100_000);
+    c.bench_function("bowyer_watson (100k random pts)", |b| {
+        b.iter(|| bowyer_watson(black_box(points.clone())))
+    });
+}
+
+fn bench_bowyer_watson_3d_random(c: &mut Criterion) {
+    let points = random_points_3d(10_000);
+    c.bench_function("bowyer_watson_3d (10k random pts)", |b| {
+        b.iter(|| bowyer_watson_3d(black_box(points.clone())))
+    });
+}
+
 fn bench_advancing_front(c: &mut Criterion) {
     let p = [
         Point3D {
@@ -273,7 +311,9 @@ fn bench_delaunay_refinement(c: &mut Criterion) {
 
 criterion_group!(
     benches,
+    bench_bowyer_watson,
     bench_bowyer_watson_3d,
+    bench_bowyer_watson_3d_random,
     bench_advancing_front,
     bench_octree,
     bench_marching_cubes,
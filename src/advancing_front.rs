@@ -262,8 +262,6 @@ mod tests {
 
         let result = advancing_front(faces, points);
         assert!(!result.is_empty());
-        // Should produce at least 1 tetrahedron
-        assert!(result.len() >= 1);
     }
 
     #[test]
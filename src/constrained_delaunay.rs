@@ -0,0 +1,730 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::error::MeshingError;
+use crate::predicates::{incircle, orient2d};
+use crate::{bowyer_watson, Edge, Point2D, Triangle};
+
+/// Returns `true` if `s` and `t` are orientations of opposite, non-zero sign.
+fn opposite_signs(s: f64, t: f64) -> bool {
+    (s > 0.0 && t < 0.0) || (s < 0.0 && t > 0.0)
+}
+
+fn undirected(a: usize, b: usize) -> (usize, usize) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Index-based triangulation with edge flipping and constrained edges.
+///
+/// Triangles are stored counter-clockwise as positions into `points`, and
+/// `edges` maps every directed edge to the triangle that owns it, so the
+/// neighbour across `(u, v)` is the owner of `(v, u)`. `exterior` flags
+/// triangles outside the meshed domain; triangles created by splitting
+/// inherit the flag of the triangle they replace.
+pub(crate) struct Cdt {
+    pub(crate) points: Vec<Point2D>,
+    pub(crate) triangles: Vec<[usize; 3]>,
+    pub(crate) exterior: Vec<bool>,
+    edges: HashMap<(usize, usize), usize>,
+    constrained: HashSet<(usize, usize)>,
+    /// [`Point2D::index`] given to the next inserted vertex.
+    next_index: i64,
+    /// Triangles created or modified since the last [`Cdt::take_touched`].
+    touched: Vec<usize>,
+}
+
+/// Outcome of [`Cdt::walk`].
+pub(crate) enum Walk {
+    /// The target lies in the triangle, on the given edge if any.
+    Found(usize, Option<(usize, usize)>),
+    /// The path to the target crosses the given constrained or hull edge.
+    Blocked(usize, usize),
+}
+
+impl Cdt {
+    /// Builds the unconstrained Delaunay triangulation of `points` with
+    /// [`bowyer_watson`].
+    pub(crate) fn new(points: Vec<Point2D>) -> Result<Cdt, MeshingError> {
+        let triangles = bowyer_watson(points.clone())?;
+        let position: HashMap<i64, usize> = points
+            .iter()
+            .enumerate()
+            .map(|(pos, p)| (p.index, pos))
+            .collect();
+
+        let next_index = points.iter().map(|p| p.index).max().unwrap_or(-1) + 1;
+        let mut cdt = Cdt {
+            points,
+            triangles: Vec::with_capacity(triangles.len()),
+            exterior: vec![false; triangles.len()],
+            edges: HashMap::new(),
+            constrained: HashSet::new(),
+            next_index,
+            touched: Vec::new(),
+        };
+        for t in &triangles {
+            let (a, b, c) = (
+                position[&t.a.index],
+                position[&t.b.index],
+                position[&t.c.index],
+            );
+            let tri = if orient2d(&cdt.points[a], &cdt.points[b], &cdt.points[c]) > 0.0 {
+                [a, b, c]
+            } else {
+                [a, c, b]
+            };
+            let id = cdt.triangles.len();
+            cdt.triangles.push(tri);
+            cdt.register(id);
+        }
+        Ok(cdt)
+    }
+
+    fn register(&mut self, id: usize) {
+        let [a, b, c] = self.triangles[id];
+        self.edges.insert((a, b), id);
+        self.edges.insert((b, c), id);
+        self.edges.insert((c, a), id);
+    }
+
+    fn unregister(&mut self, id: usize) {
+        let [a, b, c] = self.triangles[id];
+        self.edges.remove(&(a, b));
+        self.edges.remove(&(b, c));
+        self.edges.remove(&(c, a));
+    }
+
+    /// Returns the vertex of the triangle owning directed edge `(u, v)` that
+    /// is opposite to that edge.
+    fn apex(&self, u: usize, v: usize) -> Option<usize> {
+        let id = *self.edges.get(&(u, v))?;
+        self.triangles[id]
+            .iter()
+            .copied()
+            .find(|&w| w != u && w != v)
+    }
+
+    pub(crate) fn has_edge(&self, u: usize, v: usize) -> bool {
+        self.edges.contains_key(&(u, v)) || self.edges.contains_key(&(v, u))
+    }
+
+    /// Returns the triangle owning directed edge `(u, v)`, i.e. the triangle
+    /// lying to the left of `u -> v`.
+    pub(crate) fn owner(&self, u: usize, v: usize) -> Option<usize> {
+        self.edges.get(&(u, v)).copied()
+    }
+
+    pub(crate) fn is_constrained(&self, u: usize, v: usize) -> bool {
+        self.constrained.contains(&undirected(u, v))
+    }
+
+    /// Returns every constrained edge as a pair of vertex positions.
+    pub(crate) fn constrained_edges(&self) -> Vec<(usize, usize)> {
+        self.constrained.iter().copied().collect()
+    }
+
+    /// Returns the vertex opposite to directed edge `(u, v)` in its owning
+    /// triangle.
+    pub(crate) fn opposite(&self, u: usize, v: usize) -> Option<usize> {
+        self.apex(u, v)
+    }
+
+    fn add_triangle(&mut self, tri: [usize; 3], exterior: bool) -> usize {
+        let id = self.triangles.len();
+        self.triangles.push(tri);
+        self.exterior.push(exterior);
+        self.register(id);
+        self.touched.push(id);
+        id
+    }
+
+    fn replace_triangle(&mut self, id: usize, tri: [usize; 3]) {
+        self.unregister(id);
+        self.triangles[id] = tri;
+        self.register(id);
+        self.touched.push(id);
+    }
+
+    fn push_point(&mut self, x: f64, y: f64) -> usize {
+        let index = self.next_index;
+        self.next_index += 1;
+        self.points.push(Point2D { index, x, y });
+        self.points.len() - 1
+    }
+
+    /// Returns the triangles created or modified since the previous call,
+    /// possibly with repetitions.
+    pub(crate) fn take_touched(&mut self) -> Vec<usize> {
+        std::mem::take(&mut self.touched)
+    }
+
+    /// Finds the triangle containing `(x, y)`.
+    ///
+    /// Returns the triangle and, if the point lies on one of its edges, that
+    /// edge as a directed pair owned by the triangle.
+    pub(crate) fn locate(&self, x: f64, y: f64) -> Option<(usize, Option<(usize, usize)>)> {
+        let q = Point2D {
+            index: i64::MAX,
+            x,
+            y,
+        };
+        for (t, &[a, b, c]) in self.triangles.iter().enumerate() {
+            let p = &self.points;
+            let o = [
+                orient2d(&p[a], &p[b], &q),
+                orient2d(&p[b], &p[c], &q),
+                orient2d(&p[c], &p[a], &q),
+            ];
+            if o.iter().any(|&v| v < 0.0) {
+                continue;
+            }
+            let edges = [(a, b), (b, c), (c, a)];
+            let on_edge = (0..3).find(|&i| o[i] == 0.0).map(|i| edges[i]);
+            return Some((t, on_edge));
+        }
+        None
+    }
+
+    /// Walks along the straight line from the centroid of triangle `start`
+    /// to `(x, y)` and returns the triangle containing it, or the first
+    /// constrained or hull edge the line crosses.
+    ///
+    /// Falls back to [`Cdt::locate`] if the walk degenerates, e.g. when the
+    /// line passes exactly through a vertex.
+    pub(crate) fn walk(&self, start: usize, x: f64, y: f64) -> Option<Walk> {
+        let p = &self.points;
+        let target = Point2D {
+            index: i64::MAX,
+            x,
+            y,
+        };
+        let [a, b, c] = self.triangles[start];
+        let origin = Point2D {
+            index: i64::MAX,
+            x: (p[a].x + p[b].x + p[c].x) / 3.0,
+            y: (p[a].y + p[b].y + p[c].y) / 3.0,
+        };
+        let mut t = start;
+        'walk: for _ in 0..self.triangles.len() {
+            let [a, b, c] = self.triangles[t];
+            let edges = [(a, b), (b, c), (c, a)];
+            let o = edges.map(|(u, v)| orient2d(&p[u], &p[v], &target));
+            if o.iter().all(|&v| v >= 0.0) {
+                let on_edge = (0..3).find(|&i| o[i] == 0.0).map(|i| edges[i]);
+                return Some(Walk::Found(t, on_edge));
+            }
+            // Leave through the edge whose endpoints straddle the line, with
+            // the target beyond it.
+            for (i, &(u, v)) in edges.iter().enumerate() {
+                if o[i] < 0.0
+                    && orient2d(&origin, &target, &p[u]) <= 0.0
+                    && orient2d(&origin, &target, &p[v]) > 0.0
+                {
+                    match self.owner(v, u) {
+                        Some(next) if !self.is_constrained(u, v) => {
+                            t = next;
+                            continue 'walk;
+                        }
+                        _ => return Some(Walk::Blocked(u, v)),
+                    }
+                }
+            }
+            break;
+        }
+        self.locate(x, y)
+            .map(|(t, on_edge)| Walk::Found(t, on_edge))
+    }
+
+    /// Inserts a new vertex at `(x, y)` inside triangle `t`, splitting it into
+    /// three, and restores the constrained Delaunay property around it.
+    pub(crate) fn insert_in_triangle(&mut self, t: usize, x: f64, y: f64) -> usize {
+        let q = self.push_point(x, y);
+        let [a, b, c] = self.triangles[t];
+        let exterior = self.exterior[t];
+        self.replace_triangle(t, [a, b, q]);
+        self.add_triangle([b, c, q], exterior);
+        self.add_triangle([c, a, q], exterior);
+        self.legalize(q, vec![(a, b), (b, c), (c, a)]);
+        q
+    }
+
+    /// Inserts a new vertex at `(x, y)` on edge `(u, v)`, splitting both
+    /// adjacent triangles. If the edge is constrained, the constraint is
+    /// replaced by its two halves.
+    pub(crate) fn split_edge(&mut self, u: usize, v: usize, x: f64, y: f64) -> usize {
+        let q = self.push_point(x, y);
+        let left = self.owner(u, v).zip(self.apex(u, v));
+        let right = self.owner(v, u).zip(self.apex(v, u));
+        let mut pending = Vec::new();
+        if let Some((t, w)) = left {
+            let exterior = self.exterior[t];
+            self.replace_triangle(t, [u, q, w]);
+            self.add_triangle([q, v, w], exterior);
+            pending.push((w, u));
+            pending.push((v, w));
+        }
+        if let Some((t, x)) = right {
+            let exterior = self.exterior[t];
+            self.replace_triangle(t, [v, q, x]);
+            self.add_triangle([q, u, x], exterior);
+            pending.push((x, v));
+            pending.push((u, x));
+        }
+        if self.constrained.remove(&undirected(u, v)) {
+            self.constrained.insert(undirected(u, q));
+            self.constrained.insert(undirected(q, v));
+        }
+        self.legalize(q, pending);
+        q
+    }
+
+    /// Lawson flips around a freshly inserted vertex `q`. Each pending edge
+    /// `(a, b)` belongs to a counter-clockwise triangle `(a, b, q)`.
+    fn legalize(&mut self, q: usize, mut pending: Vec<(usize, usize)>) {
+        while let Some((a, b)) = pending.pop() {
+            if self.is_constrained(a, b) || self.apex(a, b) != Some(q) {
+                continue;
+            }
+            if self.is_illegal(a, b) && self.is_flippable(a, b) {
+                if let Some((_, d)) = self.flip(a, b) {
+                    pending.push((a, d));
+                    pending.push((d, b));
+                }
+            }
+        }
+    }
+
+    /// Replaces diagonal `(u, v)` of the quad formed by its two adjacent
+    /// triangles with the opposite diagonal. Returns the new diagonal.
+    fn flip(&mut self, u: usize, v: usize) -> Option<(usize, usize)> {
+        let t1 = *self.edges.get(&(u, v))?;
+        let t2 = *self.edges.get(&(v, u))?;
+        let w = self.apex(u, v)?;
+        let x = self.apex(v, u)?;
+        self.unregister(t1);
+        self.unregister(t2);
+        self.triangles[t1] = [u, x, w];
+        self.triangles[t2] = [x, v, w];
+        self.register(t1);
+        self.register(t2);
+        self.touched.push(t1);
+        self.touched.push(t2);
+        Some((w, x))
+    }
+
+    /// Returns `true` if the quad around edge `(u, v)` is strictly convex, so
+    /// that flipping the edge produces two valid triangles.
+    fn is_flippable(&self, u: usize, v: usize) -> bool {
+        let (w, x) = match (self.apex(u, v), self.apex(v, u)) {
+            (Some(w), Some(x)) => (w, x),
+            _ => return false,
+        };
+        let p = &self.points;
+        opposite_signs(orient2d(&p[w], &p[x], &p[u]), orient2d(&p[w], &p[x], &p[v]))
+    }
+
+    /// Returns `true` if the edge `(u, v)` violates the Delaunay condition.
+    fn is_illegal(&self, u: usize, v: usize) -> bool {
+        let (w, x) = match (self.apex(u, v), self.apex(v, u)) {
+            (Some(w), Some(x)) => (w, x),
+            _ => return false,
+        };
+        let p = &self.points;
+        incircle(&p[u], &p[v], &p[w], &p[x]) > 0.0
+    }
+
+    fn crosses(&self, a: usize, b: usize, u: usize, v: usize) -> bool {
+        if u == a || u == b || v == a || v == b {
+            return false;
+        }
+        let p = &self.points;
+        opposite_signs(orient2d(&p[a], &p[b], &p[u]), orient2d(&p[a], &p[b], &p[v]))
+            && opposite_signs(orient2d(&p[u], &p[v], &p[a]), orient2d(&p[u], &p[v], &p[b]))
+    }
+
+    /// Returns a vertex lying strictly inside segment `(a, b)`, if any.
+    fn vertex_on_segment(&self, a: usize, b: usize) -> Option<usize> {
+        let p = &self.points;
+        let (pa, pb) = (&p[a], &p[b]);
+        let len_sq = pa.distance_squared(pb);
+        (0..p.len())
+            .filter(|&i| i != a && i != b && orient2d(pa, pb, &p[i]) == 0.0)
+            .filter(|&i| {
+                let t = (p[i].x - pa.x) * (pb.x - pa.x) + (p[i].y - pa.y) * (pb.y - pa.y);
+                t > 0.0 && t < len_sq
+            })
+            .min_by(|&i, &j| {
+                pa.distance_squared(&p[i])
+                    .partial_cmp(&pa.distance_squared(&p[j]))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+    }
+
+    /// Forces the segment between vertices `a` and `b` into the triangulation
+    /// and marks it as constrained.
+    ///
+    /// Segments passing through other input vertices are split at those
+    /// vertices. Edges crossing the segment are removed by flipping, and the
+    /// newly created edges are flipped back towards Delaunay afterwards.
+    pub(crate) fn insert_constraint(&mut self, a: usize, b: usize) -> Result<(), MeshingError> {
+        if a == b {
+            return Err(MeshingError::InvalidConstraint(
+                "constraint edge has identical endpoints".to_string(),
+            ));
+        }
+        if let Some(mid) = self.vertex_on_segment(a, b) {
+            self.insert_constraint(a, mid)?;
+            return self.insert_constraint(mid, b);
+        }
+        if self.has_edge(a, b) {
+            self.constrained.insert(undirected(a, b));
+            return Ok(());
+        }
+
+        let mut crossing: Vec<(usize, usize)> = Vec::new();
+        for &(u, v) in self.edges.keys() {
+            let unique = u < v || !self.edges.contains_key(&(v, u));
+            if unique && self.crosses(a, b, u, v) {
+                crossing.push((u, v));
+            }
+        }
+        if crossing.is_empty() {
+            return Err(MeshingError::InvalidConstraint(format!(
+                "segment {} -> {} leaves the triangulated region",
+                self.points[a].index, self.points[b].index
+            )));
+        }
+        if crossing
+            .iter()
+            .any(|&(u, v)| self.constrained.contains(&undirected(u, v)))
+        {
+            return Err(MeshingError::InvalidConstraint(format!(
+                "segment {} -> {} crosses another constraint",
+                self.points[a].index, self.points[b].index
+            )));
+        }
+
+        // Flip away every edge that crosses the segment. Edges whose quad is
+        // not convex yet are retried after their neighbours have been flipped.
+        let mut queue: std::collections::VecDeque<(usize, usize)> = crossing.into();
+        let mut created: Vec<(usize, usize)> = Vec::new();
+        let max_attempts = queue.len() * queue.len() + 100;
+        let mut attempts = 0;
+        while let Some((u, v)) = queue.pop_front() {
+            attempts += 1;
+            if attempts > max_attempts {
+                return Err(MeshingError::InvalidConstraint(format!(
+                    "failed to recover segment {} -> {}",
+                    self.points[a].index, self.points[b].index
+                )));
+            }
+            if !self.is_flippable(u, v) {
+                queue.push_back((u, v));
+                continue;
+            }
+            let (w, x) = self.flip(u, v).expect("flippable edge has two triangles");
+            if self.crosses(a, b, w, x) {
+                queue.push_back((w, x));
+            } else {
+                created.push((w, x));
+            }
+        }
+
+        self.constrained.insert(undirected(a, b));
+
+        // Restore the Delaunay property on the new edges, leaving the
+        // constrained segment in place.
+        let mut swapped = true;
+        while swapped {
+            swapped = false;
+            for edge in created.iter_mut() {
+                let (u, v) = *edge;
+                if self.constrained.contains(&undirected(u, v)) {
+                    continue;
+                }
+                if self.is_illegal(u, v) && self.is_flippable(u, v) {
+                    *edge = self.flip(u, v).expect("flippable edge has two triangles");
+                    swapped = true;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn triangle(&self, id: usize) -> Triangle {
+        let [a, b, c] = self.triangles[id];
+        Triangle {
+            a: self.points[a],
+            b: self.points[b],
+            c: self.points[c],
+        }
+    }
+}
+
+/// Computes a constrained Delaunay triangulation of a set of 2D points.
+///
+/// Starts from the [`bowyer_watson`] triangulation and recovers every edge in
+/// `constraints` by flipping the edges that cross it, so each constraint
+/// appears as an edge of the output. Triangles not adjacent to a constraint
+/// keep the Delaunay property. Constraint endpoints are matched to `points`
+/// by [`Point2D::index`], and a constraint that passes through another input
+/// point is split there.
+///
+/// # Errors
+///
+/// Returns the errors of [`bowyer_watson`], and
+/// [`MeshingError::InvalidConstraint`] if a constraint references an unknown
+/// point, is degenerate, or crosses another constraint.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::constrained_delaunay::constrained_delaunay;
+/// use meshing::{Edge, Point2D};
+///
+/// let points = vec![
+///     Point2D { index: 0, x: 0.0, y: 0.0 },
+///     Point2D { index: 1, x: 2.0, y: 0.0 },
+///     Point2D { index: 2, x: 2.0, y: 1.0 },
+///     Point2D { index: 3, x: 0.0, y: 1.0 },
+/// ];
+/// let constraint = Edge { start: points[0], end: points[2] };
+/// let triangles = constrained_delaunay(points, vec![constraint]).unwrap();
+/// assert!(triangles.iter().any(|t| t.contains_edge((constraint.start, constraint.end))));
+/// ```
+pub fn constrained_delaunay(
+    points: Vec<Point2D>,
+    constraints: Vec<Edge>,
+) -> Result<Vec<Triangle>, MeshingError> {
+    let mut cdt = Cdt::new(points)?;
+    let position: HashMap<i64, usize> = cdt
+        .points
+        .iter()
+        .enumerate()
+        .map(|(pos, p)| (p.index, pos))
+        .collect();
+
+    for edge in &constraints {
+        let lookup = |p: &Point2D| {
+            position.get(&p.index).copied().ok_or_else(|| {
+                MeshingError::InvalidConstraint(format!(
+                    "constraint endpoint {} is not an input point",
+                    p.index
+                ))
+            })
+        };
+        let a = lookup(&edge.start)?;
+        let b = lookup(&edge.end)?;
+        cdt.insert_constraint(a, b)?;
+    }
+
+    Ok((0..cdt.triangles.len()).map(|i| cdt.triangle(i)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(index: i64, x: f64, y: f64) -> Point2D {
+        Point2D { index, x, y }
+    }
+
+    fn has_edge(triangles: &[Triangle], a: Point2D, b: Point2D) -> bool {
+        triangles.iter().any(|t| t.contains_edge((a, b)))
+    }
+
+    #[test]
+    fn test_no_constraints_matches_bowyer_watson() {
+        let points = vec![
+            p(0, 0.0, 0.0),
+            p(1, 1.0, 0.0),
+            p(2, 0.0, 1.0),
+            p(3, 1.0, 1.0),
+        ];
+        let cdt = constrained_delaunay(points.clone(), vec![]).unwrap();
+        assert_eq!(cdt.len(), bowyer_watson(points).unwrap().len());
+    }
+
+    #[test]
+    fn test_recovers_missing_diagonal() {
+        // A wide rectangle: Delaunay picks one diagonal, force the other.
+        let points = vec![
+            p(0, 0.0, 0.0),
+            p(1, 4.0, 0.0),
+            p(2, 4.0, 1.0),
+            p(3, 0.0, 1.0),
+        ];
+        let unconstrained = bowyer_watson(points.clone()).unwrap();
+        let (a, b) = if has_edge(&unconstrained, points[0], points[2]) {
+            (points[1], points[3])
+        } else {
+            (points[0], points[2])
+        };
+        let result = constrained_delaunay(points, vec![Edge { start: a, end: b }]).unwrap();
+        assert_eq!(result.len(), 2);
+        assert!(has_edge(&result, a, b));
+    }
+
+    #[test]
+    fn test_constraint_across_many_triangles() {
+        // A row of points above and below a long horizontal segment.
+        let mut points = vec![p(0, 0.0, 0.0), p(1, 10.0, 0.0)];
+        for i in 1..10 {
+            points.push(p(2 * i as i64, i as f64, 0.3));
+            points.push(p(2 * i as i64 + 1, i as f64 + 0.5, -0.3));
+        }
+        let a = points[0];
+        let b = points[1];
+        let result = constrained_delaunay(points.clone(), vec![Edge { start: a, end: b }]).unwrap();
+        assert!(has_edge(&result, a, b));
+        // Flipping preserves the triangle count of the base triangulation.
+        assert_eq!(result.len(), bowyer_watson(points).unwrap().len());
+        for t in &result {
+            assert!(orient2d(&t.a, &t.b, &t.c).abs() > 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_constraint_through_collinear_vertex_is_split() {
+        let points = vec![
+            p(0, 0.0, 0.0),
+            p(1, 1.0, 0.0),
+            p(2, 2.0, 0.0),
+            p(3, 1.0, 1.0),
+            p(4, 1.0, -1.0),
+        ];
+        let result = constrained_delaunay(
+            points.clone(),
+            vec![Edge {
+                start: points[0],
+                end: points[2],
+            }],
+        )
+        .unwrap();
+        assert!(has_edge(&result, points[0], points[1]));
+        assert!(has_edge(&result, points[1], points[2]));
+    }
+
+    #[test]
+    fn test_unknown_constraint_point() {
+        let points = vec![p(0, 0.0, 0.0), p(1, 1.0, 0.0), p(2, 0.0, 1.0)];
+        let result = constrained_delaunay(
+            points.clone(),
+            vec![Edge {
+                start: points[0],
+                end: p(99, 5.0, 5.0),
+            }],
+        );
+        assert!(matches!(result, Err(MeshingError::InvalidConstraint(_))));
+    }
+
+    #[test]
+    fn test_crossing_constraints_rejected() {
+        let points = vec![
+            p(0, 0.0, 0.0),
+            p(1, 1.0, 0.0),
+            p(2, 1.0, 1.0),
+            p(3, 0.0, 1.0),
+        ];
+        let result = constrained_delaunay(
+            points.clone(),
+            vec![
+                Edge {
+                    start: points[0],
+                    end: points[2],
+                },
+                Edge {
+                    start: points[1],
+                    end: points[3],
+                },
+            ],
+        );
+        assert!(matches!(result, Err(MeshingError::InvalidConstraint(_))));
+    }
+
+    #[test]
+    fn test_random_points_with_long_constraints() {
+        let mut seed: u64 = 12345;
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let mut points = vec![
+            p(0, 0.0, 0.0),
+            p(1, 1.0, 0.0),
+            p(2, 1.0, 1.0),
+            p(3, 0.0, 1.0),
+        ];
+        for i in 4..200 {
+            points.push(p(i, next(), next()));
+        }
+        let diagonal = Edge {
+            start: points[0],
+            end: points[2],
+        };
+        let result = constrained_delaunay(points.clone(), vec![diagonal]).unwrap();
+        assert!(has_edge(&result, diagonal.start, diagonal.end));
+        assert_eq!(result.len(), bowyer_watson(points).unwrap().len());
+        for t in &result {
+            assert!(orient2d(&t.a, &t.b, &t.c).abs() > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_walk_stops_at_constraints() {
+        let mut seed: u64 = 777;
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let mut points = vec![
+            p(0, 0.0, 0.0),
+            p(1, 1.0, 0.0),
+            p(2, 1.0, 1.0),
+            p(3, 0.0, 1.0),
+        ];
+        for i in 4..100 {
+            points.push(p(i, next(), next()));
+        }
+        let mut cdt = Cdt::new(points).unwrap();
+        cdt.insert_constraint(0, 2).unwrap();
+        let side = |q: &Point2D| orient2d(&cdt.points[0], &cdt.points[2], q) > 0.0;
+
+        for _ in 0..20 {
+            let target = p(-1, next(), next());
+            for start in 0..cdt.triangles.len() {
+                let [a, b, c] = cdt.triangles[start];
+                let centroid = p(
+                    -1,
+                    (cdt.points[a].x + cdt.points[b].x + cdt.points[c].x) / 3.0,
+                    (cdt.points[a].y + cdt.points[b].y + cdt.points[c].y) / 3.0,
+                );
+                match cdt.walk(start, target.x, target.y) {
+                    Some(Walk::Found(t, _)) => {
+                        assert_eq!(side(&centroid), side(&target));
+                        assert_eq!(cdt.locate(target.x, target.y).unwrap().0, t);
+                    }
+                    Some(Walk::Blocked(u, v)) => {
+                        assert_ne!(side(&centroid), side(&target));
+                        assert_eq!(undirected(u, v), (0, 2));
+                    }
+                    None => panic!("target inside the hull was not found"),
+                }
+            }
+        }
+
+        // Leaving the hull from below the diagonal never crosses it.
+        let start = (0..cdt.triangles.len())
+            .find(|&t| cdt.triangles[t].contains(&1))
+            .unwrap();
+        let outside = cdt.walk(start, 2.0, 0.5);
+        assert!(matches!(outside, Some(Walk::Blocked(u, v)) if cdt.owner(v, u).is_none()));
+    }
+}
@@ -0,0 +1,485 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+
+use crate::constrained_delaunay::{Cdt, Walk};
+use crate::error::MeshingError;
+use crate::polygon::build_domain;
+use crate::predicates::incircle;
+use crate::{Point2D, Triangle};
+
+fn shortest_edge_length(triangle: &Triangle) -> f64 {
+    triangle
+        .edges()
+        .iter()
+        .map(|e| e.start.distance(&e.end))
+        .fold(f64::MAX, f64::min)
+}
+
+fn area(triangle: &Triangle) -> f64 {
+    let (a, b, c) = (triangle.a, triangle.b, triangle.c);
+    ((b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)).abs() / 2.0
+}
+
+/// Circumradius-to-shortest-edge ratio. A triangle with minimum angle
+/// `theta` has ratio `1 / (2 sin(theta))`.
+fn radius_edge_ratio(triangle: &Triangle) -> f64 {
+    triangle.generate_circumcircle().radius / shortest_edge_length(triangle)
+}
+
+/// Returns `true` if `p` lies strictly inside the diametral circle of the
+/// segment `(a, b)`.
+fn encroaches(a: &Point2D, b: &Point2D, p: &Point2D) -> bool {
+    (a.x - p.x) * (b.x - p.x) + (a.y - p.y) * (b.y - p.y) < 0.0
+}
+
+/// Returns `true` if segment `(u, v)` is encroached by the apex of one of
+/// its adjacent triangles.
+fn is_encroached(cdt: &Cdt, u: usize, v: usize) -> bool {
+    [cdt.opposite(u, v), cdt.opposite(v, u)]
+        .into_iter()
+        .flatten()
+        .any(|w| encroaches(&cdt.points[u], &cdt.points[v], &cdt.points[w]))
+}
+
+/// Returns the constrained edges bounding the cavity of `p`: the triangles
+/// reachable from `host` without crossing a segment whose circumcircle
+/// contains `p`. These are the segments `p` could encroach once inserted.
+fn cavity_segments(cdt: &Cdt, host: usize, p: &Point2D) -> Vec<(usize, usize)> {
+    let mut visited = HashSet::from([host]);
+    let mut stack = vec![host];
+    let mut segments = Vec::new();
+    while let Some(t) = stack.pop() {
+        let [a, b, c] = cdt.triangles[t];
+        for (u, v) in [(a, b), (b, c), (c, a)] {
+            if cdt.is_constrained(u, v) {
+                segments.push((u, v));
+                continue;
+            }
+            if let Some(n) = cdt.owner(v, u) {
+                let [x, y, z] = cdt.triangles[n];
+                let q = &cdt.points;
+                if !visited.contains(&n) && incircle(&q[x], &q[y], &q[z], p) > 0.0 {
+                    visited.insert(n);
+                    stack.push(n);
+                }
+            }
+        }
+    }
+    segments
+}
+
+/// A triangle violating the quality bounds, ordered by [`badness`]. The
+/// vertices detect entries made stale by later insertions.
+struct BadTriangle {
+    score: f64,
+    id: usize,
+    vertices: [usize; 3],
+}
+
+impl PartialEq for BadTriangle {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for BadTriangle {}
+
+impl PartialOrd for BadTriangle {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BadTriangle {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then(self.id.cmp(&other.id))
+    }
+}
+
+/// How much a triangle violates the bounds: infinite if it is too large,
+/// otherwise its radius-edge ratio, or `None` if it meets both bounds.
+fn badness(triangle: &Triangle, max_ratio: f64, max_area: Option<f64>) -> Option<f64> {
+    if max_area.is_some_and(|limit| area(triangle) > limit) {
+        return Some(f64::INFINITY);
+    }
+    let ratio = radius_edge_ratio(triangle);
+    (ratio > max_ratio).then_some(ratio)
+}
+
+fn is_bad(triangle: &Triangle, max_ratio: f64, max_area: Option<f64>) -> bool {
+    badness(triangle, max_ratio, max_area).is_some()
+}
+
+/// Queues the triangles and segments changed by the last insertion.
+fn enqueue_touched(
+    cdt: &mut Cdt,
+    bad: &mut BinaryHeap<BadTriangle>,
+    segments: &mut Vec<(usize, usize)>,
+    max_ratio: f64,
+    max_area: Option<f64>,
+) {
+    for id in cdt.take_touched() {
+        let [a, b, c] = cdt.triangles[id];
+        for (u, v) in [(a, b), (b, c), (c, a)] {
+            if cdt.is_constrained(u, v) {
+                segments.push((u, v));
+            }
+        }
+        if cdt.exterior[id] {
+            continue;
+        }
+        if let Some(score) = badness(&cdt.triangle(id), max_ratio, max_area) {
+            bad.push(BadTriangle {
+                score,
+                id,
+                vertices: [a, b, c],
+            });
+        }
+    }
+}
+
+fn split_segment(cdt: &mut Cdt, u: usize, v: usize) {
+    let (a, b) = (cdt.points[u], cdt.points[v]);
+    cdt.split_edge(u, v, (a.x + b.x) / 2.0, (a.y + b.y) / 2.0);
+}
+
+/// Refines a constrained triangulation of a polygonal domain until every
+/// triangle meets a minimum angle and, optionally, a maximum area
+/// (Ruppert's algorithm).
+///
+/// The domain is built as in [`triangulate_polygon`](crate::polygon::triangulate_polygon).
+/// Boundary segments encroached by a vertex are split at their midpoint;
+/// otherwise the worst triangle (highest circumradius-to-shortest-edge ratio,
+/// or too large) is split by inserting its [`Triangle::circumcenter`], unless
+/// that circumcenter would encroach a segment or lies behind one, in which
+/// case the segment is split instead. Bad triangles are kept in a priority
+/// queue and the circumcenter is located by walking from its triangle, so
+/// each insertion only costs time proportional to the triangles it changes.
+///
+/// # Arguments
+///
+/// * `outer` - Closed outer boundary of the domain.
+/// * `holes` - Closed hole boundaries.
+/// * `interior_points` - Extra vertices that must appear in the mesh.
+/// * `min_angle_degrees` - Minimum angle target. Values up to about 20.7° are
+///   guaranteed to terminate for inputs without small angles.
+/// * `max_area` - Optional upper bound on triangle area.
+///
+/// Refinement stops after `100` vertex insertions per input vertex, plus
+/// enough for the area bound. An angle between two input segments that is
+/// smaller than `min_angle_degrees` cannot be fixed by inserting vertices:
+/// splitting the segments only produces smaller skinny triangles in the
+/// corner. Such inputs exhaust the budget and return
+/// [`MeshingError::RefinementIncomplete`] instead of a mesh that misses the
+/// bound; lower `min_angle_degrees` below the smallest input angle to mesh
+/// them.
+///
+/// # Errors
+///
+/// Returns the errors of [`triangulate_polygon`](crate::polygon::triangulate_polygon),
+/// [`MeshingError::InvalidParameter`] if `min_angle_degrees` is not in
+/// `[0, 60)` or `max_area` is not positive, and
+/// [`MeshingError::RefinementIncomplete`] if triangles still violate the
+/// bounds when the insertion budget runs out.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::delaunay_refinement_2d::delaunay_refinement_2d;
+/// use meshing::Point2D;
+///
+/// let p = |index, x, y| Point2D { index, x, y };
+/// let outer = vec![p(0, 0.0, 0.0), p(1, 4.0, 0.0), p(2, 4.0, 0.2), p(3, 0.0, 0.2)];
+/// let triangles = delaunay_refinement_2d(outer, vec![], vec![], 20.7, None).unwrap();
+/// assert!(triangles.len() > 2);
+/// ```
+pub fn delaunay_refinement_2d(
+    outer: Vec<Point2D>,
+    holes: Vec<Vec<Point2D>>,
+    interior_points: Vec<Point2D>,
+    min_angle_degrees: f64,
+    max_area: Option<f64>,
+) -> Result<Vec<Triangle>, MeshingError> {
+    if !(0.0..60.0).contains(&min_angle_degrees) {
+        return Err(MeshingError::InvalidParameter(format!(
+            "minimum angle must be in [0, 60) degrees, got {}",
+            min_angle_degrees
+        )));
+    }
+    if let Some(limit) = max_area {
+        if limit.is_nan() || limit <= 0.0 {
+            return Err(MeshingError::InvalidParameter(format!(
+                "maximum area must be positive, got {}",
+                limit
+            )));
+        }
+    }
+
+    let mut cdt = build_domain(&outer, &holes, &interior_points)?;
+    let max_ratio = if min_angle_degrees > 0.0 {
+        1.0 / (2.0 * min_angle_degrees.to_radians().sin())
+    } else {
+        f64::INFINITY
+    };
+
+    let domain_area: f64 = (0..cdt.triangles.len())
+        .filter(|&t| !cdt.exterior[t])
+        .map(|t| area(&cdt.triangle(t)))
+        .sum();
+    let area_budget = match max_area {
+        Some(limit) => (4.0 * domain_area / limit).ceil() as usize,
+        None => 0,
+    };
+    let max_iterations = 100 * cdt.points.len() + area_budget;
+
+    let mut bad = BinaryHeap::new();
+    let mut segments = cdt.constrained_edges();
+    cdt.take_touched();
+    for id in 0..cdt.triangles.len() {
+        if cdt.exterior[id] {
+            continue;
+        }
+        if let Some(score) = badness(&cdt.triangle(id), max_ratio, max_area) {
+            bad.push(BadTriangle {
+                score,
+                id,
+                vertices: cdt.triangles[id],
+            });
+        }
+    }
+
+    let mut insertions = 0;
+    while insertions < max_iterations {
+        if let Some((u, v)) = segments.pop() {
+            if cdt.is_constrained(u, v) && is_encroached(&cdt, u, v) {
+                split_segment(&mut cdt, u, v);
+                insertions += 1;
+                enqueue_touched(&mut cdt, &mut bad, &mut segments, max_ratio, max_area);
+            }
+            continue;
+        }
+
+        let worst = match bad.pop() {
+            Some(worst) => worst,
+            None => break,
+        };
+        let t = worst.id;
+        if cdt.triangles[t] != worst.vertices || cdt.exterior[t] {
+            continue;
+        }
+
+        let center = cdt.triangle(t).circumcenter();
+        match cdt.walk(t, center.x, center.y) {
+            Some(Walk::Found(host, on_edge)) if !cdt.exterior[host] => {
+                let encroached: Vec<(usize, usize)> = cavity_segments(&cdt, host, &center)
+                    .into_iter()
+                    .filter(|&(u, v)| encroaches(&cdt.points[u], &cdt.points[v], &center))
+                    .collect();
+                if encroached.is_empty() {
+                    match on_edge {
+                        None => cdt.insert_in_triangle(host, center.x, center.y),
+                        Some((u, v)) => cdt.split_edge(u, v, center.x, center.y),
+                    };
+                    insertions += 1;
+                } else {
+                    for (u, v) in encroached {
+                        if cdt.is_constrained(u, v) {
+                            split_segment(&mut cdt, u, v);
+                            insertions += 1;
+                        }
+                    }
+                }
+            }
+            // A segment shields the circumcenter from the triangle.
+            Some(Walk::Blocked(u, v)) if cdt.is_constrained(u, v) => {
+                split_segment(&mut cdt, u, v);
+                insertions += 1;
+            }
+            // The circumcenter is outside the domain without a segment in the
+            // way; fall back to splitting the longest edge of the triangle.
+            _ => {
+                let [a, b, c] = cdt.triangles[t];
+                let (u, v) = [(a, b), (b, c), (c, a)]
+                    .into_iter()
+                    .max_by(|&(a1, b1), &(a2, b2)| {
+                        let d1 = cdt.points[a1].distance_squared(&cdt.points[b1]);
+                        let d2 = cdt.points[a2].distance_squared(&cdt.points[b2]);
+                        d1.partial_cmp(&d2).unwrap_or(Ordering::Equal)
+                    })
+                    .expect("triangle has three edges");
+                split_segment(&mut cdt, u, v);
+                insertions += 1;
+            }
+        }
+        // The triangle survives when only segments were split; the entry is
+        // discarded as stale otherwise.
+        bad.push(worst);
+        enqueue_touched(&mut cdt, &mut bad, &mut segments, max_ratio, max_area);
+    }
+
+    let remaining = (0..cdt.triangles.len())
+        .filter(|&t| !cdt.exterior[t] && is_bad(&cdt.triangle(t), max_ratio, max_area))
+        .count();
+    if remaining > 0 {
+        return Err(MeshingError::RefinementIncomplete(format!(
+            "{} triangles still violate the minimum angle of {}° or the area bound after {} \
+             insertions; the input probably has a smaller angle",
+            remaining, min_angle_degrees, max_iterations
+        )));
+    }
+
+    Ok((0..cdt.triangles.len())
+        .filter(|&t| !cdt.exterior[t])
+        .map(|t| cdt.triangle(t))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(index: i64, x: f64, y: f64) -> Point2D {
+        Point2D { index, x, y }
+    }
+
+    fn min_angle_degrees(t: &Triangle) -> f64 {
+        let v = t.vertices();
+        (0..3)
+            .map(|i| {
+                let a = v[i];
+                let b = v[(i + 1) % 3];
+                let c = v[(i + 2) % 3];
+                let (ux, uy) = (b.x - a.x, b.y - a.y);
+                let (wx, wy) = (c.x - a.x, c.y - a.y);
+                let cos =
+                    (ux * wx + uy * wy) / ((ux * ux + uy * uy).sqrt() * (wx * wx + wy * wy).sqrt());
+                cos.clamp(-1.0, 1.0).acos().to_degrees()
+            })
+            .fold(f64::MAX, f64::min)
+    }
+
+    fn total_area(triangles: &[Triangle]) -> f64 {
+        triangles.iter().map(area).sum()
+    }
+
+    #[test]
+    fn test_thin_rectangle_meets_min_angle() {
+        let outer = vec![
+            p(0, 0.0, 0.0),
+            p(1, 4.0, 0.0),
+            p(2, 4.0, 0.2),
+            p(3, 0.0, 0.2),
+        ];
+        let result = delaunay_refinement_2d(outer, vec![], vec![], 20.7, None).unwrap();
+        assert!((total_area(&result) - 0.8).abs() < 1e-9);
+        for t in &result {
+            assert!(
+                min_angle_degrees(t) >= 20.7 - 1e-6,
+                "angle {}",
+                min_angle_degrees(t)
+            );
+        }
+    }
+
+    #[test]
+    fn test_max_area_is_respected() {
+        let outer = vec![
+            p(0, 0.0, 0.0),
+            p(1, 1.0, 0.0),
+            p(2, 1.0, 1.0),
+            p(3, 0.0, 1.0),
+        ];
+        let result = delaunay_refinement_2d(outer, vec![], vec![], 20.0, Some(0.01)).unwrap();
+        assert!((total_area(&result) - 1.0).abs() < 1e-9);
+        assert!(result.len() >= 100);
+        for t in &result {
+            assert!(area(t) <= 0.01 + 1e-12);
+            assert!(min_angle_degrees(t) >= 20.0 - 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_fine_mesh_meets_bounds() {
+        let outer = vec![
+            p(0, 0.0, 0.0),
+            p(1, 1.0, 0.0),
+            p(2, 1.0, 1.0),
+            p(3, 0.0, 1.0),
+        ];
+        let result = delaunay_refinement_2d(outer, vec![], vec![], 30.0, Some(2.5e-4)).unwrap();
+        assert!((total_area(&result) - 1.0).abs() < 1e-9);
+        assert!(result.len() >= 4000);
+        for t in &result {
+            assert!(area(t) <= 2.5e-4 + 1e-12);
+            assert!(min_angle_degrees(t) >= 30.0 - 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_domain_with_hole() {
+        let outer = vec![
+            p(0, 0.0, 0.0),
+            p(1, 3.0, 0.0),
+            p(2, 3.0, 3.0),
+            p(3, 0.0, 3.0),
+        ];
+        let hole = vec![
+            p(4, 1.0, 1.0),
+            p(5, 2.0, 1.0),
+            p(6, 2.0, 2.0),
+            p(7, 1.0, 2.0),
+        ];
+        let result = delaunay_refinement_2d(outer, vec![hole], vec![], 25.0, Some(0.2)).unwrap();
+        assert!((total_area(&result) - 8.0).abs() < 1e-9);
+        for t in &result {
+            assert!(min_angle_degrees(t) >= 25.0 - 1e-6);
+            let cx = (t.a.x + t.b.x + t.c.x) / 3.0;
+            let cy = (t.a.y + t.b.y + t.c.y) / 3.0;
+            assert!(!(cx > 1.0 && cx < 2.0 && cy > 1.0 && cy < 2.0));
+        }
+    }
+
+    #[test]
+    fn test_zero_angle_without_area_keeps_input() {
+        let outer = vec![
+            p(0, 0.0, 0.0),
+            p(1, 1.0, 0.0),
+            p(2, 1.0, 1.0),
+            p(3, 0.0, 1.0),
+        ];
+        let result = delaunay_refinement_2d(outer, vec![], vec![], 0.0, None).unwrap();
+        assert_eq!(result.len(), 2);
+    }
+
+    #[test]
+    fn test_small_input_angle_reports_unmet_bound() {
+        // A 3° wedge cannot be meshed with 30° angles.
+        let angle = 3.0f64.to_radians();
+        let outer = vec![
+            p(0, 0.0, 0.0),
+            p(1, 1.0, 0.0),
+            p(2, angle.cos(), angle.sin()),
+        ];
+        assert!(matches!(
+            delaunay_refinement_2d(outer.clone(), vec![], vec![], 30.0, None),
+            Err(MeshingError::RefinementIncomplete(_))
+        ));
+        let result = delaunay_refinement_2d(outer, vec![], vec![], 2.5, None).unwrap();
+        assert!(result.iter().all(|t| min_angle_degrees(t) >= 2.5 - 1e-6));
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let outer = vec![p(0, 0.0, 0.0), p(1, 1.0, 0.0), p(2, 0.0, 1.0)];
+        assert!(matches!(
+            delaunay_refinement_2d(outer.clone(), vec![], vec![], 61.0, None),
+            Err(MeshingError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            delaunay_refinement_2d(outer, vec![], vec![], 20.0, Some(0.0)),
+            Err(MeshingError::InvalidParameter(_))
+        ));
+    }
+}
@@ -6,4 +6,16 @@ pub enum MeshingError {
     EmptyInput,
     #[error("insufficient points for triangulation: need at least 3, got {0}")]
     InsufficientPoints(usize),
+    #[error("invalid constraint: {0}")]
+    InvalidConstraint(String),
+    #[error("invalid polygon: {0}")]
+    InvalidPolygon(String),
+    #[error("invalid parameter: {0}")]
+    InvalidParameter(String),
+    #[error("refinement incomplete: {0}")]
+    RefinementIncomplete(String),
+    #[error("malformed file: {0}")]
+    MalformedFile(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
 }
@@ -1,7 +1,11 @@
-use crate::export::stl::extract_surface_faces;
-use crate::{Face, Point3D, Tetrahedron};
+use std::io::{self, Write};
 
-fn base64_encode(data: &[u8]) -> String {
+use super::gltf_meshopt::{encode_array, optimize_vertex_cache, MeshoptView};
+use super::gltf_quantized::{quantized_normals, quantized_positions};
+use super::{invalid, to_bytes, to_string};
+use crate::{Face, SurfaceMesh, TetMesh, Tetrahedron};
+
+pub(super) fn base64_encode(data: &[u8]) -> String {
     const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
     let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
     for chunk in data.chunks(3) {
@@ -25,128 +29,1050 @@ fn base64_encode(data: &[u8]) -> String {
     result
 }
 
-struct MeshData {
-    positions: Vec<f32>,
-    indices: Vec<u32>,
-    min: [f32; 3],
-    max: [f32; 3],
+/// Number of bytes [`Base64Writer`] collects before encoding them.
+const BASE64_CHUNK: usize = 3 * 16 * 1024;
+
+/// Base64-encodes the bytes written to it into `inner`.
+///
+/// Bytes are encoded in chunks of whole 3-byte groups, so only the last
+/// group is padded, by [`Base64Writer::finish`].
+pub(super) struct Base64Writer<'a, W: Write> {
+    inner: &'a mut W,
+    pending: Vec<u8>,
+}
+
+impl<'a, W: Write> Base64Writer<'a, W> {
+    pub(super) fn new(inner: &'a mut W) -> Self {
+        Base64Writer {
+            inner,
+            pending: Vec::with_capacity(BASE64_CHUNK),
+        }
+    }
+
+    /// Encodes the remaining bytes.
+    pub(super) fn finish(self) -> io::Result<()> {
+        self.inner
+            .write_all(base64_encode(&self.pending).as_bytes())
+    }
+}
+
+impl<W: Write> Write for Base64Writer<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        if self.pending.len() >= BASE64_CHUNK {
+            let whole = self.pending.len() / 3 * 3;
+            self.inner
+                .write_all(base64_encode(&self.pending[..whole]).as_bytes())?;
+            self.pending.drain(..whole);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writes a GLB container: the 12-byte header, the JSON chunk padded with
+/// spaces and, unless `bin_length` is 0, a BIN chunk of `bin_length` bytes
+/// from `write_bin` padded with zeros.
+///
+/// Returns [`io::ErrorKind::InvalidInput`] if the file would exceed the
+/// 4 GiB limit of the format.
+pub(super) fn write_glb<W: Write>(
+    writer: &mut W,
+    json: &str,
+    bin_length: usize,
+    write_bin: impl FnOnce(&mut W) -> io::Result<()>,
+) -> io::Result<()> {
+    let json_padded_len = (json.len() + 3) & !3;
+    let bin_padded_len = (bin_length + 3) & !3;
+    let total_length = 12
+        + 8
+        + json_padded_len
+        + if bin_length == 0 {
+            0
+        } else {
+            8 + bin_padded_len
+        };
+    let total_length = u32::try_from(total_length)
+        .map_err(|_| invalid("GLB files are limited to 4 GiB".to_string()))?;
+
+    // GLB Header
+    writer.write_all(b"glTF")?; // magic
+    writer.write_all(&2u32.to_le_bytes())?; // version
+    writer.write_all(&total_length.to_le_bytes())?; // total length
+
+    // JSON chunk
+    writer.write_all(&(json_padded_len as u32).to_le_bytes())?; // chunk length
+    writer.write_all(&0x4E4F534Au32.to_le_bytes())?; // chunk type "JSON"
+    writer.write_all(json.as_bytes())?;
+    writer.write_all(&b"   "[..json_padded_len - json.len()])?;
+
+    // Binary chunk (only if there's data)
+    if bin_length > 0 {
+        writer.write_all(&(bin_padded_len as u32).to_le_bytes())?; // chunk length
+        writer.write_all(&0x004E4942u32.to_le_bytes())?; // chunk type "BIN\0"
+        write_bin(writer)?;
+        writer.write_all(&[0; 3][..bin_padded_len - bin_length])?;
+    }
+    Ok(())
+}
+/// glTF container used by [`write_gltf`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GltfFormat {
+    /// `.gltf` JSON with the buffer embedded as a base64 data URI.
+    #[default]
+    Json,
+    /// `.glb` binary container.
+    Glb,
+}
+
+/// Vertex normals written as the `NORMAL` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GltfNormals {
+    /// No normals; viewers shade each triangle flat.
+    #[default]
+    None,
+    /// Area-weighted averages of the normals of the faces around each
+    /// vertex, for smooth shading of curved surfaces.
+    Smooth,
+    /// One normal per face. Vertices are duplicated so that every face has
+    /// its own three.
+    Flat,
+}
+
+/// Colour scale mapping values in `[0, 1]` to RGB.
+///
+/// The stops are sRGB values, as published with matplotlib, and are
+/// interpolated in sRGB so that the scales look as they do there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Colormap {
+    /// Perceptually uniform dark blue, green and yellow scale.
+    #[default]
+    Viridis,
+    /// Black to white.
+    Grayscale,
+    /// Diverging blue, white and red scale.
+    CoolWarm,
+}
+
+/// Converts an sRGB channel value to linear RGB, as `COLOR_0` requires.
+fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl Colormap {
+    /// Returns the linear RGB colour of `t`, clamped to `[0, 1]`.
+    pub fn color(self, t: f64) -> [f64; 3] {
+        const VIRIDIS: [[f64; 3]; 9] = [
+            [0.267, 0.005, 0.329],
+            [0.278, 0.176, 0.482],
+            [0.231, 0.322, 0.545],
+            [0.173, 0.447, 0.557],
+            [0.128, 0.567, 0.551],
+            [0.153, 0.682, 0.502],
+            [0.369, 0.789, 0.383],
+            [0.678, 0.863, 0.190],
+            [0.993, 0.906, 0.144],
+        ];
+        const COOL_WARM: [[f64; 3]; 3] = [
+            [0.230, 0.299, 0.754],
+            [0.865, 0.865, 0.865],
+            [0.706, 0.016, 0.150],
+        ];
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let stops: &[[f64; 3]] = match self {
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Grayscale => &[[0.0; 3], [1.0; 3]],
+            Colormap::CoolWarm => &COOL_WARM,
+        };
+        let position = t * (stops.len() - 1) as f64;
+        let i = (position as usize).min(stops.len() - 2);
+        let f = position - i as f64;
+        [0, 1, 2].map(|c| srgb_to_linear(stops[i][c] + (stops[i + 1][c] - stops[i][c]) * f))
+    }
+}
+
+/// Scalar values written as `COLOR_0` through a [`Colormap`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GltfColors {
+    /// One value per vertex in [`GltfOptions::colors`], or one per
+    /// tetrahedron in [`GltfTetOptions::cell_colors`](super::GltfTetOptions).
+    pub values: Vec<f64>,
+    pub colormap: Colormap,
+    /// Values mapped to the ends of the colormap; the range of `values`
+    /// if `None`.
+    pub range: Option<[f64; 2]>,
+}
+
+impl GltfColors {
+    /// Samples a scalar field at the vertices of `mesh`.
+    ///
+    /// For the `faces_*` exporters, pass `SurfaceMesh::from_faces(faces)`.
+    pub fn from_field(
+        mesh: &SurfaceMesh,
+        colormap: Colormap,
+        field: impl Fn(f64, f64, f64) -> f64,
+    ) -> GltfColors {
+        GltfColors {
+            values: mesh.vertices.iter().map(|v| field(v.x, v.y, v.z)).collect(),
+            colormap,
+            range: None,
+        }
+    }
+
+    /// Colours the tetrahedra of `mesh` by their
+    /// [`radius_ratio`](Tetrahedron::radius_ratio), over the full `[0, 1]`
+    /// range.
+    pub fn from_quality(mesh: &TetMesh, colormap: Colormap) -> GltfColors {
+        GltfColors {
+            values: (0..mesh.num_tetrahedra())
+                .map(|i| mesh.tetrahedron(i).radius_ratio())
+                .collect(),
+            colormap,
+            range: Some([0.0, 1.0]),
+        }
+    }
+
+    /// Colours cells by region labels, e.g. the tetrahedron references of a
+    /// [`MeditMesh`](super::MeditMesh).
+    pub fn from_regions(regions: &[i32], colormap: Colormap) -> GltfColors {
+        GltfColors {
+            values: regions.iter().map(|&r| r as f64).collect(),
+            colormap,
+            range: None,
+        }
+    }
+
+    fn rgb(&self) -> Vec<[f64; 3]> {
+        let [low, high] = self.range.unwrap_or_else(|| {
+            let finite = self.values.iter().copied().filter(|v| v.is_finite());
+            finite.fold([f64::MAX, f64::MIN], |[low, high], v| {
+                [low.min(v), high.max(v)]
+            })
+        });
+        let span = if high > low { high - low } else { 1.0 };
+        self.values
+            .iter()
+            .map(|v| self.colormap.color((v - low) / span))
+            .collect()
+    }
+}
+
+/// A `pbrMetallicRoughness` material for the mesh.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GltfMaterial {
+    /// Linear RGBA base colour, multiplied with `COLOR_0` when present.
+    pub base_color: [f64; 4],
+    pub metallic: f64,
+    pub roughness: f64,
+    /// Render back faces too, e.g. for surfaces with inconsistent winding.
+    pub double_sided: bool,
+}
+
+impl Default for GltfMaterial {
+    /// A white, non-metallic, single-sided material with roughness 0.5.
+    fn default() -> Self {
+        GltfMaterial {
+            base_color: [1.0; 4],
+            metallic: 0.0,
+            roughness: 0.5,
+            double_sided: false,
+        }
+    }
+}
+
+/// Optional vertex attributes and material for [`write_gltf`].
+///
+/// The default writes only positions and indices, as the plain exporters
+/// do.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GltfOptions {
+    pub normals: GltfNormals,
+    pub colors: Option<GltfColors>,
+    /// Per-vertex scalar attributes written as `FLOAT` accessors. Names
+    /// must start with an underscore, e.g. `_TEMPERATURE`.
+    pub custom_attributes: Vec<(String, Vec<f64>)>,
+    pub material: Option<GltfMaterial>,
+    /// Store positions as `i16` and normals as `i8` with the
+    /// `KHR_mesh_quantization` extension, and colours as `u8`.
+    pub quantized: bool,
+    /// Reorder faces for the post-transform vertex cache of GPUs (Tipsify)
+    /// and vertices by first use, which also makes them compress better.
+    pub optimize_vertex_cache: bool,
+    /// Write indices as `u16` when fewer than 65535 vertices are written.
+    pub compact_indices: bool,
+    /// Compress the buffer views with the `EXT_meshopt_compression`
+    /// extension.
+    pub meshopt_compression: bool,
+}
+
+/// Maps the vertices written to the vertices of the mesh: the same ones
+/// for shared vertices, or three per face for flat shading.
+#[derive(Clone, Copy)]
+pub(super) struct Layout<'a> {
+    pub(super) mesh: &'a SurfaceMesh,
+    pub(super) flat: bool,
+}
+
+impl<'a> Layout<'a> {
+    pub(super) fn num_vertices(&self) -> usize {
+        if self.flat {
+            self.mesh.faces.len() * 3
+        } else {
+            self.mesh.vertices.len()
+        }
+    }
+
+    /// The mesh vertex of each vertex written.
+    pub(super) fn sources(self) -> Box<dyn Iterator<Item = usize> + 'a> {
+        if self.flat {
+            Box::new(self.mesh.faces.iter().flatten().copied())
+        } else {
+            Box::new(0..self.mesh.vertices.len())
+        }
+    }
+}
+
+/// Writes the elements of an [`Array`] to the buffer.
+pub(super) type WriteElements<'a> = Box<dyn Fn(&mut dyn Write) -> io::Result<()> + 'a>;
+
+/// An accessor of the binary buffer, stored in its own buffer view.
+pub(super) struct Array<'a> {
+    /// Attribute name in the primitive, or `None` for the indices.
+    pub(super) attribute: Option<String>,
+    pub(super) component_type: u32,
+    pub(super) normalized: bool,
+    pub(super) kind: &'static str,
+    pub(super) count: usize,
+    /// Bytes written per element, including padding to a 4-byte boundary.
+    pub(super) element_size: usize,
+    /// Whether the elements are padded, so that the view needs a
+    /// `byteStride`.
+    pub(super) padded: bool,
+    /// `"min"` and `"max"` members of the accessor, if any.
+    pub(super) bounds: Option<String>,
+    pub(super) write: WriteElements<'a>,
+}
+
+impl<'a> Array<'a> {
+    /// A `FLOAT` attribute of `N` components per vertex.
+    fn floats<const N: usize>(
+        attribute: &str,
+        layout: Layout<'a>,
+        values: impl Fn(usize, usize) -> [f64; N] + 'a,
+    ) -> Array<'a> {
+        Array {
+            attribute: Some(attribute.to_string()),
+            component_type: 5126,
+            normalized: false,
+            kind: ["SCALAR", "VEC2", "VEC3", "VEC4"][N - 1],
+            count: layout.num_vertices(),
+            element_size: 4 * N,
+            padded: false,
+            bounds: None,
+            write: Box::new(move |w| {
+                for (i, source) in layout.sources().enumerate() {
+                    for value in values(i, source) {
+                        w.write_all(&(value as f32).to_le_bytes())?;
+                    }
+                }
+                Ok(())
+            }),
+        }
+    }
+
+    pub(super) fn byte_length(&self) -> usize {
+        self.count * self.element_size
+    }
+}
+
+fn json_numbers(values: impl IntoIterator<Item = impl std::fmt::Display>) -> String {
+    let values: Vec<String> = values.into_iter().map(|v| v.to_string()).collect();
+    format!("[{}]", values.join(","))
 }
 
-fn collect_mesh_data(faces: &[Face]) -> MeshData {
-    let mut vertices: Vec<(i64, Point3D)> = Vec::new();
-    for face in faces {
-        for v in face.vertices() {
-            if !vertices.iter().any(|(idx, _)| *idx == v.index) {
-                vertices.push((v.index, v));
+/// Quotes and escapes a JSON string.
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Unit normal of a triangle, or +z if it is degenerate.
+fn unit(n: [f64; 3]) -> [f64; 3] {
+    let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    if len < 1e-300 {
+        [0.0, 0.0, 1.0]
+    } else {
+        n.map(|c| c / len)
+    }
+}
+
+/// Area-weighted normal (twice the area) of face `f`.
+fn face_normal(mesh: &SurfaceMesh, f: usize) -> [f64; 3] {
+    let [a, b, c] = mesh.faces[f].map(|i| mesh.vertices[i]);
+    let u = [b.x - a.x, b.y - a.y, b.z - a.z];
+    let v = [c.x - a.x, c.y - a.y, c.z - a.z];
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+/// Unit normals of the vertices written for `layout`.
+pub(super) fn vertex_normals(layout: Layout) -> Vec<[f64; 3]> {
+    let mesh = layout.mesh;
+    if layout.flat {
+        return (0..mesh.faces.len())
+            .flat_map(|f| [unit(face_normal(mesh, f)); 3])
+            .collect();
+    }
+    let mut normals = vec![[0.0; 3]; mesh.vertices.len()];
+    for f in 0..mesh.faces.len() {
+        let n = face_normal(mesh, f);
+        for v in mesh.faces[f] {
+            for c in 0..3 {
+                normals[v][c] += n[c];
             }
         }
     }
-    vertices.sort_by_key(|(idx, _)| *idx);
+    normals.into_iter().map(unit).collect()
+}
 
-    let mut min = [f32::MAX; 3];
-    let mut max = [f32::MIN; 3];
-    let mut positions = Vec::with_capacity(vertices.len() * 3);
-    for (_, v) in &vertices {
-        let coords = [v.x as f32, v.y as f32, v.z as f32];
-        for i in 0..3 {
-            if coords[i] < min[i] {
-                min[i] = coords[i];
+/// Placement of a node in a glTF scene: scale, then rotation, then
+/// translation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GltfTransform {
+    pub translation: [f64; 3],
+    /// Unit quaternion `[x, y, z, w]`.
+    pub rotation: [f64; 4],
+    pub scale: [f64; 3],
+}
+
+impl Default for GltfTransform {
+    /// The identity transform.
+    fn default() -> Self {
+        GltfTransform {
+            translation: [0.0; 3],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            scale: [1.0; 3],
+        }
+    }
+}
+
+impl GltfTransform {
+    /// The `translation`, `rotation` and `scale` members of a node that
+    /// differ from the identity, each followed by a comma.
+    fn json_members(&self) -> String {
+        let identity = GltfTransform::default();
+        let mut json = String::new();
+        if self.translation != identity.translation {
+            json.push_str(&format!(
+                "\"translation\":{},",
+                json_numbers(self.translation)
+            ));
+        }
+        if self.rotation != identity.rotation {
+            let length = self.rotation.iter().map(|c| c * c).sum::<f64>().sqrt();
+            let rotation = self.rotation.map(|c| c / length);
+            json.push_str(&format!("\"rotation\":{},", json_numbers(rotation)));
+        }
+        if self.scale != identity.scale {
+            json.push_str(&format!("\"scale\":{},", json_numbers(self.scale)));
+        }
+        json
+    }
+}
+
+/// One mesh of a [`Document`] and the node placing it.
+pub(super) struct DocumentMesh<'a> {
+    /// Name of the node and mesh, if any.
+    pub(super) name: Option<String>,
+    pub(super) transform: GltfTransform,
+    pub(super) arrays: Vec<Array<'a>>,
+    /// Node matrix decoding quantized positions.
+    pub(super) matrix: Option<[f32; 16]>,
+    /// Primitive mode, e.g. 1 for `LINES`; triangles if `None`.
+    pub(super) mode: Option<u32>,
+    pub(super) material: Option<GltfMaterial>,
+    /// Whether the arrays are compressed with `EXT_meshopt_compression`.
+    pub(super) meshopt: bool,
+}
+
+impl<'a> DocumentMesh<'a> {
+    /// An unnamed mesh at the origin.
+    pub(super) fn new(arrays: Vec<Array<'a>>, matrix: Option<[f32; 16]>) -> DocumentMesh<'a> {
+        DocumentMesh {
+            name: None,
+            transform: GltfTransform::default(),
+            arrays,
+            matrix,
+            mode: None,
+            material: None,
+            meshopt: false,
+        }
+    }
+}
+
+/// A glTF document with one scene of meshes sharing a single buffer.
+pub(super) struct Document<'a> {
+    pub(super) meshes: Vec<DocumentMesh<'a>>,
+}
+
+impl Document<'_> {
+    fn arrays(&self) -> impl Iterator<Item = &Array<'_>> {
+        self.meshes.iter().flat_map(|mesh| &mesh.arrays)
+    }
+
+    /// Compresses the arrays of the meshes using `EXT_meshopt_compression`,
+    /// in order, with `None` for the arrays streamed as they are.
+    fn encode(&self) -> io::Result<Vec<Option<MeshoptView>>> {
+        let mut encoded = Vec::new();
+        for mesh in &self.meshes {
+            for array in &mesh.arrays {
+                encoded.push(if mesh.meshopt {
+                    Some(encode_array(array)?)
+                } else {
+                    None
+                });
+            }
+        }
+        Ok(encoded)
+    }
+
+    /// Byte offset of each array in the buffer, each aligned to 4 bytes,
+    /// and the total buffer length.
+    fn offsets(&self, encoded: &[Option<MeshoptView>]) -> (Vec<usize>, usize) {
+        let mut offsets = Vec::new();
+        let mut end = 0;
+        for (array, encoded) in self.arrays().zip(encoded) {
+            let offset = (end + 3) & !3;
+            offsets.push(offset);
+            end = offset
+                + match encoded {
+                    Some(view) => view.data.len(),
+                    None => array.byte_length(),
+                };
+        }
+        (offsets, end)
+    }
+
+    fn write_buffer(
+        &self,
+        writer: &mut dyn Write,
+        encoded: &[Option<MeshoptView>],
+    ) -> io::Result<()> {
+        let mut end = 0;
+        for (array, encoded) in self.arrays().zip(encoded) {
+            let offset = (end + 3) & !3;
+            writer.write_all(&[0; 3][..offset - end])?;
+            end = offset
+                + match encoded {
+                    Some(view) => {
+                        writer.write_all(&view.data)?;
+                        view.data.len()
+                    }
+                    None => {
+                        (array.write)(writer)?;
+                        array.byte_length()
+                    }
+                };
+        }
+        Ok(())
+    }
+
+    /// The `nodes` list: one node per mesh, in order, followed by child
+    /// nodes holding the quantization matrix of transformed meshes.
+    fn json_nodes(&self) -> String {
+        let mut nodes = Vec::new();
+        let mut children = Vec::new();
+        for (i, mesh) in self.meshes.iter().enumerate() {
+            let mut node = String::from("{");
+            if let Some(name) = &mesh.name {
+                node.push_str(&format!("\"name\":{},", json_string(name)));
             }
-            if coords[i] > max[i] {
-                max[i] = coords[i];
+            // A node cannot have both a matrix and a transform.
+            let transform = mesh.transform.json_members();
+            node.push_str(&transform);
+            let mut holder = format!("\"mesh\":{}", i);
+            if let Some(matrix) = mesh.matrix {
+                holder.push_str(&format!(",\"matrix\":{}", json_numbers(matrix)));
             }
+            if mesh.matrix.is_some() && !transform.is_empty() {
+                node.push_str(&format!(
+                    "\"children\":[{}]",
+                    self.meshes.len() + children.len()
+                ));
+                children.push(format!("{{{}}}", holder));
+            } else {
+                node.push_str(&holder);
+            }
+            node.push('}');
+            nodes.push(node);
         }
-        positions.extend_from_slice(&coords);
+        nodes.extend(children);
+        format!("[{}]", nodes.join(","))
     }
 
-    let mut indices = Vec::with_capacity(faces.len() * 3);
-    for face in faces {
-        let a = vertices
-            .iter()
-            .position(|(idx, _)| *idx == face.a.index)
-            .unwrap() as u32;
-        let b = vertices
-            .iter()
-            .position(|(idx, _)| *idx == face.b.index)
-            .unwrap() as u32;
-        let c = vertices
+    /// Builds the glTF JSON up to the byte length of the buffer holding the
+    /// data, leaving the buffer object open so that a `uri` can be
+    /// appended, and the rest of the document closing it.
+    ///
+    /// Compressed arrays are decoded into a second, fallback buffer without
+    /// data.
+    fn json(&self, encoded: &[Option<MeshoptView>]) -> (String, String) {
+        let mut json = String::from("{\"asset\":{\"version\":\"2.0\",\"generator\":\"meshing\"},");
+        let mut extensions = Vec::new();
+        if self.meshes.iter().any(|mesh| mesh.matrix.is_some()) {
+            extensions.push("\"KHR_mesh_quantization\"");
+        }
+        if self.meshes.iter().any(|mesh| mesh.meshopt) {
+            extensions.push("\"EXT_meshopt_compression\"");
+        }
+        if !extensions.is_empty() {
+            let extensions = extensions.join(",");
+            json.push_str(&format!(
+                "\"extensionsUsed\":[{}],\"extensionsRequired\":[{}],",
+                extensions, extensions
+            ));
+        }
+        json.push_str(&format!(
+            "\"scene\":0,\"scenes\":[{{\"nodes\":{}}}],\"nodes\":{},",
+            json_numbers(0..self.meshes.len()),
+            self.json_nodes()
+        ));
+
+        let mut meshes = Vec::new();
+        let mut materials = Vec::new();
+        let mut first = 0;
+        for mesh in &self.meshes {
+            let mut attributes = Vec::new();
+            let mut indices = None;
+            for (i, array) in mesh.arrays.iter().enumerate() {
+                match &array.attribute {
+                    Some(name) => attributes.push(format!("\"{}\":{}", name, first + i)),
+                    None => indices = Some(first + i),
+                }
+            }
+            first += mesh.arrays.len();
+            let mut primitive = format!("{{\"attributes\":{{{}}}", attributes.join(","));
+            if let Some(i) = indices {
+                primitive.push_str(&format!(",\"indices\":{}", i));
+            }
+            if let Some(mode) = mesh.mode {
+                primitive.push_str(&format!(",\"mode\":{}", mode));
+            }
+            if let Some(material) = &mesh.material {
+                primitive.push_str(&format!(",\"material\":{}", materials.len()));
+                materials.push(format!(
+                    concat!(
+                        "{{\"pbrMetallicRoughness\":{{\"baseColorFactor\":{},",
+                        "\"metallicFactor\":{},\"roughnessFactor\":{}}},\"doubleSided\":{}}}"
+                    ),
+                    json_numbers(material.base_color),
+                    material.metallic,
+                    material.roughness,
+                    material.double_sided
+                ));
+            }
+            primitive.push('}');
+            let name = match &mesh.name {
+                Some(name) => format!("\"name\":{},", json_string(name)),
+                None => String::new(),
+            };
+            meshes.push(format!("{{{}\"primitives\":[{}]}}", name, primitive));
+        }
+        json.push_str(&format!("\"meshes\":[{}],", meshes.join(",")));
+        if !materials.is_empty() {
+            json.push_str(&format!("\"materials\":[{}],", materials.join(",")));
+        }
+
+        let accessors: Vec<String> = self
+            .arrays()
+            .enumerate()
+            .map(|(i, array)| {
+                let mut accessor = format!(
+                    "{{\"bufferView\":{},\"componentType\":{},",
+                    i, array.component_type
+                );
+                if array.normalized {
+                    accessor.push_str("\"normalized\":true,");
+                }
+                accessor.push_str(&format!(
+                    "\"count\":{},\"type\":\"{}\"",
+                    array.count, array.kind
+                ));
+                if let Some(bounds) = &array.bounds {
+                    accessor.push(',');
+                    accessor.push_str(bounds);
+                }
+                accessor.push('}');
+                accessor
+            })
+            .collect();
+        let (offsets, buffer_length) = self.offsets(encoded);
+        let mut fallback_length = 0;
+        let views: Vec<String> = self
+            .arrays()
+            .zip(&offsets)
+            .zip(encoded)
+            .map(|((array, offset), encoded)| {
+                let target = if array.attribute.is_some() {
+                    34962
+                } else {
+                    34963
+                };
+                let Some(view) = encoded else {
+                    let stride = if array.padded {
+                        format!(",\"byteStride\":{}", array.element_size)
+                    } else {
+                        String::new()
+                    };
+                    return format!(
+                        "{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{}{},\"target\":{}}}",
+                        offset,
+                        array.byte_length(),
+                        stride,
+                        target
+                    );
+                };
+                let fallback_offset = (fallback_length + 3) & !3;
+                let length = array.count * view.stride;
+                fallback_length = fallback_offset + length;
+                let stride = if array.attribute.is_some()
+                    && (array.padded || view.stride != array.element_size)
+                {
+                    format!(",\"byteStride\":{}", view.stride)
+                } else {
+                    String::new()
+                };
+                format!(
+                    concat!(
+                        "{{\"buffer\":1,\"byteOffset\":{},\"byteLength\":{}{},\"target\":{},",
+                        "\"extensions\":{{\"EXT_meshopt_compression\":{{\"buffer\":0,",
+                        "\"byteOffset\":{},\"byteLength\":{},\"byteStride\":{},",
+                        "\"count\":{},\"mode\":\"{}\"}}}}}}"
+                    ),
+                    fallback_offset,
+                    length,
+                    stride,
+                    target,
+                    offset,
+                    view.data.len(),
+                    view.stride,
+                    array.count,
+                    view.mode
+                )
+            })
+            .collect();
+        json.push_str(&format!(
+            "\"accessors\":[{}],\"bufferViews\":[{}],\"buffers\":[{{\"byteLength\":{}",
+            accessors.join(","),
+            views.join(","),
+            buffer_length
+        ));
+        let tail = if encoded.iter().any(Option::is_some) {
+            format!(
+                concat!(
+                    "}},{{\"byteLength\":{},",
+                    "\"extensions\":{{\"EXT_meshopt_compression\":{{\"fallback\":true}}}}}}]}}"
+                ),
+                fallback_length
+            )
+        } else {
+            "}]}".to_string()
+        };
+        (json, tail)
+    }
+}
+
+pub(super) fn validate(mesh: &SurfaceMesh, options: &GltfOptions) -> io::Result<()> {
+    let written = if options.normals == GltfNormals::Flat {
+        mesh.faces.len() * 3
+    } else {
+        mesh.vertices.len()
+    };
+    if written > u32::MAX as usize {
+        return Err(invalid(
+            "glTF indices are limited to u32::MAX vertices".to_string(),
+        ));
+    }
+    let n = mesh.vertices.len();
+    if let Some(colors) = &options.colors {
+        if colors.values.len() != n {
+            return Err(invalid(format!(
+                "{} colour values given for {} vertices",
+                colors.values.len(),
+                n
+            )));
+        }
+    }
+    for (i, (name, values)) in options.custom_attributes.iter().enumerate() {
+        if !name.starts_with('_') || name.contains(['"', '\\']) {
+            return Err(invalid(format!(
+                "custom attribute '{}' must start with '_' and contain no quotes",
+                name
+            )));
+        }
+        if options.custom_attributes[..i]
             .iter()
-            .position(|(idx, _)| *idx == face.c.index)
-            .unwrap() as u32;
-        indices.push(a);
-        indices.push(b);
-        indices.push(c);
+            .any(|(other, _)| other == name)
+        {
+            return Err(invalid(format!("duplicate custom attribute '{}'", name)));
+        }
+        if values.len() != n {
+            return Err(invalid(format!(
+                "{} values of '{}' given for {} vertices",
+                values.len(),
+                name,
+                n
+            )));
+        }
     }
+    Ok(())
+}
 
-    if vertices.is_empty() {
+/// The `POSITION` array of the vertices written for `layout`, and the node
+/// matrix decoding quantized positions.
+pub(super) fn positions(layout: Layout, quantized: bool) -> (Array, Option<[f32; 16]>) {
+    if quantized {
+        let (positions, matrix) = quantized_positions(layout);
+        return (positions, Some(matrix));
+    }
+    let mesh = layout.mesh;
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+    for v in &mesh.vertices {
+        let coords = [v.x as f32, v.y as f32, v.z as f32];
+        for i in 0..3 {
+            min[i] = min[i].min(coords[i]);
+            max[i] = max[i].max(coords[i]);
+        }
+    }
+    if mesh.vertices.is_empty() {
         min = [0.0; 3];
         max = [0.0; 3];
     }
+    let mut positions = Array::floats("POSITION", layout, |_, v| {
+        let p = mesh.vertices[v];
+        [p.x, p.y, p.z]
+    });
+    positions.bounds = Some(format!(
+        "\"min\":{},\"max\":{}",
+        json_numbers(min),
+        json_numbers(max)
+    ));
+    (positions, None)
+}
 
-    MeshData {
-        positions,
-        indices,
-        min,
-        max,
+pub(super) fn build_mesh<'a>(mesh: &'a SurfaceMesh, options: &'a GltfOptions) -> DocumentMesh<'a> {
+    let layout = Layout {
+        mesh,
+        flat: options.normals == GltfNormals::Flat,
+    };
+    let (positions, matrix) = positions(layout, options.quantized);
+    let mut arrays = vec![positions];
+
+    // 65535 is the primitive restart value, which indices must not use.
+    let short = options.compact_indices && layout.num_vertices() <= u16::MAX as usize;
+    arrays.push(Array {
+        attribute: None,
+        component_type: if short { 5123 } else { 5125 },
+        normalized: false,
+        kind: "SCALAR",
+        count: mesh.faces.len() * 3,
+        element_size: if short { 2 } else { 4 },
+        padded: false,
+        bounds: None,
+        write: Box::new(move |w| {
+            for (i, &index) in mesh.faces.iter().flatten().enumerate() {
+                let index = if layout.flat { i } else { index };
+                if short {
+                    w.write_all(&(index as u16).to_le_bytes())?;
+                } else {
+                    w.write_all(&(index as u32).to_le_bytes())?;
+                }
+            }
+            Ok(())
+        }),
+    });
+
+    if options.normals != GltfNormals::None {
+        let normals = vertex_normals(layout);
+        arrays.push(if options.quantized {
+            quantized_normals(normals)
+        } else {
+            Array::floats("NORMAL", layout, move |i, _| normals[i])
+        });
     }
-}
 
-fn build_binary_buffer(data: &MeshData) -> Vec<u8> {
-    let pos_bytes = data.positions.len() * 4;
-    let idx_bytes = data.indices.len() * 4;
-    let mut buffer = Vec::with_capacity(pos_bytes + idx_bytes);
+    if let Some(colors) = &options.colors {
+        let rgb = colors.rgb();
+        arrays.push(if options.quantized {
+            Array {
+                attribute: Some("COLOR_0".to_string()),
+                component_type: 5121,
+                normalized: true,
+                kind: "VEC4",
+                count: layout.num_vertices(),
+                element_size: 4,
+                padded: false,
+                bounds: None,
+                write: Box::new(move |w| {
+                    for v in layout.sources() {
+                        let [r, g, b] = rgb[v].map(|c| (c * 255.0).round() as u8);
+                        w.write_all(&[r, g, b, 255])?;
+                    }
+                    Ok(())
+                }),
+            }
+        } else {
+            Array::floats("COLOR_0", layout, move |_, v| rgb[v])
+        });
+    }
 
-    for &val in &data.positions {
-        buffer.extend_from_slice(&val.to_le_bytes());
+    for (name, values) in &options.custom_attributes {
+        arrays.push(Array::floats(name, layout, move |_, v| [values[v]]));
     }
-    for &val in &data.indices {
-        buffer.extend_from_slice(&val.to_le_bytes());
+
+    DocumentMesh {
+        material: options.material,
+        meshopt: options.meshopt_compression,
+        ..DocumentMesh::new(arrays, matrix)
     }
+}
 
-    buffer
+/// Writes an indexed surface mesh as glTF 2.0 with the attributes and
+/// material chosen in `options`.
+///
+/// Vertex data is generated as it is written, so memory use stays close
+/// to that of the mesh. Wrap files in a [`std::io::BufWriter`].
+///
+/// # Errors
+///
+/// Returns any error from `writer`, and [`io::ErrorKind::InvalidInput`] if
+/// more than `u32::MAX` vertices would be written, colour or custom values
+/// do not have one entry per vertex, a custom attribute name does not start
+/// with `_` or is repeated, or a GLB would exceed 4 GiB.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::{write_gltf, Colormap, GltfColors, GltfFormat, GltfNormals, GltfOptions};
+/// use meshing::{Face, Point3D, SurfaceMesh};
+///
+/// let p = |index, x, y, z| Point3D { index, x, y, z };
+/// let mesh = SurfaceMesh::from_faces(&[Face {
+///     a: p(0, 0.0, 0.0, 0.0),
+///     b: p(1, 1.0, 0.0, 0.0),
+///     c: p(2, 0.0, 1.0, 0.0),
+/// }]);
+/// let options = GltfOptions {
+///     normals: GltfNormals::Smooth,
+///     colors: Some(GltfColors::from_field(&mesh, Colormap::Viridis, |x, _, _| x)),
+///     custom_attributes: vec![("_ID".to_string(), vec![0.0, 1.0, 2.0])],
+///     ..GltfOptions::default()
+/// };
+/// let mut gltf = Vec::new();
+/// write_gltf(&mut gltf, &mesh, &options, GltfFormat::Json).unwrap();
+/// let json = String::from_utf8(gltf).unwrap();
+/// assert!(json.contains("\"NORMAL\":2,\"COLOR_0\":3,\"_ID\":4"));
+/// ```
+pub fn write_gltf<W: Write>(
+    writer: &mut W,
+    mesh: &SurfaceMesh,
+    options: &GltfOptions,
+    format: GltfFormat,
+) -> io::Result<()> {
+    validate(mesh, options)?;
+    let optimized;
+    let (mesh, options) = if options.optimize_vertex_cache {
+        optimized = optimize_vertex_cache(mesh, options);
+        (&optimized.0, &optimized.1)
+    } else {
+        (mesh, options)
+    };
+    let document = Document {
+        meshes: vec![build_mesh(mesh, options)],
+    };
+    write_document(writer, &document, format)
 }
 
-fn build_json(data: &MeshData, buffer_uri: Option<&str>, buffer_byte_length: usize) -> String {
-    let num_vertices = data.positions.len() / 3;
-    let num_indices = data.indices.len();
-    let pos_byte_length = num_vertices * 12;
-    let idx_byte_length = num_indices * 4;
+/// Writes a glTF document with its buffer embedded or in a GLB BIN chunk.
+///
+/// Arrays compressed with `EXT_meshopt_compression` are encoded in memory
+/// first; the others are streamed.
+pub(super) fn write_document<W: Write>(
+    writer: &mut W,
+    document: &Document,
+    format: GltfFormat,
+) -> io::Result<()> {
+    let encoded = document.encode()?;
+    let (json, tail) = document.json(&encoded);
+    match format {
+        GltfFormat::Json => {
+            writer.write_all(json.as_bytes())?;
+            writer.write_all(b",\"uri\":\"data:application/octet-stream;base64,")?;
+            let mut base64 = Base64Writer::new(writer);
+            document.write_buffer(&mut base64, &encoded)?;
+            base64.finish()?;
+            writer.write_all(b"\"")?;
+            writer.write_all(tail.as_bytes())
+        }
+        GltfFormat::Glb => {
+            let (_, buffer_length) = document.offsets(&encoded);
+            write_glb(writer, &(json + &tail), buffer_length, |w| {
+                document.write_buffer(w, &encoded)
+            })
+        }
+    }
+}
 
-    let buffer_line = match buffer_uri {
-        Some(uri) => format!(
-            "{{\"uri\":\"{}\",\"byteLength\":{}}}",
-            uri, buffer_byte_length
-        ),
-        None => format!("{{\"byteLength\":{}}}", buffer_byte_length),
-    };
+/// Exports an indexed surface mesh to glTF 2.0 with the attributes and
+/// material chosen in `options`.
+///
+/// # Panics
+///
+/// Panics if the mesh or options are invalid, see [`write_gltf`].
+pub fn surface_mesh_to_gltf_with_options(
+    mesh: &SurfaceMesh,
+    options: &GltfOptions,
+    format: GltfFormat,
+) -> Vec<u8> {
+    to_bytes(|w| write_gltf(w, mesh, options, format))
+}
 
-    format!(
-        concat!(
-            "{{",
-            "\"asset\":{{\"version\":\"2.0\",\"generator\":\"meshing\"}},",
-            "\"scene\":0,",
-            "\"scenes\":[{{\"nodes\":[0]}}],",
-            "\"nodes\":[{{\"mesh\":0}}],",
-            "\"meshes\":[{{\"primitives\":[{{\"attributes\":{{\"POSITION\":0}},\"indices\":1}}]}}],",
-            "\"accessors\":[",
-            "{{\"bufferView\":0,\"componentType\":5126,\"count\":{},\"type\":\"VEC3\",\"min\":[{},{},{}],\"max\":[{},{},{}]}},",
-            "{{\"bufferView\":1,\"componentType\":5125,\"count\":{},\"type\":\"SCALAR\"}}",
-            "],",
-            "\"bufferViews\":[",
-            "{{\"buffer\":0,\"byteOffset\":0,\"byteLength\":{},\"target\":34962}},",
-            "{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\"target\":34963}}",
-            "],",
-            "\"buffers\":[{}]",
-            "}}"
-        ),
-        num_vertices,
-        data.min[0], data.min[1], data.min[2],
-        data.max[0], data.max[1], data.max[2],
-        num_indices,
-        pos_byte_length,
-        pos_byte_length, idx_byte_length,
-        buffer_line
-    )
+/// Exports 3D faces to glTF 2.0 with the attributes and material chosen in
+/// `options`.
+///
+/// Per-vertex values follow the vertices of
+/// [`SurfaceMesh::from_faces`], i.e. ascending [`Point3D::index`](crate::Point3D::index).
+///
+/// # Panics
+///
+/// Panics if the faces or options are invalid, see [`write_gltf`].
+pub fn faces_to_gltf_with_options(
+    faces: &[Face],
+    options: &GltfOptions,
+    format: GltfFormat,
+) -> Vec<u8> {
+    surface_mesh_to_gltf_with_options(&SurfaceMesh::from_faces(faces), options, format)
 }
 
 /// Exports 3D faces to glTF 2.0 JSON format with embedded base64 binary data.
@@ -154,6 +1080,10 @@ fn build_json(data: &MeshData, buffer_uri: Option<&str>, buffer_byte_length: usi
 /// Returns a complete `.gltf` JSON string that can be written directly to a file.
 /// The binary buffer is embedded as a base64 data URI.
 ///
+/// # Panics
+///
+/// Panics if there are more than `u32::MAX` unique vertices.
+///
 /// # Examples
 ///
 /// ```
@@ -169,11 +1099,36 @@ fn build_json(data: &MeshData, buffer_uri: Option<&str>, buffer_byte_length: usi
 /// assert!(json.contains("\"version\":\"2.0\""));
 /// ```
 pub fn faces_to_gltf(faces: &[Face]) -> String {
-    let data = collect_mesh_data(faces);
-    let buffer = build_binary_buffer(&data);
-    let b64 = base64_encode(&buffer);
-    let uri = format!("data:application/octet-stream;base64,{}", b64);
-    build_json(&data, Some(&uri), buffer.len())
+    to_string(|w| write_faces_gltf(w, faces))
+}
+
+/// Exports an indexed surface mesh to glTF 2.0 JSON format with embedded
+/// base64 binary data.
+pub fn surface_mesh_to_gltf(mesh: &SurfaceMesh) -> String {
+    to_string(|w| write_surface_mesh_gltf(w, mesh))
+}
+
+/// Writes 3D faces as glTF 2.0 JSON with an embedded base64 buffer.
+///
+/// # Errors
+///
+/// Same as [`write_surface_mesh_gltf`].
+pub fn write_faces_gltf<W: Write>(writer: &mut W, faces: &[Face]) -> io::Result<()> {
+    write_surface_mesh_gltf(writer, &SurfaceMesh::from_faces(faces))
+}
+
+/// Writes an indexed surface mesh as glTF 2.0 JSON with an embedded base64
+/// buffer.
+///
+/// The buffer is encoded as it is written, so memory use does not grow with
+/// the mesh. Wrap files in a [`std::io::BufWriter`].
+///
+/// # Errors
+///
+/// Returns any error from `writer`, and [`io::ErrorKind::InvalidInput`] if
+/// the mesh has more than `u32::MAX` vertices.
+pub fn write_surface_mesh_gltf<W: Write>(writer: &mut W, mesh: &SurfaceMesh) -> io::Result<()> {
+    write_gltf(writer, mesh, &GltfOptions::default(), GltfFormat::Json)
 }
 
 /// Exports 3D faces to GLB (binary glTF) format.
@@ -181,6 +1136,10 @@ pub fn faces_to_gltf(faces: &[Face]) -> String {
 /// Returns the complete `.glb` file content as bytes. This is a self-contained
 /// binary format that includes both JSON metadata and binary vertex/index data.
 ///
+/// # Panics
+///
+/// Panics if the file would exceed 4 GiB.
+///
 /// # Examples
 ///
 /// ```
@@ -197,59 +1156,102 @@ pub fn faces_to_gltf(faces: &[Face]) -> String {
 /// assert_eq!(&glb[0..4], b"glTF");
 /// ```
 pub fn faces_to_glb(faces: &[Face]) -> Vec<u8> {
-    let data = collect_mesh_data(faces);
-    let bin_buffer = build_binary_buffer(&data);
-    let json_str = build_json(&data, None, bin_buffer.len());
-
-    // Pad JSON to 4-byte alignment
-    let json_bytes = json_str.as_bytes();
-    let json_padded_len = (json_bytes.len() + 3) & !3;
-
-    // Pad binary to 4-byte alignment
-    let bin_padded_len = (bin_buffer.len() + 3) & !3;
+    to_bytes(|w| write_faces_glb(w, faces))
+}
 
-    let total_length = 12 + 8 + json_padded_len + 8 + bin_padded_len;
+/// Exports an indexed surface mesh to GLB (binary glTF) format.
+pub fn surface_mesh_to_glb(mesh: &SurfaceMesh) -> Vec<u8> {
+    to_bytes(|w| write_surface_mesh_glb(w, mesh))
+}
 
-    let mut glb = Vec::with_capacity(total_length);
+/// Writes 3D faces as GLB (binary glTF).
+///
+/// # Errors
+///
+/// Same as [`write_surface_mesh_glb`].
+pub fn write_faces_glb<W: Write>(writer: &mut W, faces: &[Face]) -> io::Result<()> {
+    write_surface_mesh_glb(writer, &SurfaceMesh::from_faces(faces))
+}
 
-    // GLB Header
-    glb.extend_from_slice(b"glTF"); // magic
-    glb.extend_from_slice(&2u32.to_le_bytes()); // version
-    glb.extend_from_slice(&(total_length as u32).to_le_bytes()); // total length
+/// Writes an indexed surface mesh as GLB (binary glTF), streaming the
+/// vertex and index data straight from `mesh`.
+///
+/// # Errors
+///
+/// Returns any error from `writer`, and [`io::ErrorKind::InvalidInput`] if
+/// the mesh has more than `u32::MAX` vertices or the file would exceed
+/// 4 GiB.
+pub fn write_surface_mesh_glb<W: Write>(writer: &mut W, mesh: &SurfaceMesh) -> io::Result<()> {
+    write_gltf(writer, mesh, &GltfOptions::default(), GltfFormat::Glb)
+}
 
-    // JSON chunk
-    glb.extend_from_slice(&(json_padded_len as u32).to_le_bytes()); // chunk length
-    glb.extend_from_slice(&0x4E4F534Au32.to_le_bytes()); // chunk type "JSON"
-    glb.extend_from_slice(json_bytes);
-    glb.extend(std::iter::repeat_n(
-        b' ',
-        json_padded_len - json_bytes.len(),
-    ));
+/// Exports a tetrahedral mesh to glTF 2.0 JSON by extracting surface faces.
+pub fn tetrahedra_to_gltf(tetrahedra: &[Tetrahedron]) -> String {
+    to_string(|w| write_tetrahedra_gltf(w, tetrahedra))
+}
 
-    // Binary chunk
-    glb.extend_from_slice(&(bin_padded_len as u32).to_le_bytes()); // chunk length
-    glb.extend_from_slice(&0x004E4942u32.to_le_bytes()); // chunk type "BIN\0"
-    glb.extend_from_slice(&bin_buffer);
-    glb.extend(std::iter::repeat_n(0u8, bin_padded_len - bin_buffer.len()));
+/// Exports the boundary surface of an indexed tetrahedral mesh to glTF 2.0 JSON.
+pub fn tet_mesh_to_gltf(mesh: &TetMesh) -> String {
+    to_string(|w| write_tet_mesh_gltf(w, mesh))
+}
 
-    glb
+/// Writes the boundary surface of a tetrahedral mesh as glTF 2.0 JSON.
+///
+/// # Errors
+///
+/// Same as [`write_surface_mesh_gltf`].
+pub fn write_tetrahedra_gltf<W: Write>(
+    writer: &mut W,
+    tetrahedra: &[Tetrahedron],
+) -> io::Result<()> {
+    write_tet_mesh_gltf(writer, &TetMesh::from_tetrahedra(tetrahedra))
 }
 
-/// Exports a tetrahedral mesh to glTF 2.0 JSON by extracting surface faces.
-pub fn tetrahedra_to_gltf(tetrahedra: &[Tetrahedron]) -> String {
-    let surface = extract_surface_faces(tetrahedra);
-    faces_to_gltf(&surface)
+/// Writes the boundary surface of an indexed tetrahedral mesh as glTF 2.0
+/// JSON.
+///
+/// # Errors
+///
+/// Same as [`write_surface_mesh_gltf`].
+pub fn write_tet_mesh_gltf<W: Write>(writer: &mut W, mesh: &TetMesh) -> io::Result<()> {
+    write_surface_mesh_gltf(writer, &mesh.surface())
 }
 
 /// Exports a tetrahedral mesh to GLB by extracting surface faces.
 pub fn tetrahedra_to_glb(tetrahedra: &[Tetrahedron]) -> Vec<u8> {
-    let surface = extract_surface_faces(tetrahedra);
-    faces_to_glb(&surface)
+    to_bytes(|w| write_tetrahedra_glb(w, tetrahedra))
+}
+
+/// Exports the boundary surface of an indexed tetrahedral mesh to GLB.
+pub fn tet_mesh_to_glb(mesh: &TetMesh) -> Vec<u8> {
+    to_bytes(|w| write_tet_mesh_glb(w, mesh))
+}
+
+/// Writes the boundary surface of a tetrahedral mesh as GLB.
+///
+/// # Errors
+///
+/// Same as [`write_surface_mesh_glb`].
+pub fn write_tetrahedra_glb<W: Write>(
+    writer: &mut W,
+    tetrahedra: &[Tetrahedron],
+) -> io::Result<()> {
+    write_tet_mesh_glb(writer, &TetMesh::from_tetrahedra(tetrahedra))
+}
+
+/// Writes the boundary surface of an indexed tetrahedral mesh as GLB.
+///
+/// # Errors
+///
+/// Same as [`write_surface_mesh_glb`].
+pub fn write_tet_mesh_glb<W: Write>(writer: &mut W, mesh: &TetMesh) -> io::Result<()> {
+    write_surface_mesh_glb(writer, &mesh.surface())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Point3D;
 
     fn test_face() -> Face {
         Face {
@@ -583,4 +1585,294 @@ mod tests {
         let bin_offset = 20 + json_len;
         assert!(glb.len() > bin_offset + 8); // BIN chunk header exists
     }
+
+    #[test]
+    fn test_base64_writer_matches_encode() {
+        let data: Vec<u8> = (0..2 * BASE64_CHUNK + 7).map(|i| (i * 31) as u8).collect();
+        for len in [0, 1, 2, BASE64_CHUNK - 1, BASE64_CHUNK + 1, data.len()] {
+            let mut out = Vec::new();
+            let mut writer = Base64Writer::new(&mut out);
+            // Uneven writes so that chunks end mid-group.
+            for part in data[..len].chunks(1000) {
+                writer.write_all(part).unwrap();
+            }
+            writer.finish().unwrap();
+            assert_eq!(out, base64_encode(&data[..len]).into_bytes(), "{}", len);
+        }
+    }
+
+    #[test]
+    fn test_streamed_gltf_embeds_buffer() {
+        let mesh = SurfaceMesh::from_faces(&[test_face()]);
+        let mut out = Vec::new();
+        write_surface_mesh_gltf(&mut out, &mesh).unwrap();
+        let json = String::from_utf8(out).unwrap();
+        assert_eq!(json, surface_mesh_to_gltf(&mesh));
+
+        let mut buffer = Vec::new();
+        let options = GltfOptions::default();
+        let document = Document {
+            meshes: vec![build_mesh(&mesh, &options)],
+        };
+        document.write_buffer(&mut buffer, &[None, None]).unwrap();
+        let uri = format!(
+            "\"byteLength\":{},\"uri\":\"data:application/octet-stream;base64,{}\"}}]}}",
+            buffer.len(),
+            base64_encode(&buffer)
+        );
+        assert!(json.ends_with(&uri));
+    }
+
+    #[test]
+    fn test_streamed_glb_matches_wrapper() {
+        let tet = Tetrahedron {
+            a: test_face().a,
+            b: test_face().b,
+            c: test_face().c,
+            d: Point3D {
+                index: 3,
+                x: 0.0,
+                y: 0.0,
+                z: 1.0,
+            },
+        };
+        let mut out = Vec::new();
+        write_tetrahedra_glb(&mut out, &[tet]).unwrap();
+        assert_eq!(out, tetrahedra_to_glb(&[tet]));
+        let bin_len = out.len() - 20 - u32::from_le_bytes(out[12..16].try_into().unwrap()) as usize;
+        // BIN chunk header, 4 positions and 4 triangles.
+        assert_eq!(bin_len, 8 + 4 * 12 + 4 * 12);
+    }
+
+    #[test]
+    fn test_writer_errors_are_returned() {
+        struct Full;
+        impl Write for Full {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk full"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        assert!(write_faces_glb(&mut Full, &[test_face()]).is_err());
+        assert!(write_faces_gltf(&mut Full, &[test_face()]).is_err());
+    }
+
+    fn quad() -> SurfaceMesh {
+        let p = |index, x, y| Point3D {
+            index,
+            x,
+            y,
+            z: 0.0,
+        };
+        SurfaceMesh {
+            vertices: vec![
+                p(0, 0.0, 0.0),
+                p(1, 1.0, 0.0),
+                p(2, 1.0, 1.0),
+                p(3, 0.0, 1.0),
+            ],
+            faces: vec![[0, 1, 2], [0, 2, 3]],
+        }
+    }
+
+    fn glb_json(glb: &[u8]) -> &str {
+        let json_len = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+        std::str::from_utf8(&glb[20..20 + json_len]).unwrap()
+    }
+
+    #[test]
+    fn test_smooth_normals_point_outward_on_sphere() {
+        use crate::marching_cubes::marching_cubes;
+        let corner = |c| Point3D {
+            index: 0,
+            x: c,
+            y: c,
+            z: c,
+        };
+        let faces = marching_cubes(
+            12,
+            12,
+            12,
+            corner(-1.5),
+            corner(1.5),
+            &|x, y, z| x * x + y * y + z * z - 1.0,
+            0.0,
+        );
+        // Marching cubes emits three vertices per triangle; weld them so
+        // that the normals are averaged.
+        let mut ids = std::collections::HashMap::new();
+        let welded: Vec<Face> = faces
+            .iter()
+            .map(|f| {
+                let [a, b, c] = f.vertices().map(|v| {
+                    let next = ids.len() as i64;
+                    let index = *ids.entry([v.x, v.y, v.z].map(f64::to_bits)).or_insert(next);
+                    Point3D { index, ..v }
+                });
+                Face { a, b, c }
+            })
+            .collect();
+        let mesh = SurfaceMesh::from_faces(&welded);
+        assert!(mesh.vertices.len() < faces.len());
+        let normals = vertex_normals(Layout {
+            mesh: &mesh,
+            flat: false,
+        });
+        assert_eq!(normals.len(), mesh.vertices.len());
+        for (n, v) in normals.iter().zip(&mesh.vertices) {
+            let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            assert!((length - 1.0).abs() < 1e-9);
+            // Close to the radial direction of the unit sphere.
+            let r = (v.x * v.x + v.y * v.y + v.z * v.z).sqrt();
+            assert!((n[0] * v.x + n[1] * v.y + n[2] * v.z) / r > 0.9);
+        }
+
+        let options = GltfOptions {
+            normals: GltfNormals::Smooth,
+            ..GltfOptions::default()
+        };
+        let glb = surface_mesh_to_gltf_with_options(&mesh, &options, GltfFormat::Glb);
+        let json = glb_json(&glb);
+        assert!(json.contains("\"attributes\":{\"POSITION\":0,\"NORMAL\":2},\"indices\":1"));
+        let total = u32::from_le_bytes(glb[8..12].try_into().unwrap());
+        assert_eq!(total as usize, glb.len());
+    }
+
+    #[test]
+    fn test_flat_normals_unweld_vertices() {
+        let options = GltfOptions {
+            normals: GltfNormals::Flat,
+            custom_attributes: vec![("_ID".to_string(), vec![0.0, 1.0, 2.0, 3.0])],
+            ..GltfOptions::default()
+        };
+        let glb = surface_mesh_to_gltf_with_options(&quad(), &options, GltfFormat::Glb);
+        let json = glb_json(&glb);
+        // Six vertices for two triangles, each with its own normal and value.
+        assert_eq!(json.matches("\"count\":6").count(), 4);
+        let bin = &glb[28 + json.len()..];
+        let floats = |at: usize, n: usize| -> Vec<f32> {
+            (0..n)
+                .map(|i| f32::from_le_bytes(bin[at + 4 * i..at + 4 * i + 4].try_into().unwrap()))
+                .collect()
+        };
+        let indices: Vec<u32> = (0..6)
+            .map(|i| u32::from_le_bytes(bin[72 + 4 * i..76 + 4 * i].try_into().unwrap()))
+            .collect();
+        assert_eq!(indices, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(floats(96, 3), vec![0.0, 0.0, 1.0]);
+        assert_eq!(floats(168, 6), vec![0.0, 1.0, 2.0, 0.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_colormap_endpoints() {
+        assert_eq!(Colormap::Grayscale.color(0.0), [0.0; 3]);
+        assert_eq!(Colormap::Grayscale.color(2.0), [1.0; 3]);
+        let close = |a: [f64; 3], b: [f64; 3]| (0..3).all(|c| (a[c] - b[c]).abs() < 1e-6);
+        // Viridis starts at sRGB (0.267, 0.005, 0.329).
+        assert!(close(
+            Colormap::Viridis.color(0.0),
+            [0.057949, 0.000387, 0.088428]
+        ));
+        assert!(close(
+            Colormap::Viridis.color(1.0),
+            [0.984150, 0.799338, 0.018258]
+        ));
+        assert!(close(Colormap::CoolWarm.color(0.5), [0.719921; 3]));
+
+        let mesh = quad();
+        let colors = GltfColors::from_field(&mesh, Colormap::Grayscale, |x, y, _| x + y);
+        assert_eq!(colors.values, vec![0.0, 1.0, 2.0, 1.0]);
+        let rgb = colors.rgb();
+        assert_eq!(rgb[0], [0.0; 3]);
+        // sRGB mid-grey.
+        assert!(close(rgb[1], [0.214041; 3]));
+        assert_eq!(rgb[2], [1.0; 3]);
+    }
+
+    #[test]
+    fn test_invalid_options_are_rejected() {
+        let mesh = quad();
+        let custom = |name: &str, n: usize| GltfOptions {
+            custom_attributes: vec![(name.to_string(), vec![0.0; n])],
+            ..GltfOptions::default()
+        };
+        let cases = [
+            custom("TEMPERATURE", 4),
+            custom("_TEMPERATURE", 3),
+            GltfOptions {
+                custom_attributes: vec![("_A".to_string(), vec![0.0; 4]); 2],
+                ..GltfOptions::default()
+            },
+            GltfOptions {
+                colors: Some(GltfColors {
+                    values: vec![1.0],
+                    ..GltfColors::default()
+                }),
+                ..GltfOptions::default()
+            },
+        ];
+        for options in &cases {
+            let err = write_gltf(&mut Vec::new(), &mesh, options, GltfFormat::Json).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn test_material_block() {
+        let options = GltfOptions {
+            material: Some(GltfMaterial {
+                base_color: [1.0, 0.5, 0.25, 1.0],
+                metallic: 0.2,
+                double_sided: true,
+                ..GltfMaterial::default()
+            }),
+            ..GltfOptions::default()
+        };
+        let json = String::from_utf8(faces_to_gltf_with_options(
+            &[test_face()],
+            &options,
+            GltfFormat::Json,
+        ))
+        .unwrap();
+        assert!(json.contains("\"indices\":1,\"material\":0"));
+        assert!(json.contains(concat!(
+            "\"materials\":[{\"pbrMetallicRoughness\":{\"baseColorFactor\":[1,0.5,0.25,1],",
+            "\"metallicFactor\":0.2,\"roughnessFactor\":0.5},\"doubleSided\":true}]"
+        )));
+    }
+
+    #[test]
+    fn test_quantized_options() {
+        let mesh = quad();
+        let options = GltfOptions {
+            normals: GltfNormals::Smooth,
+            colors: Some(GltfColors::from_field(
+                &mesh,
+                Colormap::Viridis,
+                |x, _, _| x,
+            )),
+            quantized: true,
+            ..GltfOptions::default()
+        };
+        let glb = surface_mesh_to_gltf_with_options(&mesh, &options, GltfFormat::Glb);
+        let total = u32::from_le_bytes(glb[8..12].try_into().unwrap());
+        assert_eq!(total as usize, glb.len());
+        let json = glb_json(&glb);
+        assert!(json.contains("\"extensionsRequired\":[\"KHR_mesh_quantization\"]"));
+        assert!(json.contains(concat!(
+            "{\"bufferView\":2,\"componentType\":5120,\"normalized\":true,",
+            "\"count\":4,\"type\":\"VEC3\"}"
+        )));
+        assert!(json
+            .contains("\"componentType\":5121,\"normalized\":true,\"count\":4,\"type\":\"VEC4\""));
+        assert!(json.contains("\"byteOffset\":48,\"byteLength\":16,\"byteStride\":4"));
+        // 24 bytes of positions, 24 of indices, 16 of normals and 16 of colours.
+        let bin_len = glb.len() - 28 - json.len();
+        assert_eq!(bin_len, 80);
+        let bin = &glb[glb.len() - 80..];
+        assert_eq!(&bin[48..52], &[0, 0, 127, 0]);
+        assert_eq!(bin[64 + 3], 255);
+    }
 }
@@ -0,0 +1,718 @@
+use std::io::{self, Write};
+
+use super::gltf::{write_gltf, Array, GltfFormat, GltfOptions};
+use super::to_bytes;
+use crate::{Face, SurfaceMesh, TetMesh, Tetrahedron};
+
+/// Exports 3D faces to a GLB sized for downloading: quantized with
+/// `KHR_mesh_quantization`, reordered for the vertex cache, with `u16`
+/// indices where they fit and compressed with `EXT_meshopt_compression`.
+///
+/// Viewers need a meshopt decoder, e.g. `MeshoptDecoder` for three.js or
+/// Babylon.js.
+///
+/// # Panics
+///
+/// Panics if the file would exceed 4 GiB.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::faces_to_glb_compressed;
+/// use meshing::{Face, Point3D};
+///
+/// let face = Face {
+///     a: Point3D { index: 0, x: 0.0, y: 0.0, z: 0.0 },
+///     b: Point3D { index: 1, x: 1.0, y: 0.0, z: 0.0 },
+///     c: Point3D { index: 2, x: 0.0, y: 1.0, z: 0.0 },
+/// };
+/// let glb = faces_to_glb_compressed(&[face]);
+/// assert_eq!(&glb[0..4], b"glTF");
+/// ```
+pub fn faces_to_glb_compressed(faces: &[Face]) -> Vec<u8> {
+    to_bytes(|w| write_faces_glb_compressed(w, faces))
+}
+
+/// Exports an indexed surface mesh to a quantized, vertex cache optimized
+/// GLB compressed with `EXT_meshopt_compression`.
+pub fn surface_mesh_to_glb_compressed(mesh: &SurfaceMesh) -> Vec<u8> {
+    to_bytes(|w| write_surface_mesh_glb_compressed(w, mesh))
+}
+
+/// Writes 3D faces as a compressed GLB.
+///
+/// # Errors
+///
+/// Same as [`write_surface_mesh_glb_compressed`].
+pub fn write_faces_glb_compressed<W: Write>(writer: &mut W, faces: &[Face]) -> io::Result<()> {
+    write_surface_mesh_glb_compressed(writer, &SurfaceMesh::from_faces(faces))
+}
+
+/// Writes an indexed surface mesh as a quantized, vertex cache optimized
+/// GLB compressed with `EXT_meshopt_compression`.
+///
+/// To add normals, colours or a material, set
+/// [`GltfOptions::meshopt_compression`] and the related options and use
+/// [`write_gltf`].
+///
+/// # Errors
+///
+/// Returns any error from `writer`, and [`io::ErrorKind::InvalidInput`] if
+/// the mesh has more than `u32::MAX` vertices or the file would exceed
+/// 4 GiB.
+pub fn write_surface_mesh_glb_compressed<W: Write>(
+    writer: &mut W,
+    mesh: &SurfaceMesh,
+) -> io::Result<()> {
+    let options = GltfOptions {
+        quantized: true,
+        optimize_vertex_cache: true,
+        compact_indices: true,
+        meshopt_compression: true,
+        ..GltfOptions::default()
+    };
+    write_gltf(writer, mesh, &options, GltfFormat::Glb)
+}
+
+/// Exports the boundary surface of a tetrahedral mesh to a compressed GLB.
+pub fn tetrahedra_to_glb_compressed(tetrahedra: &[Tetrahedron]) -> Vec<u8> {
+    to_bytes(|w| write_tetrahedra_glb_compressed(w, tetrahedra))
+}
+
+/// Exports the boundary surface of an indexed tetrahedral mesh to a
+/// compressed GLB.
+pub fn tet_mesh_to_glb_compressed(mesh: &TetMesh) -> Vec<u8> {
+    to_bytes(|w| write_tet_mesh_glb_compressed(w, mesh))
+}
+
+/// Writes the boundary surface of a tetrahedral mesh as a compressed GLB.
+///
+/// # Errors
+///
+/// Same as [`write_surface_mesh_glb_compressed`].
+pub fn write_tetrahedra_glb_compressed<W: Write>(
+    writer: &mut W,
+    tetrahedra: &[Tetrahedron],
+) -> io::Result<()> {
+    write_tet_mesh_glb_compressed(writer, &TetMesh::from_tetrahedra(tetrahedra))
+}
+
+/// Writes the boundary surface of an indexed tetrahedral mesh as a
+/// compressed GLB.
+///
+/// # Errors
+///
+/// Same as [`write_surface_mesh_glb_compressed`].
+pub fn write_tet_mesh_glb_compressed<W: Write>(writer: &mut W, mesh: &TetMesh) -> io::Result<()> {
+    write_surface_mesh_glb_compressed(writer, &mesh.surface())
+}
+
+/// Size of the simulated vertex cache the faces are ordered for.
+const CACHE_SIZE: usize = 16;
+
+/// Orders the faces with Tipsify (Sander, Nehab and Barczak 2007): faces
+/// are emitted in fans around vertices, moving to the next vertex that is
+/// still in the cache and has faces left.
+fn tipsify(faces: &[[usize; 3]], num_vertices: usize) -> Vec<usize> {
+    let mut adjacent = vec![Vec::new(); num_vertices];
+    for (f, face) in faces.iter().enumerate() {
+        for &v in face {
+            adjacent[v].push(f);
+        }
+    }
+    let mut live: Vec<usize> = adjacent.iter().map(Vec::len).collect();
+    let mut cached_at = vec![0; num_vertices];
+    let mut emitted = vec![false; faces.len()];
+    let mut dead_ends = Vec::new();
+    let mut order = Vec::with_capacity(faces.len());
+    let mut time = CACHE_SIZE + 1;
+    let mut cursor = 0;
+    let mut fan = Some(0).filter(|_| num_vertices > 0);
+
+    while let Some(f) = fan {
+        let mut candidates = Vec::new();
+        for &t in &adjacent[f] {
+            if emitted[t] {
+                continue;
+            }
+            emitted[t] = true;
+            order.push(t);
+            for v in faces[t] {
+                dead_ends.push(v);
+                candidates.push(v);
+                live[v] -= 1;
+                if time - cached_at[v] > CACHE_SIZE {
+                    cached_at[v] = time;
+                    time += 1;
+                }
+            }
+        }
+
+        // Prefer the candidate that entered the cache first among those
+        // whose remaining faces would not push it out.
+        let mut best = None;
+        let mut best_priority = 0;
+        for &v in &candidates {
+            if live[v] == 0 {
+                continue;
+            }
+            let age = time - cached_at[v];
+            let priority = if age + 2 * live[v] <= CACHE_SIZE {
+                age
+            } else {
+                0
+            };
+            if best.is_none() || priority > best_priority {
+                best = Some(v);
+                best_priority = priority;
+            }
+        }
+        fan = best.or_else(|| {
+            while let Some(v) = dead_ends.pop() {
+                if live[v] > 0 {
+                    return Some(v);
+                }
+            }
+            while cursor < num_vertices {
+                if live[cursor] > 0 {
+                    return Some(cursor);
+                }
+                cursor += 1;
+            }
+            None
+        });
+    }
+    order
+}
+
+/// The mesh with its faces in Tipsify order and its vertices in order of
+/// first use, unused ones last, and the options with their per-vertex
+/// values permuted to match.
+pub(super) fn optimize_vertex_cache(
+    mesh: &SurfaceMesh,
+    options: &GltfOptions,
+) -> (SurfaceMesh, GltfOptions) {
+    let order = tipsify(&mesh.faces, mesh.vertices.len());
+    const UNUSED: usize = usize::MAX;
+    let mut remap = vec![UNUSED; mesh.vertices.len()];
+    let mut sources = Vec::with_capacity(mesh.vertices.len());
+    let mut faces = Vec::with_capacity(mesh.faces.len());
+    for &f in &order {
+        faces.push(mesh.faces[f].map(|v| {
+            if remap[v] == UNUSED {
+                remap[v] = sources.len();
+                sources.push(v);
+            }
+            remap[v]
+        }));
+    }
+    sources.extend((0..mesh.vertices.len()).filter(|&v| remap[v] == UNUSED));
+
+    let permute = |values: &[f64]| sources.iter().map(|&v| values[v]).collect();
+    let mut options = options.clone();
+    if let Some(colors) = &mut options.colors {
+        colors.values = permute(&colors.values);
+    }
+    for (_, values) in &mut options.custom_attributes {
+        *values = permute(values);
+    }
+    let mesh = SurfaceMesh {
+        vertices: sources.iter().map(|&v| mesh.vertices[v]).collect(),
+        faces,
+    };
+    (mesh, options)
+}
+
+/// An array encoded with `EXT_meshopt_compression`.
+pub(super) struct MeshoptView {
+    pub(super) data: Vec<u8>,
+    /// Bytes per element once decoded.
+    pub(super) stride: usize,
+    /// `"ATTRIBUTES"` or `"INDICES"`.
+    pub(super) mode: &'static str,
+}
+
+/// Encodes an array: indices with the index sequence codec, attributes
+/// with the vertex codec after padding their elements to a multiple of
+/// 4 bytes, as that codec requires.
+pub(super) fn encode_array(array: &Array) -> io::Result<MeshoptView> {
+    let mut raw = Vec::with_capacity(array.byte_length());
+    (array.write)(&mut raw)?;
+    if array.attribute.is_none() {
+        let indices: Vec<u32> = if array.element_size == 2 {
+            raw.chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]) as u32)
+                .collect()
+        } else {
+            raw.chunks_exact(4)
+                .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                .collect()
+        };
+        return Ok(MeshoptView {
+            data: encode_index_sequence(&indices),
+            stride: array.element_size,
+            mode: "INDICES",
+        });
+    }
+    let stride = (array.element_size + 3) & !3;
+    if stride != array.element_size {
+        raw = raw
+            .chunks_exact(array.element_size)
+            .flat_map(|element| {
+                let mut padded = element.to_vec();
+                padded.resize(stride, 0);
+                padded
+            })
+            .collect();
+    }
+    Ok(MeshoptView {
+        data: encode_vertex_buffer(&raw, stride),
+        stride,
+        mode: "ATTRIBUTES",
+    })
+}
+
+/// Bytes per group of the vertex codec, each sharing a 2-bit header.
+const GROUP_SIZE: usize = 16;
+
+/// Encodes `data`, elements of `stride` bytes, with version 0 of the
+/// meshopt vertex codec.
+///
+/// Each byte of an element is stored as the zigzag-encoded difference to
+/// the same byte of the previous element, so slowly varying attributes
+/// give runs of small values that pack into 0, 2 or 4 bits each.
+fn encode_vertex_buffer(data: &[u8], stride: usize) -> Vec<u8> {
+    let count = data.len() / stride;
+    let block_size = ((8192 / stride) & !(GROUP_SIZE - 1)).min(256);
+    let mut out = vec![0xa0];
+    let first = &data[..stride.min(data.len())];
+    let mut last = first.to_vec();
+    last.resize(stride, 0);
+
+    let mut deltas = Vec::with_capacity(block_size);
+    for block in data.chunks(block_size * stride) {
+        let elements = block.len() / stride;
+        for k in 0..stride {
+            deltas.clear();
+            let mut previous = last[k];
+            for element in block.chunks_exact(stride) {
+                let delta = element[k].wrapping_sub(previous) as i8;
+                deltas.push(((delta << 1) ^ (delta >> 7)) as u8);
+                previous = element[k];
+            }
+            let aligned = elements.div_ceil(GROUP_SIZE) * GROUP_SIZE;
+            deltas.resize(aligned, deltas[elements - 1]);
+            encode_bytes(&mut out, &deltas);
+        }
+        last.copy_from_slice(&block[block.len() - stride..]);
+    }
+
+    // The first element, at the end of a tail of at least 32 bytes.
+    let tail = stride.max(32);
+    out.resize(out.len() + tail - stride, 0);
+    if count > 0 {
+        out.extend_from_slice(first);
+    } else {
+        out.resize(out.len() + stride, 0);
+    }
+    out
+}
+
+/// Packs groups of 16 bytes with the smallest of 0, 2, 4 or 8 bits per
+/// byte, after a header of 2 bits per group. Bytes too large for 2 or
+/// 4 bits are stored whole after the packed group.
+fn encode_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    let groups = bytes.len() / GROUP_SIZE;
+    let header = out.len();
+    out.resize(header + groups.div_ceil(4), 0);
+    for (g, group) in bytes.chunks_exact(GROUP_SIZE).enumerate() {
+        let size = |bits: usize| {
+            let sentinel = (1u8 << bits) - 1;
+            GROUP_SIZE * bits / 8 + group.iter().filter(|&&b| b >= sentinel).count()
+        };
+        let (code, bits) = if group.iter().all(|&b| b == 0) {
+            (0, 0)
+        } else {
+            [(1, 2), (2, 4)]
+                .into_iter()
+                .filter(|&(_, bits)| size(bits) < GROUP_SIZE)
+                .min_by_key(|&(_, bits)| size(bits))
+                .unwrap_or((3, 8))
+        };
+        out[header + g / 4] |= code << (g % 4 * 2);
+        match bits {
+            0 => {}
+            8 => out.extend_from_slice(group),
+            _ => {
+                let sentinel = (1u8 << bits) - 1;
+                for packed in group.chunks(8 / bits) {
+                    let byte = packed
+                        .iter()
+                        .fold(0u8, |byte, &b| (byte << bits) | b.min(sentinel));
+                    out.push(byte);
+                }
+                out.extend(group.iter().filter(|&&b| b >= sentinel));
+            }
+        }
+    }
+}
+
+/// Encodes indices with version 1 of the meshopt index sequence codec:
+/// varint-coded zigzag differences to one of two baselines, switching
+/// baseline when the difference grows large.
+fn encode_index_sequence(indices: &[u32]) -> Vec<u8> {
+    let mut out = vec![0xd1];
+    let mut last = [0u32; 2];
+    let mut current = 0;
+    for &index in indices {
+        let difference = index.wrapping_sub(last[current]) as i32;
+        if difference.unsigned_abs() >= 30 {
+            current ^= 1;
+        }
+        let delta = index.wrapping_sub(last[current]);
+        let zigzag = (delta << 1) ^ ((delta as i32 >> 31) as u32);
+        let mut value = ((zigzag as u64) << 1) | current as u64;
+        loop {
+            let byte = (value & 127) as u8;
+            value >>= 7;
+            if value == 0 {
+                out.push(byte);
+                break;
+            }
+            out.push(byte | 128);
+        }
+        last[current] = index;
+    }
+    out.extend_from_slice(&[0; 4]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{faces_to_glb_quantized, surface_mesh_to_gltf_with_options, GltfNormals};
+    use crate::Point3D;
+
+    /// Decodes version 0 of the vertex codec, following the
+    /// `EXT_meshopt_compression` specification.
+    fn decode_vertex_buffer(data: &[u8], count: usize, stride: usize) -> Vec<u8> {
+        assert_eq!(data[0], 0xa0);
+        let tail = stride.max(32);
+        let mut last = data[data.len() - stride..].to_vec();
+        let block_size = ((8192 / stride) & !(GROUP_SIZE - 1)).min(256);
+        let mut pos = 1;
+        let mut out = vec![0; count * stride];
+        let mut start = 0;
+        while start < count {
+            let elements = block_size.min(count - start);
+            let aligned = elements.div_ceil(GROUP_SIZE) * GROUP_SIZE;
+            for k in 0..stride {
+                let groups = aligned / GROUP_SIZE;
+                let header = pos;
+                pos += groups.div_ceil(4);
+                let mut bytes = Vec::new();
+                for g in 0..groups {
+                    let bits = [0, 2, 4, 8][((data[header + g / 4] >> (g % 4 * 2)) & 3) as usize];
+                    match bits {
+                        0 => bytes.extend([0; GROUP_SIZE]),
+                        8 => {
+                            bytes.extend_from_slice(&data[pos..pos + GROUP_SIZE]);
+                            pos += GROUP_SIZE;
+                        }
+                        _ => {
+                            let sentinel = (1u8 << bits) - 1;
+                            let packed = &data[pos..pos + GROUP_SIZE * bits / 8];
+                            pos += GROUP_SIZE * bits / 8;
+                            for byte in packed {
+                                for j in (0..8 / bits).rev() {
+                                    let value = (byte >> (j * bits)) & sentinel;
+                                    if value == sentinel {
+                                        bytes.push(data[pos]);
+                                        pos += 1;
+                                    } else {
+                                        bytes.push(value);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                let mut previous = last[k];
+                for (i, &z) in bytes[..elements].iter().enumerate() {
+                    let delta = (z >> 1) ^ (z & 1).wrapping_neg();
+                    previous = previous.wrapping_add(delta);
+                    out[(start + i) * stride + k] = previous;
+                }
+            }
+            last.copy_from_slice(
+                &out[(start + elements - 1) * stride..(start + elements) * stride],
+            );
+            start += elements;
+        }
+        assert_eq!(data.len() - pos, tail);
+        out
+    }
+
+    fn decode_index_sequence(data: &[u8], count: usize) -> Vec<u32> {
+        assert_eq!(data[0], 0xd1);
+        let mut pos = 1;
+        let mut last = [0u32; 2];
+        let mut out = Vec::new();
+        for _ in 0..count {
+            let mut value = 0u64;
+            let mut shift = 0;
+            loop {
+                let byte = data[pos];
+                pos += 1;
+                value |= ((byte & 127) as u64) << shift;
+                shift += 7;
+                if byte < 128 {
+                    break;
+                }
+            }
+            let current = (value & 1) as usize;
+            let v = (value >> 1) as u32;
+            let delta = (v >> 1) ^ (v & 1).wrapping_neg();
+            last[current] = last[current].wrapping_add(delta);
+            out.push(last[current]);
+        }
+        assert_eq!(data.len() - pos, 4);
+        out
+    }
+
+    /// A UV sphere with `n` rings and `2n` segments.
+    fn sphere(n: usize) -> SurfaceMesh {
+        let mut vertices = Vec::new();
+        for i in 0..=n {
+            let theta = std::f64::consts::PI * i as f64 / n as f64;
+            for j in 0..2 * n {
+                let phi = std::f64::consts::PI * j as f64 / n as f64;
+                vertices.push(Point3D {
+                    index: vertices.len() as i64,
+                    x: theta.sin() * phi.cos(),
+                    y: theta.sin() * phi.sin(),
+                    z: theta.cos(),
+                });
+            }
+        }
+        let at = |i: usize, j: usize| i * 2 * n + j % (2 * n);
+        let mut faces = Vec::new();
+        for i in 0..n {
+            for j in 0..2 * n {
+                faces.push([at(i, j), at(i + 1, j), at(i + 1, j + 1)]);
+                faces.push([at(i, j), at(i + 1, j + 1), at(i, j + 1)]);
+            }
+        }
+        SurfaceMesh { vertices, faces }
+    }
+
+    /// Average number of vertices transformed per face with a FIFO cache
+    /// of 16 entries.
+    fn acmr(faces: &[[usize; 3]]) -> f64 {
+        let mut cache = std::collections::VecDeque::new();
+        let mut misses = 0;
+        for &v in faces.iter().flatten() {
+            if !cache.contains(&v) {
+                misses += 1;
+                cache.push_back(v);
+                if cache.len() > 16 {
+                    cache.pop_front();
+                }
+            }
+        }
+        misses as f64 / faces.len() as f64
+    }
+
+    fn glb_json(glb: &[u8]) -> &str {
+        let json_len = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+        std::str::from_utf8(&glb[20..20 + json_len])
+            .unwrap()
+            .trim_end()
+    }
+
+    #[test]
+    fn test_vertex_codec_round_trip() {
+        let mut data = Vec::new();
+        for i in 0..700u32 {
+            data.extend_from_slice(&(i * 3).to_le_bytes());
+            data.extend_from_slice(&(i.wrapping_mul(2654435761) >> 7).to_le_bytes());
+        }
+        let encoded = encode_vertex_buffer(&data, 8);
+        assert_eq!(decode_vertex_buffer(&encoded, 700, 8), data);
+
+        let single = [1, 2, 3, 4];
+        let encoded = encode_vertex_buffer(&single, 4);
+        assert_eq!(encoded.len(), 1 + 4 + 32);
+        assert_eq!(decode_vertex_buffer(&encoded, 1, 4), single);
+    }
+
+    #[test]
+    fn test_index_codec_round_trip() {
+        let indices = [0, 1, 2, 2, 1, 3, 1000, 5, 70000, 69999, 4, 0];
+        let encoded = encode_index_sequence(&indices);
+        assert_eq!(decode_index_sequence(&encoded, indices.len()), indices);
+    }
+
+    #[test]
+    fn test_tipsify_emits_every_face_once() {
+        let mesh = sphere(12);
+        let mut order = tipsify(&mesh.faces, mesh.vertices.len());
+        order.sort_unstable();
+        assert_eq!(order, (0..mesh.faces.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_vertex_cache_optimization_lowers_acmr() {
+        let mut mesh = sphere(24);
+        // Scatter the faces so that the input order has little locality.
+        let n = mesh.faces.len();
+        mesh.faces = (0..n).map(|i| mesh.faces[i * 7919 % n]).collect();
+        let options = GltfOptions {
+            custom_attributes: vec![(
+                "_Z".to_string(),
+                mesh.vertices.iter().map(|v| v.z).collect(),
+            )],
+            ..GltfOptions::default()
+        };
+        let (optimized, optimized_options) = optimize_vertex_cache(&mesh, &options);
+        assert!(acmr(&optimized.faces) < 0.8);
+        assert!(acmr(&optimized.faces) < acmr(&mesh.faces) / 2.0);
+
+        // Same triangles and attributes, renumbered.
+        let key = |mesh: &SurfaceMesh, f: [usize; 3]| {
+            let mut corners = f.map(|v| {
+                let p = mesh.vertices[v];
+                [p.x, p.y, p.z].map(f64::to_bits)
+            });
+            corners.sort();
+            corners
+        };
+        let mut before: Vec<_> = mesh.faces.iter().map(|&f| key(&mesh, f)).collect();
+        let mut after: Vec<_> = optimized
+            .faces
+            .iter()
+            .map(|&f| key(&optimized, f))
+            .collect();
+        before.sort();
+        after.sort();
+        assert_eq!(before, after);
+        let values = &optimized_options.custom_attributes[0].1;
+        for (v, &z) in optimized.vertices.iter().zip(values) {
+            assert_eq!(v.z, z);
+        }
+        // Vertices are numbered by first use.
+        let mut next = 0;
+        for &v in optimized.faces.iter().flatten() {
+            assert!(v <= next);
+            next = next.max(v + 1);
+        }
+    }
+
+    #[test]
+    fn test_compact_indices() {
+        let mesh = sphere(4);
+        let options = GltfOptions {
+            compact_indices: true,
+            ..GltfOptions::default()
+        };
+        let glb = surface_mesh_to_gltf_with_options(&mesh, &options, GltfFormat::Glb);
+        let json = glb_json(&glb);
+        assert!(json.contains(&format!(
+            "{{\"bufferView\":1,\"componentType\":5123,\"count\":{},\"type\":\"SCALAR\"}}",
+            mesh.faces.len() * 3
+        )));
+        let glb = faces_to_glb_quantized(&mesh.to_faces());
+        assert!(glb_json(&glb).contains("\"bufferView\":1,\"componentType\":5125"));
+    }
+
+    #[test]
+    fn test_compressed_glb_structure() {
+        let mesh = sphere(16);
+        let options = GltfOptions {
+            normals: GltfNormals::Smooth,
+            quantized: true,
+            compact_indices: true,
+            meshopt_compression: true,
+            ..GltfOptions::default()
+        };
+        let glb = surface_mesh_to_gltf_with_options(&mesh, &options, GltfFormat::Glb);
+        let total = u32::from_le_bytes(glb[8..12].try_into().unwrap());
+        assert_eq!(total as usize, glb.len());
+        assert_eq!(glb.len() % 4, 0);
+        let json = glb_json(&glb);
+        assert!(json.contains(concat!(
+            "\"extensionsRequired\":[\"KHR_mesh_quantization\",",
+            "\"EXT_meshopt_compression\"]"
+        )));
+        // Quantized positions are padded to 8 bytes for the vertex codec.
+        let count = mesh.vertices.len();
+        assert!(json.contains(&format!(
+            concat!(
+                "{{\"buffer\":1,\"byteOffset\":0,\"byteLength\":{},\"byteStride\":8,",
+                "\"target\":34962,\"extensions\":{{\"EXT_meshopt_compression\":",
+                "{{\"buffer\":0,\"byteOffset\":0,"
+            ),
+            count * 8
+        )));
+        assert!(json.contains(&format!(
+            "\"byteStride\":8,\"count\":{},\"mode\":\"ATTRIBUTES\"",
+            count
+        )));
+        assert!(json.contains(&format!(
+            "\"byteStride\":2,\"count\":{},\"mode\":\"INDICES\"",
+            mesh.faces.len() * 3
+        )));
+        assert!(json.ends_with("{\"EXT_meshopt_compression\":{\"fallback\":true}}}]}"));
+    }
+
+    #[test]
+    fn test_compressed_json_round_trips_indices() {
+        let mesh = sphere(6);
+        let options = GltfOptions {
+            meshopt_compression: true,
+            ..GltfOptions::default()
+        };
+        let json = String::from_utf8(surface_mesh_to_gltf_with_options(
+            &mesh,
+            &options,
+            GltfFormat::Json,
+        ))
+        .unwrap();
+        assert!(json.contains("\"mode\":\"INDICES\""));
+        assert!(json.contains("\"uri\":\"data:application/octet-stream;base64,"));
+        assert!(json.ends_with("{\"fallback\":true}}}]}"));
+
+        // Encode the same arrays directly and check they decode back.
+        let positions: Vec<u8> = mesh
+            .vertices
+            .iter()
+            .flat_map(|v| [v.x as f32, v.y as f32, v.z as f32])
+            .flat_map(f32::to_le_bytes)
+            .collect();
+        let encoded = encode_vertex_buffer(&positions, 12);
+        assert_eq!(
+            decode_vertex_buffer(&encoded, mesh.vertices.len(), 12),
+            positions
+        );
+        let indices: Vec<u32> = mesh.faces.iter().flatten().map(|&i| i as u32).collect();
+        let encoded = encode_index_sequence(&indices);
+        assert_eq!(decode_index_sequence(&encoded, indices.len()), indices);
+    }
+
+    #[test]
+    fn test_compressed_glb_is_smaller() {
+        let faces = sphere(40).to_faces();
+        let quantized = faces_to_glb_quantized(&faces);
+        let compressed = faces_to_glb_compressed(&faces);
+        assert!(compressed.len() * 2 < quantized.len());
+    }
+
+    #[test]
+    fn test_streamed_compressed_glb_matches_wrapper() {
+        let faces = sphere(3).to_faces();
+        let mut out = Vec::new();
+        write_faces_glb_compressed(&mut out, &faces).unwrap();
+        assert_eq!(out, faces_to_glb_compressed(&faces));
+        assert_eq!(&faces_to_glb_compressed(&[])[0..4], b"glTF");
+    }
+}
@@ -1,12 +1,17 @@
-use crate::export::stl::extract_surface_faces;
-use crate::{Face, Tetrahedron};
+use std::io::{self, Write};
+
+use super::gltf::{write_gltf, Array, GltfFormat, GltfOptions, Layout};
+use super::to_bytes;
+use crate::{Face, SurfaceMesh, TetMesh, Tetrahedron};
 
 /// Exports 3D faces to a quantized GLB (binary glTF) format using
 /// the `KHR_mesh_quantization` extension.
 ///
 /// Vertex positions are quantized from `f32` to `i16`, reducing position
 /// data size by 50%. A node transformation matrix is applied to decode
-/// the quantized coordinates back to world space.
+/// the quantized coordinates back to world space. To add normals, colours
+/// or a material, set [`GltfOptions::quantized`] and use
+/// [`faces_to_gltf_with_options`](super::faces_to_gltf_with_options).
 ///
 /// # Arguments
 ///
@@ -16,6 +21,10 @@ use crate::{Face, Tetrahedron};
 ///
 /// A `Vec<u8>` containing the complete quantized GLB file.
 ///
+/// # Panics
+///
+/// Panics if the file would exceed 4 GiB.
+///
 /// # Examples
 ///
 /// ```
@@ -31,112 +40,86 @@ use crate::{Face, Tetrahedron};
 /// assert_eq!(&glb[0..4], b"glTF");
 /// ```
 pub fn faces_to_glb_quantized(faces: &[Face]) -> Vec<u8> {
-    let (vertices, indices) = collect_unique_vertices(faces);
-
-    let num_vertices = vertices.len();
-    let num_indices = indices.len();
-
-    if num_vertices == 0 {
-        return build_glb(&build_quantized_json(0, 0, 0, 0, [0.0; 3], [1.0; 3]), &[]);
-    }
-
-    // Compute bounding box
-    let (bb_min, bb_max) = bounding_box(&vertices);
-
-    // Compute scale and offset for quantization
-    let scale = [
-        if bb_max[0] > bb_min[0] {
-            bb_max[0] - bb_min[0]
-        } else {
-            1.0
-        },
-        if bb_max[1] > bb_min[1] {
-            bb_max[1] - bb_min[1]
-        } else {
-            1.0
-        },
-        if bb_max[2] > bb_min[2] {
-            bb_max[2] - bb_min[2]
-        } else {
-            1.0
-        },
-    ];
-    let offset = bb_min;
-
-    // Quantize positions to i16 range [-32767, 32767]
-    let mut quantized: Vec<i16> = Vec::with_capacity(num_vertices * 3);
-    for v in &vertices {
-        for i in 0..3 {
-            let normalized = (v[i] - offset[i]) / scale[i]; // [0, 1]
-            let q = (normalized * 65534.0 - 32767.0).round() as i16; // [-32767, 32767]
-            quantized.push(q);
-        }
-    }
-
-    // Build binary buffer: quantized positions (i16) + indices (u32)
-    let pos_byte_length = quantized.len() * 2;
-    // Pad position data to 4-byte boundary for index alignment
-    let pos_padded = (pos_byte_length + 3) & !3;
-    let idx_byte_length = num_indices * 4;
+    to_bytes(|w| write_faces_glb_quantized(w, faces))
+}
 
-    let mut buffer = Vec::with_capacity(pos_padded + idx_byte_length);
-    for &val in &quantized {
-        buffer.extend_from_slice(&val.to_le_bytes());
-    }
-    // Pad to 4-byte alignment
-    buffer.extend(std::iter::repeat_n(0u8, pos_padded - pos_byte_length));
-    for &val in &indices {
-        buffer.extend_from_slice(&val.to_le_bytes());
-    }
+/// Exports an indexed surface mesh to a quantized GLB using the
+/// `KHR_mesh_quantization` extension.
+pub fn surface_mesh_to_glb_quantized(mesh: &SurfaceMesh) -> Vec<u8> {
+    to_bytes(|w| write_surface_mesh_glb_quantized(w, mesh))
+}
 
-    let json = build_quantized_json(
-        num_vertices,
-        num_indices,
-        pos_padded,
-        idx_byte_length,
-        offset,
-        scale,
-    );
+/// Writes 3D faces as a quantized GLB.
+///
+/// # Errors
+///
+/// Same as [`write_surface_mesh_glb_quantized`].
+pub fn write_faces_glb_quantized<W: Write>(writer: &mut W, faces: &[Face]) -> io::Result<()> {
+    write_surface_mesh_glb_quantized(writer, &SurfaceMesh::from_faces(faces))
+}
 
-    build_glb(&json, &buffer)
+/// Writes an indexed surface mesh as a quantized GLB using the
+/// `KHR_mesh_quantization` extension, quantizing positions as they are
+/// written.
+///
+/// # Errors
+///
+/// Returns any error from `writer`, and [`io::ErrorKind::InvalidInput`] if
+/// the mesh has more than `u32::MAX` vertices or the file would exceed
+/// 4 GiB.
+pub fn write_surface_mesh_glb_quantized<W: Write>(
+    writer: &mut W,
+    mesh: &SurfaceMesh,
+) -> io::Result<()> {
+    let options = GltfOptions {
+        quantized: true,
+        ..GltfOptions::default()
+    };
+    write_gltf(writer, mesh, &options, GltfFormat::Glb)
 }
 
 /// Exports a tetrahedral mesh to quantized GLB by extracting surface faces.
 pub fn tetrahedra_to_glb_quantized(tetrahedra: &[Tetrahedron]) -> Vec<u8> {
-    let surface = extract_surface_faces(tetrahedra);
-    faces_to_glb_quantized(&surface)
+    to_bytes(|w| write_tetrahedra_glb_quantized(w, tetrahedra))
 }
 
-fn collect_unique_vertices(faces: &[Face]) -> (Vec<[f32; 3]>, Vec<u32>) {
-    let mut vertex_list: Vec<(i64, [f32; 3])> = Vec::new();
-    for face in faces {
-        for v in face.vertices() {
-            if !vertex_list.iter().any(|(idx, _)| *idx == v.index) {
-                vertex_list.push((v.index, [v.x as f32, v.y as f32, v.z as f32]));
-            }
-        }
-    }
-    vertex_list.sort_by_key(|(idx, _)| *idx);
-
-    let mut indices = Vec::with_capacity(faces.len() * 3);
-    for face in faces {
-        for pt in [face.a, face.b, face.c] {
-            let pos = vertex_list
-                .iter()
-                .position(|(idx, _)| *idx == pt.index)
-                .unwrap();
-            indices.push(pos as u32);
-        }
-    }
+/// Exports the boundary surface of an indexed tetrahedral mesh to quantized GLB.
+pub fn tet_mesh_to_glb_quantized(mesh: &TetMesh) -> Vec<u8> {
+    to_bytes(|w| write_tet_mesh_glb_quantized(w, mesh))
+}
 
-    let vertices: Vec<[f32; 3]> = vertex_list.into_iter().map(|(_, v)| v).collect();
-    (vertices, indices)
+/// Writes the boundary surface of a tetrahedral mesh as a quantized GLB.
+///
+/// # Errors
+///
+/// Same as [`write_surface_mesh_glb_quantized`].
+pub fn write_tetrahedra_glb_quantized<W: Write>(
+    writer: &mut W,
+    tetrahedra: &[Tetrahedron],
+) -> io::Result<()> {
+    write_tet_mesh_glb_quantized(writer, &TetMesh::from_tetrahedra(tetrahedra))
 }
 
-fn bounding_box(vertices: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+/// Writes the boundary surface of an indexed tetrahedral mesh as a
+/// quantized GLB.
+///
+/// # Errors
+///
+/// Same as [`write_surface_mesh_glb_quantized`].
+pub fn write_tet_mesh_glb_quantized<W: Write>(writer: &mut W, mesh: &TetMesh) -> io::Result<()> {
+    write_surface_mesh_glb_quantized(writer, &mesh.surface())
+}
+
+/// Bounding box offset and extent used to quantize positions, with unit
+/// extents along flat axes.
+fn quantization_box(mesh: &SurfaceMesh) -> ([f32; 3], [f32; 3]) {
+    if mesh.vertices.is_empty() {
+        return ([0.0; 3], [1.0; 3]);
+    }
     let mut min = [f32::MAX; 3];
     let mut max = [f32::MIN; 3];
-    for v in vertices {
+    for v in &mesh.vertices {
+        let v = [v.x as f32, v.y as f32, v.z as f32];
         for i in 0..3 {
             if v[i] < min[i] {
                 min[i] = v[i];
@@ -146,17 +129,21 @@ fn bounding_box(vertices: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
             }
         }
     }
-    (min, max)
+    let scale = [0, 1, 2].map(|i| {
+        if max[i] > min[i] {
+            max[i] - min[i]
+        } else {
+            1.0
+        }
+    });
+    (min, scale)
 }
 
-fn build_quantized_json(
-    num_vertices: usize,
-    num_indices: usize,
-    pos_byte_length: usize,
-    idx_byte_length: usize,
-    offset: [f32; 3],
-    scale: [f32; 3],
-) -> String {
+/// `i16` positions of the vertices written for `layout`, and the node
+/// matrix decoding them back to world coordinates.
+pub(super) fn quantized_positions(layout: Layout) -> (Array, [f32; 16]) {
+    let (offset, scale) = quantization_box(layout.mesh);
+
     // The node matrix transforms quantized i16 [-32767, 32767] back to world coords:
     // world = (quantized + 32767) / 65534 * scale + offset
     // As a 4x4 column-major matrix:
@@ -164,84 +151,59 @@ fn build_quantized_json(
     //    0 sy  0  0
     //    0  0 sz  0
     //   tx ty tz  1
-    let sx = scale[0] / 65534.0;
-    let sy = scale[1] / 65534.0;
-    let sz = scale[2] / 65534.0;
-    let tx = offset[0] + scale[0] * 32767.0 / 65534.0;
-    let ty = offset[1] + scale[1] * 32767.0 / 65534.0;
-    let tz = offset[2] + scale[2] * 32767.0 / 65534.0;
-
-    let buffer_byte_length = pos_byte_length + idx_byte_length;
-
-    format!(
-        concat!(
-            "{{",
-            "\"asset\":{{\"version\":\"2.0\",\"generator\":\"meshing\"}},",
-            "\"extensionsUsed\":[\"KHR_mesh_quantization\"],",
-            "\"extensionsRequired\":[\"KHR_mesh_quantization\"],",
-            "\"scene\":0,",
-            "\"scenes\":[{{\"nodes\":[0]}}],",
-            "\"nodes\":[{{\"mesh\":0,\"matrix\":[{},0,0,0,0,{},0,0,0,0,{},0,{},{},{},1]}}],",
-            "\"meshes\":[{{\"primitives\":[{{\"attributes\":{{\"POSITION\":0}},\"indices\":1}}]}}],",
-            "\"accessors\":[",
-            "{{\"bufferView\":0,\"componentType\":5122,\"count\":{},\"type\":\"VEC3\",\"max\":[32767,32767,32767],\"min\":[-32767,-32767,-32767]}},",
-            "{{\"bufferView\":1,\"componentType\":5125,\"count\":{},\"type\":\"SCALAR\"}}",
-            "],",
-            "\"bufferViews\":[",
-            "{{\"buffer\":0,\"byteOffset\":0,\"byteLength\":{},\"target\":34962}},",
-            "{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\"target\":34963}}",
-            "],",
-            "\"buffers\":[{{\"byteLength\":{}}}]",
-            "}}"
-        ),
-        sx, sy, sz, tx, ty, tz,
-        num_vertices,
-        num_indices,
-        pos_byte_length,
-        pos_byte_length, idx_byte_length,
-        buffer_byte_length,
-    )
-}
-
-fn build_glb(json_str: &str, bin_buffer: &[u8]) -> Vec<u8> {
-    let json_bytes = json_str.as_bytes();
-    let json_padded_len = (json_bytes.len() + 3) & !3;
-    let bin_padded_len = (bin_buffer.len() + 3) & !3;
+    let [sx, sy, sz] = scale.map(|s| s / 65534.0);
+    let [tx, ty, tz] = [0, 1, 2].map(|i| offset[i] + scale[i] * 32767.0 / 65534.0);
+    let matrix = [
+        sx, 0.0, 0.0, 0.0, 0.0, sy, 0.0, 0.0, 0.0, 0.0, sz, 0.0, tx, ty, tz, 1.0,
+    ];
 
-    let total_length = 12
-        + 8
-        + json_padded_len
-        + if bin_buffer.is_empty() {
-            0
-        } else {
-            8 + bin_padded_len
-        };
+    let positions = Array {
+        attribute: Some("POSITION".to_string()),
+        component_type: 5122,
+        normalized: false,
+        kind: "VEC3",
+        count: layout.num_vertices(),
+        element_size: 6,
+        padded: false,
+        bounds: Some("\"max\":[32767,32767,32767],\"min\":[-32767,-32767,-32767]".to_string()),
+        write: Box::new(move |w| {
+            let mesh = layout.mesh;
+            for v in layout.sources() {
+                let v = mesh.vertices[v];
+                let coords = [v.x as f32, v.y as f32, v.z as f32];
+                let mut record = [0u8; 6];
+                for i in 0..3 {
+                    let normalized = (coords[i] - offset[i]) / scale[i]; // [0, 1]
+                    let q = (normalized * 65534.0 - 32767.0).round() as i16; // [-32767, 32767]
+                    record[2 * i..2 * i + 2].copy_from_slice(&q.to_le_bytes());
+                }
+                w.write_all(&record)?;
+            }
+            Ok(())
+        }),
+    };
+    (positions, matrix)
+}
 
-    let mut glb = Vec::with_capacity(total_length);
-
-    // GLB Header
-    glb.extend_from_slice(b"glTF");
-    glb.extend_from_slice(&2u32.to_le_bytes());
-    glb.extend_from_slice(&(total_length as u32).to_le_bytes());
-
-    // JSON chunk
-    glb.extend_from_slice(&(json_padded_len as u32).to_le_bytes());
-    glb.extend_from_slice(&0x4E4F534Au32.to_le_bytes());
-    glb.extend_from_slice(json_bytes);
-    glb.extend(std::iter::repeat_n(
-        b' ',
-        json_padded_len - json_bytes.len(),
-    ));
-
-    // Binary chunk (only if there's data)
-    if !bin_buffer.is_empty() {
-        glb.extend_from_slice(&(bin_padded_len as u32).to_le_bytes());
-        glb.extend_from_slice(&0x004E4942u32.to_le_bytes());
-        glb.extend_from_slice(bin_buffer);
-        glb.extend(std::iter::repeat_n(0u8, bin_padded_len - bin_buffer.len()));
+/// Unit normals as normalized `i8`, padded to 4 bytes per vertex.
+pub(super) fn quantized_normals<'a>(normals: Vec<[f64; 3]>) -> Array<'a> {
+    Array {
+        attribute: Some("NORMAL".to_string()),
+        component_type: 5120,
+        normalized: true,
+        kind: "VEC3",
+        count: normals.len(),
+        element_size: 4,
+        padded: true,
+        bounds: None,
+        write: Box::new(move |w| {
+            for n in &normals {
+                let [x, y, z] = n.map(|c| (c * 127.0).round() as i8 as u8);
+                w.write_all(&[x, y, z, 0])?;
+            }
+            Ok(())
+        }),
     }
-
-    glb
 }
 
 #[cfg(test)]
@@ -557,4 +519,15 @@ mod tests {
             u32::from_le_bytes([quantized[8], quantized[9], quantized[10], quantized[11]]);
         assert_eq!(q_total as usize, quantized.len());
     }
+
+    #[test]
+    fn test_streamed_quantized_glb_matches_wrapper() {
+        let mut out = Vec::new();
+        write_faces_glb_quantized(&mut out, &[test_face()]).unwrap();
+        assert_eq!(out, faces_to_glb_quantized(&[test_face()]));
+
+        let mut empty = Vec::new();
+        write_faces_glb_quantized(&mut empty, &[]).unwrap();
+        assert_eq!(empty, faces_to_glb_quantized(&[]));
+    }
 }
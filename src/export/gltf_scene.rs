@@ -0,0 +1,245 @@
+use std::io::{self, Write};
+
+use super::gltf::{
+    build_mesh, validate, write_document, Document, DocumentMesh, GltfFormat, GltfOptions,
+    GltfTransform,
+};
+use super::gltf_meshopt::optimize_vertex_cache;
+use super::{invalid, to_bytes};
+use crate::SurfaceMesh;
+
+/// A named surface mesh placed in a glTF scene by [`write_gltf_scene`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfNode<'a> {
+    /// Name of the node and its mesh, shown in viewers and editors.
+    pub name: String,
+    pub mesh: &'a SurfaceMesh,
+    /// Attributes, material and quantization of this mesh.
+    pub options: GltfOptions,
+    pub transform: GltfTransform,
+}
+
+impl<'a> GltfNode<'a> {
+    /// A node showing `mesh` in place with the default options.
+    pub fn new(name: impl Into<String>, mesh: &'a SurfaceMesh) -> GltfNode<'a> {
+        GltfNode {
+            name: name.into(),
+            mesh,
+            options: GltfOptions::default(),
+            transform: GltfTransform::default(),
+        }
+    }
+}
+
+fn validate_node(node: &GltfNode) -> io::Result<()> {
+    validate(node.mesh, &node.options)
+        .map_err(|err| invalid(format!("node '{}': {}", node.name, err)))?;
+    let transform = &node.transform;
+    let mut values = transform
+        .translation
+        .iter()
+        .chain(&transform.rotation)
+        .chain(&transform.scale);
+    if !values.all(|v| v.is_finite()) {
+        return Err(invalid(format!(
+            "node '{}': transform is not finite",
+            node.name
+        )));
+    }
+    if transform.rotation.iter().all(|&c| c == 0.0) {
+        return Err(invalid(format!(
+            "node '{}': rotation is a zero quaternion",
+            node.name
+        )));
+    }
+    Ok(())
+}
+
+/// Writes several surface meshes as one glTF 2.0 scene, with a named node
+/// per mesh and all vertex data in a single buffer.
+///
+/// Each node has its own transform, attributes and material, given by
+/// [`GltfNode::options`]. A quantized mesh with a transform is placed by a
+/// child node holding its dequantization matrix. Rotations are normalized.
+///
+/// # Errors
+///
+/// Returns any error from `writer`, and [`io::ErrorKind::InvalidInput`] if
+/// the options of a node are invalid (see [`write_gltf`](super::write_gltf)),
+/// a transform is not finite or its rotation is zero, or a GLB would exceed
+/// 4 GiB.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::{write_gltf_scene, GltfFormat, GltfMaterial, GltfNode};
+/// use meshing::{Face, Point3D, SurfaceMesh};
+///
+/// let p = |index, x, y, z| Point3D { index, x, y, z };
+/// let mesh = SurfaceMesh::from_faces(&[Face {
+///     a: p(0, 0.0, 0.0, 0.0),
+///     b: p(1, 1.0, 0.0, 0.0),
+///     c: p(2, 0.0, 1.0, 0.0),
+/// }]);
+/// let mut copy = GltfNode::new("copy", &mesh);
+/// copy.transform.translation = [2.0, 0.0, 0.0];
+/// copy.options.material = Some(GltfMaterial {
+///     base_color: [1.0, 0.0, 0.0, 1.0],
+///     ..GltfMaterial::default()
+/// });
+/// let mut glb = Vec::new();
+/// write_gltf_scene(&mut glb, &[GltfNode::new("original", &mesh), copy], GltfFormat::Glb)
+///     .unwrap();
+/// assert_eq!(&glb[0..4], b"glTF");
+/// ```
+pub fn write_gltf_scene<W: Write>(
+    writer: &mut W,
+    nodes: &[GltfNode],
+    format: GltfFormat,
+) -> io::Result<()> {
+    for node in nodes {
+        validate_node(node)?;
+    }
+    let optimized: Vec<_> = nodes
+        .iter()
+        .map(|node| {
+            node.options
+                .optimize_vertex_cache
+                .then(|| optimize_vertex_cache(node.mesh, &node.options))
+        })
+        .collect();
+    let document = Document {
+        meshes: nodes
+            .iter()
+            .zip(&optimized)
+            .map(|(node, optimized)| {
+                let (mesh, options) = match optimized {
+                    Some((mesh, options)) => (mesh, options),
+                    None => (node.mesh, &node.options),
+                };
+                DocumentMesh {
+                    name: Some(node.name.clone()),
+                    transform: node.transform,
+                    ..build_mesh(mesh, options)
+                }
+            })
+            .collect(),
+    };
+    write_document(writer, &document, format)
+}
+
+/// Exports several surface meshes as one glTF 2.0 scene.
+///
+/// # Panics
+///
+/// Panics if a node is invalid, see [`write_gltf_scene`].
+pub fn scene_to_gltf(nodes: &[GltfNode], format: GltfFormat) -> Vec<u8> {
+    to_bytes(|w| write_gltf_scene(w, nodes, format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{surface_mesh_to_glb, GltfMaterial};
+    use crate::{Face, Point3D};
+
+    fn triangle(offset: f64) -> SurfaceMesh {
+        let p = |index, x: f64, y| Point3D {
+            index,
+            x: x + offset,
+            y,
+            z: 0.0,
+        };
+        SurfaceMesh::from_faces(&[Face {
+            a: p(0, 0.0, 0.0),
+            b: p(1, 1.0, 0.0),
+            c: p(2, 0.0, 1.0),
+        }])
+    }
+
+    fn glb_json(glb: &[u8]) -> &str {
+        let json_len = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+        std::str::from_utf8(&glb[20..20 + json_len]).unwrap()
+    }
+
+    #[test]
+    fn test_meshes_share_one_buffer() {
+        let (first, second) = (triangle(0.0), triangle(5.0));
+        let glb = scene_to_gltf(
+            &[GltfNode::new("a", &first), GltfNode::new("b", &second)],
+            GltfFormat::Glb,
+        );
+        let json = glb_json(&glb);
+        assert_eq!(json.matches("\"buffer\":0").count(), 4);
+        assert!(!json.contains("\"buffer\":1"));
+        assert!(json.contains("\"scenes\":[{\"nodes\":[0,1]}]"));
+        assert!(json.contains(
+            "{\"name\":\"b\",\"primitives\":[{\"attributes\":{\"POSITION\":2},\"indices\":3}]}"
+        ));
+        assert!(json.contains("\"byteOffset\":48,\"byteLength\":36"));
+
+        // The BIN chunk is the two single-mesh buffers back to back.
+        let bin_len = glb.len() - 28 - json.len();
+        assert_eq!(bin_len, 96);
+        let single = surface_mesh_to_glb(&second);
+        assert_eq!(&glb[glb.len() - 48..], &single[single.len() - 48..]);
+    }
+
+    #[test]
+    fn test_named_nodes_with_transforms_and_materials() {
+        let mesh = triangle(0.0);
+        let mut moved = GltfNode::new("moved \"copy\"", &mesh);
+        moved.transform = GltfTransform {
+            translation: [1.0, 2.0, 3.0],
+            rotation: [0.0, 0.0, 2.0, 0.0],
+            scale: [2.0; 3],
+        };
+        moved.options.material = Some(GltfMaterial {
+            roughness: 1.0,
+            ..GltfMaterial::default()
+        });
+        let mut plain = GltfNode::new("plain", &mesh);
+        plain.options.material = Some(GltfMaterial::default());
+        let glb = scene_to_gltf(&[moved, plain], GltfFormat::Glb);
+        let json = glb_json(&glb);
+        assert!(json.contains(concat!(
+            "\"nodes\":[{\"name\":\"moved \\\"copy\\\"\",\"translation\":[1,2,3],",
+            "\"rotation\":[0,0,1,0],\"scale\":[2,2,2],\"mesh\":0},",
+            "{\"name\":\"plain\",\"mesh\":1}]"
+        )));
+        assert!(json.contains("\"indices\":1,\"material\":0"));
+        assert!(json.contains("\"indices\":3,\"material\":1"));
+        assert!(json.contains("\"roughnessFactor\":1}"));
+    }
+
+    #[test]
+    fn test_quantized_node_with_transform_has_child() {
+        let mesh = triangle(0.0);
+        let mut quantized = GltfNode::new("q", &mesh);
+        quantized.options.quantized = true;
+        quantized.transform.scale = [0.5; 3];
+        let mut in_place = quantized.clone();
+        in_place.transform = GltfTransform::default();
+        let glb = scene_to_gltf(&[quantized, in_place], GltfFormat::Glb);
+        let json = glb_json(&glb);
+        assert!(json.contains("{\"name\":\"q\",\"scale\":[0.5,0.5,0.5],\"children\":[2]}"));
+        assert!(json.contains("{\"name\":\"q\",\"mesh\":1,\"matrix\":["));
+        assert!(json.contains("{\"mesh\":0,\"matrix\":["));
+        assert!(json.contains("\"scenes\":[{\"nodes\":[0,1]}]"));
+    }
+
+    #[test]
+    fn test_invalid_nodes_are_rejected() {
+        let mesh = triangle(0.0);
+        let mut not_finite = GltfNode::new("a", &mesh);
+        not_finite.transform.translation[1] = f64::NAN;
+        let mut zero_rotation = GltfNode::new("b", &mesh);
+        zero_rotation.transform.rotation = [0.0; 4];
+        let mut bad_options = GltfNode::new("c", &mesh);
+        bad_options.options.custom_attributes = vec![("NAME".to_string(), vec![0.0; 3])];
+        for node in [not_finite, zero_rotation, bad_options] {
+            let err = write_gltf_scene(&mut Vec::new(), &[node], GltfFormat::Json).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
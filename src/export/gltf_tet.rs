@@ -0,0 +1,451 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+use super::gltf::{
+    positions, write_document, write_gltf, Array, Document, DocumentMesh, GltfColors, GltfFormat,
+    GltfMaterial, GltfNormals, GltfOptions, Layout,
+};
+use super::{invalid, to_bytes};
+use crate::{Point3D, SurfaceMesh, TetMesh, Tetrahedron};
+
+/// How the glTF tetrahedral mesh exporters show the cells.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GltfTetView {
+    /// The boundary surface, as [`tet_mesh_to_gltf`](super::tet_mesh_to_gltf).
+    #[default]
+    Surface,
+    /// Every edge of every tetrahedron as a `LINES` primitive, showing the
+    /// interior elements.
+    Wireframe,
+    /// Every tetrahedron as its own four triangles, scaled about its
+    /// centroid by `shrink` in `(0, 1]` so that the cells separate.
+    Exploded { shrink: f64 },
+}
+
+/// View, colours and material of the glTF tetrahedral mesh exporters.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GltfTetOptions {
+    pub view: GltfTetView,
+    /// One value per tetrahedron, e.g. from
+    /// [`GltfColors::from_quality`] or [`GltfColors::from_regions`].
+    /// Requires [`GltfTetView::Exploded`].
+    pub cell_colors: Option<GltfColors>,
+    pub material: Option<GltfMaterial>,
+    /// Store positions as `i16` with the `KHR_mesh_quantization` extension.
+    pub quantized: bool,
+}
+
+fn validate(mesh: &TetMesh, options: &GltfTetOptions) -> io::Result<()> {
+    if let GltfTetView::Exploded { shrink } = options.view {
+        if !(shrink > 0.0 && shrink <= 1.0) {
+            return Err(invalid(format!(
+                "shrink factor {} is not in (0, 1]",
+                shrink
+            )));
+        }
+    }
+    if let Some(colors) = &options.cell_colors {
+        if !matches!(options.view, GltfTetView::Exploded { .. }) {
+            return Err(invalid(
+                "cell colours require the exploded view".to_string(),
+            ));
+        }
+        if colors.values.len() != mesh.num_tetrahedra() {
+            return Err(invalid(format!(
+                "{} cell values given for {} tetrahedra",
+                colors.values.len(),
+                mesh.num_tetrahedra()
+            )));
+        }
+    }
+    if mesh.num_vertices() > u32::MAX as usize {
+        return Err(invalid(
+            "glTF indices are limited to u32::MAX vertices".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// The distinct edges of the tetrahedra, in order of first appearance.
+fn unique_edges(mesh: &TetMesh) -> Vec<[usize; 2]> {
+    const EDGES: [[usize; 2]; 6] = [[0, 1], [0, 2], [0, 3], [1, 2], [1, 3], [2, 3]];
+    let mut seen = HashSet::new();
+    let mut edges = Vec::new();
+    for tet in &mesh.tetrahedra {
+        for [i, j] in EDGES {
+            let edge = [tet[i].min(tet[j]), tet[i].max(tet[j])];
+            if seen.insert(edge) {
+                edges.push(edge);
+            }
+        }
+    }
+    edges
+}
+
+fn write_wireframe<W: Write>(
+    writer: &mut W,
+    mesh: &TetMesh,
+    options: &GltfTetOptions,
+    format: GltfFormat,
+) -> io::Result<()> {
+    let points = SurfaceMesh {
+        vertices: mesh.vertices.clone(),
+        faces: Vec::new(),
+    };
+    let layout = Layout {
+        mesh: &points,
+        flat: false,
+    };
+    let (positions, matrix) = positions(layout, options.quantized);
+    let edges = unique_edges(mesh);
+    let indices = Array {
+        attribute: None,
+        component_type: 5125,
+        normalized: false,
+        kind: "SCALAR",
+        count: edges.len() * 2,
+        element_size: 4,
+        padded: false,
+        bounds: None,
+        write: Box::new(|w| {
+            for &index in edges.iter().flatten() {
+                w.write_all(&(index as u32).to_le_bytes())?;
+            }
+            Ok(())
+        }),
+    };
+    let document = Document {
+        meshes: vec![DocumentMesh {
+            mode: Some(1),
+            material: options.material,
+            ..DocumentMesh::new(vec![positions, indices], matrix)
+        }],
+    };
+    write_document(writer, &document, format)
+}
+
+/// Four vertices per tetrahedron, scaled about its centroid, and its faces
+/// wound outwards.
+fn exploded_mesh(mesh: &TetMesh, shrink: f64) -> SurfaceMesh {
+    // Local faces of a tetrahedron and the vertex opposite each.
+    const FACES: [([usize; 3], usize); 4] = [
+        ([0, 1, 2], 3),
+        ([0, 1, 3], 2),
+        ([0, 2, 3], 1),
+        ([1, 2, 3], 0),
+    ];
+    let mut exploded = SurfaceMesh {
+        vertices: Vec::with_capacity(mesh.num_tetrahedra() * 4),
+        faces: Vec::with_capacity(mesh.num_tetrahedra() * 4),
+    };
+    for t in 0..mesh.num_tetrahedra() {
+        let corners = mesh.tetrahedron(t).vertices();
+        let centroid = [
+            corners.iter().map(|v| v.x).sum::<f64>() / 4.0,
+            corners.iter().map(|v| v.y).sum::<f64>() / 4.0,
+            corners.iter().map(|v| v.z).sum::<f64>() / 4.0,
+        ];
+        let first = exploded.vertices.len();
+        for v in corners {
+            exploded.vertices.push(Point3D {
+                index: exploded.vertices.len() as i64,
+                x: centroid[0] + (v.x - centroid[0]) * shrink,
+                y: centroid[1] + (v.y - centroid[1]) * shrink,
+                z: centroid[2] + (v.z - centroid[2]) * shrink,
+            });
+        }
+        let inverted = Tetrahedron {
+            a: corners[0],
+            b: corners[1],
+            c: corners[2],
+            d: corners[3],
+        }
+        .signed_volume()
+            < 0.0;
+        for ([a, b, c], opposite) in FACES {
+            // Face abc of a positively oriented tetrahedron faces d, so
+            // flip it unless the orientation and opposite vertex cancel.
+            let flip = (opposite % 2 == 1) != inverted;
+            let face = if flip { [a, c, b] } else { [a, b, c] };
+            exploded.faces.push(face.map(|i| first + i));
+        }
+    }
+    exploded
+}
+
+/// Writes an indexed tetrahedral mesh as glTF 2.0 in the view chosen in
+/// `options`.
+///
+/// The exploded view writes flat normals, and the cell colours as
+/// `COLOR_0`. Vertex data is generated as it is written; see
+/// [`write_gltf`] for the buffer layout.
+///
+/// # Errors
+///
+/// Returns any error from `writer`, and [`io::ErrorKind::InvalidInput`] if
+/// the shrink factor is not in `(0, 1]`, cell colours are given for another
+/// view than [`GltfTetView::Exploded`] or do not have one value per
+/// tetrahedron, or the mesh is too large for glTF.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::{
+///     write_tet_mesh_gltf_with_options, Colormap, GltfColors, GltfFormat, GltfTetOptions,
+///     GltfTetView,
+/// };
+/// use meshing::{Point3D, TetMesh, Tetrahedron};
+///
+/// let p = |index, x, y, z| Point3D { index, x, y, z };
+/// let mesh = TetMesh::from_tetrahedra(&[Tetrahedron {
+///     a: p(0, 0.0, 0.0, 0.0),
+///     b: p(1, 1.0, 0.0, 0.0),
+///     c: p(2, 0.0, 1.0, 0.0),
+///     d: p(3, 0.0, 0.0, 1.0),
+/// }]);
+/// let options = GltfTetOptions {
+///     view: GltfTetView::Exploded { shrink: 0.8 },
+///     cell_colors: Some(GltfColors::from_quality(&mesh, Colormap::Viridis)),
+///     ..GltfTetOptions::default()
+/// };
+/// let mut gltf = Vec::new();
+/// write_tet_mesh_gltf_with_options(&mut gltf, &mesh, &options, GltfFormat::Json).unwrap();
+/// assert!(String::from_utf8(gltf).unwrap().contains("\"COLOR_0\""));
+/// ```
+pub fn write_tet_mesh_gltf_with_options<W: Write>(
+    writer: &mut W,
+    mesh: &TetMesh,
+    options: &GltfTetOptions,
+    format: GltfFormat,
+) -> io::Result<()> {
+    validate(mesh, options)?;
+    let surface_options = |normals, colors| GltfOptions {
+        normals,
+        colors,
+        material: options.material,
+        quantized: options.quantized,
+        ..GltfOptions::default()
+    };
+    match options.view {
+        GltfTetView::Surface => write_gltf(
+            writer,
+            &mesh.surface(),
+            &surface_options(GltfNormals::None, None),
+            format,
+        ),
+        GltfTetView::Wireframe => write_wireframe(writer, mesh, options, format),
+        GltfTetView::Exploded { shrink } => {
+            let colors = options.cell_colors.as_ref().map(|cells| GltfColors {
+                values: cells.values.iter().flat_map(|&v| [v; 4]).collect(),
+                ..cells.clone()
+            });
+            write_gltf(
+                writer,
+                &exploded_mesh(mesh, shrink),
+                &surface_options(GltfNormals::Flat, colors),
+                format,
+            )
+        }
+    }
+}
+
+/// Exports an indexed tetrahedral mesh to glTF 2.0 in the view chosen in
+/// `options`.
+///
+/// # Panics
+///
+/// Panics if the options are invalid, see
+/// [`write_tet_mesh_gltf_with_options`].
+pub fn tet_mesh_to_gltf_with_options(
+    mesh: &TetMesh,
+    options: &GltfTetOptions,
+    format: GltfFormat,
+) -> Vec<u8> {
+    to_bytes(|w| write_tet_mesh_gltf_with_options(w, mesh, options, format))
+}
+
+/// Exports tetrahedra to glTF 2.0 in the view chosen in `options`, with
+/// cells in the order of `tetrahedra`.
+///
+/// # Panics
+///
+/// Panics if the options are invalid, see
+/// [`write_tet_mesh_gltf_with_options`].
+pub fn tetrahedra_to_gltf_with_options(
+    tetrahedra: &[Tetrahedron],
+    options: &GltfTetOptions,
+    format: GltfFormat,
+) -> Vec<u8> {
+    tet_mesh_to_gltf_with_options(&TetMesh::from_tetrahedra(tetrahedra), options, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::Colormap;
+    use crate::voxel_mesh::voxel_mesh_indexed;
+
+    fn p(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    fn cube() -> TetMesh {
+        voxel_mesh_indexed(p(0, 0.0, 0.0, 0.0), p(0, 1.0, 1.0, 1.0), 1, 1, 1, &|_| true)
+    }
+
+    fn glb_json(glb: &[u8]) -> &str {
+        let json_len = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+        std::str::from_utf8(&glb[20..20 + json_len]).unwrap()
+    }
+
+    #[test]
+    fn test_radius_ratio() {
+        let s = 1.0 / 2f64.sqrt();
+        let regular = Tetrahedron {
+            a: p(0, 1.0, 0.0, -s),
+            b: p(1, -1.0, 0.0, -s),
+            c: p(2, 0.0, 1.0, s),
+            d: p(3, 0.0, -1.0, s),
+        };
+        assert!((regular.radius_ratio() - 1.0).abs() < 1e-12);
+        let flat = Tetrahedron {
+            d: p(3, 0.0, 1.0, s),
+            ..regular
+        };
+        assert_eq!(flat.radius_ratio(), 0.0);
+        let sliver = Tetrahedron {
+            d: p(3, 0.0, -1.0, 0.9 * s),
+            ..regular
+        };
+        assert!(sliver.radius_ratio() < regular.radius_ratio());
+    }
+
+    #[test]
+    fn test_wireframe_lists_each_edge_once() {
+        let mesh = cube();
+        let edges = unique_edges(&mesh);
+        let distinct: HashSet<_> = edges.iter().collect();
+        assert_eq!(distinct.len(), edges.len());
+        // Every tetrahedron edge is in the list.
+        for tet in &mesh.tetrahedra {
+            for i in 0..4 {
+                for j in i + 1..4 {
+                    assert!(distinct.contains(&[tet[i].min(tet[j]), tet[i].max(tet[j])]));
+                }
+            }
+        }
+
+        let options = GltfTetOptions {
+            view: GltfTetView::Wireframe,
+            ..GltfTetOptions::default()
+        };
+        let glb = tet_mesh_to_gltf_with_options(&mesh, &options, GltfFormat::Glb);
+        let json = glb_json(&glb);
+        assert!(json.contains("\"indices\":1,\"mode\":1"));
+        assert!(json.contains(&format!("\"count\":{}", edges.len() * 2)));
+        let bin_len = glb.len() - 28 - json.len();
+        assert_eq!(bin_len, mesh.num_vertices() * 12 + edges.len() * 8);
+    }
+
+    #[test]
+    fn test_exploded_cells_shrink_and_face_outwards() {
+        let mesh = cube();
+        let exploded = exploded_mesh(&mesh, 0.5);
+        assert_eq!(exploded.vertices.len(), 4 * mesh.num_tetrahedra());
+        for t in 0..mesh.num_tetrahedra() {
+            let original = mesh.tetrahedron(t);
+            let corners: Vec<Point3D> = (0..4).map(|k| exploded.vertices[4 * t + k]).collect();
+            let shrunk = Tetrahedron {
+                a: corners[0],
+                b: corners[1],
+                c: corners[2],
+                d: corners[3],
+            };
+            let ratio = shrunk.signed_volume() / original.signed_volume();
+            assert!((ratio - 0.125).abs() < 1e-12);
+            for face in &exploded.faces[4 * t..4 * t + 4] {
+                let opposite = (0..4).map(|k| 4 * t + k).find(|v| !face.contains(v));
+                let [a, b, c] = face.map(|v| exploded.vertices[v]);
+                let d = exploded.vertices[opposite.unwrap()];
+                // The opposite vertex lies behind every face.
+                assert!(Tetrahedron { a, b, c, d }.signed_volume() < 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_exploded_cell_colors() {
+        let mesh = cube();
+        let regions: Vec<i32> = (0..mesh.num_tetrahedra() as i32).collect();
+        let options = GltfTetOptions {
+            view: GltfTetView::Exploded { shrink: 0.9 },
+            cell_colors: Some(GltfColors::from_regions(&regions, Colormap::Grayscale)),
+            ..GltfTetOptions::default()
+        };
+        let glb = tet_mesh_to_gltf_with_options(&mesh, &options, GltfFormat::Glb);
+        let json = glb_json(&glb);
+        let vertices = 12 * mesh.num_tetrahedra();
+        assert!(json.contains("\"attributes\":{\"POSITION\":0,\"NORMAL\":2,\"COLOR_0\":3}"));
+        // Positions, indices, normals and colours of three vertices per face.
+        let bin_len = glb.len() - 28 - json.len();
+        assert_eq!(bin_len, vertices * (12 + 4 + 12 + 12));
+        let bin = &glb[glb.len() - bin_len..];
+        let color = |v: usize| {
+            let at = vertices * 28 + v * 12;
+            f32::from_le_bytes(bin[at..at + 4].try_into().unwrap())
+        };
+        // First cell black, last cell white.
+        assert_eq!(color(0), 0.0);
+        assert_eq!(color(vertices - 1), 1.0);
+
+        let quality = GltfColors::from_quality(&mesh, Colormap::Viridis);
+        // Four corner tetrahedra around a regular one.
+        let corner = 3f64.sqrt() - 1.0;
+        assert!(quality.values[..4]
+            .iter()
+            .all(|q| (q - corner).abs() < 1e-12));
+        assert!((quality.values[4] - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_invalid_options_are_rejected() {
+        let mesh = cube();
+        let colors = Some(GltfColors::from_quality(&mesh, Colormap::Viridis));
+        let cases = [
+            GltfTetOptions {
+                view: GltfTetView::Exploded { shrink: 0.0 },
+                ..GltfTetOptions::default()
+            },
+            GltfTetOptions {
+                view: GltfTetView::Exploded { shrink: f64::NAN },
+                ..GltfTetOptions::default()
+            },
+            GltfTetOptions {
+                view: GltfTetView::Wireframe,
+                cell_colors: colors.clone(),
+                ..GltfTetOptions::default()
+            },
+            GltfTetOptions {
+                view: GltfTetView::Exploded { shrink: 0.5 },
+                cell_colors: Some(GltfColors::from_regions(&[1, 2], Colormap::Viridis)),
+                ..GltfTetOptions::default()
+            },
+        ];
+        for options in &cases {
+            let err =
+                write_tet_mesh_gltf_with_options(&mut Vec::new(), &mesh, options, GltfFormat::Glb)
+                    .unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn test_surface_view_matches_plain_export() {
+        let mesh = cube();
+        let options = GltfTetOptions::default();
+        assert_eq!(
+            tet_mesh_to_gltf_with_options(&mesh, &options, GltfFormat::Glb),
+            super::super::tet_mesh_to_glb(&mesh)
+        );
+    }
+}
@@ -0,0 +1,490 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+use super::{extract_surface_faces, invalid, to_string};
+use crate::{Point3D, TetMesh, Tetrahedron};
+
+/// Generated set holding every node.
+const ALL_NODES: &str = "NALL";
+/// Generated set holding every element.
+const ALL_ELEMENTS: &str = "EALL";
+/// Generated node set and surface of the boundary.
+const BOUNDARY: &str = "BOUNDARY";
+
+/// Abaqus face labels of the sides of [`Tetrahedron::faces`], in that order
+/// (`abc`, `abd`, `acd`, `bcd`).
+const SIDES: [usize; 4] = [1, 2, 4, 3];
+
+/// Mid-side nodes of each side of [`Tetrahedron::faces`], as positions in
+/// [`InpMesh::midside_nodes`].
+const SIDE_MIDSIDE_NODES: [[usize; 3]; 4] = [[0, 1, 2], [0, 3, 4], [2, 3, 5], [1, 4, 5]];
+
+/// Corner pairs of the C3D10 mid-side nodes 5 to 10.
+const EDGES: [[usize; 2]; 6] = [[0, 1], [1, 2], [0, 2], [0, 3], [1, 3], [2, 3]];
+
+/// Maximum number of entries on one set data line.
+const ENTRIES_PER_LINE: usize = 16;
+
+/// A tetrahedral mesh for an Abaqus or CalculiX input deck.
+///
+/// Elements and sets index into `nodes` and `tetrahedra`. When
+/// `midside_nodes` is non-empty it holds the six mid-side nodes of each
+/// tetrahedron, in C3D10 order (edges 1-2, 2-3, 3-1, 1-4, 2-4, 3-4), and
+/// the elements are written as C3D10 instead of C3D4.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InpMesh {
+    pub nodes: Vec<Point3D>,
+    pub tetrahedra: Vec<[usize; 4]>,
+    pub midside_nodes: Vec<[usize; 6]>,
+    /// Named node sets, as positions in `nodes`.
+    pub node_sets: Vec<(String, Vec<usize>)>,
+    /// Named element sets, as positions in `tetrahedra`.
+    pub element_sets: Vec<(String, Vec<usize>)>,
+}
+
+impl InpMesh {
+    /// Builds a linear mesh from an indexed tetrahedral mesh, without named
+    /// sets.
+    pub fn from_tet_mesh(mesh: &TetMesh) -> InpMesh {
+        InpMesh {
+            nodes: mesh.vertices.clone(),
+            tetrahedra: mesh.tetrahedra.clone(),
+            ..InpMesh::default()
+        }
+    }
+
+    /// Builds a linear mesh from tetrahedra, with nodes numbered as in
+    /// [`TetMesh::from_tetrahedra`] and without named sets.
+    pub fn from_tetrahedra(tetrahedra: &[Tetrahedron]) -> InpMesh {
+        InpMesh::from_tet_mesh(&TetMesh::from_tetrahedra(tetrahedra))
+    }
+
+    /// Makes the mesh quadratic by adding a node at the midpoint of every
+    /// edge, shared between the tetrahedra around it.
+    ///
+    /// New nodes are appended to `nodes` with [`Point3D::index`] values
+    /// following the largest existing one. Does nothing if the mesh already
+    /// has mid-side nodes.
+    pub fn add_midside_nodes(&mut self) {
+        if !self.midside_nodes.is_empty() {
+            return;
+        }
+        let mut next_index = self.nodes.iter().map(|v| v.index + 1).max().unwrap_or(0);
+        let mut midpoints: HashMap<[usize; 2], usize> = HashMap::new();
+        for tet in &self.tetrahedra {
+            let mut midside = [0; 6];
+            for (slot, [i, j]) in midside.iter_mut().zip(EDGES) {
+                let (a, b) = (tet[i].min(tet[j]), tet[i].max(tet[j]));
+                *slot = *midpoints.entry([a, b]).or_insert_with(|| {
+                    let (p, q) = (self.nodes[a], self.nodes[b]);
+                    self.nodes.push(Point3D {
+                        index: next_index,
+                        x: (p.x + q.x) / 2.0,
+                        y: (p.y + q.y) / 2.0,
+                        z: (p.z + q.z) / 2.0,
+                    });
+                    next_index += 1;
+                    self.nodes.len() - 1
+                });
+            }
+            self.midside_nodes.push(midside);
+        }
+    }
+}
+
+fn validate(mesh: &InpMesh) -> io::Result<()> {
+    let n = mesh.nodes.len();
+    if !mesh.midside_nodes.is_empty() && mesh.midside_nodes.len() != mesh.tetrahedra.len() {
+        return Err(invalid(format!(
+            "{} mid-side node lists given for {} tetrahedra",
+            mesh.midside_nodes.len(),
+            mesh.tetrahedra.len()
+        )));
+    }
+    let elements = mesh.tetrahedra.iter().flatten();
+    if let Some(i) = elements
+        .chain(mesh.midside_nodes.iter().flatten())
+        .find(|&&i| i >= n)
+    {
+        return Err(invalid(format!(
+            "element refers to node {} but there are {} nodes",
+            i, n
+        )));
+    }
+    let generated = [ALL_NODES, ALL_ELEMENTS, BOUNDARY];
+    let mut names = HashSet::new();
+    let sets = mesh.node_sets.iter().map(|set| (set, n, "node"));
+    for ((name, members), count, kind) in sets.chain(
+        mesh.element_sets
+            .iter()
+            .map(|set| (set, mesh.tetrahedra.len(), "element")),
+    ) {
+        let valid = name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && name.len() <= 80;
+        if !valid {
+            return Err(invalid(format!(
+                "invalid set name '{}': use up to 80 letters, digits and underscores, \
+                 starting with a letter",
+                name
+            )));
+        }
+        let upper = name.to_ascii_uppercase();
+        if generated.contains(&upper.as_str())
+            || upper.starts_with("BOUNDARY_S")
+            || !names.insert(upper)
+        {
+            return Err(invalid(format!("set name '{}' is already in use", name)));
+        }
+        if let Some(i) = members.iter().find(|&&i| i >= count) {
+            return Err(invalid(format!(
+                "{} set '{}' refers to {} {} but there are {}",
+                kind, name, kind, i, count
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Writes 1-based ids, [`ENTRIES_PER_LINE`] to a line.
+fn write_ids<W: Write>(writer: &mut W, ids: &[usize]) -> io::Result<()> {
+    for chunk in ids.chunks(ENTRIES_PER_LINE) {
+        let line: Vec<String> = chunk.iter().map(|i| (i + 1).to_string()).collect();
+        writeln!(writer, "{}", line.join(", "))?;
+    }
+    Ok(())
+}
+
+/// Writes an [`InpMesh`] as an Abaqus/CalculiX input deck.
+///
+/// Nodes and elements are numbered from 1 in the order of the mesh and
+/// written with `*NODE` and `*ELEMENT, TYPE=C3D4` (or `C3D10`), in the
+/// generated sets `NALL` and `EALL`. The boundary faces found with
+/// [`extract_surface_faces`] give the node set `BOUNDARY` and the
+/// element-based surface `BOUNDARY`, built from one element set
+/// `BOUNDARY_S1` to `BOUNDARY_S4` per face label. The named sets of the
+/// mesh follow as `*NSET` and `*ELSET` blocks.
+///
+/// # Errors
+///
+/// Returns any error from `writer`, and [`io::ErrorKind::InvalidInput`] if
+/// an element or set refers to a missing node or element, there is not one
+/// mid-side node list per tetrahedron, or a set name is not a valid Abaqus
+/// name or clashes with another set.
+pub fn write_inp<W: Write>(writer: &mut W, mesh: &InpMesh) -> io::Result<()> {
+    validate(mesh)?;
+    let quadratic = !mesh.midside_nodes.is_empty();
+
+    writeln!(writer, "*HEADING")?;
+    writeln!(writer, "Tetrahedral mesh exported by meshing")?;
+    writeln!(writer, "*NODE, NSET={}", ALL_NODES)?;
+    for (i, v) in mesh.nodes.iter().enumerate() {
+        writeln!(writer, "{}, {:?}, {:?}, {:?}", i + 1, v.x, v.y, v.z)?;
+    }
+    if !mesh.tetrahedra.is_empty() {
+        let element_type = if quadratic { "C3D10" } else { "C3D4" };
+        writeln!(
+            writer,
+            "*ELEMENT, TYPE={}, ELSET={}",
+            element_type, ALL_ELEMENTS
+        )?;
+        for (i, tet) in mesh.tetrahedra.iter().enumerate() {
+            let midside = mesh.midside_nodes.get(i).map_or(&[][..], |m| &m[..]);
+            let ids: Vec<String> = std::iter::once(i)
+                .chain(tet.iter().copied())
+                .chain(midside.iter().copied())
+                .map(|id| (id + 1).to_string())
+                .collect();
+            writeln!(writer, "{}", ids.join(", "))?;
+        }
+    }
+
+    // Match the boundary faces back to element sides by node position.
+    let tetrahedra: Vec<Tetrahedron> = mesh
+        .tetrahedra
+        .iter()
+        .map(|tet| {
+            let [a, b, c, d] = tet.map(|i| Point3D {
+                index: i as i64,
+                ..mesh.nodes[i]
+            });
+            Tetrahedron { a, b, c, d }
+        })
+        .collect();
+    let key = |indices: [i64; 3]| {
+        let mut key = indices;
+        key.sort_unstable();
+        key
+    };
+    let boundary: HashSet<[i64; 3]> = extract_surface_faces(&tetrahedra)
+        .iter()
+        .map(|f| key(f.vertices().map(|v| v.index)))
+        .collect();
+    let mut side_elements: [Vec<usize>; 4] = Default::default();
+    let mut boundary_nodes = Vec::new();
+    for (i, tet) in tetrahedra.iter().enumerate() {
+        for (side, face) in tet.faces().iter().enumerate() {
+            if boundary.contains(&key(face.vertices().map(|v| v.index))) {
+                side_elements[SIDES[side] - 1].push(i);
+                boundary_nodes.extend(face.vertices().map(|v| v.index as usize));
+                if let Some(midside) = mesh.midside_nodes.get(i) {
+                    boundary_nodes.extend(SIDE_MIDSIDE_NODES[side].map(|k| midside[k]));
+                }
+            }
+        }
+    }
+    boundary_nodes.sort_unstable();
+    boundary_nodes.dedup();
+
+    if !boundary_nodes.is_empty() {
+        writeln!(writer, "*NSET, NSET={}", BOUNDARY)?;
+        write_ids(writer, &boundary_nodes)?;
+        for (side, elements) in side_elements.iter().enumerate() {
+            if !elements.is_empty() {
+                writeln!(writer, "*ELSET, ELSET={}_S{}", BOUNDARY, side + 1)?;
+                write_ids(writer, elements)?;
+            }
+        }
+        writeln!(writer, "*SURFACE, NAME={}, TYPE=ELEMENT", BOUNDARY)?;
+        for (side, elements) in side_elements.iter().enumerate() {
+            if !elements.is_empty() {
+                writeln!(writer, "{}_S{}, S{}", BOUNDARY, side + 1, side + 1)?;
+            }
+        }
+    }
+
+    for (name, nodes) in &mesh.node_sets {
+        writeln!(writer, "*NSET, NSET={}", name)?;
+        write_ids(writer, nodes)?;
+    }
+    for (name, elements) in &mesh.element_sets {
+        writeln!(writer, "*ELSET, ELSET={}", name)?;
+        write_ids(writer, elements)?;
+    }
+    Ok(())
+}
+
+/// Exports an [`InpMesh`] to an Abaqus/CalculiX input deck.
+///
+/// # Panics
+///
+/// Panics if the mesh is invalid, see [`write_inp`].
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::{mesh_to_inp, InpMesh};
+/// use meshing::{Point3D, Tetrahedron};
+///
+/// let p = |index, x, y, z| Point3D { index, x, y, z };
+/// let tet = Tetrahedron {
+///     a: p(0, 0.0, 0.0, 0.0),
+///     b: p(1, 1.0, 0.0, 0.0),
+///     c: p(2, 0.0, 1.0, 0.0),
+///     d: p(3, 0.0, 0.0, 1.0),
+/// };
+/// let mut mesh = InpMesh::from_tetrahedra(&[tet]);
+/// mesh.add_midside_nodes();
+/// mesh.node_sets.push(("FIXED".to_string(), vec![0, 1, 2]));
+/// let inp = mesh_to_inp(&mesh);
+/// assert!(inp.contains("*ELEMENT, TYPE=C3D10, ELSET=EALL\n1, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10\n"));
+/// assert!(inp.contains("*NSET, NSET=FIXED\n1, 2, 3\n"));
+/// ```
+pub fn mesh_to_inp(mesh: &InpMesh) -> String {
+    to_string(|w| write_inp(w, mesh))
+}
+
+/// Exports tetrahedra to an Abaqus/CalculiX input deck of C3D4 elements,
+/// with boundary sets and the given named sets.
+///
+/// Node sets list [`Point3D::index`] values and element sets positions in
+/// `tetrahedra`.
+///
+/// # Panics
+///
+/// Panics if a node set lists an index that no tetrahedron uses, or a set
+/// is otherwise invalid, see [`write_inp`].
+pub fn tetrahedra_to_inp(
+    tetrahedra: &[Tetrahedron],
+    node_sets: &[(String, Vec<i64>)],
+    element_sets: &[(String, Vec<usize>)],
+) -> String {
+    let mut mesh = InpMesh::from_tetrahedra(tetrahedra);
+    let lookup: HashMap<i64, usize> = mesh
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(pos, v)| (v.index, pos))
+        .collect();
+    mesh.node_sets = node_sets
+        .iter()
+        .map(|(name, indices)| {
+            let nodes = indices
+                .iter()
+                .map(|index| match lookup.get(index) {
+                    Some(&pos) => pos,
+                    None => panic!("node set '{}' refers to unknown point {}", name, index),
+                })
+                .collect();
+            (name.clone(), nodes)
+        })
+        .collect();
+    mesh.element_sets = element_sets.to_vec();
+    mesh_to_inp(&mesh)
+}
+
+/// Exports an indexed tetrahedral mesh to an Abaqus/CalculiX input deck of
+/// C3D4 elements with boundary sets.
+pub fn tet_mesh_to_inp(mesh: &TetMesh) -> String {
+    mesh_to_inp(&InpMesh::from_tet_mesh(mesh))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel_mesh::voxel_mesh_indexed;
+
+    fn p(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    fn single_tet() -> Tetrahedron {
+        Tetrahedron {
+            a: p(20, 0.0, 0.0, 0.0),
+            b: p(21, 1.0, 0.0, 0.0),
+            c: p(22, 0.0, 1.0, 0.0),
+            d: p(23, 0.0, 0.0, 1.0),
+        }
+    }
+
+    /// The data lines of the block starting with `keyword`.
+    fn block<'a>(inp: &'a str, keyword: &str) -> Vec<&'a str> {
+        inp.lines()
+            .skip_while(|line| *line != keyword)
+            .skip(1)
+            .take_while(|line| !line.starts_with('*'))
+            .collect()
+    }
+
+    #[test]
+    fn test_single_tetrahedron() {
+        let inp = tetrahedra_to_inp(
+            &[single_tet()],
+            &[("APEX".to_string(), vec![23])],
+            &[("SOLID".to_string(), vec![0])],
+        );
+        let expected = "*HEADING\nTetrahedral mesh exported by meshing\n\
+                        *NODE, NSET=NALL\n\
+                        1, 0.0, 0.0, 0.0\n2, 1.0, 0.0, 0.0\n3, 0.0, 1.0, 0.0\n4, 0.0, 0.0, 1.0\n\
+                        *ELEMENT, TYPE=C3D4, ELSET=EALL\n1, 1, 2, 3, 4\n\
+                        *NSET, NSET=BOUNDARY\n1, 2, 3, 4\n\
+                        *ELSET, ELSET=BOUNDARY_S1\n1\n*ELSET, ELSET=BOUNDARY_S2\n1\n\
+                        *ELSET, ELSET=BOUNDARY_S3\n1\n*ELSET, ELSET=BOUNDARY_S4\n1\n\
+                        *SURFACE, NAME=BOUNDARY, TYPE=ELEMENT\n\
+                        BOUNDARY_S1, S1\nBOUNDARY_S2, S2\nBOUNDARY_S3, S3\nBOUNDARY_S4, S4\n\
+                        *NSET, NSET=APEX\n4\n*ELSET, ELSET=SOLID\n1\n";
+        assert_eq!(inp, expected);
+    }
+
+    #[test]
+    fn test_surface_excludes_interior_faces() {
+        let tets = voxel_mesh_indexed(p(0, 0.0, 0.0, 0.0), p(0, 2.0, 2.0, 2.0), 2, 2, 2, &|_| true);
+        let inp = tet_mesh_to_inp(&tets);
+        let surface = block(&inp, "*SURFACE, NAME=BOUNDARY, TYPE=ELEMENT");
+        let faces: usize = surface
+            .iter()
+            .map(|line| {
+                let set = line.split(',').next().unwrap();
+                block(&inp, &format!("*ELSET, ELSET={}", set))
+                    .iter()
+                    .map(|line| line.split(", ").count())
+                    .sum::<usize>()
+            })
+            .sum();
+        assert_eq!(faces, tets.surface().num_faces());
+        let boundary: usize = block(&inp, "*NSET, NSET=BOUNDARY")
+            .iter()
+            .map(|line| line.split(", ").count())
+            .sum();
+        let surface_nodes: HashSet<usize> = tets.surface().faces.into_iter().flatten().collect();
+        assert_eq!(boundary, surface_nodes.len());
+        assert!(block(&inp, "*NSET, NSET=BOUNDARY")
+            .iter()
+            .all(|line| line.split(", ").count() <= ENTRIES_PER_LINE));
+    }
+
+    #[test]
+    fn test_quadratic_nodes_are_shared() {
+        let tets = voxel_mesh_indexed(p(0, 0.0, 0.0, 0.0), p(0, 1.0, 1.0, 1.0), 1, 1, 1, &|_| true);
+        let mut mesh = InpMesh::from_tet_mesh(&tets);
+        mesh.add_midside_nodes();
+        let edges: HashSet<[usize; 2]> = mesh
+            .tetrahedra
+            .iter()
+            .flat_map(|t| EDGES.map(|[i, j]| [t[i].min(t[j]), t[i].max(t[j])]))
+            .collect();
+        assert_eq!(mesh.nodes.len(), tets.num_vertices() + edges.len());
+        for (tet, midside) in mesh.tetrahedra.iter().zip(&mesh.midside_nodes) {
+            for (&m, [i, j]) in midside.iter().zip(EDGES) {
+                let (a, b, mid) = (mesh.nodes[tet[i]], mesh.nodes[tet[j]], mesh.nodes[m]);
+                assert_eq!(mid.x, (a.x + b.x) / 2.0);
+                assert_eq!(mid.z, (a.z + b.z) / 2.0);
+            }
+        }
+
+        let inp = mesh_to_inp(&mesh);
+        let elements = block(&inp, "*ELEMENT, TYPE=C3D10, ELSET=EALL");
+        assert_eq!(elements.len(), mesh.tetrahedra.len());
+        assert!(elements.iter().all(|line| line.split(", ").count() == 11));
+        // Only the mid-side nodes of edges through the cube are interior.
+        let boundary: usize = block(&inp, "*NSET, NSET=BOUNDARY")
+            .iter()
+            .map(|line| line.split(", ").count())
+            .sum();
+        let interior_edges = edges
+            .iter()
+            .filter(|[a, b]| {
+                let (p, q) = (mesh.nodes[*a], mesh.nodes[*b]);
+                let mid = [(p.x + q.x) / 2.0, (p.y + q.y) / 2.0, (p.z + q.z) / 2.0];
+                mid.iter().all(|&c| c > 0.0 && c < 1.0)
+            })
+            .count();
+        assert_eq!(boundary, mesh.nodes.len() - interior_edges);
+    }
+
+    #[test]
+    fn test_invalid_sets_are_rejected() {
+        let base = InpMesh::from_tetrahedra(&[single_tet()]);
+        let cases = [
+            InpMesh {
+                node_sets: vec![("1ST".to_string(), vec![0])],
+                ..base.clone()
+            },
+            InpMesh {
+                node_sets: vec![("FIXED, GEN".to_string(), vec![0])],
+                ..base.clone()
+            },
+            InpMesh {
+                element_sets: vec![("boundary".to_string(), vec![0])],
+                ..base.clone()
+            },
+            InpMesh {
+                node_sets: vec![("LOAD".to_string(), vec![0])],
+                element_sets: vec![("load".to_string(), vec![0])],
+                ..base.clone()
+            },
+            InpMesh {
+                element_sets: vec![("SOLID".to_string(), vec![1])],
+                ..base.clone()
+            },
+            InpMesh {
+                midside_nodes: vec![[0; 6]; 2],
+                ..base.clone()
+            },
+        ];
+        for mesh in &cases {
+            let err = write_inp(&mut Vec::new(), mesh).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{:?}", mesh);
+        }
+    }
+}
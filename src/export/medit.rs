@@ -0,0 +1,402 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use super::{extract_surface_faces, invalid, to_bytes};
+use crate::{Face, Point3D, SurfaceMesh, TetMesh, Tetrahedron};
+
+/// Medit encoding used by the Medit exporters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MeditFormat {
+    /// `.mesh` keyword text.
+    #[default]
+    Ascii,
+    /// `.meshb` little-endian binary, version 2 (double coordinates,
+    /// 32-bit integers and positions).
+    Binary,
+}
+
+/// Binary keyword codes of the `.meshb` sections used here.
+pub(crate) const MESHB_DIMENSION: i32 = 3;
+pub(crate) const MESHB_VERTICES: i32 = 4;
+pub(crate) const MESHB_TRIANGLES: i32 = 6;
+pub(crate) const MESHB_TETRAHEDRA: i32 = 8;
+pub(crate) const MESHB_END: i32 = 54;
+
+/// A Medit mesh of vertices, triangles and tetrahedra with reference
+/// labels.
+///
+/// Elements index into `vertices`. Each `*_refs` vector holds the Medit
+/// reference of the matching entity (a boundary or region label) and may
+/// be left empty, in which case every reference is written as 0.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeditMesh {
+    pub vertices: Vec<Point3D>,
+    pub vertex_refs: Vec<i32>,
+    pub triangles: Vec<[usize; 3]>,
+    pub triangle_refs: Vec<i32>,
+    pub tetrahedra: Vec<[usize; 4]>,
+    pub tetrahedron_refs: Vec<i32>,
+}
+
+impl MeditMesh {
+    /// Builds a mesh holding `tetrahedra` and their boundary triangles (from
+    /// [`extract_surface_faces`]), with vertices numbered as in
+    /// [`TetMesh::from_tetrahedra`] and all references 0.
+    pub fn from_tetrahedra(tetrahedra: &[Tetrahedron]) -> MeditMesh {
+        let mesh = TetMesh::from_tetrahedra(tetrahedra);
+        let triangles = surface_indices(&mesh, &extract_surface_faces(tetrahedra));
+        MeditMesh {
+            vertices: mesh.vertices,
+            triangles,
+            tetrahedra: mesh.tetrahedra,
+            ..MeditMesh::default()
+        }
+    }
+
+    /// Builds a mesh holding the triangles of `mesh`, with all references 0.
+    pub fn from_surface_mesh(mesh: &SurfaceMesh) -> MeditMesh {
+        MeditMesh {
+            vertices: mesh.vertices.clone(),
+            triangles: mesh.faces.clone(),
+            ..MeditMesh::default()
+        }
+    }
+
+    /// Expands the tetrahedra back into [`Tetrahedron`]s.
+    pub fn to_tetrahedra(&self) -> Vec<Tetrahedron> {
+        self.tetrahedra
+            .iter()
+            .map(|&[a, b, c, d]| Tetrahedron {
+                a: self.vertices[a],
+                b: self.vertices[b],
+                c: self.vertices[c],
+                d: self.vertices[d],
+            })
+            .collect()
+    }
+
+    /// Expands the triangles back into [`Face`]s.
+    pub fn to_faces(&self) -> Vec<Face> {
+        self.triangles
+            .iter()
+            .map(|&[a, b, c]| Face {
+                a: self.vertices[a],
+                b: self.vertices[b],
+                c: self.vertices[c],
+            })
+            .collect()
+    }
+}
+
+/// Maps faces onto positions in `mesh.vertices` through their point
+/// indices.
+pub(crate) fn surface_indices(mesh: &TetMesh, faces: &[Face]) -> Vec<[usize; 3]> {
+    let lookup: HashMap<i64, usize> = mesh
+        .vertices
+        .iter()
+        .enumerate()
+        .map(|(pos, v)| (v.index, pos))
+        .collect();
+    faces
+        .iter()
+        .map(|f| f.vertices().map(|v| lookup[&v.index]))
+        .collect()
+}
+
+fn reference(refs: &[i32], i: usize) -> i32 {
+    refs.get(i).copied().unwrap_or(0)
+}
+
+fn validate(mesh: &MeditMesh) -> io::Result<()> {
+    let n = mesh.vertices.len();
+    for (kind, count, refs) in [
+        ("vertex", n, &mesh.vertex_refs),
+        ("triangle", mesh.triangles.len(), &mesh.triangle_refs),
+        ("tetrahedron", mesh.tetrahedra.len(), &mesh.tetrahedron_refs),
+    ] {
+        if !refs.is_empty() && refs.len() != count {
+            return Err(invalid(format!(
+                "{} {} references given for {} entities",
+                refs.len(),
+                kind,
+                count
+            )));
+        }
+    }
+    let indices = mesh.triangles.iter().flatten();
+    if let Some(i) = indices
+        .chain(mesh.tetrahedra.iter().flatten())
+        .find(|&&i| i >= n)
+    {
+        return Err(invalid(format!(
+            "element refers to vertex {} but there are {} vertices",
+            i, n
+        )));
+    }
+    if n > i32::MAX as usize
+        || mesh.triangles.len() > i32::MAX as usize
+        || mesh.tetrahedra.len() > i32::MAX as usize
+    {
+        return Err(invalid(
+            "Medit files are limited to i32::MAX entities".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn write_ascii<W: Write>(writer: &mut W, mesh: &MeditMesh) -> io::Result<()> {
+    writeln!(writer, "MeshVersionFormatted 2")?;
+    writeln!(writer)?;
+    writeln!(writer, "Dimension 3")?;
+    writeln!(writer)?;
+    writeln!(writer, "Vertices")?;
+    writeln!(writer, "{}", mesh.vertices.len())?;
+    for (i, v) in mesh.vertices.iter().enumerate() {
+        writeln!(
+            writer,
+            "{} {} {} {}",
+            v.x,
+            v.y,
+            v.z,
+            reference(&mesh.vertex_refs, i)
+        )?;
+    }
+    if !mesh.triangles.is_empty() {
+        writeln!(writer)?;
+        writeln!(writer, "Triangles")?;
+        writeln!(writer, "{}", mesh.triangles.len())?;
+        for (i, [a, b, c]) in mesh.triangles.iter().enumerate() {
+            let r = reference(&mesh.triangle_refs, i);
+            writeln!(writer, "{} {} {} {}", a + 1, b + 1, c + 1, r)?;
+        }
+    }
+    if !mesh.tetrahedra.is_empty() {
+        writeln!(writer)?;
+        writeln!(writer, "Tetrahedra")?;
+        writeln!(writer, "{}", mesh.tetrahedra.len())?;
+        for (i, [a, b, c, d]) in mesh.tetrahedra.iter().enumerate() {
+            let r = reference(&mesh.tetrahedron_refs, i);
+            writeln!(writer, "{} {} {} {} {}", a + 1, b + 1, c + 1, d + 1, r)?;
+        }
+    }
+    writeln!(writer)?;
+    writeln!(writer, "End")
+}
+
+fn write_binary<W: Write>(writer: &mut W, mesh: &MeditMesh) -> io::Result<()> {
+    // Every keyword stores the absolute offset of the next one, so compute
+    // the section sizes up front: code, next position and count or value
+    // are 4 bytes each.
+    let sections = [
+        (MESHB_DIMENSION, 12),
+        (MESHB_VERTICES, 12 + 28 * mesh.vertices.len() as u64),
+        (MESHB_TRIANGLES, 12 + 16 * mesh.triangles.len() as u64),
+        (MESHB_TETRAHEDRA, 12 + 20 * mesh.tetrahedra.len() as u64),
+    ];
+    let mut position = 8u64;
+    let mut next = Vec::with_capacity(sections.len());
+    for &(code, size) in &sections {
+        let skip = (code == MESHB_TRIANGLES && mesh.triangles.is_empty())
+            || (code == MESHB_TETRAHEDRA && mesh.tetrahedra.is_empty());
+        if !skip {
+            position += size;
+            next.push(
+                i32::try_from(position)
+                    .map_err(|_| invalid("binary Medit file would exceed 2 GiB".to_string()))?,
+            );
+        } else {
+            next.push(0);
+        }
+    }
+    let int = |w: &mut W, value: i32| w.write_all(&value.to_le_bytes());
+
+    int(writer, 1)?;
+    int(writer, 2)?;
+    int(writer, MESHB_DIMENSION)?;
+    int(writer, next[0])?;
+    int(writer, 3)?;
+
+    int(writer, MESHB_VERTICES)?;
+    int(writer, next[1])?;
+    int(writer, mesh.vertices.len() as i32)?;
+    for (i, v) in mesh.vertices.iter().enumerate() {
+        for c in [v.x, v.y, v.z] {
+            writer.write_all(&c.to_le_bytes())?;
+        }
+        int(writer, reference(&mesh.vertex_refs, i))?;
+    }
+    if !mesh.triangles.is_empty() {
+        int(writer, MESHB_TRIANGLES)?;
+        int(writer, next[2])?;
+        int(writer, mesh.triangles.len() as i32)?;
+        for (i, triangle) in mesh.triangles.iter().enumerate() {
+            for &v in triangle {
+                int(writer, v as i32 + 1)?;
+            }
+            int(writer, reference(&mesh.triangle_refs, i))?;
+        }
+    }
+    if !mesh.tetrahedra.is_empty() {
+        int(writer, MESHB_TETRAHEDRA)?;
+        int(writer, next[3])?;
+        int(writer, mesh.tetrahedra.len() as i32)?;
+        for (i, tet) in mesh.tetrahedra.iter().enumerate() {
+            for &v in tet {
+                int(writer, v as i32 + 1)?;
+            }
+            int(writer, reference(&mesh.tetrahedron_refs, i))?;
+        }
+    }
+    int(writer, MESHB_END)?;
+    int(writer, 0)
+}
+
+/// Writes a [`MeditMesh`] as Medit `.mesh` text or `.meshb` binary.
+///
+/// Vertices are numbered from 1 in the order of `mesh.vertices`; empty
+/// `Triangles` and `Tetrahedra` sections are omitted.
+///
+/// # Errors
+///
+/// Returns any error from `writer`, and [`io::ErrorKind::InvalidInput`] if a
+/// reference vector has the wrong length, an element refers to a missing
+/// vertex, or a count or binary offset does not fit in an `i32`.
+pub fn write_medit<W: Write>(
+    writer: &mut W,
+    mesh: &MeditMesh,
+    format: MeditFormat,
+) -> io::Result<()> {
+    validate(mesh)?;
+    match format {
+        MeditFormat::Ascii => write_ascii(writer, mesh),
+        MeditFormat::Binary => write_binary(writer, mesh),
+    }
+}
+
+/// Exports a [`MeditMesh`] to `.mesh` or `.meshb`.
+///
+/// # Panics
+///
+/// Panics if the mesh is invalid, see [`write_medit`].
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::{mesh_to_medit, MeditFormat, MeditMesh};
+/// use meshing::{Point3D, Tetrahedron};
+///
+/// let p = |index, x, y, z| Point3D { index, x, y, z };
+/// let tet = Tetrahedron {
+///     a: p(0, 0.0, 0.0, 0.0),
+///     b: p(1, 1.0, 0.0, 0.0),
+///     c: p(2, 0.0, 1.0, 0.0),
+///     d: p(3, 0.0, 0.0, 1.0),
+/// };
+/// let mut mesh = MeditMesh::from_tetrahedra(&[tet]);
+/// mesh.tetrahedron_refs = vec![2];
+/// let text = String::from_utf8(mesh_to_medit(&mesh, MeditFormat::Ascii)).unwrap();
+/// assert!(text.contains("Tetrahedra\n1\n1 2 3 4 2\n"));
+/// assert!(text.contains("Triangles\n4\n"));
+/// ```
+pub fn mesh_to_medit(mesh: &MeditMesh, format: MeditFormat) -> Vec<u8> {
+    to_bytes(|w| write_medit(w, mesh, format))
+}
+
+/// Exports tetrahedra and their boundary triangles to `.mesh` or `.meshb`.
+pub fn tetrahedra_to_medit(tetrahedra: &[Tetrahedron], format: MeditFormat) -> Vec<u8> {
+    mesh_to_medit(&MeditMesh::from_tetrahedra(tetrahedra), format)
+}
+
+/// Exports 3D faces to `.mesh` or `.meshb` as triangles, with vertices
+/// numbered as in [`SurfaceMesh::from_faces`].
+pub fn faces_to_medit(faces: &[Face], format: MeditFormat) -> Vec<u8> {
+    mesh_to_medit(
+        &MeditMesh::from_surface_mesh(&SurfaceMesh::from_faces(faces)),
+        format,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    fn single_tet() -> Tetrahedron {
+        Tetrahedron {
+            a: p(10, 0.0, 0.0, 0.0),
+            b: p(11, 1.0, 0.0, 0.0),
+            c: p(12, 0.0, 1.0, 0.0),
+            d: p(13, 0.0, 0.0, 1.0),
+        }
+    }
+
+    #[test]
+    fn test_tetrahedra_to_medit_ascii() {
+        let text =
+            String::from_utf8(tetrahedra_to_medit(&[single_tet()], MeditFormat::Ascii)).unwrap();
+        let expected = "MeshVersionFormatted 2\n\nDimension 3\n\n\
+                        Vertices\n4\n0 0 0 0\n1 0 0 0\n0 1 0 0\n0 0 1 0\n\n\
+                        Triangles\n4\n1 2 3 0\n1 2 4 0\n1 3 4 0\n2 3 4 0\n\n\
+                        Tetrahedra\n1\n1 2 3 4 0\n\nEnd\n";
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn test_binary_offsets() {
+        let bytes = tetrahedra_to_medit(&[single_tet()], MeditFormat::Binary);
+        let int = |at: usize| i32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        assert_eq!([int(0), int(4)], [1, 2]);
+        // Walk the keyword chain through the stored next positions.
+        let mut codes = Vec::new();
+        let mut at = 8;
+        loop {
+            codes.push(int(at));
+            let next = int(at + 4) as usize;
+            if next == 0 {
+                break;
+            }
+            at = next;
+        }
+        assert_eq!(
+            codes,
+            vec![
+                MESHB_DIMENSION,
+                MESHB_VERTICES,
+                MESHB_TRIANGLES,
+                MESHB_TETRAHEDRA,
+                MESHB_END
+            ]
+        );
+        assert_eq!(at + 8, bytes.len());
+    }
+
+    #[test]
+    fn test_faces_to_medit_has_no_tetrahedra() {
+        let faces = extract_surface_faces(&[single_tet()]);
+        let text = String::from_utf8(faces_to_medit(&faces, MeditFormat::Ascii)).unwrap();
+        assert!(text.contains("Triangles\n4\n"));
+        assert!(!text.contains("Tetrahedra"));
+    }
+
+    #[test]
+    fn test_invalid_meshes_are_rejected() {
+        let base = MeditMesh::from_tetrahedra(&[single_tet()]);
+        let cases = [
+            MeditMesh {
+                vertex_refs: vec![1],
+                ..base.clone()
+            },
+            MeditMesh {
+                triangles: vec![[0, 1, 4]],
+                ..base.clone()
+            },
+        ];
+        for mesh in &cases {
+            let err = write_medit(&mut Vec::new(), mesh, MeditFormat::Binary).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
@@ -1,11 +1,95 @@
+use std::io;
+
 mod gltf;
+mod gltf_meshopt;
 mod gltf_quantized;
+mod gltf_scene;
+mod gltf_tet;
+mod inp;
+pub(crate) mod medit;
+pub(crate) mod msh;
 mod obj;
+mod ply;
 pub(crate) mod stl;
+mod tetgen;
 mod vtk;
+mod vtu;
+
+pub use gltf::{
+    faces_to_glb, faces_to_gltf, faces_to_gltf_with_options, surface_mesh_to_glb,
+    surface_mesh_to_gltf, surface_mesh_to_gltf_with_options, tet_mesh_to_glb, tet_mesh_to_gltf,
+    tetrahedra_to_glb, tetrahedra_to_gltf, write_faces_glb, write_faces_gltf, write_gltf,
+    write_surface_mesh_glb, write_surface_mesh_gltf, write_tet_mesh_glb, write_tet_mesh_gltf,
+    write_tetrahedra_glb, write_tetrahedra_gltf, Colormap, GltfColors, GltfFormat, GltfMaterial,
+    GltfNormals, GltfOptions, GltfTransform,
+};
+pub use gltf_meshopt::{
+    faces_to_glb_compressed, surface_mesh_to_glb_compressed, tet_mesh_to_glb_compressed,
+    tetrahedra_to_glb_compressed, write_faces_glb_compressed, write_surface_mesh_glb_compressed,
+    write_tet_mesh_glb_compressed, write_tetrahedra_glb_compressed,
+};
+pub use gltf_quantized::{
+    faces_to_glb_quantized, surface_mesh_to_glb_quantized, tet_mesh_to_glb_quantized,
+    tetrahedra_to_glb_quantized, write_faces_glb_quantized, write_surface_mesh_glb_quantized,
+    write_tet_mesh_glb_quantized, write_tetrahedra_glb_quantized,
+};
+pub use gltf_scene::{scene_to_gltf, write_gltf_scene, GltfNode};
+pub use gltf_tet::{
+    tet_mesh_to_gltf_with_options, tetrahedra_to_gltf_with_options,
+    write_tet_mesh_gltf_with_options, GltfTetOptions, GltfTetView,
+};
+pub use inp::{mesh_to_inp, tet_mesh_to_inp, tetrahedra_to_inp, write_inp, InpMesh};
+pub use medit::{
+    faces_to_medit, mesh_to_medit, tetrahedra_to_medit, write_medit, MeditFormat, MeditMesh,
+};
+pub use msh::{
+    faces_to_msh, mesh_to_msh, surface_mesh_to_msh, tet_mesh_to_msh, tetrahedra_to_msh,
+    tri_mesh_2d_to_msh, triangles_to_msh, write_msh, MshFormat, MshMesh, MshPhysicalName,
+};
+pub use obj::{
+    faces_to_obj, surface_mesh_to_obj, tet_mesh_to_obj, tetrahedra_to_obj, tri_mesh_2d_to_obj,
+    triangles_to_obj, write_faces_obj, write_surface_mesh_obj, write_tet_mesh_obj,
+    write_tetrahedra_obj, write_tri_mesh_2d_obj, write_triangles_obj,
+};
+pub use ply::{
+    faces_to_ply, points_to_ply, surface_mesh_to_ply, surface_mesh_to_ply_with_attributes,
+    tet_mesh_to_ply, tetrahedra_to_ply, write_ply, PlyAttributes, PlyFormat,
+};
+pub use stl::{
+    extract_surface_faces, faces_to_stl, faces_to_stl_binary, surface_mesh_to_stl,
+    surface_mesh_to_stl_binary, tet_mesh_to_stl, tet_mesh_to_stl_binary, tetrahedra_to_stl,
+    tetrahedra_to_stl_binary, tri_mesh_2d_to_stl, tri_mesh_2d_to_stl_binary, triangles_to_stl,
+    triangles_to_stl_binary, write_faces_stl, write_surface_mesh_stl, write_tet_mesh_stl,
+    write_tetrahedra_stl, write_tri_mesh_2d_stl, write_triangles_stl, StlFormat,
+};
+pub use tetgen::{mesh_to_tetgen, tetrahedra_to_tetgen, TetGenFiles, TetGenMesh};
+pub use vtk::{tet_mesh_to_vtk, tetrahedra_to_vtk, write_tet_mesh_vtk, write_tetrahedra_vtk};
+pub use vtu::{
+    faces_to_vtu, surface_mesh_to_vtu, tet_mesh_to_vtu, tetrahedra_to_vtu, write_surface_mesh_vtu,
+    write_tet_mesh_vtu, VtuArray, VtuAttributes, VtuEncoding, VtuValues,
+};
+
+/// Wraps a message about input an exporter cannot write in an
+/// [`io::ErrorKind::InvalidInput`] error.
+pub(crate) fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// Runs a `write_*` exporter on an in-memory buffer.
+///
+/// # Panics
+///
+/// Writing to memory only fails when the exporter rejects its input, so
+/// this panics with that error.
+pub(crate) fn to_bytes(write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    if let Err(err) = write(&mut buffer) {
+        panic!("cannot export mesh: {}", err);
+    }
+    buffer
+}
 
-pub use gltf::{faces_to_glb, faces_to_gltf, tetrahedra_to_glb, tetrahedra_to_gltf};
-pub use gltf_quantized::{faces_to_glb_quantized, tetrahedra_to_glb_quantized};
-pub use obj::{faces_to_obj, tetrahedra_to_obj, triangles_to_obj};
-pub use stl::{extract_surface_faces, faces_to_stl, tetrahedra_to_stl, triangles_to_stl};
-pub use vtk::tetrahedra_to_vtk;
+/// [`to_bytes`] for text formats.
+pub(crate) fn to_string(write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> String {
+    String::from_utf8(to_bytes(write)).expect("text exporters write valid UTF-8")
+}
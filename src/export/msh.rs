@@ -0,0 +1,586 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use super::{invalid, to_bytes};
+use crate::{Face, Point3D, SurfaceMesh, TetMesh, Tetrahedron, TriMesh2D, Triangle};
+
+/// Gmsh MSH encoding used by the MSH exporters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MshFormat {
+    /// Text sections.
+    #[default]
+    Ascii,
+    /// Little-endian binary sections with 8-byte `size_t`.
+    Binary,
+}
+
+/// Gmsh element type of a 4-node tetrahedron.
+pub(crate) const MSH_TETRAHEDRON: i32 = 4;
+/// Gmsh element type of a 3-node triangle.
+pub(crate) const MSH_TRIANGLE: i32 = 2;
+
+/// A name given to a physical group in `$PhysicalNames`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MshPhysicalName {
+    /// 3 for volume groups, 2 for surface groups.
+    pub dimension: i32,
+    pub tag: i32,
+    pub name: String,
+}
+
+/// A Gmsh mesh of linear tetrahedra and triangles with physical groups.
+///
+/// Elements index into `nodes`. `tetrahedron_groups[i]` and
+/// `triangle_groups[i]` hold the physical tag of each element, with 0
+/// meaning "no physical group"; either vector may be left empty when no
+/// element has a group.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MshMesh {
+    pub nodes: Vec<Point3D>,
+    pub tetrahedra: Vec<[usize; 4]>,
+    pub tetrahedron_groups: Vec<i32>,
+    pub triangles: Vec<[usize; 3]>,
+    pub triangle_groups: Vec<i32>,
+    pub physical_names: Vec<MshPhysicalName>,
+}
+
+impl MshMesh {
+    /// Builds a mesh holding the tetrahedra of `mesh`, without groups.
+    pub fn from_tet_mesh(mesh: &TetMesh) -> MshMesh {
+        MshMesh {
+            nodes: mesh.vertices.clone(),
+            tetrahedra: mesh.tetrahedra.clone(),
+            ..MshMesh::default()
+        }
+    }
+
+    /// Builds a mesh holding the triangles of `mesh`, without groups.
+    pub fn from_surface_mesh(mesh: &SurfaceMesh) -> MshMesh {
+        MshMesh {
+            nodes: mesh.vertices.clone(),
+            triangles: mesh.faces.clone(),
+            ..MshMesh::default()
+        }
+    }
+
+    /// Expands the tetrahedra back into [`Tetrahedron`]s.
+    pub fn to_tetrahedra(&self) -> Vec<Tetrahedron> {
+        self.tetrahedra
+            .iter()
+            .map(|&[a, b, c, d]| Tetrahedron {
+                a: self.nodes[a],
+                b: self.nodes[b],
+                c: self.nodes[c],
+                d: self.nodes[d],
+            })
+            .collect()
+    }
+
+    /// Expands the triangles back into [`Face`]s.
+    pub fn to_faces(&self) -> Vec<Face> {
+        self.triangles
+            .iter()
+            .map(|&[a, b, c]| Face {
+                a: self.nodes[a],
+                b: self.nodes[b],
+                c: self.nodes[c],
+            })
+            .collect()
+    }
+
+    /// Returns the tetrahedra of the physical group `tag`.
+    pub fn tetrahedra_in_group(&self, tag: i32) -> Vec<Tetrahedron> {
+        self.to_tetrahedra()
+            .into_iter()
+            .enumerate()
+            .filter(|&(i, _)| group(&self.tetrahedron_groups, i) == tag)
+            .map(|(_, t)| t)
+            .collect()
+    }
+
+    /// Returns the triangles of the physical group `tag`.
+    pub fn faces_in_group(&self, tag: i32) -> Vec<Face> {
+        self.to_faces()
+            .into_iter()
+            .enumerate()
+            .filter(|&(i, _)| group(&self.triangle_groups, i) == tag)
+            .map(|(_, f)| f)
+            .collect()
+    }
+}
+
+fn group(groups: &[i32], i: usize) -> i32 {
+    groups.get(i).copied().unwrap_or(0)
+}
+
+/// One geometric entity per (dimension, physical group): its elements and
+/// bounding box.
+struct Entity<'a> {
+    dimension: i32,
+    tag: i32,
+    physical: i32,
+    element_type: i32,
+    elements: Vec<&'a [usize]>,
+    min: [f64; 3],
+    max: [f64; 3],
+}
+
+fn entities<'a>(
+    nodes: &[Point3D],
+    dimension: i32,
+    element_type: i32,
+    elements: impl Iterator<Item = &'a [usize]>,
+    groups: &[i32],
+) -> Vec<Entity<'a>> {
+    let mut by_group: BTreeMap<i32, Vec<&'a [usize]>> = BTreeMap::new();
+    for (i, element) in elements.enumerate() {
+        by_group.entry(group(groups, i)).or_default().push(element);
+    }
+    by_group
+        .into_iter()
+        .enumerate()
+        .map(|(i, (physical, elements))| {
+            let mut min = [f64::INFINITY; 3];
+            let mut max = [f64::NEG_INFINITY; 3];
+            for &v in elements.iter().flat_map(|e| e.iter()) {
+                let p = nodes[v];
+                for (k, c) in [p.x, p.y, p.z].into_iter().enumerate() {
+                    min[k] = min[k].min(c);
+                    max[k] = max[k].max(c);
+                }
+            }
+            Entity {
+                dimension,
+                tag: i as i32 + 1,
+                physical,
+                element_type,
+                elements,
+                min,
+                max,
+            }
+        })
+        .collect()
+}
+
+fn validate(mesh: &MshMesh) -> io::Result<()> {
+    let n = mesh.nodes.len();
+    for (kind, count, groups) in [
+        (
+            "tetrahedron",
+            mesh.tetrahedra.len(),
+            &mesh.tetrahedron_groups,
+        ),
+        ("triangle", mesh.triangles.len(), &mesh.triangle_groups),
+    ] {
+        if !groups.is_empty() && groups.len() != count {
+            return Err(invalid(format!(
+                "{} {} groups given for {} elements",
+                groups.len(),
+                kind,
+                count
+            )));
+        }
+        if let Some(tag) = groups.iter().find(|&&tag| tag < 0) {
+            return Err(invalid(format!("negative physical tag {}", tag)));
+        }
+    }
+    let indices = mesh.tetrahedra.iter().flatten();
+    if let Some(i) = indices
+        .chain(mesh.triangles.iter().flatten())
+        .find(|&&i| i >= n)
+    {
+        return Err(invalid(format!(
+            "element refers to node {} but there are {} nodes",
+            i, n
+        )));
+    }
+    if n > 0 && mesh.tetrahedra.is_empty() && mesh.triangles.is_empty() {
+        return Err(invalid(
+            "MSH nodes must belong to an element entity".to_string(),
+        ));
+    }
+    if let Some(name) = mesh.physical_names.iter().find(|p| p.name.contains('"')) {
+        return Err(invalid(format!("invalid physical name '{}'", name.name)));
+    }
+    Ok(())
+}
+
+/// Writes a section body value either as text or as little-endian bytes.
+struct Sink<'w, W: Write> {
+    writer: &'w mut W,
+    binary: bool,
+    line_start: bool,
+}
+
+impl<W: Write> Sink<'_, W> {
+    fn text(&mut self, value: impl std::fmt::Display) -> io::Result<()> {
+        if !self.line_start {
+            write!(self.writer, " ")?;
+        }
+        self.line_start = false;
+        write!(self.writer, "{}", value)
+    }
+
+    fn int(&mut self, value: i32) -> io::Result<()> {
+        if self.binary {
+            self.writer.write_all(&value.to_le_bytes())
+        } else {
+            self.text(value)
+        }
+    }
+
+    fn size(&mut self, value: usize) -> io::Result<()> {
+        if self.binary {
+            self.writer.write_all(&(value as u64).to_le_bytes())
+        } else {
+            self.text(value)
+        }
+    }
+
+    fn float(&mut self, value: f64) -> io::Result<()> {
+        if self.binary {
+            self.writer.write_all(&value.to_le_bytes())
+        } else {
+            self.text(value)
+        }
+    }
+
+    /// Ends a line of an ASCII section; binary data has no line breaks.
+    fn end_line(&mut self) -> io::Result<()> {
+        if self.binary {
+            return Ok(());
+        }
+        self.line_start = true;
+        writeln!(self.writer)
+    }
+}
+
+/// Writes a Gmsh MSH 4.1 file with `$PhysicalNames`, `$Entities`,
+/// `$Nodes` and `$Elements` sections.
+///
+/// Each distinct physical tag of the tetrahedra becomes one volume entity
+/// and each tag of the triangles one surface entity. All nodes are listed
+/// in a single block on the first entity of the highest dimension, tagged
+/// from 1 in the order of `mesh.nodes`; elements are tagged from 1,
+/// tetrahedra first.
+///
+/// # Errors
+///
+/// Returns any error from `writer`, and [`io::ErrorKind::InvalidInput`] if a
+/// group vector has the wrong length or a negative tag, an element refers to
+/// a missing node, there are nodes but no elements, or a physical name
+/// contains `"`.
+pub fn write_msh<W: Write>(writer: &mut W, mesh: &MshMesh, format: MshFormat) -> io::Result<()> {
+    validate(mesh)?;
+    let binary = format == MshFormat::Binary;
+
+    writeln!(writer, "$MeshFormat")?;
+    writeln!(writer, "4.1 {} 8", if binary { 1 } else { 0 })?;
+    if binary {
+        writer.write_all(&1i32.to_le_bytes())?;
+        writeln!(writer)?;
+    }
+    writeln!(writer, "$EndMeshFormat")?;
+
+    if !mesh.physical_names.is_empty() {
+        writeln!(writer, "$PhysicalNames")?;
+        writeln!(writer, "{}", mesh.physical_names.len())?;
+        for p in &mesh.physical_names {
+            writeln!(writer, "{} {} \"{}\"", p.dimension, p.tag, p.name)?;
+        }
+        writeln!(writer, "$EndPhysicalNames")?;
+    }
+
+    let volumes = entities(
+        &mesh.nodes,
+        3,
+        MSH_TETRAHEDRON,
+        mesh.tetrahedra.iter().map(|t| &t[..]),
+        &mesh.tetrahedron_groups,
+    );
+    let surfaces = entities(
+        &mesh.nodes,
+        2,
+        MSH_TRIANGLE,
+        mesh.triangles.iter().map(|t| &t[..]),
+        &mesh.triangle_groups,
+    );
+    let mut sink = Sink {
+        writer,
+        binary,
+        line_start: true,
+    };
+
+    writeln!(sink.writer, "$Entities")?;
+    for count in [0, 0, surfaces.len(), volumes.len()] {
+        sink.size(count)?;
+    }
+    sink.end_line()?;
+    for entity in surfaces.iter().chain(&volumes) {
+        sink.int(entity.tag)?;
+        for c in entity.min.iter().chain(&entity.max) {
+            sink.float(*c)?;
+        }
+        if entity.physical == 0 {
+            sink.size(0)?;
+        } else {
+            sink.size(1)?;
+            sink.int(entity.physical)?;
+        }
+        // No bounding entities of lower dimension.
+        sink.size(0)?;
+        sink.end_line()?;
+    }
+    if binary {
+        writeln!(sink.writer)?;
+    }
+    writeln!(sink.writer, "$EndEntities")?;
+
+    let n = mesh.nodes.len();
+    writeln!(sink.writer, "$Nodes")?;
+    let node_entity = volumes.first().or(surfaces.first());
+    sink.size(node_entity.map_or(0, |_| 1))?;
+    sink.size(n)?;
+    sink.size(n.min(1))?;
+    sink.size(n)?;
+    sink.end_line()?;
+    if let Some(entity) = node_entity {
+        sink.int(entity.dimension)?;
+        sink.int(entity.tag)?;
+        sink.int(0)?;
+        sink.size(n)?;
+        sink.end_line()?;
+        for tag in 1..=n {
+            sink.size(tag)?;
+            sink.end_line()?;
+        }
+        for p in &mesh.nodes {
+            sink.float(p.x)?;
+            sink.float(p.y)?;
+            sink.float(p.z)?;
+            sink.end_line()?;
+        }
+    }
+    if binary {
+        writeln!(sink.writer)?;
+    }
+    writeln!(sink.writer, "$EndNodes")?;
+
+    let num_elements = mesh.tetrahedra.len() + mesh.triangles.len();
+    writeln!(sink.writer, "$Elements")?;
+    sink.size(volumes.len() + surfaces.len())?;
+    sink.size(num_elements)?;
+    sink.size(num_elements.min(1))?;
+    sink.size(num_elements)?;
+    sink.end_line()?;
+    let mut element_tag = 0;
+    for entity in volumes.iter().chain(&surfaces) {
+        sink.int(entity.dimension)?;
+        sink.int(entity.tag)?;
+        sink.int(entity.element_type)?;
+        sink.size(entity.elements.len())?;
+        sink.end_line()?;
+        for element in &entity.elements {
+            element_tag += 1;
+            sink.size(element_tag)?;
+            for &v in element.iter() {
+                sink.size(v + 1)?;
+            }
+            sink.end_line()?;
+        }
+    }
+    if binary {
+        writeln!(sink.writer)?;
+    }
+    writeln!(sink.writer, "$EndElements")
+}
+
+/// Exports an [`MshMesh`] to Gmsh MSH 4.1.
+///
+/// # Panics
+///
+/// Panics if the mesh is invalid, see [`write_msh`].
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::{mesh_to_msh, MshFormat, MshMesh, MshPhysicalName};
+/// use meshing::{Point3D, TetMesh, Tetrahedron};
+///
+/// let p = |index, x, y, z| Point3D { index, x, y, z };
+/// let tet = Tetrahedron {
+///     a: p(0, 0.0, 0.0, 0.0),
+///     b: p(1, 1.0, 0.0, 0.0),
+///     c: p(2, 0.0, 1.0, 0.0),
+///     d: p(3, 0.0, 0.0, 1.0),
+/// };
+/// let mut mesh = MshMesh::from_tet_mesh(&TetMesh::from_tetrahedra(&[tet]));
+/// mesh.tetrahedron_groups = vec![5];
+/// mesh.physical_names.push(MshPhysicalName { dimension: 3, tag: 5, name: "solid".into() });
+/// let msh = String::from_utf8(mesh_to_msh(&mesh, MshFormat::Ascii)).unwrap();
+/// assert!(msh.contains("$PhysicalNames\n1\n3 5 \"solid\"\n"));
+/// ```
+pub fn mesh_to_msh(mesh: &MshMesh, format: MshFormat) -> Vec<u8> {
+    to_bytes(|w| write_msh(w, mesh, format))
+}
+
+/// Exports tetrahedra to Gmsh MSH 4.1, with nodes numbered as in
+/// [`TetMesh::from_tetrahedra`].
+pub fn tetrahedra_to_msh(tetrahedra: &[Tetrahedron], format: MshFormat) -> Vec<u8> {
+    tet_mesh_to_msh(&TetMesh::from_tetrahedra(tetrahedra), format)
+}
+
+/// Exports an indexed tetrahedral mesh to Gmsh MSH 4.1.
+pub fn tet_mesh_to_msh(mesh: &TetMesh, format: MshFormat) -> Vec<u8> {
+    mesh_to_msh(&MshMesh::from_tet_mesh(mesh), format)
+}
+
+/// Exports 2D triangles to Gmsh MSH 4.1 with z set to 0.
+pub fn triangles_to_msh(triangles: &[Triangle], format: MshFormat) -> Vec<u8> {
+    tri_mesh_2d_to_msh(&TriMesh2D::from_triangles(triangles), format)
+}
+
+/// Exports an indexed 2D triangle mesh to Gmsh MSH 4.1 with z set to 0.
+pub fn tri_mesh_2d_to_msh(mesh: &TriMesh2D, format: MshFormat) -> Vec<u8> {
+    let msh = MshMesh {
+        nodes: mesh
+            .vertices
+            .iter()
+            .map(|v| Point3D {
+                index: v.index,
+                x: v.x,
+                y: v.y,
+                z: 0.0,
+            })
+            .collect(),
+        triangles: mesh.triangles.clone(),
+        ..MshMesh::default()
+    };
+    mesh_to_msh(&msh, format)
+}
+
+/// Exports 3D faces to Gmsh MSH 4.1 as triangles.
+pub fn faces_to_msh(faces: &[Face], format: MshFormat) -> Vec<u8> {
+    surface_mesh_to_msh(&SurfaceMesh::from_faces(faces), format)
+}
+
+/// Exports an indexed surface mesh to Gmsh MSH 4.1 as triangles.
+pub fn surface_mesh_to_msh(mesh: &SurfaceMesh, format: MshFormat) -> Vec<u8> {
+    mesh_to_msh(&MshMesh::from_surface_mesh(mesh), format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point2D;
+
+    fn p(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    fn single_tet() -> Tetrahedron {
+        Tetrahedron {
+            a: p(0, 0.0, 0.0, 0.0),
+            b: p(1, 1.0, 0.0, 0.0),
+            c: p(2, 0.0, 1.0, 0.0),
+            d: p(3, 0.0, 0.0, 1.0),
+        }
+    }
+
+    #[test]
+    fn test_tetrahedra_to_msh_ascii() {
+        let msh = String::from_utf8(tetrahedra_to_msh(&[single_tet()], MshFormat::Ascii)).unwrap();
+        let expected = "$MeshFormat\n4.1 0 8\n$EndMeshFormat\n\
+                        $Entities\n0 0 0 1\n1 0 0 0 1 1 1 0 0\n$EndEntities\n\
+                        $Nodes\n1 4 1 4\n3 1 0 4\n1\n2\n3\n4\n\
+                        0 0 0\n1 0 0\n0 1 0\n0 0 1\n$EndNodes\n\
+                        $Elements\n1 1 1 1\n3 1 4 1\n1 1 2 3 4\n$EndElements\n";
+        assert_eq!(msh, expected);
+    }
+
+    #[test]
+    fn test_groups_become_entities() {
+        let tet = single_tet();
+        let shifted = Tetrahedron {
+            a: p(4, 2.0, 0.0, 0.0),
+            b: p(5, 3.0, 0.0, 0.0),
+            c: p(6, 2.0, 1.0, 0.0),
+            d: p(7, 2.0, 0.0, 1.0),
+        };
+        let mut mesh = MshMesh::from_tet_mesh(&TetMesh::from_tetrahedra(&[tet, shifted, tet]));
+        mesh.tetrahedron_groups = vec![2, 1, 2];
+        let msh = String::from_utf8(mesh_to_msh(&mesh, MshFormat::Ascii)).unwrap();
+        // Entity 1 holds group 1, entity 2 holds both tetrahedra of group 2.
+        assert!(msh.contains("$Entities\n0 0 0 2\n1 2 0 0 3 1 1 1 1 0\n2 0 0 0 1 1 1 1 2 0\n"));
+        assert!(msh.contains("3 1 4 1\n1 5 6 7 8\n3 2 4 2\n2 1 2 3 4\n3 1 2 3 4\n"));
+        assert_eq!(mesh.tetrahedra_in_group(2).len(), 2);
+    }
+
+    #[test]
+    fn test_binary_layout() {
+        let msh = tetrahedra_to_msh(&[single_tet()], MshFormat::Binary);
+        assert!(msh.starts_with(b"$MeshFormat\n4.1 1 8\n\x01\x00\x00\x00\n$EndMeshFormat\n"));
+        let nodes = msh.windows(7).position(|w| w == b"$Nodes\n").unwrap() + 7;
+        let size = |at: usize| u64::from_le_bytes(msh[at..at + 8].try_into().unwrap());
+        assert_eq!([size(nodes), size(nodes + 8)], [1, 4]);
+        // Block header: three ints and a size_t, then four tags and the
+        // coordinates.
+        let coords = nodes + 32 + 20 + 4 * 8;
+        let x = |i: usize| {
+            f64::from_le_bytes(msh[coords + 8 * i..coords + 8 * i + 8].try_into().unwrap())
+        };
+        assert_eq!([x(3), x(4), x(7), x(11)], [1.0, 0.0, 1.0, 1.0]);
+        assert!(msh.ends_with(b"\n$EndElements\n"));
+    }
+
+    #[test]
+    fn test_triangles_to_msh() {
+        let triangle = Triangle {
+            a: Point2D {
+                index: 0,
+                x: 0.0,
+                y: 0.0,
+            },
+            b: Point2D {
+                index: 1,
+                x: 1.0,
+                y: 0.0,
+            },
+            c: Point2D {
+                index: 2,
+                x: 0.0,
+                y: 1.0,
+            },
+        };
+        let msh = String::from_utf8(triangles_to_msh(&[triangle], MshFormat::Ascii)).unwrap();
+        assert!(msh.contains("$Entities\n0 0 1 0\n"));
+        assert!(msh.contains("$Elements\n1 1 1 1\n2 1 2 1\n1 1 2 3\n"));
+    }
+
+    #[test]
+    fn test_invalid_meshes_are_rejected() {
+        let base = MshMesh::from_tet_mesh(&TetMesh::from_tetrahedra(&[single_tet()]));
+        let cases = [
+            MshMesh {
+                tetrahedron_groups: vec![1, 2],
+                ..base.clone()
+            },
+            MshMesh {
+                tetrahedron_groups: vec![-1],
+                ..base.clone()
+            },
+            MshMesh {
+                tetrahedra: vec![[0, 1, 2, 4]],
+                ..base.clone()
+            },
+            MshMesh {
+                tetrahedra: Vec::new(),
+                ..base.clone()
+            },
+        ];
+        for mesh in &cases {
+            let err = write_msh(&mut Vec::new(), mesh, MshFormat::Ascii).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
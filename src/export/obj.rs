@@ -1,103 +1,136 @@
-use crate::export::stl::extract_surface_faces;
-use crate::{Face, Point3D, Tetrahedron, Triangle};
+use std::io::{self, Write};
+
+use super::to_string;
+use crate::{Face, SurfaceMesh, TetMesh, Tetrahedron, TriMesh2D, Triangle};
 
 /// Exports a slice of triangles to Wavefront OBJ format.
 /// Since the triangles are 2D, z coordinates are set to 0.
 /// Vertices are deduplicated by index and faces reference vertex positions.
 pub fn triangles_to_obj(triangles: &[Triangle]) -> String {
-    let mut vertices: Vec<(i64, f64, f64)> = Vec::new();
-
-    for triangle in triangles {
-        for vertex in &triangle.vertices() {
-            if !vertices.iter().any(|(idx, _, _)| *idx == vertex.index) {
-                vertices.push((vertex.index, vertex.x, vertex.y));
-            }
-        }
-    }
+    to_string(|w| write_triangles_obj(w, triangles))
+}
 
-    vertices.sort_by_key(|(idx, _, _)| *idx);
+/// Writes a slice of triangles as Wavefront OBJ, with z set to 0.
+///
+/// # Errors
+///
+/// Returns any error from `writer`.
+pub fn write_triangles_obj<W: Write>(writer: &mut W, triangles: &[Triangle]) -> io::Result<()> {
+    write_tri_mesh_2d_obj(writer, &TriMesh2D::from_triangles(triangles))
+}
 
-    let mut result = String::new();
+/// Exports an indexed 2D triangle mesh to Wavefront OBJ format.
+/// z coordinates are set to 0.
+pub fn tri_mesh_2d_to_obj(mesh: &TriMesh2D) -> String {
+    to_string(|w| write_tri_mesh_2d_obj(w, mesh))
+}
 
-    for (_, x, y) in &vertices {
-        result.push_str(&format!("v {} {} 0\n", x, y));
+/// Writes an indexed 2D triangle mesh as Wavefront OBJ, with z set to 0.
+///
+/// # Errors
+///
+/// Returns any error from `writer`.
+pub fn write_tri_mesh_2d_obj<W: Write>(writer: &mut W, mesh: &TriMesh2D) -> io::Result<()> {
+    for v in &mesh.vertices {
+        writeln!(writer, "v {} {} 0", v.x, v.y)?;
     }
 
-    for triangle in triangles {
-        let a_pos = vertices
-            .iter()
-            .position(|(idx, _, _)| *idx == triangle.a.index)
-            .unwrap()
-            + 1;
-        let b_pos = vertices
-            .iter()
-            .position(|(idx, _, _)| *idx == triangle.b.index)
-            .unwrap()
-            + 1;
-        let c_pos = vertices
-            .iter()
-            .position(|(idx, _, _)| *idx == triangle.c.index)
-            .unwrap()
-            + 1;
-        result.push_str(&format!("f {} {} {}\n", a_pos, b_pos, c_pos));
+    for [a, b, c] in &mesh.triangles {
+        writeln!(writer, "f {} {} {}", a + 1, b + 1, c + 1)?;
     }
 
-    result
+    Ok(())
 }
 
 /// Exports a tetrahedral mesh to Wavefront OBJ format by extracting surface faces.
 pub fn tetrahedra_to_obj(tetrahedra: &[Tetrahedron]) -> String {
-    let surface = extract_surface_faces(tetrahedra);
-    faces_to_obj(&surface)
+    to_string(|w| write_tetrahedra_obj(w, tetrahedra))
+}
+
+/// Writes the boundary surface of a tetrahedral mesh as Wavefront OBJ.
+///
+/// # Errors
+///
+/// Returns any error from `writer`.
+pub fn write_tetrahedra_obj<W: Write>(
+    writer: &mut W,
+    tetrahedra: &[Tetrahedron],
+) -> io::Result<()> {
+    write_tet_mesh_obj(writer, &TetMesh::from_tetrahedra(tetrahedra))
+}
+
+/// Exports the boundary surface of an indexed tetrahedral mesh to Wavefront OBJ format.
+pub fn tet_mesh_to_obj(mesh: &TetMesh) -> String {
+    to_string(|w| write_tet_mesh_obj(w, mesh))
+}
+
+/// Writes the boundary surface of an indexed tetrahedral mesh as Wavefront
+/// OBJ.
+///
+/// # Errors
+///
+/// Returns any error from `writer`.
+pub fn write_tet_mesh_obj<W: Write>(writer: &mut W, mesh: &TetMesh) -> io::Result<()> {
+    write_surface_mesh_obj(writer, &mesh.surface())
 }
 
 /// Exports 3D faces to Wavefront OBJ format.
 /// Vertices are deduplicated by index.
 pub fn faces_to_obj(faces: &[Face]) -> String {
-    let mut vertices: Vec<(i64, Point3D)> = Vec::new();
-
-    for face in faces {
-        for vertex in &face.vertices() {
-            if !vertices.iter().any(|(idx, _)| *idx == vertex.index) {
-                vertices.push((vertex.index, *vertex));
-            }
-        }
-    }
+    to_string(|w| write_faces_obj(w, faces))
+}
 
-    vertices.sort_by_key(|(idx, _)| *idx);
+/// Writes 3D faces as Wavefront OBJ, with vertices deduplicated by index.
+///
+/// # Errors
+///
+/// Returns any error from `writer`.
+pub fn write_faces_obj<W: Write>(writer: &mut W, faces: &[Face]) -> io::Result<()> {
+    write_surface_mesh_obj(writer, &SurfaceMesh::from_faces(faces))
+}
 
-    let mut result = String::new();
+/// Exports an indexed surface mesh to Wavefront OBJ format.
+pub fn surface_mesh_to_obj(mesh: &SurfaceMesh) -> String {
+    to_string(|w| write_surface_mesh_obj(w, mesh))
+}
 
-    for (_, v) in &vertices {
-        result.push_str(&format!("v {} {} {}\n", v.x, v.y, v.z));
+/// Writes an indexed surface mesh as Wavefront OBJ.
+///
+/// Lines are written one at a time, so wrap files in a
+/// [`std::io::BufWriter`].
+///
+/// # Errors
+///
+/// Returns any error from `writer`.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::write_surface_mesh_obj;
+/// use meshing::{Face, Point3D, SurfaceMesh};
+///
+/// let p = |index, x, y, z| Point3D { index, x, y, z };
+/// let face = Face { a: p(0, 0.0, 0.0, 0.0), b: p(1, 1.0, 0.0, 0.0), c: p(2, 0.0, 1.0, 0.0) };
+/// let mut out = Vec::new();
+/// write_surface_mesh_obj(&mut out, &SurfaceMesh::from_faces(&[face])).unwrap();
+/// assert!(out.ends_with(b"f 1 2 3\n"));
+/// ```
+pub fn write_surface_mesh_obj<W: Write>(writer: &mut W, mesh: &SurfaceMesh) -> io::Result<()> {
+    for v in &mesh.vertices {
+        writeln!(writer, "v {} {} {}", v.x, v.y, v.z)?;
     }
 
-    for face in faces {
-        let a_pos = vertices
-            .iter()
-            .position(|(idx, _)| *idx == face.a.index)
-            .unwrap()
-            + 1;
-        let b_pos = vertices
-            .iter()
-            .position(|(idx, _)| *idx == face.b.index)
-            .unwrap()
-            + 1;
-        let c_pos = vertices
-            .iter()
-            .position(|(idx, _)| *idx == face.c.index)
-            .unwrap()
-            + 1;
-        result.push_str(&format!("f {} {} {}\n", a_pos, b_pos, c_pos));
+    for [a, b, c] in &mesh.faces {
+        writeln!(writer, "f {} {} {}", a + 1, b + 1, c + 1)?;
     }
 
-    result
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Point2D;
+    use crate::{Point2D, Point3D};
 
     #[test]
     fn test_triangles_to_obj_empty() {
@@ -389,4 +422,48 @@ mod tests {
         assert!(obj.contains("-0.987654321"));
         assert!(obj.contains("42"));
     }
+
+    #[test]
+    fn test_tet_mesh_to_obj_matches_element_export() {
+        let p = |i: i64, x: f64, y: f64, z: f64| Point3D { index: i, x, y, z };
+        let tets = [
+            Tetrahedron {
+                a: p(0, 0.0, 0.0, 0.0),
+                b: p(1, 1.0, 0.0, 0.0),
+                c: p(2, 0.0, 1.0, 0.0),
+                d: p(3, 0.0, 0.0, 1.0),
+            },
+            Tetrahedron {
+                a: p(1, 1.0, 0.0, 0.0),
+                b: p(2, 0.0, 1.0, 0.0),
+                c: p(3, 0.0, 0.0, 1.0),
+                d: p(4, 1.0, 1.0, 1.0),
+            },
+        ];
+        let mesh = TetMesh::from_tetrahedra(&tets);
+        let obj = tet_mesh_to_obj(&mesh);
+        assert_eq!(
+            obj,
+            faces_to_obj(&crate::export::extract_surface_faces(&tets))
+        );
+        assert_eq!(obj.lines().filter(|l| l.starts_with("f ")).count(), 6);
+    }
+
+    #[test]
+    fn test_write_obj_matches_string_export() {
+        let p = |i: i64, x: f64, y: f64, z: f64| Point3D { index: i, x, y, z };
+        let tet = Tetrahedron {
+            a: p(0, 0.0, 0.0, 0.0),
+            b: p(1, 1.0, 0.0, 0.0),
+            c: p(2, 0.0, 1.0, 0.0),
+            d: p(3, 0.0, 0.0, 1.0),
+        };
+        let mut out = Vec::new();
+        write_tetrahedra_obj(&mut out, &[tet]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), tetrahedra_to_obj(&[tet]));
+
+        let mut out = Vec::new();
+        write_faces_obj(&mut out, &tet.faces()).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), faces_to_obj(&tet.faces()));
+    }
 }
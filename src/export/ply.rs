@@ -0,0 +1,342 @@
+use std::io::{self, Write};
+
+use super::{invalid, to_bytes};
+use crate::{Face, Point3D, SurfaceMesh, TetMesh, Tetrahedron};
+
+/// PLY encoding used by the PLY exporters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlyFormat {
+    /// Whitespace-separated text after the header.
+    #[default]
+    Ascii,
+    /// Packed little-endian values after the header.
+    BinaryLittleEndian,
+}
+
+/// Optional per-vertex data written alongside the positions.
+///
+/// Every vector must have one entry per vertex. Normals become the
+/// `nx`/`ny`/`nz` float properties; each scalar becomes a double property
+/// with the given name, e.g. the field value at each marching cubes vertex.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlyAttributes {
+    pub normals: Option<Vec<[f64; 3]>>,
+    pub scalars: Vec<(String, Vec<f64>)>,
+}
+
+/// Property names that the writer uses for positions and normals.
+const RESERVED: [&str; 6] = ["x", "y", "z", "nx", "ny", "nz"];
+
+fn validate(mesh: &SurfaceMesh, attributes: &PlyAttributes) -> io::Result<()> {
+    let n = mesh.vertices.len();
+    if let Some(normals) = &attributes.normals {
+        if normals.len() != n {
+            return Err(invalid(format!(
+                "{} normals given for {} vertices",
+                normals.len(),
+                n
+            )));
+        }
+    }
+    for (i, (name, values)) in attributes.scalars.iter().enumerate() {
+        if name.is_empty()
+            || name.contains(char::is_whitespace)
+            || RESERVED.contains(&name.as_str())
+        {
+            return Err(invalid(format!("invalid PLY property name '{}'", name)));
+        }
+        if attributes.scalars[..i]
+            .iter()
+            .any(|(other, _)| other == name)
+        {
+            return Err(invalid(format!("duplicate PLY property '{}'", name)));
+        }
+        if values.len() != n {
+            return Err(invalid(format!(
+                "scalar '{}' has {} values for {} vertices",
+                name,
+                values.len(),
+                n
+            )));
+        }
+    }
+    if n > i32::MAX as usize {
+        return Err(invalid(
+            "PLY face indices are limited to i32::MAX vertices".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn write_header<W: Write>(
+    writer: &mut W,
+    mesh: &SurfaceMesh,
+    attributes: &PlyAttributes,
+    format: PlyFormat,
+) -> io::Result<()> {
+    writeln!(writer, "ply")?;
+    match format {
+        PlyFormat::Ascii => writeln!(writer, "format ascii 1.0")?,
+        PlyFormat::BinaryLittleEndian => writeln!(writer, "format binary_little_endian 1.0")?,
+    }
+    writeln!(writer, "comment generated by meshing")?;
+    writeln!(writer, "element vertex {}", mesh.vertices.len())?;
+    for axis in ["x", "y", "z"] {
+        writeln!(writer, "property double {}", axis)?;
+    }
+    if attributes.normals.is_some() {
+        for axis in ["nx", "ny", "nz"] {
+            writeln!(writer, "property float {}", axis)?;
+        }
+    }
+    for (name, _) in &attributes.scalars {
+        writeln!(writer, "property double {}", name)?;
+    }
+    // Point clouds have no face element at all.
+    if !mesh.faces.is_empty() {
+        writeln!(writer, "element face {}", mesh.faces.len())?;
+        writeln!(writer, "property list uchar int vertex_indices")?;
+    }
+    writeln!(writer, "end_header")
+}
+
+/// Writes an indexed surface mesh as PLY with optional per-vertex
+/// attributes.
+///
+/// Positions and scalars are stored as doubles, normals as floats and
+/// faces as `vertex_indices` lists of `int`. A mesh without faces is
+/// written as a point cloud with only the vertex element.
+///
+/// # Errors
+///
+/// Returns any error from `writer`, and [`io::ErrorKind::InvalidInput`] if
+/// an attribute does not have one value per vertex, a scalar name is empty,
+/// contains whitespace, is used twice or clashes with `x`/`y`/`z`/`nx`/`ny`/`nz`,
+/// or there are more than `i32::MAX` vertices.
+pub fn write_ply<W: Write>(
+    writer: &mut W,
+    mesh: &SurfaceMesh,
+    attributes: &PlyAttributes,
+    format: PlyFormat,
+) -> io::Result<()> {
+    validate(mesh, attributes)?;
+    write_header(writer, mesh, attributes, format)?;
+
+    for (i, v) in mesh.vertices.iter().enumerate() {
+        let normal = attributes.normals.as_ref().map(|normals| normals[i]);
+        let scalars = attributes.scalars.iter().map(|(_, values)| values[i]);
+        match format {
+            PlyFormat::Ascii => {
+                write!(writer, "{} {} {}", v.x, v.y, v.z)?;
+                if let Some(normal) = normal {
+                    for c in normal {
+                        write!(writer, " {}", c as f32)?;
+                    }
+                }
+                for s in scalars {
+                    write!(writer, " {}", s)?;
+                }
+                writeln!(writer)?;
+            }
+            PlyFormat::BinaryLittleEndian => {
+                for c in [v.x, v.y, v.z] {
+                    writer.write_all(&c.to_le_bytes())?;
+                }
+                if let Some(normal) = normal {
+                    for c in normal {
+                        writer.write_all(&(c as f32).to_le_bytes())?;
+                    }
+                }
+                for s in scalars {
+                    writer.write_all(&s.to_le_bytes())?;
+                }
+            }
+        }
+    }
+
+    for &[a, b, c] in &mesh.faces {
+        match format {
+            PlyFormat::Ascii => writeln!(writer, "3 {} {} {}", a, b, c)?,
+            PlyFormat::BinaryLittleEndian => {
+                writer.write_all(&[3])?;
+                for i in [a, b, c] {
+                    writer.write_all(&(i as i32).to_le_bytes())?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Exports an indexed surface mesh to PLY.
+pub fn surface_mesh_to_ply(mesh: &SurfaceMesh, format: PlyFormat) -> Vec<u8> {
+    surface_mesh_to_ply_with_attributes(mesh, &PlyAttributes::default(), format)
+}
+
+/// Exports an indexed surface mesh to PLY with per-vertex normals and
+/// scalars.
+///
+/// # Panics
+///
+/// Panics if the attributes are invalid, see [`write_ply`].
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::{surface_mesh_to_ply_with_attributes, PlyAttributes, PlyFormat};
+/// use meshing::marching_cubes::marching_cubes_indexed;
+/// use meshing::Point3D;
+///
+/// let min = Point3D { index: 0, x: -2.0, y: -2.0, z: -2.0 };
+/// let max = Point3D { index: 0, x: 2.0, y: 2.0, z: 2.0 };
+/// let field = |x: f64, y: f64, z: f64| x * x + y * y + z * z - 1.0;
+/// let mesh = marching_cubes_indexed(8, 8, 8, min, max, &field, 0.0);
+/// let attributes = PlyAttributes {
+///     normals: None,
+///     scalars: vec![("height".to_string(), mesh.vertices.iter().map(|v| v.z).collect())],
+/// };
+/// let ply = surface_mesh_to_ply_with_attributes(&mesh, &attributes, PlyFormat::Ascii);
+/// assert!(String::from_utf8(ply).unwrap().contains("property double height\n"));
+/// ```
+pub fn surface_mesh_to_ply_with_attributes(
+    mesh: &SurfaceMesh,
+    attributes: &PlyAttributes,
+    format: PlyFormat,
+) -> Vec<u8> {
+    to_bytes(|w| write_ply(w, mesh, attributes, format))
+}
+
+/// Exports 3D faces to PLY. Vertices are deduplicated by index.
+pub fn faces_to_ply(faces: &[Face], format: PlyFormat) -> Vec<u8> {
+    surface_mesh_to_ply(&SurfaceMesh::from_faces(faces), format)
+}
+
+/// Exports a tetrahedral mesh to PLY by extracting its boundary faces (the
+/// same faces as [`extract_surface_faces`](super::extract_surface_faces)).
+pub fn tetrahedra_to_ply(tetrahedra: &[Tetrahedron], format: PlyFormat) -> Vec<u8> {
+    tet_mesh_to_ply(&TetMesh::from_tetrahedra(tetrahedra), format)
+}
+
+/// Exports the boundary surface of an indexed tetrahedral mesh to PLY.
+pub fn tet_mesh_to_ply(mesh: &TetMesh, format: PlyFormat) -> Vec<u8> {
+    surface_mesh_to_ply(&mesh.surface(), format)
+}
+
+/// Exports a point cloud to PLY with optional per-point attributes.
+///
+/// # Panics
+///
+/// Panics if the attributes are invalid, see [`write_ply`].
+pub fn points_to_ply(points: &[Point3D], attributes: &PlyAttributes, format: PlyFormat) -> Vec<u8> {
+    let cloud = SurfaceMesh {
+        vertices: points.to_vec(),
+        faces: Vec::new(),
+    };
+    surface_mesh_to_ply_with_attributes(&cloud, attributes, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::extract_surface_faces;
+
+    fn p(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    fn triangle() -> Face {
+        Face {
+            a: p(4, 0.0, 0.0, 0.0),
+            b: p(5, 1.0, 0.0, 0.0),
+            c: p(6, 0.0, 1.0, 0.5),
+        }
+    }
+
+    #[test]
+    fn test_faces_to_ply_ascii() {
+        let ply = String::from_utf8(faces_to_ply(&[triangle()], PlyFormat::Ascii)).unwrap();
+        let expected = "ply\nformat ascii 1.0\ncomment generated by meshing\n\
+                        element vertex 3\nproperty double x\nproperty double y\n\
+                        property double z\nelement face 1\n\
+                        property list uchar int vertex_indices\nend_header\n\
+                        0 0 0\n1 0 0\n0 1 0.5\n3 0 1 2\n";
+        assert_eq!(ply, expected);
+    }
+
+    #[test]
+    fn test_faces_to_ply_binary_layout() {
+        let ply = faces_to_ply(&[triangle()], PlyFormat::BinaryLittleEndian);
+        let header_end = b"end_header\n";
+        let start = ply
+            .windows(header_end.len())
+            .position(|w| w == header_end)
+            .unwrap()
+            + header_end.len();
+        let body = &ply[start..];
+        assert_eq!(body.len(), 3 * 24 + 1 + 12);
+        assert_eq!(f64::from_le_bytes(body[64..72].try_into().unwrap()), 0.5);
+        assert_eq!(body[72], 3);
+        assert_eq!(i32::from_le_bytes(body[81..85].try_into().unwrap()), 2);
+    }
+
+    #[test]
+    fn test_tetrahedra_to_ply_uses_surface() {
+        let tet = Tetrahedron {
+            a: p(0, 0.0, 0.0, 0.0),
+            b: p(1, 1.0, 0.0, 0.0),
+            c: p(2, 0.0, 1.0, 0.0),
+            d: p(3, 0.0, 0.0, 1.0),
+        };
+        assert_eq!(
+            tetrahedra_to_ply(&[tet], PlyFormat::Ascii),
+            faces_to_ply(&extract_surface_faces(&[tet]), PlyFormat::Ascii)
+        );
+    }
+
+    #[test]
+    fn test_points_to_ply_has_no_face_element() {
+        let attributes = PlyAttributes {
+            normals: Some(vec![[0.0, 0.0, 1.0]]),
+            scalars: vec![("value".to_string(), vec![2.5])],
+        };
+        let ply = points_to_ply(&[p(0, 1.0, 2.0, 3.0)], &attributes, PlyFormat::Ascii);
+        let text = String::from_utf8(ply).unwrap();
+        assert!(!text.contains("element face"));
+        assert!(text.contains("property float nz\nproperty double value\n"));
+        assert!(text.ends_with("end_header\n1 2 3 0 0 1 2.5\n"));
+    }
+
+    #[test]
+    fn test_invalid_attributes_are_rejected() {
+        let mesh = SurfaceMesh::from_faces(&[triangle()]);
+        let cases = [
+            PlyAttributes {
+                normals: Some(vec![[0.0, 0.0, 1.0]]),
+                scalars: Vec::new(),
+            },
+            PlyAttributes {
+                normals: None,
+                scalars: vec![("value".to_string(), vec![1.0])],
+            },
+            PlyAttributes {
+                normals: None,
+                scalars: vec![("nx".to_string(), vec![1.0; 3])],
+            },
+            PlyAttributes {
+                normals: None,
+                scalars: vec![("two words".to_string(), vec![1.0; 3])],
+            },
+            PlyAttributes {
+                normals: None,
+                scalars: vec![
+                    ("value".to_string(), vec![1.0; 3]),
+                    ("value".to_string(), vec![2.0; 3]),
+                ],
+            },
+        ];
+        for attributes in &cases {
+            let err = write_ply(&mut Vec::new(), &mesh, attributes, PlyFormat::Ascii).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
@@ -1,40 +1,192 @@
-use crate::{Face, Tetrahedron, Triangle};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use super::{invalid, to_bytes, to_string};
+use crate::{Face, SurfaceMesh, TetMesh, Tetrahedron, TriMesh2D, Triangle};
+
+/// STL encoding used by the `write_*_stl` functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StlFormat {
+    /// Human-readable `solid ... endsolid` text.
+    #[default]
+    Ascii,
+    /// 80-byte header, `u32` facet count and 50 bytes per facet
+    /// (`f32` normal, three `f32` vertices, `u16` attribute byte count).
+    Binary,
+}
+
+/// A single STL facet: unit normal and three vertices.
+type Facet = ([f64; 3], [[f64; 3]; 3]);
+
+fn write_ascii<W: Write>(
+    writer: &mut W,
+    facets: impl Iterator<Item = Facet>,
+    name: &str,
+) -> io::Result<()> {
+    writeln!(writer, "solid {}", name)?;
+    for ([nx, ny, nz], vertices) in facets {
+        writeln!(writer, "  facet normal {} {} {}", nx, ny, nz)?;
+        writeln!(writer, "    outer loop")?;
+        for [x, y, z] in vertices {
+            writeln!(writer, "      vertex {} {} {}", x, y, z)?;
+        }
+        writeln!(writer, "    endloop")?;
+        writeln!(writer, "  endfacet")?;
+    }
+    writeln!(writer, "endsolid {}", name)
+}
+
+fn write_binary<W: Write>(
+    writer: &mut W,
+    facets: impl ExactSizeIterator<Item = Facet>,
+    name: &str,
+) -> io::Result<()> {
+    let count = u32::try_from(facets.len())
+        .map_err(|_| invalid("binary STL supports at most u32::MAX facets".to_string()))?;
+
+    // The header is free-form; store the solid name, truncated and
+    // zero-padded to 80 bytes.
+    let mut header = [0u8; 80];
+    let name = name.as_bytes();
+    let len = name.len().min(header.len());
+    header[..len].copy_from_slice(&name[..len]);
+    writer.write_all(&header)?;
+    writer.write_all(&count.to_le_bytes())?;
+
+    let mut record = [0u8; 50];
+    for (normal, vertices) in facets {
+        let values = normal.iter().chain(vertices.iter().flatten());
+        for (chunk, value) in record.chunks_exact_mut(4).zip(values) {
+            chunk.copy_from_slice(&(*value as f32).to_le_bytes());
+        }
+        // Attribute byte count, unused.
+        record[48..].copy_from_slice(&0u16.to_le_bytes());
+        writer.write_all(&record)?;
+    }
+    Ok(())
+}
+
+fn write_facets<W: Write>(
+    writer: &mut W,
+    facets: impl ExactSizeIterator<Item = Facet>,
+    name: &str,
+    format: StlFormat,
+) -> io::Result<()> {
+    match format {
+        StlFormat::Ascii => write_ascii(writer, facets, name),
+        StlFormat::Binary => write_binary(writer, facets, name),
+    }
+}
+
+fn triangle_facets(triangles: &[Triangle]) -> impl ExactSizeIterator<Item = Facet> + '_ {
+    triangles
+        .iter()
+        .map(|t| ([0.0, 0.0, 1.0], t.vertices().map(|v| [v.x, v.y, 0.0])))
+}
+
+fn face_facet(face: &Face) -> Facet {
+    let (nx, ny, nz) = face_normal(face);
+    ([nx, ny, nz], face.vertices().map(|v| [v.x, v.y, v.z]))
+}
+
+fn face_facets(faces: &[Face]) -> impl ExactSizeIterator<Item = Facet> + '_ {
+    faces.iter().map(face_facet)
+}
+
+fn surface_mesh_facets(mesh: &SurfaceMesh) -> impl ExactSizeIterator<Item = Facet> + '_ {
+    mesh.faces.iter().map(|&[a, b, c]| {
+        face_facet(&Face {
+            a: mesh.vertices[a],
+            b: mesh.vertices[b],
+            c: mesh.vertices[c],
+        })
+    })
+}
 
 /// Exports a slice of triangles to ASCII STL format.
 /// Since the triangles are 2D, z coordinates are set to 0
 /// and face normals point in the +z direction (0, 0, 1).
 pub fn triangles_to_stl(triangles: &[Triangle], name: &str) -> String {
-    let mut result = format!("solid {}\n", name);
+    to_string(|w| write_triangles_stl(w, triangles, name, StlFormat::Ascii))
+}
 
-    for triangle in triangles {
-        result.push_str("  facet normal 0 0 1\n");
-        result.push_str("    outer loop\n");
-        for vertex in &triangle.vertices() {
-            result.push_str(&format!("      vertex {} {} 0\n", vertex.x, vertex.y));
-        }
-        result.push_str("    endloop\n");
-        result.push_str("  endfacet\n");
-    }
+/// Exports a slice of triangles to binary STL, with z set to 0 and +z normals.
+///
+/// `name` is stored in the 80-byte header (truncated if longer).
+///
+/// # Panics
+///
+/// Panics if there are more than `u32::MAX` triangles.
+pub fn triangles_to_stl_binary(triangles: &[Triangle], name: &str) -> Vec<u8> {
+    to_bytes(|w| write_triangles_stl(w, triangles, name, StlFormat::Binary))
+}
 
-    result.push_str(&format!("endsolid {}\n", name));
-    result
+/// Writes a slice of triangles as STL in the given `format`, with z set to 0
+/// and +z normals.
+///
+/// # Errors
+///
+/// Returns any error from `writer`, and [`io::ErrorKind::InvalidInput`] if a
+/// binary STL would need more than `u32::MAX` facets.
+pub fn write_triangles_stl<W: Write>(
+    writer: &mut W,
+    triangles: &[Triangle],
+    name: &str,
+    format: StlFormat,
+) -> io::Result<()> {
+    write_facets(writer, triangle_facets(triangles), name, format)
+}
+
+/// Exports an indexed 2D triangle mesh to ASCII STL format.
+/// z coordinates are set to 0 and face normals point in the +z direction.
+pub fn tri_mesh_2d_to_stl(mesh: &TriMesh2D, name: &str) -> String {
+    to_string(|w| write_tri_mesh_2d_stl(w, mesh, name, StlFormat::Ascii))
+}
+
+/// Exports an indexed 2D triangle mesh to binary STL.
+pub fn tri_mesh_2d_to_stl_binary(mesh: &TriMesh2D, name: &str) -> Vec<u8> {
+    to_bytes(|w| write_tri_mesh_2d_stl(w, mesh, name, StlFormat::Binary))
+}
+
+/// Writes an indexed 2D triangle mesh as STL in the given `format`, with z
+/// set to 0 and +z normals.
+///
+/// # Errors
+///
+/// Same as [`write_triangles_stl`].
+pub fn write_tri_mesh_2d_stl<W: Write>(
+    writer: &mut W,
+    mesh: &TriMesh2D,
+    name: &str,
+    format: StlFormat,
+) -> io::Result<()> {
+    let facets = mesh.triangles.iter().map(|triangle| {
+        let vertices = triangle.map(|i| [mesh.vertices[i].x, mesh.vertices[i].y, 0.0]);
+        ([0.0, 0.0, 1.0], vertices)
+    });
+    write_facets(writer, facets, name, format)
 }
 
 /// Extracts the boundary surface faces from a tetrahedral mesh.
 /// A face is on the boundary if it appears in exactly one tetrahedron.
+///
+/// Faces are matched by the [`Point3D::index`](crate::Point3D::index) of
+/// their vertices and returned in the order they are first met.
 pub fn extract_surface_faces(tetrahedra: &[Tetrahedron]) -> Vec<Face> {
-    let all_faces: Vec<Face> = tetrahedra.iter().flat_map(|t| t.faces()).collect();
-    let mut surface = Vec::new();
-    for face in &all_faces {
-        let count = all_faces.iter().filter(|f| *f == face).count();
-        if count == 1 {
-            // Avoid duplicates in the output (each unique boundary face appears once)
-            if !surface.iter().any(|f: &Face| f == face) {
-                surface.push(*face);
-            }
-        }
+    let key = |face: &Face| {
+        let mut key = face.vertices().map(|v| v.index);
+        key.sort_unstable();
+        key
+    };
+    let mut counts: HashMap<[i64; 3], usize> = HashMap::new();
+    for face in tetrahedra.iter().flat_map(|t| t.faces()) {
+        *counts.entry(key(&face)).or_insert(0) += 1;
     }
-    surface
+    tetrahedra
+        .iter()
+        .flat_map(|t| t.faces())
+        .filter(|face| counts[&key(face)] == 1)
+        .collect()
 }
 
 fn face_normal(face: &Face) -> (f64, f64, f64) {
@@ -56,30 +208,128 @@ fn face_normal(face: &Face) -> (f64, f64, f64) {
 
 /// Exports a tetrahedral mesh to ASCII STL format by extracting surface faces.
 pub fn tetrahedra_to_stl(tetrahedra: &[Tetrahedron], name: &str) -> String {
-    let surface = extract_surface_faces(tetrahedra);
-    faces_to_stl(&surface, name)
+    tet_mesh_to_stl(&TetMesh::from_tetrahedra(tetrahedra), name)
+}
+
+/// Exports the boundary surface of a tetrahedral mesh to binary STL.
+///
+/// # Panics
+///
+/// Panics if the surface has more than `u32::MAX` faces.
+pub fn tetrahedra_to_stl_binary(tetrahedra: &[Tetrahedron], name: &str) -> Vec<u8> {
+    tet_mesh_to_stl_binary(&TetMesh::from_tetrahedra(tetrahedra), name)
+}
+
+/// Writes the boundary surface of a tetrahedral mesh as STL in the given
+/// `format`.
+///
+/// # Errors
+///
+/// Same as [`write_triangles_stl`].
+pub fn write_tetrahedra_stl<W: Write>(
+    writer: &mut W,
+    tetrahedra: &[Tetrahedron],
+    name: &str,
+    format: StlFormat,
+) -> io::Result<()> {
+    write_tet_mesh_stl(writer, &TetMesh::from_tetrahedra(tetrahedra), name, format)
+}
+
+/// Exports the boundary surface of an indexed tetrahedral mesh to ASCII STL format.
+pub fn tet_mesh_to_stl(mesh: &TetMesh, name: &str) -> String {
+    to_string(|w| write_tet_mesh_stl(w, mesh, name, StlFormat::Ascii))
+}
+
+/// Exports the boundary surface of an indexed tetrahedral mesh to binary STL.
+pub fn tet_mesh_to_stl_binary(mesh: &TetMesh, name: &str) -> Vec<u8> {
+    to_bytes(|w| write_tet_mesh_stl(w, mesh, name, StlFormat::Binary))
+}
+
+/// Writes the boundary surface of an indexed tetrahedral mesh as STL in the
+/// given `format`.
+///
+/// # Errors
+///
+/// Same as [`write_triangles_stl`].
+pub fn write_tet_mesh_stl<W: Write>(
+    writer: &mut W,
+    mesh: &TetMesh,
+    name: &str,
+    format: StlFormat,
+) -> io::Result<()> {
+    write_surface_mesh_stl(writer, &mesh.surface(), name, format)
 }
 
 /// Exports 3D faces to ASCII STL format with computed normals.
 pub fn faces_to_stl(faces: &[Face], name: &str) -> String {
-    let mut result = format!("solid {}\n", name);
-
-    for face in faces {
-        let (nx, ny, nz) = face_normal(face);
-        result.push_str(&format!("  facet normal {} {} {}\n", nx, ny, nz));
-        result.push_str("    outer loop\n");
-        for vertex in &face.vertices() {
-            result.push_str(&format!(
-                "      vertex {} {} {}\n",
-                vertex.x, vertex.y, vertex.z
-            ));
-        }
-        result.push_str("    endloop\n");
-        result.push_str("  endfacet\n");
-    }
+    to_string(|w| write_faces_stl(w, faces, name, StlFormat::Ascii))
+}
+
+/// Exports 3D faces to binary STL with computed normals.
+///
+/// About five times smaller than the ASCII form and much faster to parse,
+/// which matters for large marching cubes surfaces.
+///
+/// # Panics
+///
+/// Panics if there are more than `u32::MAX` faces.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::faces_to_stl_binary;
+/// use meshing::{Face, Point3D};
+///
+/// let p = |index, x, y, z| Point3D { index, x, y, z };
+/// let face = Face { a: p(0, 0.0, 0.0, 0.0), b: p(1, 1.0, 0.0, 0.0), c: p(2, 0.0, 1.0, 0.0) };
+/// let stl = faces_to_stl_binary(&[face], "mesh");
+/// assert_eq!(stl.len(), 80 + 4 + 50);
+/// assert_eq!(u32::from_le_bytes(stl[80..84].try_into().unwrap()), 1);
+/// ```
+pub fn faces_to_stl_binary(faces: &[Face], name: &str) -> Vec<u8> {
+    to_bytes(|w| write_faces_stl(w, faces, name, StlFormat::Binary))
+}
+
+/// Writes 3D faces as STL in the given `format`, with computed normals.
+///
+/// # Errors
+///
+/// Same as [`write_triangles_stl`].
+pub fn write_faces_stl<W: Write>(
+    writer: &mut W,
+    faces: &[Face],
+    name: &str,
+    format: StlFormat,
+) -> io::Result<()> {
+    write_facets(writer, face_facets(faces), name, format)
+}
+
+/// Exports an indexed surface mesh to ASCII STL format with computed normals.
+pub fn surface_mesh_to_stl(mesh: &SurfaceMesh, name: &str) -> String {
+    to_string(|w| write_surface_mesh_stl(w, mesh, name, StlFormat::Ascii))
+}
+
+/// Exports an indexed surface mesh to binary STL with computed normals.
+pub fn surface_mesh_to_stl_binary(mesh: &SurfaceMesh, name: &str) -> Vec<u8> {
+    to_bytes(|w| write_surface_mesh_stl(w, mesh, name, StlFormat::Binary))
+}
 
-    result.push_str(&format!("endsolid {}\n", name));
-    result
+/// Writes an indexed surface mesh as STL in the given `format`, with
+/// computed normals.
+///
+/// Facets are expanded one at a time, so no per-face copy of the mesh is
+/// made.
+///
+/// # Errors
+///
+/// Same as [`write_triangles_stl`].
+pub fn write_surface_mesh_stl<W: Write>(
+    writer: &mut W,
+    mesh: &SurfaceMesh,
+    name: &str,
+    format: StlFormat,
+) -> io::Result<()> {
+    write_facets(writer, surface_mesh_facets(mesh), name, format)
 }
 
 #[cfg(test)]
@@ -409,4 +659,114 @@ mod tests {
         assert!(stl.contains("vertex -0.5 0 10"));
         assert!(stl.contains("vertex 0 -5 0"));
     }
+
+    fn read_f32(bytes: &[u8], offset: usize) -> f32 {
+        f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_faces_to_stl_binary_layout() {
+        let face = Face {
+            a: Point3D {
+                index: 0,
+                x: 1.5,
+                y: 2.5,
+                z: 3.5,
+            },
+            b: Point3D {
+                index: 1,
+                x: 2.5,
+                y: 2.5,
+                z: 3.5,
+            },
+            c: Point3D {
+                index: 2,
+                x: 1.5,
+                y: 3.5,
+                z: 3.5,
+            },
+        };
+        let stl = faces_to_stl_binary(&[face, face], "binary mesh");
+        assert_eq!(stl.len(), 80 + 4 + 2 * 50);
+        assert!(stl.starts_with(b"binary mesh\0"));
+        assert_eq!(u32::from_le_bytes(stl[80..84].try_into().unwrap()), 2);
+        let record = &stl[84..134];
+        let values: Vec<f32> = (0..12).map(|i| read_f32(record, i * 4)).collect();
+        assert_eq!(
+            values,
+            vec![0.0, 0.0, 1.0, 1.5, 2.5, 3.5, 2.5, 2.5, 3.5, 1.5, 3.5, 3.5]
+        );
+        assert_eq!(&record[48..50], &[0, 0]);
+    }
+
+    #[test]
+    fn test_stl_binary_header_is_truncated() {
+        let name = "x".repeat(100);
+        let stl = triangles_to_stl_binary(&[], &name);
+        assert_eq!(stl.len(), 84);
+        assert!(stl[..80].iter().all(|&b| b == b'x'));
+        assert_eq!(&stl[80..84], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_triangles_to_stl_binary_flattens_to_z0() {
+        let triangle = Triangle {
+            a: Point2D {
+                index: 0,
+                x: 0.0,
+                y: 0.0,
+            },
+            b: Point2D {
+                index: 1,
+                x: 1.0,
+                y: 0.0,
+            },
+            c: Point2D {
+                index: 2,
+                x: 0.0,
+                y: 1.0,
+            },
+        };
+        let stl = triangles_to_stl_binary(&[triangle], "flat");
+        assert_eq!(stl.len(), 134);
+        assert_eq!(read_f32(&stl, 84 + 8), 1.0);
+        for vertex in 0..3 {
+            assert_eq!(read_f32(&stl, 84 + 12 + vertex * 12 + 8), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_tetrahedra_to_stl_binary_matches_ascii_facets() {
+        let ascii = tetrahedra_to_stl(&[single_tet()], "tet");
+        let binary = tetrahedra_to_stl_binary(&[single_tet()], "tet");
+        let facets = ascii.matches("facet normal").count();
+        assert_eq!(binary.len(), 84 + facets * 50);
+    }
+
+    #[test]
+    fn test_write_stl_to_writer_matches_string_output() {
+        let faces = TetMesh::from_tetrahedra(&[single_tet()])
+            .surface()
+            .to_faces();
+        let mut ascii = Vec::new();
+        write_faces_stl(&mut ascii, &faces, "w", StlFormat::Ascii).unwrap();
+        assert_eq!(String::from_utf8(ascii).unwrap(), faces_to_stl(&faces, "w"));
+
+        let mut binary = Vec::new();
+        write_tetrahedra_stl(&mut binary, &[single_tet()], "w", StlFormat::Binary).unwrap();
+        assert_eq!(binary, tetrahedra_to_stl_binary(&[single_tet()], "w"));
+    }
+
+    #[test]
+    fn test_write_surface_mesh_stl_matches_faces() {
+        let tets = [single_tet()];
+        let surface = TetMesh::from_tetrahedra(&tets).surface();
+        for format in [StlFormat::Ascii, StlFormat::Binary] {
+            let mut streamed = Vec::new();
+            write_surface_mesh_stl(&mut streamed, &surface, "s", format).unwrap();
+            let mut expected = Vec::new();
+            write_faces_stl(&mut expected, &surface.to_faces(), "s", format).unwrap();
+            assert_eq!(streamed, expected);
+        }
+    }
 }
@@ -0,0 +1,238 @@
+use std::fmt::Write;
+
+use super::extract_surface_faces;
+use super::medit::surface_indices;
+use crate::{Face, Point3D, TetMesh, Tetrahedron};
+
+/// A TetGen mesh: nodes, tetrahedra and boundary faces with optional
+/// markers.
+///
+/// Elements index into `nodes`. `node_markers` and `face_markers` hold
+/// boundary markers and `tetrahedron_regions` region attributes; each may
+/// be left empty, in which case the column is not written.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TetGenMesh {
+    pub nodes: Vec<Point3D>,
+    pub node_markers: Vec<i32>,
+    pub tetrahedra: Vec<[usize; 4]>,
+    pub tetrahedron_regions: Vec<i32>,
+    pub faces: Vec<[usize; 3]>,
+    pub face_markers: Vec<i32>,
+}
+
+/// The contents of the `.node`, `.ele` and `.face` files of a TetGen mesh.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TetGenFiles {
+    pub node: String,
+    pub ele: String,
+    pub face: String,
+}
+
+impl TetGenMesh {
+    /// Builds a mesh holding `tetrahedra` and their boundary faces (from
+    /// [`extract_surface_faces`]), with nodes numbered as in
+    /// [`TetMesh::from_tetrahedra`] and no markers.
+    pub fn from_tetrahedra(tetrahedra: &[Tetrahedron]) -> TetGenMesh {
+        let mesh = TetMesh::from_tetrahedra(tetrahedra);
+        let faces = surface_indices(&mesh, &extract_surface_faces(tetrahedra));
+        TetGenMesh {
+            nodes: mesh.vertices,
+            tetrahedra: mesh.tetrahedra,
+            faces,
+            ..TetGenMesh::default()
+        }
+    }
+
+    /// Expands the tetrahedra back into [`Tetrahedron`]s.
+    pub fn to_tetrahedra(&self) -> Vec<Tetrahedron> {
+        self.tetrahedra
+            .iter()
+            .map(|&[a, b, c, d]| Tetrahedron {
+                a: self.nodes[a],
+                b: self.nodes[b],
+                c: self.nodes[c],
+                d: self.nodes[d],
+            })
+            .collect()
+    }
+
+    /// Expands the faces back into [`Face`]s.
+    pub fn to_faces(&self) -> Vec<Face> {
+        self.faces
+            .iter()
+            .map(|&[a, b, c]| Face {
+                a: self.nodes[a],
+                b: self.nodes[b],
+                c: self.nodes[c],
+            })
+            .collect()
+    }
+}
+
+/// Exports a [`TetGenMesh`] to the `.node`, `.ele` and `.face` formats.
+///
+/// Nodes, tetrahedra and faces are numbered from 1 in the order of the
+/// mesh. Marker and region columns are written only for non-empty vectors.
+///
+/// # Panics
+///
+/// Panics if a non-empty marker or region vector does not have one entry
+/// per node, tetrahedron or face, or an element refers to a missing node.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::{mesh_to_tetgen, TetGenMesh};
+/// use meshing::{Point3D, Tetrahedron};
+///
+/// let p = |index, x, y, z| Point3D { index, x, y, z };
+/// let tet = Tetrahedron {
+///     a: p(0, 0.0, 0.0, 0.0),
+///     b: p(1, 1.0, 0.0, 0.0),
+///     c: p(2, 0.0, 1.0, 0.0),
+///     d: p(3, 0.0, 0.0, 1.0),
+/// };
+/// let mut mesh = TetGenMesh::from_tetrahedra(&[tet]);
+/// mesh.face_markers = vec![1; mesh.faces.len()];
+/// let files = mesh_to_tetgen(&mesh);
+/// assert!(files.node.starts_with("4 3 0 0\n1 0 0 0\n"));
+/// assert!(files.ele.starts_with("1 4 0\n1 1 2 3 4\n"));
+/// assert!(files.face.starts_with("4 1\n1 1 2 3 1\n"));
+/// ```
+pub fn mesh_to_tetgen(mesh: &TetGenMesh) -> TetGenFiles {
+    let n = mesh.nodes.len();
+    for (kind, count, values) in [
+        ("node markers", n, &mesh.node_markers),
+        (
+            "tetrahedron regions",
+            mesh.tetrahedra.len(),
+            &mesh.tetrahedron_regions,
+        ),
+        ("face markers", mesh.faces.len(), &mesh.face_markers),
+    ] {
+        assert!(
+            values.is_empty() || values.len() == count,
+            "{} {} given for {} entries",
+            values.len(),
+            kind,
+            count
+        );
+    }
+    assert!(
+        mesh.tetrahedra
+            .iter()
+            .flatten()
+            .chain(mesh.faces.iter().flatten())
+            .all(|&i| i < n),
+        "element refers to a missing node"
+    );
+
+    let column = |values: &[i32], i: usize| match values.get(i) {
+        Some(value) => format!(" {}", value),
+        None => String::new(),
+    };
+    let mut files = TetGenFiles::default();
+
+    let has_markers = !mesh.node_markers.is_empty() as u8;
+    writeln!(files.node, "{} 3 0 {}", n, has_markers).unwrap();
+    for (i, v) in mesh.nodes.iter().enumerate() {
+        let marker = column(&mesh.node_markers, i);
+        writeln!(files.node, "{} {} {} {}{}", i + 1, v.x, v.y, v.z, marker).unwrap();
+    }
+
+    let has_regions = !mesh.tetrahedron_regions.is_empty() as u8;
+    writeln!(files.ele, "{} 4 {}", mesh.tetrahedra.len(), has_regions).unwrap();
+    for (i, [a, b, c, d]) in mesh.tetrahedra.iter().enumerate() {
+        let region = column(&mesh.tetrahedron_regions, i);
+        writeln!(
+            files.ele,
+            "{} {} {} {} {}{}",
+            i + 1,
+            a + 1,
+            b + 1,
+            c + 1,
+            d + 1,
+            region
+        )
+        .unwrap();
+    }
+
+    let has_markers = !mesh.face_markers.is_empty() as u8;
+    writeln!(files.face, "{} {}", mesh.faces.len(), has_markers).unwrap();
+    for (i, [a, b, c]) in mesh.faces.iter().enumerate() {
+        let marker = column(&mesh.face_markers, i);
+        writeln!(
+            files.face,
+            "{} {} {} {}{}",
+            i + 1,
+            a + 1,
+            b + 1,
+            c + 1,
+            marker
+        )
+        .unwrap();
+    }
+
+    for file in [&mut files.node, &mut files.ele, &mut files.face] {
+        file.push_str("# Generated by meshing\n");
+    }
+    files
+}
+
+/// Exports tetrahedra and their boundary faces to the TetGen `.node`,
+/// `.ele` and `.face` formats.
+pub fn tetrahedra_to_tetgen(tetrahedra: &[Tetrahedron]) -> TetGenFiles {
+    mesh_to_tetgen(&TetGenMesh::from_tetrahedra(tetrahedra))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    fn single_tet() -> Tetrahedron {
+        Tetrahedron {
+            a: p(5, 0.0, 0.0, 0.0),
+            b: p(6, 1.0, 0.0, 0.0),
+            c: p(7, 0.0, 1.0, 0.0),
+            d: p(8, 0.0, 0.0, 1.5),
+        }
+    }
+
+    #[test]
+    fn test_tetrahedra_to_tetgen() {
+        let files = tetrahedra_to_tetgen(&[single_tet()]);
+        assert_eq!(
+            files.node,
+            "4 3 0 0\n1 0 0 0\n2 1 0 0\n3 0 1 0\n4 0 0 1.5\n# Generated by meshing\n"
+        );
+        assert_eq!(files.ele, "1 4 0\n1 1 2 3 4\n# Generated by meshing\n");
+        assert_eq!(
+            files.face,
+            "4 0\n1 1 2 3\n2 1 2 4\n3 1 3 4\n4 2 3 4\n# Generated by meshing\n"
+        );
+    }
+
+    #[test]
+    fn test_markers_and_regions() {
+        let mut mesh = TetGenMesh::from_tetrahedra(&[single_tet()]);
+        mesh.node_markers = vec![1, 0, 0, 2];
+        mesh.tetrahedron_regions = vec![-3];
+        let files = mesh_to_tetgen(&mesh);
+        assert!(files.node.starts_with("4 3 0 1\n1 0 0 0 1\n"));
+        assert!(files.node.contains("\n4 0 0 1.5 2\n"));
+        assert!(files.ele.starts_with("1 4 1\n1 1 2 3 4 -3\n"));
+        assert!(files.face.starts_with("4 0\n"));
+    }
+
+    #[test]
+    #[should_panic(expected = "face markers")]
+    fn test_wrong_marker_count_panics() {
+        let mut mesh = TetGenMesh::from_tetrahedra(&[single_tet()]);
+        mesh.face_markers = vec![1];
+        mesh_to_tetgen(&mesh);
+    }
+}
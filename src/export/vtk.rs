@@ -1,4 +1,7 @@
-use crate::{Point3D, Tetrahedron};
+use std::io::{self, Write};
+
+use super::to_string;
+use crate::{TetMesh, Tetrahedron};
 
 /// Exports a tetrahedral mesh to VTK Legacy unstructured grid format (.vtk).
 ///
@@ -30,70 +33,74 @@ use crate::{Point3D, Tetrahedron};
 /// assert!(vtk.contains("CELL_TYPES"));
 /// ```
 pub fn tetrahedra_to_vtk(tetrahedra: &[Tetrahedron], title: &str) -> String {
-    // Collect unique vertices
-    let mut vertices: Vec<(i64, Point3D)> = Vec::new();
-    for tet in tetrahedra {
-        for v in tet.vertices() {
-            if !vertices.iter().any(|(idx, _)| *idx == v.index) {
-                vertices.push((v.index, v));
-            }
-        }
-    }
-    vertices.sort_by_key(|(idx, _)| *idx);
+    to_string(|w| write_tetrahedra_vtk(w, tetrahedra, title))
+}
+
+/// Writes a tetrahedral mesh as a VTK Legacy unstructured grid.
+///
+/// # Errors
+///
+/// Returns any error from `writer`.
+pub fn write_tetrahedra_vtk<W: Write>(
+    writer: &mut W,
+    tetrahedra: &[Tetrahedron],
+    title: &str,
+) -> io::Result<()> {
+    write_tet_mesh_vtk(writer, &TetMesh::from_tetrahedra(tetrahedra), title)
+}
 
-    let num_points = vertices.len();
-    let num_cells = tetrahedra.len();
+/// Exports an indexed tetrahedral mesh to VTK Legacy unstructured grid format (.vtk).
+///
+/// Points are written in the order of `mesh.vertices` and cells reference
+/// them directly, so no vertex table needs to be rebuilt.
+pub fn tet_mesh_to_vtk(mesh: &TetMesh, title: &str) -> String {
+    to_string(|w| write_tet_mesh_vtk(w, mesh, title))
+}
 
-    let mut result = String::new();
+/// Writes an indexed tetrahedral mesh as a VTK Legacy unstructured grid.
+///
+/// Lines are written one at a time, so wrap files in a
+/// [`std::io::BufWriter`].
+///
+/// # Errors
+///
+/// Returns any error from `writer`.
+pub fn write_tet_mesh_vtk<W: Write>(writer: &mut W, mesh: &TetMesh, title: &str) -> io::Result<()> {
+    let num_points = mesh.vertices.len();
+    let num_cells = mesh.tetrahedra.len();
 
     // VTK header
-    result.push_str("# vtk DataFile Version 3.0\n");
-    result.push_str(title);
-    result.push('\n');
-    result.push_str("ASCII\n");
-    result.push_str("DATASET UNSTRUCTURED_GRID\n");
+    writeln!(writer, "# vtk DataFile Version 3.0")?;
+    writeln!(writer, "{}", title)?;
+    writeln!(writer, "ASCII")?;
+    writeln!(writer, "DATASET UNSTRUCTURED_GRID")?;
 
     // Points
-    result.push_str(&format!("POINTS {} double\n", num_points));
-    for (_, v) in &vertices {
-        result.push_str(&format!("{} {} {}\n", v.x, v.y, v.z));
+    writeln!(writer, "POINTS {} double", num_points)?;
+    for v in &mesh.vertices {
+        writeln!(writer, "{} {} {}", v.x, v.y, v.z)?;
     }
 
     // Cells: each tetrahedron has 4 vertices, so cell size entry = 5 (count + 4 indices)
     let cell_list_size = num_cells * 5;
-    result.push_str(&format!("CELLS {} {}\n", num_cells, cell_list_size));
-    for tet in tetrahedra {
-        let a = vertices
-            .iter()
-            .position(|(idx, _)| *idx == tet.a.index)
-            .unwrap();
-        let b = vertices
-            .iter()
-            .position(|(idx, _)| *idx == tet.b.index)
-            .unwrap();
-        let c = vertices
-            .iter()
-            .position(|(idx, _)| *idx == tet.c.index)
-            .unwrap();
-        let d = vertices
-            .iter()
-            .position(|(idx, _)| *idx == tet.d.index)
-            .unwrap();
-        result.push_str(&format!("4 {} {} {} {}\n", a, b, c, d));
+    writeln!(writer, "CELLS {} {}", num_cells, cell_list_size)?;
+    for [a, b, c, d] in &mesh.tetrahedra {
+        writeln!(writer, "4 {} {} {} {}", a, b, c, d)?;
     }
 
     // Cell types: 10 = VTK_TETRA
-    result.push_str(&format!("CELL_TYPES {}\n", num_cells));
+    writeln!(writer, "CELL_TYPES {}", num_cells)?;
     for _ in 0..num_cells {
-        result.push_str("10\n");
+        writeln!(writer, "10")?;
     }
 
-    result
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Point3D;
 
     fn single_tet() -> Tetrahedron {
         Tetrahedron {
@@ -349,4 +356,22 @@ mod tests {
         assert!(vtk.contains("POINTS 5 double"));
         assert!(vtk.contains("CELLS 2 10"));
     }
+
+    #[test]
+    fn test_tet_mesh_to_vtk_uses_vertex_buffer_order() {
+        let mesh = TetMesh::from_tetrahedra(&[single_tet()]);
+        let vtk = tet_mesh_to_vtk(&mesh, "indexed");
+        assert_eq!(vtk, tetrahedra_to_vtk(&[single_tet()], "indexed"));
+        assert!(vtk.contains("4 0 1 2 3"));
+    }
+
+    #[test]
+    fn test_write_vtk_matches_string_export() {
+        let mut out = Vec::new();
+        write_tetrahedra_vtk(&mut out, &[single_tet()], "streamed").unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            tetrahedra_to_vtk(&[single_tet()], "streamed")
+        );
+    }
 }
@@ -0,0 +1,634 @@
+use std::io::{self, Write};
+
+use super::gltf::base64_encode;
+use super::{invalid, to_bytes};
+use crate::{Face, SurfaceMesh, TetMesh, Tetrahedron};
+
+/// How the data arrays of a `.vtu` file are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VtuEncoding {
+    /// Whitespace-separated text inside each `DataArray`.
+    #[default]
+    Ascii,
+    /// Inline base64 blocks (`format="binary"`), each prefixed by its
+    /// `UInt64` byte count.
+    Base64,
+    /// Raw little-endian blocks in a trailing `<AppendedData
+    /// encoding="raw">` section (`format="appended"`). Most compact, but the
+    /// file is no longer valid UTF-8.
+    Appended,
+}
+
+/// Values of a [`VtuArray`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum VtuValues {
+    Float64(Vec<f64>),
+    Int32(Vec<i32>),
+}
+
+/// A named point-data or cell-data array.
+///
+/// `values` holds `components` entries per point (or cell), tuple by
+/// tuple.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VtuArray {
+    pub name: String,
+    pub components: usize,
+    pub values: VtuValues,
+}
+
+impl VtuArray {
+    /// A scalar field, e.g. per-tet quality or field values at points.
+    pub fn scalars(name: &str, values: Vec<f64>) -> VtuArray {
+        VtuArray {
+            name: name.to_string(),
+            components: 1,
+            values: VtuValues::Float64(values),
+        }
+    }
+
+    /// A 3-component vector field, e.g. normals or gradients.
+    pub fn vectors(name: &str, values: &[[f64; 3]]) -> VtuArray {
+        VtuArray {
+            name: name.to_string(),
+            components: 3,
+            values: VtuValues::Float64(values.iter().flatten().copied().collect()),
+        }
+    }
+
+    /// An integer label per point or cell, e.g. a region id.
+    pub fn labels(name: &str, values: Vec<i32>) -> VtuArray {
+        VtuArray {
+            name: name.to_string(),
+            components: 1,
+            values: VtuValues::Int32(values),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match &self.values {
+            VtuValues::Float64(v) => v.len(),
+            VtuValues::Int32(v) => v.len(),
+        }
+    }
+}
+
+/// Point-data and cell-data arrays attached to a `.vtu` file.
+///
+/// Point arrays follow the order of the mesh vertices and cell arrays the
+/// order of its tetrahedra or faces.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VtuAttributes {
+    pub point_data: Vec<VtuArray>,
+    pub cell_data: Vec<VtuArray>,
+}
+
+/// A `DataArray` ready to be written.
+struct Array<'a> {
+    name: &'a str,
+    components: usize,
+    values: Values<'a>,
+}
+
+enum Values<'a> {
+    Float64(&'a [f64]),
+    Int32(&'a [i32]),
+    Int64(Vec<i64>),
+    UInt8(Vec<u8>),
+}
+
+impl Values<'_> {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Values::Float64(_) => "Float64",
+            Values::Int32(_) => "Int32",
+            Values::Int64(_) => "Int64",
+            Values::UInt8(_) => "UInt8",
+        }
+    }
+
+    fn to_ascii(&self, row: usize) -> String {
+        fn join<T: ToString>(values: &[T], row: usize) -> String {
+            values
+                .chunks(row.max(1))
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        match self {
+            Values::Float64(v) => join(v, row),
+            Values::Int32(v) => join(v, row),
+            Values::Int64(v) => join(v, row),
+            Values::UInt8(v) => join(v, row),
+        }
+    }
+
+    /// Little-endian bytes prefixed by the `UInt64` block size.
+    fn to_block(&self) -> Vec<u8> {
+        let data: Vec<u8> = match self {
+            Values::Float64(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            Values::Int32(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            Values::Int64(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            Values::UInt8(v) => v.clone(),
+        };
+        let mut block = (data.len() as u64).to_le_bytes().to_vec();
+        block.extend(data);
+        block
+    }
+}
+
+impl<'a> Array<'a> {
+    fn from_attribute(array: &'a VtuArray) -> Array<'a> {
+        Array {
+            name: &array.name,
+            components: array.components,
+            values: match &array.values {
+                VtuValues::Float64(v) => Values::Float64(v),
+                VtuValues::Int32(v) => Values::Int32(v),
+            },
+        }
+    }
+}
+
+/// The unstructured grid to write: points, cells of a single type and the
+/// attached data.
+struct Grid<'a> {
+    points: Vec<f64>,
+    cells: Vec<i64>,
+    nodes_per_cell: usize,
+    cell_type: u8,
+    attributes: &'a VtuAttributes,
+}
+
+fn validate(arrays: &[VtuArray], count: usize, kind: &str) -> io::Result<()> {
+    for array in arrays {
+        if array.name.is_empty() || array.name.contains(['"', '<', '>', '&']) {
+            return Err(invalid(format!(
+                "invalid {} array name '{}'",
+                kind, array.name
+            )));
+        }
+        if array.components == 0 || array.len() != array.components * count {
+            return Err(invalid(format!(
+                "{} array '{}' has {} values, expected {} components for each of {} {}s",
+                kind,
+                array.name,
+                array.len(),
+                array.components,
+                count,
+                kind
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn write_grid<W: Write>(writer: &mut W, grid: Grid, encoding: VtuEncoding) -> io::Result<()> {
+    let num_points = grid.points.len() / 3;
+    let num_cells = grid.cells.len() / grid.nodes_per_cell;
+    validate(&grid.attributes.point_data, num_points, "point")?;
+    validate(&grid.attributes.cell_data, num_cells, "cell")?;
+
+    let offsets = (1..=num_cells)
+        .map(|i| (i * grid.nodes_per_cell) as i64)
+        .collect();
+    let sections: [(&str, Vec<Array>); 4] = [
+        (
+            "PointData",
+            grid.attributes
+                .point_data
+                .iter()
+                .map(Array::from_attribute)
+                .collect(),
+        ),
+        (
+            "CellData",
+            grid.attributes
+                .cell_data
+                .iter()
+                .map(Array::from_attribute)
+                .collect(),
+        ),
+        (
+            "Points",
+            vec![Array {
+                name: "Points",
+                components: 3,
+                values: Values::Float64(&grid.points),
+            }],
+        ),
+        (
+            "Cells",
+            vec![
+                Array {
+                    name: "connectivity",
+                    components: grid.nodes_per_cell,
+                    values: Values::Int64(grid.cells.clone()),
+                },
+                Array {
+                    name: "offsets",
+                    components: 1,
+                    values: Values::Int64(offsets),
+                },
+                Array {
+                    name: "types",
+                    components: 1,
+                    values: Values::UInt8(vec![grid.cell_type; num_cells]),
+                },
+            ],
+        ),
+    ];
+
+    writeln!(writer, "<?xml version=\"1.0\"?>")?;
+    writeln!(
+        writer,
+        "<VTKFile type=\"UnstructuredGrid\" version=\"1.0\" byte_order=\"LittleEndian\" header_type=\"UInt64\">"
+    )?;
+    writeln!(writer, "  <UnstructuredGrid>")?;
+    writeln!(
+        writer,
+        "    <Piece NumberOfPoints=\"{}\" NumberOfCells=\"{}\">",
+        num_points, num_cells
+    )?;
+
+    let mut appended: Vec<u8> = Vec::new();
+    for (section, arrays) in &sections {
+        writeln!(writer, "      <{}>", section)?;
+        for array in arrays {
+            // Connectivity is a flat list; its component count only sets the
+            // row width of the ASCII form.
+            let components = if array.name == "connectivity" {
+                1
+            } else {
+                array.components
+            };
+            write!(
+                writer,
+                "        <DataArray type=\"{}\" Name=\"{}\" NumberOfComponents=\"{}\"",
+                array.values.type_name(),
+                array.name,
+                components
+            )?;
+            match encoding {
+                VtuEncoding::Ascii => {
+                    writeln!(writer, " format=\"ascii\">")?;
+                    writeln!(writer, "{}", array.values.to_ascii(array.components))?;
+                    writeln!(writer, "        </DataArray>")?;
+                }
+                VtuEncoding::Base64 => {
+                    writeln!(writer, " format=\"binary\">")?;
+                    writeln!(writer, "{}", base64_encode(&array.values.to_block()))?;
+                    writeln!(writer, "        </DataArray>")?;
+                }
+                VtuEncoding::Appended => {
+                    writeln!(
+                        writer,
+                        " format=\"appended\" offset=\"{}\"/>",
+                        appended.len()
+                    )?;
+                    appended.extend(array.values.to_block());
+                }
+            }
+        }
+        writeln!(writer, "      </{}>", section)?;
+    }
+
+    writeln!(writer, "    </Piece>")?;
+    writeln!(writer, "  </UnstructuredGrid>")?;
+    if encoding == VtuEncoding::Appended {
+        write!(writer, "  <AppendedData encoding=\"raw\">\n   _")?;
+        writer.write_all(&appended)?;
+        writeln!(writer, "\n  </AppendedData>")?;
+    }
+    writeln!(writer, "</VTKFile>")
+}
+
+/// Writes an indexed tetrahedral mesh as a VTK XML unstructured grid
+/// (`.vtu`, cell type 10) with optional point and cell data.
+///
+/// # Errors
+///
+/// Returns any error from `writer`, and [`io::ErrorKind::InvalidInput`] if
+/// a data array is empty-named, has a name containing `"`, `<`, `>` or
+/// `&`, or does not hold `components` values per point or cell.
+pub fn write_tet_mesh_vtu<W: Write>(
+    writer: &mut W,
+    mesh: &TetMesh,
+    attributes: &VtuAttributes,
+    encoding: VtuEncoding,
+) -> io::Result<()> {
+    let grid = Grid {
+        points: mesh.vertices.iter().flat_map(|v| [v.x, v.y, v.z]).collect(),
+        cells: mesh
+            .tetrahedra
+            .iter()
+            .flatten()
+            .map(|&i| i as i64)
+            .collect(),
+        nodes_per_cell: 4,
+        cell_type: 10,
+        attributes,
+    };
+    write_grid(writer, grid, encoding)
+}
+
+/// Writes an indexed triangle surface as a VTK XML unstructured grid
+/// (`.vtu`, cell type 5) with optional point and cell data.
+///
+/// # Errors
+///
+/// Same as [`write_tet_mesh_vtu`].
+pub fn write_surface_mesh_vtu<W: Write>(
+    writer: &mut W,
+    mesh: &SurfaceMesh,
+    attributes: &VtuAttributes,
+    encoding: VtuEncoding,
+) -> io::Result<()> {
+    let grid = Grid {
+        points: mesh.vertices.iter().flat_map(|v| [v.x, v.y, v.z]).collect(),
+        cells: mesh.faces.iter().flatten().map(|&i| i as i64).collect(),
+        nodes_per_cell: 3,
+        cell_type: 5,
+        attributes,
+    };
+    write_grid(writer, grid, encoding)
+}
+
+/// Exports an indexed tetrahedral mesh to `.vtu`.
+///
+/// # Panics
+///
+/// Panics if the attributes are invalid, see [`write_tet_mesh_vtu`].
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::{tet_mesh_to_vtu, VtuArray, VtuAttributes, VtuEncoding};
+/// use meshing::{Point3D, TetMesh, Tetrahedron};
+///
+/// let p = |index, x, y, z| Point3D { index, x, y, z };
+/// let tet = Tetrahedron {
+///     a: p(0, 0.0, 0.0, 0.0),
+///     b: p(1, 1.0, 0.0, 0.0),
+///     c: p(2, 0.0, 1.0, 0.0),
+///     d: p(3, 0.0, 0.0, 1.0),
+/// };
+/// let mesh = TetMesh::from_tetrahedra(&[tet]);
+/// let attributes = VtuAttributes {
+///     point_data: vec![VtuArray::scalars("height", mesh.vertices.iter().map(|v| v.z).collect())],
+///     cell_data: vec![VtuArray::labels("region", vec![7])],
+/// };
+/// let vtu = tet_mesh_to_vtu(&mesh, &attributes, VtuEncoding::Base64);
+/// assert!(String::from_utf8(vtu).unwrap().contains("Name=\"region\""));
+/// ```
+pub fn tet_mesh_to_vtu(
+    mesh: &TetMesh,
+    attributes: &VtuAttributes,
+    encoding: VtuEncoding,
+) -> Vec<u8> {
+    to_bytes(|w| write_tet_mesh_vtu(w, mesh, attributes, encoding))
+}
+
+/// Exports tetrahedra to `.vtu`.
+///
+/// Points are numbered as in [`TetMesh::from_tetrahedra`] (sorted by
+/// [`Point3D::index`](crate::Point3D::index)), which is the order point data
+/// must follow; cell data follows the order of `tetrahedra`.
+///
+/// # Panics
+///
+/// Panics if the attributes are invalid, see [`write_tet_mesh_vtu`].
+pub fn tetrahedra_to_vtu(
+    tetrahedra: &[Tetrahedron],
+    attributes: &VtuAttributes,
+    encoding: VtuEncoding,
+) -> Vec<u8> {
+    tet_mesh_to_vtu(&TetMesh::from_tetrahedra(tetrahedra), attributes, encoding)
+}
+
+/// Exports an indexed triangle surface to `.vtu`.
+///
+/// # Panics
+///
+/// Panics if the attributes are invalid, see [`write_tet_mesh_vtu`].
+pub fn surface_mesh_to_vtu(
+    mesh: &SurfaceMesh,
+    attributes: &VtuAttributes,
+    encoding: VtuEncoding,
+) -> Vec<u8> {
+    to_bytes(|w| write_surface_mesh_vtu(w, mesh, attributes, encoding))
+}
+
+/// Exports 3D faces to `.vtu`, numbering points as
+/// [`SurfaceMesh::from_faces`] does.
+///
+/// # Panics
+///
+/// Panics if the attributes are invalid, see [`write_tet_mesh_vtu`].
+pub fn faces_to_vtu(faces: &[Face], attributes: &VtuAttributes, encoding: VtuEncoding) -> Vec<u8> {
+    surface_mesh_to_vtu(&SurfaceMesh::from_faces(faces), attributes, encoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel_mesh::voxel_mesh_indexed;
+    use crate::Point3D;
+
+    fn p(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    fn cube() -> TetMesh {
+        voxel_mesh_indexed(p(0, 0.0, 0.0, 0.0), p(0, 1.0, 1.0, 1.0), 1, 1, 1, &|_| true)
+    }
+
+    fn attributes(mesh: &TetMesh) -> VtuAttributes {
+        VtuAttributes {
+            point_data: vec![
+                VtuArray::scalars("height", mesh.vertices.iter().map(|v| v.z).collect()),
+                VtuArray::vectors(
+                    "position",
+                    &mesh
+                        .vertices
+                        .iter()
+                        .map(|v| [v.x, v.y, v.z])
+                        .collect::<Vec<_>>(),
+                ),
+            ],
+            cell_data: vec![
+                VtuArray::scalars(
+                    "volume",
+                    mesh.to_tetrahedra()
+                        .iter()
+                        .map(|t| t.signed_volume().abs())
+                        .collect(),
+                ),
+                VtuArray::labels("region", (0..mesh.num_tetrahedra() as i32).collect()),
+            ],
+        }
+    }
+
+    /// Returns the text between the opening tag of the `DataArray` named
+    /// `name` and its closing tag.
+    fn array_body<'a>(xml: &'a str, name: &str) -> (&'a str, &'a str) {
+        let name_at = xml.find(&format!("Name=\"{}\"", name)).unwrap();
+        let start = xml[..name_at].rfind("<DataArray").unwrap();
+        let tag_end = start + xml[start..].find('>').unwrap();
+        let body_end = tag_end + xml[tag_end..].find("</DataArray>").unwrap();
+        (&xml[start..tag_end], xml[tag_end + 1..body_end].trim())
+    }
+
+    #[test]
+    fn test_ascii_layout() {
+        let mesh = cube();
+        let vtu = String::from_utf8(tet_mesh_to_vtu(
+            &mesh,
+            &attributes(&mesh),
+            VtuEncoding::Ascii,
+        ))
+        .unwrap();
+        assert!(vtu.starts_with("<?xml version=\"1.0\"?>\n<VTKFile type=\"UnstructuredGrid\""));
+        assert!(vtu.contains("<Piece NumberOfPoints=\"8\" NumberOfCells=\"5\">"));
+        assert!(vtu.trim_end().ends_with("</VTKFile>"));
+
+        let (_, types) = array_body(&vtu, "types");
+        assert_eq!(types, "10\n10\n10\n10\n10");
+        let (_, offsets) = array_body(&vtu, "offsets");
+        assert_eq!(offsets, "4\n8\n12\n16\n20");
+        let (_, connectivity) = array_body(&vtu, "connectivity");
+        let first: Vec<String> = mesh.tetrahedra[0].iter().map(|i| i.to_string()).collect();
+        assert_eq!(connectivity.lines().next().unwrap(), first.join(" "));
+
+        let (tag, volume) = array_body(&vtu, "volume");
+        assert!(tag.contains("type=\"Float64\""));
+        let total: f64 = volume.lines().map(|v| v.parse::<f64>().unwrap()).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+        let (tag, _) = array_body(&vtu, "position");
+        assert!(tag.contains("NumberOfComponents=\"3\""));
+        let (tag, region) = array_body(&vtu, "region");
+        assert!(tag.contains("type=\"Int32\""));
+        assert_eq!(region, "0\n1\n2\n3\n4");
+
+        let point_data = vtu.find("<PointData>").unwrap();
+        let cell_data = vtu.find("<CellData>").unwrap();
+        assert!(point_data < vtu.find("Name=\"height\"").unwrap());
+        assert!(cell_data < vtu.find("Name=\"region\"").unwrap());
+    }
+
+    #[test]
+    fn test_base64_blocks() {
+        let mesh = cube();
+        let vtu = String::from_utf8(tet_mesh_to_vtu(
+            &mesh,
+            &attributes(&mesh),
+            VtuEncoding::Base64,
+        ))
+        .unwrap();
+        let (tag, region) = array_body(&vtu, "region");
+        assert!(tag.contains("format=\"binary\""));
+        let mut expected = 20u64.to_le_bytes().to_vec();
+        for i in 0..5i32 {
+            expected.extend(i.to_le_bytes());
+        }
+        assert_eq!(region, base64_encode(&expected));
+    }
+
+    #[test]
+    fn test_appended_offsets() {
+        let mesh = cube();
+        let vtu = tet_mesh_to_vtu(&mesh, &attributes(&mesh), VtuEncoding::Appended);
+        let marker = b"<AppendedData encoding=\"raw\">\n   _";
+        let data_start =
+            vtu.windows(marker.len()).position(|w| w == marker).unwrap() + marker.len();
+        let xml = String::from_utf8_lossy(&vtu[..data_start]);
+        let block = |name: &str| -> &[u8] {
+            let tag = &xml[xml.find(&format!("Name=\"{}\"", name)).unwrap()..];
+            let offset: usize = tag[tag.find("offset=\"").unwrap() + 8..]
+                .split('"')
+                .next()
+                .unwrap()
+                .parse()
+                .unwrap();
+            let start = data_start + offset;
+            let size = u64::from_le_bytes(vtu[start..start + 8].try_into().unwrap()) as usize;
+            &vtu[start + 8..start + 8 + size]
+        };
+
+        assert_eq!(block("types"), &[10; 5]);
+        let heights: Vec<f64> = block("height")
+            .chunks_exact(8)
+            .map(|c| f64::from_le_bytes(c.try_into().unwrap()))
+            .collect();
+        assert_eq!(
+            heights,
+            mesh.vertices.iter().map(|v| v.z).collect::<Vec<_>>()
+        );
+        let connectivity: Vec<i64> = block("connectivity")
+            .chunks_exact(8)
+            .map(|c| i64::from_le_bytes(c.try_into().unwrap()))
+            .collect();
+        let expected: Vec<i64> = mesh
+            .tetrahedra
+            .iter()
+            .flatten()
+            .map(|&i| i as i64)
+            .collect();
+        assert_eq!(connectivity, expected);
+        assert!(vtu.ends_with(b"\n  </AppendedData>\n</VTKFile>\n"));
+    }
+
+    #[test]
+    fn test_surface_mesh_uses_triangle_cells() {
+        let surface = cube().surface();
+        let attributes = VtuAttributes {
+            point_data: Vec::new(),
+            cell_data: vec![VtuArray::labels("patch", vec![1; surface.num_faces()])],
+        };
+        let vtu = String::from_utf8(surface_mesh_to_vtu(
+            &surface,
+            &attributes,
+            VtuEncoding::Ascii,
+        ))
+        .unwrap();
+        assert!(vtu.contains("NumberOfPoints=\"8\" NumberOfCells=\"12\""));
+        let (_, types) = array_body(&vtu, "types");
+        assert!(types.lines().all(|t| t == "5"));
+        let (_, offsets) = array_body(&vtu, "offsets");
+        assert_eq!(offsets.lines().last(), Some("36"));
+        assert_eq!(
+            faces_to_vtu(&surface.to_faces(), &attributes, VtuEncoding::Ascii),
+            vtu.into_bytes()
+        );
+    }
+
+    #[test]
+    fn test_invalid_arrays_are_rejected() {
+        let mesh = cube();
+        let cases = [
+            VtuAttributes {
+                point_data: vec![VtuArray::scalars("short", vec![0.0; 7])],
+                cell_data: Vec::new(),
+            },
+            VtuAttributes {
+                point_data: Vec::new(),
+                cell_data: vec![VtuArray::vectors("flow", &[[0.0; 3]; 4])],
+            },
+            VtuAttributes {
+                point_data: vec![VtuArray::scalars("a\"b", vec![0.0; 8])],
+                cell_data: Vec::new(),
+            },
+        ];
+        for attributes in &cases {
+            let err = write_tet_mesh_vtu(&mut Vec::new(), &mesh, attributes, VtuEncoding::Ascii)
+                .unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
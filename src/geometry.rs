@@ -1,4 +1,4 @@
-use crate::{Edge, Point2D, Triangle};
+use crate::{Point2D, Triangle};
 
 /// Creates a super-triangle that encloses all the given points.
 ///
@@ -52,34 +52,3 @@ pub fn create_super_triangle(points: &[Point2D]) -> Triangle {
 
     Triangle { a, b, c }
 }
-
-/// Returns `true` if the given edge is shared by any of the provided triangles.
-///
-/// An edge is considered shared if it matches any edge of any triangle,
-/// regardless of direction.
-pub fn edge_is_shared_by_triangles(edge: &Edge, triangles: &[Triangle]) -> bool {
-    for triangle in triangles {
-        let edges_of_triangle = triangle.edges();
-        for edge_of_triangle in edges_of_triangle {
-            if edge_of_triangle == *edge {
-                return true;
-            }
-            if edge_of_triangle.reverse() == *edge {
-                return true;
-            }
-        }
-    }
-    false
-}
-
-/// Creates a new triangle from an edge and a point.
-///
-/// The new triangle has vertices at the edge's start, the edge's end, and
-/// the given point. Used during the re-triangulation step of Bowyer-Watson.
-pub fn retriangulate(edge: &Edge, point: &Point2D) -> Triangle {
-    Triangle {
-        a: edge.start,
-        b: edge.end,
-        c: *point,
-    }
-}
@@ -1,4 +1,4 @@
-use crate::{Face, Point3D, Tetrahedron};
+use crate::{Point3D, Tetrahedron};
 
 pub fn create_super_tetrahedron(points: &Vec<Point3D>) -> Tetrahedron {
     if points.is_empty() {
@@ -71,24 +71,3 @@ pub fn create_super_tetrahedron(points: &Vec<Point3D>) -> Tetrahedron {
 
     Tetrahedron { a, b, c, d }
 }
-
-pub fn face_is_shared_by_tetrahedra(face: &Face, tetrahedra: &Vec<Tetrahedron>) -> bool {
-    for tet in tetrahedra {
-        let faces_of_tet = tet.faces();
-        for face_of_tet in faces_of_tet {
-            if face_of_tet == *face {
-                return true;
-            }
-        }
-    }
-    false
-}
-
-pub fn retetrahedralize(face: &Face, point: &Point3D) -> Tetrahedron {
-    Tetrahedron {
-        a: face.a,
-        b: face.b,
-        c: face.c,
-        d: *point,
-    }
-}
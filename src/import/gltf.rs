@@ -0,0 +1,850 @@
+use std::io::Read;
+
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+
+use super::stl::Welder;
+use crate::error::MeshingError;
+use crate::{Face, SurfaceMesh};
+
+fn malformed(message: impl Into<String>) -> MeshingError {
+    MeshingError::MalformedFile(format!("glTF: {}", message.into()))
+}
+
+/// The parts of a glTF document the importer reads; other members are
+/// ignored.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Document {
+    asset: Asset,
+    #[serde(default)]
+    extensions_required: Vec<String>,
+    scene: Option<usize>,
+    scenes: Option<Vec<Scene>>,
+    #[serde(default)]
+    nodes: Vec<Node>,
+    #[serde(default)]
+    meshes: Vec<Mesh>,
+    #[serde(default)]
+    accessors: Vec<Accessor>,
+    #[serde(default)]
+    buffer_views: Vec<BufferView>,
+    #[serde(default)]
+    buffers: Vec<Buffer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Scene {
+    #[serde(default)]
+    nodes: Vec<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Node {
+    #[serde(default)]
+    children: Vec<usize>,
+    mesh: Option<usize>,
+    matrix: Option<Matrix>,
+    translation: Option<[f64; 3]>,
+    rotation: Option<[f64; 4]>,
+    scale: Option<[f64; 3]>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Mesh {
+    primitives: Vec<Primitive>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Primitive {
+    attributes: Attributes,
+    indices: Option<usize>,
+    #[serde(default = "triangles_mode")]
+    mode: u32,
+}
+
+fn triangles_mode() -> u32 {
+    4
+}
+
+#[derive(Debug, Deserialize)]
+struct Attributes {
+    #[serde(rename = "POSITION")]
+    position: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Accessor {
+    buffer_view: Option<usize>,
+    #[serde(default)]
+    byte_offset: usize,
+    component_type: u32,
+    #[serde(default)]
+    normalized: bool,
+    count: usize,
+    #[serde(rename = "type")]
+    kind: String,
+    sparse: Option<IgnoredAny>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BufferView {
+    buffer: usize,
+    #[serde(default)]
+    byte_offset: usize,
+    byte_length: usize,
+    byte_stride: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Buffer {
+    byte_length: usize,
+    uri: Option<String>,
+}
+
+/// Element `i` of the top-level list `list`.
+fn item<'a, T>(items: &'a [T], list: &str, i: usize) -> Result<&'a T, MeshingError> {
+    items
+        .get(i)
+        .ok_or_else(|| malformed(format!("{} {} does not exist", list, i)))
+}
+
+fn parse_json(json: &[u8]) -> Result<Document, MeshingError> {
+    serde_json::from_slice(json).map_err(|e| malformed(format!("invalid document: {}", e)))
+}
+
+fn base64_decode(text: &str) -> Result<Vec<u8>, MeshingError> {
+    let text = text.trim_end_matches('=').as_bytes();
+    let mut data = Vec::with_capacity(text.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut count = 0;
+    for &c in text {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(malformed("invalid base64 data URI")),
+        };
+        bits = (bits << 6) | value as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            data.push((bits >> count) as u8);
+        }
+    }
+    Ok(data)
+}
+
+/// Splits a GLB into its JSON text and BIN chunk.
+fn parse_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), MeshingError> {
+    let word = |i: usize| {
+        bytes
+            .get(i..i + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().expect("4 bytes")) as usize)
+    };
+    if word(4) != Some(2) {
+        return Err(malformed("GLB version is not 2"));
+    }
+    let length = word(8).ok_or_else(|| malformed("truncated GLB header"))?;
+    if length > bytes.len() {
+        return Err(malformed(format!(
+            "GLB header announces {} bytes but the file has {}",
+            length,
+            bytes.len()
+        )));
+    }
+    let mut json = None;
+    let mut bin = None;
+    let mut pos = 12;
+    while pos < length {
+        let (Some(chunk_length), Some(chunk_type)) = (word(pos), word(pos + 4)) else {
+            return Err(malformed("truncated GLB chunk header"));
+        };
+        let end = (pos + 8)
+            .checked_add(chunk_length)
+            .filter(|&end| end <= length)
+            .ok_or_else(|| malformed("GLB chunk exceeds the file"))?;
+        let data = &bytes[pos + 8..end];
+        match chunk_type {
+            0x4E4F534A if json.is_none() => json = Some(data),
+            0x004E4942 if bin.is_none() => bin = Some(data),
+            _ => {}
+        }
+        pos = end;
+    }
+    let json = json.ok_or_else(|| malformed("GLB has no JSON chunk"))?;
+    Ok((json, bin))
+}
+
+/// The data of every buffer: the BIN chunk for a GLB buffer without a
+/// `uri`, or a decoded base64 data URI.
+fn load_buffers(buffers: &[Buffer], bin: Option<&[u8]>) -> Result<Vec<Vec<u8>>, MeshingError> {
+    let mut data = Vec::new();
+    for (i, buffer) in buffers.iter().enumerate() {
+        let bytes = match &buffer.uri {
+            Some(uri) => {
+                let payload = uri
+                    .strip_prefix("data:")
+                    .and_then(|rest| rest.split_once(";base64,"))
+                    .map(|(_, payload)| payload)
+                    .ok_or_else(|| {
+                        malformed(format!(
+                            "buffer {} refers to external file '{}'; embed it or use GLB",
+                            i, uri
+                        ))
+                    })?;
+                base64_decode(payload)?
+            }
+            None if i == 0 && bin.is_some() => bin.unwrap_or_default().to_vec(),
+            // Buffers without data, e.g. compression fallbacks, must not be
+            // read from.
+            None => Vec::new(),
+        };
+        if bytes.len() < buffer.byte_length && !bytes.is_empty() {
+            return Err(malformed(format!(
+                "buffer {} has {} bytes, fewer than its byteLength {}",
+                i,
+                bytes.len(),
+                buffer.byte_length
+            )));
+        }
+        data.push(bytes);
+    }
+    Ok(data)
+}
+
+/// Reads accessor `i` as `f64`s, `components` per element, decoding
+/// normalized integers as the specification and `KHR_mesh_quantization`
+/// define.
+///
+/// Returns `None` for an accessor without a buffer view, whose elements
+/// are all zero: every face using it collapses, so its `count`, which the
+/// file does not back with data, is never allocated.
+fn read_accessor(
+    document: &Document,
+    buffers: &[Vec<u8>],
+    i: usize,
+    components: usize,
+) -> Result<Option<Vec<f64>>, MeshingError> {
+    let accessor = item(&document.accessors, "accessors", i)?;
+    let what = format!("accessor {}", i);
+    if accessor.sparse.is_some() {
+        return Err(malformed(format!(
+            "{} is sparse, which is not supported",
+            what
+        )));
+    }
+    let expected = ["SCALAR", "VEC2", "VEC3", "VEC4"][components - 1];
+    if accessor.kind != expected {
+        return Err(malformed(format!(
+            "{} has type '{}', expected {}",
+            what, accessor.kind, expected
+        )));
+    }
+    let component_type = accessor.component_type;
+    let size = match component_type {
+        5120 | 5121 => 1,
+        5122 | 5123 => 2,
+        5125 | 5126 => 4,
+        other => {
+            return Err(malformed(format!(
+                "{} has unknown componentType {}",
+                what, other
+            )))
+        }
+    };
+    let normalized = accessor.normalized;
+    let count = accessor.count;
+    let Some(view_index) = accessor.buffer_view else {
+        return Ok(None);
+    };
+
+    let view = item(&document.buffer_views, "bufferViews", view_index)?;
+    let view_what = format!("bufferView {}", view_index);
+    let buffer = &buffers
+        .get(view.buffer)
+        .ok_or_else(|| malformed(format!("{} refers to a missing buffer", view_what)))?;
+    let element_size = size * components;
+    let stride = view.byte_stride.unwrap_or(element_size);
+    let offset = accessor.byte_offset;
+    if stride < element_size {
+        return Err(malformed(format!(
+            "{} has byteStride {}, less than the {} bytes of {}",
+            view_what, stride, element_size, what
+        )));
+    }
+    let end = match count.checked_sub(1) {
+        None => Some(offset),
+        Some(last) => last
+            .checked_mul(stride)
+            .and_then(|n| n.checked_add(offset))
+            .and_then(|n| n.checked_add(element_size)),
+    };
+    let view_end = view.byte_offset.checked_add(view.byte_length);
+    if !matches!((end, view_end), (Some(end), Some(view_end))
+        if end <= view.byte_length && view_end <= buffer.len())
+    {
+        return Err(malformed(format!("{} exceeds its buffer data", what)));
+    }
+
+    // The view holds every element, so this is bounded by the input size.
+    let data = &buffer[view.byte_offset..view.byte_offset + view.byte_length];
+    let mut values = Vec::with_capacity(count * components);
+    for e in 0..count {
+        for c in 0..components {
+            let at = offset + e * stride + c * size;
+            let bytes = &data[at..at + size];
+            let value = match component_type {
+                5120 => {
+                    let v = bytes[0] as i8 as f64;
+                    if normalized {
+                        (v / 127.0).max(-1.0)
+                    } else {
+                        v
+                    }
+                }
+                5121 => bytes[0] as f64 / if normalized { 255.0 } else { 1.0 },
+                5122 => {
+                    let v = i16::from_le_bytes([bytes[0], bytes[1]]) as f64;
+                    if normalized {
+                        (v / 32767.0).max(-1.0)
+                    } else {
+                        v
+                    }
+                }
+                5123 => {
+                    let v = u16::from_le_bytes([bytes[0], bytes[1]]) as f64;
+                    v / if normalized { 65535.0 } else { 1.0 }
+                }
+                5125 => u32::from_le_bytes(bytes.try_into().expect("4 bytes")) as f64,
+                _ => f32::from_le_bytes(bytes.try_into().expect("4 bytes")) as f64,
+            };
+            values.push(value);
+        }
+    }
+    Ok(Some(values))
+}
+
+/// A column-major 4x4 matrix, as glTF stores them.
+type Matrix = [f64; 16];
+
+const IDENTITY: Matrix = [
+    1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+];
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut m = [0.0; 16];
+    for col in 0..4 {
+        for row in 0..4 {
+            m[col * 4 + row] = (0..4).map(|k| a[k * 4 + row] * b[col * 4 + k]).sum();
+        }
+    }
+    m
+}
+
+/// The local transform of a node: its `matrix`, or translation times
+/// rotation times scale.
+fn node_matrix(node: &Node) -> Matrix {
+    if let Some(matrix) = node.matrix {
+        return matrix;
+    }
+    let [tx, ty, tz] = node.translation.unwrap_or([0.0; 3]);
+    let [x, y, z, w] = node.rotation.unwrap_or([0.0, 0.0, 0.0, 1.0]);
+    let [sx, sy, sz] = node.scale.unwrap_or([1.0; 3]);
+    let rotation = [
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y + z * w),
+            2.0 * (x * z - y * w),
+        ],
+        [
+            2.0 * (x * y - z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z + x * w),
+        ],
+        [
+            2.0 * (x * z + y * w),
+            2.0 * (y * z - x * w),
+            1.0 - 2.0 * (x * x + y * y),
+        ],
+    ];
+    let [c0, c1, c2] = rotation;
+    [
+        c0[0] * sx,
+        c0[1] * sx,
+        c0[2] * sx,
+        0.0,
+        c1[0] * sy,
+        c1[1] * sy,
+        c1[2] * sy,
+        0.0,
+        c2[0] * sz,
+        c2[1] * sz,
+        c2[2] * sz,
+        0.0,
+        tx,
+        ty,
+        tz,
+        1.0,
+    ]
+}
+
+/// Every mesh instance of the default scene with its world transform.
+///
+/// Without scenes, the root nodes are used; without nodes, every mesh is
+/// placed at the origin.
+fn mesh_instances(document: &Document) -> Result<Vec<(usize, Matrix)>, MeshingError> {
+    let nodes = &document.nodes;
+    let roots: Vec<usize> = if let Some(scenes) = &document.scenes {
+        let scene = document.scene.unwrap_or(0);
+        item(scenes, "scenes", scene)?.nodes.clone()
+    } else if !nodes.is_empty() {
+        let mut is_child = vec![false; nodes.len()];
+        for &c in nodes.iter().flat_map(|node| &node.children) {
+            if c < nodes.len() {
+                is_child[c] = true;
+            }
+        }
+        (0..nodes.len()).filter(|&i| !is_child[i]).collect()
+    } else {
+        return Ok((0..document.meshes.len()).map(|m| (m, IDENTITY)).collect());
+    };
+
+    let mut instances = Vec::new();
+    let mut stack: Vec<(usize, Matrix, usize)> =
+        roots.into_iter().rev().map(|n| (n, IDENTITY, 0)).collect();
+    while let Some((n, parent, depth)) = stack.pop() {
+        // A tree has no path longer than its node count.
+        if depth > nodes.len() {
+            return Err(malformed("node hierarchy has a cycle"));
+        }
+        let node = item(nodes, "nodes", n)?;
+        let world = multiply(&parent, &node_matrix(node));
+        if let Some(mesh) = node.mesh {
+            instances.push((mesh, world));
+        }
+        for &child in node.children.iter().rev() {
+            stack.push((child, world, depth + 1));
+        }
+    }
+    Ok(instances)
+}
+
+/// Required extensions that do not change the geometry.
+fn is_supported_extension(name: &str) -> bool {
+    name == "KHR_mesh_quantization"
+        || name.starts_with("KHR_materials_")
+        || name.starts_with("KHR_texture_")
+}
+
+fn parse_document(document: &Document, bin: Option<&[u8]>) -> Result<Vec<Face>, MeshingError> {
+    let version = &document.asset.version;
+    if !version.starts_with("2.") {
+        return Err(malformed(format!("unsupported version '{}'", version)));
+    }
+    for name in &document.extensions_required {
+        if !is_supported_extension(name) {
+            return Err(malformed(format!(
+                "required extension '{}' is not supported",
+                name
+            )));
+        }
+    }
+
+    let buffers = load_buffers(&document.buffers, bin)?;
+    let mut welder = Welder::default();
+    let mut faces = Vec::new();
+    for (m, world) in mesh_instances(document)? {
+        let mesh = item(&document.meshes, "meshes", m)?;
+        // A mirroring transform turns the faces inside out.
+        let [a, b, c] = [0, 4, 8].map(|col| [world[col], world[col + 1], world[col + 2]]);
+        let determinant = a[0] * (b[1] * c[2] - b[2] * c[1]) - b[0] * (a[1] * c[2] - a[2] * c[1])
+            + c[0] * (a[1] * b[2] - a[2] * b[1]);
+        for (p, primitive) in mesh.primitives.iter().enumerate() {
+            let what = format!("primitive {} of mesh {}", p, m);
+            let mode = primitive.mode;
+            if !(4..=6).contains(&mode) {
+                // Points and lines have no faces.
+                continue;
+            }
+            let position = primitive
+                .attributes
+                .position
+                .ok_or_else(|| malformed(format!("{} has no 'POSITION'", what)))?;
+            let Some(positions) = read_accessor(document, &buffers, position, 3)? else {
+                continue;
+            };
+            let positions: Vec<[f64; 3]> = positions
+                .chunks_exact(3)
+                .map(|p| {
+                    [0, 1, 2].map(|row| {
+                        world[row] * p[0]
+                            + world[4 + row] * p[1]
+                            + world[8 + row] * p[2]
+                            + world[12 + row]
+                    })
+                })
+                .collect();
+            let indices: Vec<usize> = match primitive.indices {
+                Some(accessor) => match read_accessor(document, &buffers, accessor, 1)? {
+                    Some(indices) => indices.into_iter().map(|i| i as usize).collect(),
+                    None => continue,
+                },
+                None => (0..positions.len()).collect(),
+            };
+            if let Some(&i) = indices.iter().find(|&&i| i >= positions.len()) {
+                return Err(malformed(format!(
+                    "{} has index {} but {} vertices",
+                    what,
+                    i,
+                    positions.len()
+                )));
+            }
+            let triangles: Vec<[usize; 3]> = match mode {
+                4 => indices
+                    .chunks_exact(3)
+                    .map(|t| [t[0], t[1], t[2]])
+                    .collect(),
+                5 => (0..indices.len().saturating_sub(2))
+                    .map(|i| {
+                        if i % 2 == 0 {
+                            [indices[i], indices[i + 1], indices[i + 2]]
+                        } else {
+                            [indices[i + 1], indices[i], indices[i + 2]]
+                        }
+                    })
+                    .collect(),
+                _ => (1..indices.len().saturating_sub(1))
+                    .map(|i| [indices[i], indices[i + 1], indices[0]])
+                    .collect(),
+            };
+            for [i, j, k] in triangles {
+                let corners = if determinant < 0.0 {
+                    [positions[i], positions[k], positions[j]]
+                } else {
+                    [positions[i], positions[j], positions[k]]
+                };
+                faces.extend(welder.face(corners));
+            }
+        }
+    }
+    Ok(faces)
+}
+
+/// Parses a glTF 2.0 file, `.gltf` JSON with embedded base64 buffers or
+/// binary `.glb`, into the triangle faces of its default scene.
+///
+/// Accessors of any component type are read, including the normalized and
+/// integer positions of `KHR_mesh_quantization`, and node transforms are
+/// applied, so the faces are in world coordinates. Triangle strips and
+/// fans are split into triangles; points and lines are skipped. Vertices
+/// with identical world coordinates share one [`Point3D::index`](crate::Point3D::index),
+/// numbered from 0 in order of first appearance, so that the faces can be
+/// passed to [`advancing_front`](crate::advancing_front::advancing_front).
+/// Faces that collapse after welding are dropped.
+///
+/// # Errors
+///
+/// Returns [`MeshingError::MalformedFile`] if the data is not valid glTF,
+/// refers to external files or to missing or out-of-range data, uses
+/// sparse accessors, or requires an extension that changes the geometry,
+/// such as `EXT_meshopt_compression`.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::faces_to_glb_quantized;
+/// use meshing::import::gltf_to_faces;
+/// use meshing::{Face, Point3D};
+///
+/// let p = |index, x, y, z| Point3D { index, x, y, z };
+/// let face = Face { a: p(7, 0.0, 0.0, 0.0), b: p(8, 1.0, 0.0, 0.0), c: p(9, 0.0, 1.0, 0.0) };
+/// let faces = gltf_to_faces(&faces_to_glb_quantized(&[face])).unwrap();
+/// assert_eq!(faces.len(), 1);
+/// assert!((faces[0].b.x - 1.0).abs() < 1e-4);
+/// ```
+pub fn gltf_to_faces(bytes: &[u8]) -> Result<Vec<Face>, MeshingError> {
+    if bytes.starts_with(b"glTF") {
+        let (json, bin) = parse_glb(bytes)?;
+        return parse_document(&parse_json(json)?, bin);
+    }
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    parse_document(&parse_json(bytes)?, None)
+}
+
+/// Parses a glTF 2.0 or GLB file into an indexed [`SurfaceMesh`].
+///
+/// # Errors
+///
+/// Same as [`gltf_to_faces`].
+pub fn gltf_to_surface_mesh(bytes: &[u8]) -> Result<SurfaceMesh, MeshingError> {
+    Ok(SurfaceMesh::from_faces(&gltf_to_faces(bytes)?))
+}
+
+/// Reads a glTF 2.0 or GLB file from `reader`, as [`gltf_to_faces`].
+///
+/// # Errors
+///
+/// Returns [`MeshingError::Io`] if reading fails, otherwise the errors of
+/// [`gltf_to_faces`].
+pub fn read_gltf<R: Read>(mut reader: R) -> Result<Vec<Face>, MeshingError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    gltf_to_faces(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::export::{
+        faces_to_glb, faces_to_glb_compressed, faces_to_glb_quantized, faces_to_gltf,
+        scene_to_gltf, surface_mesh_to_gltf_with_options, GltfFormat, GltfNode, GltfNormals,
+        GltfOptions,
+    };
+    use crate::marching_cubes::marching_cubes;
+    use crate::Point3D;
+
+    fn p(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    fn sphere_faces() -> Vec<Face> {
+        marching_cubes(
+            8,
+            8,
+            8,
+            p(0, -1.5, -1.5, -1.5),
+            p(0, 1.5, 1.5, 1.5),
+            &|x, y, z| x * x + y * y + z * z - 1.0,
+            0.0,
+        )
+    }
+
+    fn assert_same_geometry(read: &[Face], written: &[Face], tolerance: f64) {
+        assert_eq!(read.len(), written.len());
+        for (r, w) in read.iter().zip(written) {
+            for (a, b) in r.vertices().iter().zip(w.vertices()) {
+                assert!((a.x - b.x).abs() < tolerance);
+                assert!((a.y - b.y).abs() < tolerance);
+                assert!((a.z - b.z).abs() < tolerance);
+            }
+        }
+    }
+
+    fn assert_closed(faces: &[Face]) {
+        let mut edges: HashMap<(i64, i64), usize> = HashMap::new();
+        for f in faces {
+            for (u, v) in [(f.a, f.b), (f.b, f.c), (f.c, f.a)] {
+                *edges
+                    .entry((u.index.min(v.index), u.index.max(v.index)))
+                    .or_default() += 1;
+            }
+        }
+        assert!(edges.values().all(|&n| n == 2));
+    }
+
+    #[test]
+    fn test_gltf_and_glb_round_trip() {
+        let faces = sphere_faces();
+        let from_json = gltf_to_faces(faces_to_gltf(&faces).as_bytes()).unwrap();
+        assert_same_geometry(&from_json, &faces, 1e-6);
+        assert_closed(&from_json);
+
+        let from_glb = read_gltf(faces_to_glb(&faces).as_slice()).unwrap();
+        assert_eq!(from_glb, from_json);
+        let mesh = gltf_to_surface_mesh(&faces_to_glb(&faces)).unwrap();
+        assert_eq!(mesh.num_faces(), faces.len());
+    }
+
+    #[test]
+    fn test_quantized_round_trip() {
+        let faces = sphere_faces();
+        let read = gltf_to_faces(&faces_to_glb_quantized(&faces)).unwrap();
+        // One quantization step of the 3-unit-wide box.
+        assert_same_geometry(&read, &faces, 3.0 / 65534.0);
+        assert_closed(&read);
+    }
+
+    #[test]
+    fn test_flat_normals_are_welded() {
+        // Weld the marching cubes triangles first, then write every face
+        // with its own vertices.
+        let mesh = gltf_to_surface_mesh(&faces_to_glb(&sphere_faces())).unwrap();
+        let options = GltfOptions {
+            normals: GltfNormals::Flat,
+            quantized: true,
+            ..GltfOptions::default()
+        };
+        let glb = surface_mesh_to_gltf_with_options(&mesh, &options, GltfFormat::Glb);
+        let read = gltf_to_surface_mesh(&glb).unwrap();
+        assert_eq!(read.num_vertices(), mesh.num_vertices());
+    }
+
+    #[test]
+    fn test_scene_transforms_and_mirroring() {
+        let face = Face {
+            a: p(0, 0.0, 0.0, 0.0),
+            b: p(1, 1.0, 0.0, 0.0),
+            c: p(2, 0.0, 1.0, 0.0),
+        };
+        let mesh = SurfaceMesh::from_faces(&[face]);
+        let mut moved = GltfNode::new("moved", &mesh);
+        moved.transform.translation = [0.0, 0.0, 5.0];
+        moved.transform.rotation = [0.0, 0.0, 0.5f64.sqrt(), 0.5f64.sqrt()];
+        let mut mirrored = GltfNode::new("mirrored", &mesh);
+        mirrored.transform.scale = [-2.0, 1.0, 1.0];
+        mirrored.options.quantized = true;
+        let bytes = scene_to_gltf(&[moved, mirrored], GltfFormat::Json);
+        let faces = gltf_to_faces(&bytes).unwrap();
+        assert_eq!(faces.len(), 2);
+
+        // Rotated a quarter turn about z, then lifted.
+        let b = faces[0].b;
+        assert!(b.x.abs() < 1e-9 && (b.y - 1.0).abs() < 1e-9 && (b.z - 5.0).abs() < 1e-9);
+
+        // The mirrored face keeps a +z normal.
+        let [a, b, c] = faces[1].vertices();
+        let normal_z = (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+        assert!(normal_z > 0.0);
+        assert!(faces[1].vertices().iter().any(|v| (v.x + 2.0).abs() < 1e-4));
+    }
+
+    #[test]
+    fn test_strips_fans_and_matrices() {
+        // Four vertices of a unit square, as f32.
+        let mut data = Vec::new();
+        for [x, y] in [[0.0f32, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]] {
+            for c in [x, y, 0.0] {
+                data.extend_from_slice(&c.to_le_bytes());
+            }
+        }
+        const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let uri: String = data
+            .chunks(3)
+            .flat_map(|chunk| {
+                let triple =
+                    chunk.iter().fold(0u32, |t, &b| (t << 8) | b as u32) << (8 * (3 - chunk.len()));
+                (0..=chunk.len())
+                    .map(move |i| CHARS[(triple >> (18 - 6 * i) & 63) as usize] as char)
+            })
+            .collect();
+        let json = format!(
+            concat!(
+                "{{\"asset\":{{\"version\":\"2.0\"}},",
+                "\"nodes\":[{{\"children\":[1]}},",
+                "{{\"mesh\":0,\"matrix\":[1,0,0,0,0,1,0,0,0,0,1,0,10,0,0,1]}}],",
+                "\"meshes\":[{{\"primitives\":[{{\"attributes\":{{\"POSITION\":0}},\"mode\":5}},",
+                "{{\"attributes\":{{\"POSITION\":0}},\"mode\":1}}]}}],",
+                "\"accessors\":[{{\"bufferView\":0,\"componentType\":5126,\"count\":4,",
+                "\"type\":\"VEC3\"}}],",
+                "\"bufferViews\":[{{\"buffer\":0,\"byteLength\":48}}],",
+                "\"buffers\":[{{\"byteLength\":48,",
+                "\"uri\":\"data:application/gltf-buffer;base64,{}\"}}]}}"
+            ),
+            uri
+        );
+        let faces = gltf_to_faces(json.as_bytes()).unwrap();
+        assert_eq!(faces.len(), 2);
+        for face in &faces {
+            let [a, b, c] = face.vertices();
+            assert!(a.x >= 10.0);
+            let normal_z = (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+            assert!(normal_z > 0.0);
+        }
+
+        let fan = json.replace("\"mode\":5", "\"mode\":6");
+        assert_eq!(gltf_to_faces(fan.as_bytes()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_imported_tet_surface_feeds_advancing_front() {
+        use crate::advancing_front::advancing_front;
+        use crate::export::tetrahedra_to_glb;
+        use crate::voxel_mesh::voxel_mesh;
+
+        let tets = voxel_mesh(p(0, 0.0, 0.0, 0.0), p(0, 1.0, 1.0, 1.0), 1, 1, 1, &|_| true);
+        let faces = gltf_to_faces(&tetrahedra_to_glb(&tets)).unwrap();
+        let mesh = SurfaceMesh::from_faces(&faces);
+        assert_eq!(mesh.num_vertices(), 8);
+        let result = advancing_front(faces, mesh.vertices.clone());
+        assert!(!result.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_files() {
+        let glb = faces_to_glb(&sphere_faces());
+        assert!(matches!(
+            gltf_to_faces(&glb[..100]),
+            Err(MeshingError::MalformedFile(_))
+        ));
+        assert!(gltf_to_faces(b"not json").is_err());
+
+        let compressed = faces_to_glb_compressed(&sphere_faces());
+        let err = gltf_to_faces(&compressed).unwrap_err();
+        assert!(
+            err.to_string().contains("EXT_meshopt_compression"),
+            "{}",
+            err
+        );
+
+        let external = concat!(
+            "{\"asset\":{\"version\":\"2.0\"},\"buffers\":[{\"byteLength\":4,",
+            "\"uri\":\"mesh.bin\"}]}"
+        );
+        let err = gltf_to_faces(external.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("mesh.bin"), "{}", err);
+
+        let out_of_range = faces_to_gltf(&sphere_faces()).replacen("\"count\":", "\"count\":9", 1);
+        assert!(gltf_to_faces(out_of_range.as_bytes()).is_err());
+        assert!(gltf_to_faces(&"[".repeat(100_000).into_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_oversized_counts_are_not_allocated() {
+        let document = |accessor: &str| {
+            format!(
+                concat!(
+                    "{{\"asset\":{{\"version\":\"2.0\"}},",
+                    "\"meshes\":[{{\"primitives\":[{{\"attributes\":{{\"POSITION\":0}}}}]}}],",
+                    "\"accessors\":[{{{}\"componentType\":5126,\"type\":\"VEC3\"}}],",
+                    "\"bufferViews\":[{{\"buffer\":0,\"byteOffset\":0,\"byteLength\":12}}],",
+                    "\"buffers\":[{{\"byteLength\":12,",
+                    "\"uri\":\"data:application/gltf-buffer;base64,AAAAAAAAAAAAAAAA\"}}]}}"
+                ),
+                accessor
+            )
+        };
+        // Without a buffer view the elements are zeros and every face
+        // collapses.
+        let valid = document("\"bufferView\":0,\"count\":1,");
+        assert_eq!(gltf_to_faces(valid.as_bytes()).unwrap(), vec![]);
+        let zeros = document("\"count\":4000000000,");
+        assert_eq!(gltf_to_faces(zeros.as_bytes()).unwrap(), vec![]);
+
+        for accessor in [
+            "\"bufferView\":0,\"count\":4000000000,",
+            "\"bufferView\":0,\"count\":18446744073709551615,",
+            "\"bufferView\":0,\"byteOffset\":18446744073709551615,\"count\":1,",
+        ] {
+            assert!(matches!(
+                gltf_to_faces(document(accessor).as_bytes()),
+                Err(MeshingError::MalformedFile(_))
+            ));
+        }
+        let view_overflow = document("\"bufferView\":0,\"count\":1,")
+            .replace("\"byteOffset\":0", "\"byteOffset\":18446744073709551615");
+        assert!(gltf_to_faces(view_overflow.as_bytes()).is_err());
+    }
+}
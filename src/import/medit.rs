@@ -0,0 +1,553 @@
+use std::io::Read;
+
+use crate::error::MeshingError;
+use crate::export::medit::{
+    MESHB_DIMENSION, MESHB_END, MESHB_TETRAHEDRA, MESHB_TRIANGLES, MESHB_VERTICES,
+};
+use crate::export::MeditMesh;
+use crate::{Face, Point3D, Tetrahedron};
+
+fn malformed(message: impl Into<String>) -> MeshingError {
+    MeshingError::MalformedFile(format!("Medit: {}", message.into()))
+}
+
+/// Number of values per entry of the text sections a reader may meet besides
+/// vertices, triangles and tetrahedra, so that they can be skipped. `None`
+/// stands for one value per coordinate.
+fn values_per_entry(keyword: &str) -> Option<Option<usize>> {
+    Some(match keyword {
+        "corners"
+        | "ridges"
+        | "requiredvertices"
+        | "requirededges"
+        | "requiredtriangles"
+        | "requiredquadrilaterals"
+        | "requiredtetrahedra" => Some(1),
+        "normalatvertices" | "tangentatvertices" => Some(2),
+        "edges" | "normalattriangles" | "tangentatedges" => Some(3),
+        "quadrilaterals" | "normalatquadrilateralvertices" => Some(5),
+        "pyramids" => Some(6),
+        "prisms" => Some(7),
+        "hexahedra" => Some(9),
+        "normals" | "tangents" => None,
+        _ => return None,
+    })
+}
+
+/// Adds an element and its reference, checking that its 1-based indices
+/// refer to existing vertices.
+fn push_element<const N: usize>(
+    elements: &mut Vec<[usize; N]>,
+    refs: &mut Vec<i32>,
+    indices: [i64; N],
+    reference: i64,
+    vertex_count: usize,
+) -> Result<(), MeshingError> {
+    let mut element = [0; N];
+    for (slot, &index) in element.iter_mut().zip(&indices) {
+        *slot = usize::try_from(index)
+            .ok()
+            .filter(|&i| (1..=vertex_count).contains(&i))
+            .ok_or_else(|| {
+                malformed(format!(
+                    "element refers to vertex {} but there are {} vertices",
+                    index, vertex_count
+                ))
+            })?
+            - 1;
+    }
+    elements.push(element);
+    refs.push(
+        i32::try_from(reference)
+            .map_err(|_| malformed(format!("reference {} does not fit in an i32", reference)))?,
+    );
+    Ok(())
+}
+
+fn parse_ascii(text: &str) -> Result<MeditMesh, MeshingError> {
+    let mut tokens = text
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or(""))
+        .flat_map(str::split_whitespace);
+    let mut next = |what: &str| {
+        tokens
+            .next()
+            .ok_or_else(|| malformed(format!("unexpected end of file reading {}", what)))
+    };
+    fn number<T: std::str::FromStr>(token: &str) -> Result<T, MeshingError> {
+        token
+            .parse()
+            .map_err(|_| malformed(format!("invalid number '{}'", token)))
+    }
+
+    let mut mesh = MeditMesh::default();
+    let mut dimension = 3;
+    let mut seen_version = false;
+    loop {
+        let keyword = match next("a keyword") {
+            Ok(keyword) => keyword.to_ascii_lowercase(),
+            // Files written by some tools stop without an `End` keyword.
+            Err(_) if seen_version => break,
+            Err(e) => return Err(e),
+        };
+        if keyword != "meshversionformatted" && !seen_version {
+            return Err(malformed("file does not start with MeshVersionFormatted"));
+        }
+        match keyword.as_str() {
+            "meshversionformatted" => {
+                let version: i32 = number(next("the version")?)?;
+                if !(1..=4).contains(&version) {
+                    return Err(malformed(format!("unsupported version {}", version)));
+                }
+                seen_version = true;
+            }
+            "dimension" => {
+                dimension = number(next("the dimension")?)?;
+                if dimension != 2 && dimension != 3 {
+                    return Err(malformed(format!("unsupported dimension {}", dimension)));
+                }
+            }
+            "vertices" => {
+                let count: usize = number(next("the vertex count")?)?;
+                for _ in 0..count {
+                    let mut coords = [0.0; 3];
+                    for c in coords.iter_mut().take(dimension) {
+                        *c = number(next("a vertex")?)?;
+                    }
+                    let reference: i64 = number(next("a vertex")?)?;
+                    mesh.vertices.push(Point3D {
+                        index: mesh.vertices.len() as i64,
+                        x: coords[0],
+                        y: coords[1],
+                        z: coords[2],
+                    });
+                    mesh.vertex_refs.push(i32::try_from(reference).map_err(|_| {
+                        malformed(format!("reference {} does not fit in an i32", reference))
+                    })?);
+                }
+            }
+            "triangles" => {
+                let count: usize = number(next("the triangle count")?)?;
+                for _ in 0..count {
+                    let mut indices = [0; 3];
+                    for i in indices.iter_mut() {
+                        *i = number(next("a triangle")?)?;
+                    }
+                    let reference = number(next("a triangle")?)?;
+                    push_element(
+                        &mut mesh.triangles,
+                        &mut mesh.triangle_refs,
+                        indices,
+                        reference,
+                        mesh.vertices.len(),
+                    )?;
+                }
+            }
+            "tetrahedra" => {
+                let count: usize = number(next("the tetrahedron count")?)?;
+                for _ in 0..count {
+                    let mut indices = [0; 4];
+                    for i in indices.iter_mut() {
+                        *i = number(next("a tetrahedron")?)?;
+                    }
+                    let reference = number(next("a tetrahedron")?)?;
+                    push_element(
+                        &mut mesh.tetrahedra,
+                        &mut mesh.tetrahedron_refs,
+                        indices,
+                        reference,
+                        mesh.vertices.len(),
+                    )?;
+                }
+            }
+            "end" => break,
+            other => {
+                let per_entry = values_per_entry(other)
+                    .ok_or_else(|| malformed(format!("unknown keyword '{}'", other)))?
+                    .unwrap_or(dimension);
+                let count: usize = number(next(other)?)?;
+                for _ in 0..count * per_entry {
+                    next(other)?;
+                }
+            }
+        }
+    }
+    Ok(mesh)
+}
+
+/// Reads values of the sizes given by a `.meshb` version.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    real_size: usize,
+    int_size: usize,
+    position_size: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], MeshingError> {
+        let raw = self
+            .bytes
+            .get(self.position..self.position + n)
+            .ok_or_else(|| malformed("unexpected end of binary data"))?;
+        self.position += n;
+        Ok(raw)
+    }
+
+    fn sized_int(&mut self, size: usize) -> Result<i64, MeshingError> {
+        let raw = self.take(size)?;
+        Ok(match size {
+            4 => i32::from_le_bytes(raw.try_into().expect("4 bytes")) as i64,
+            _ => i64::from_le_bytes(raw.try_into().expect("8 bytes")),
+        })
+    }
+
+    fn int(&mut self) -> Result<i64, MeshingError> {
+        self.sized_int(self.int_size)
+    }
+
+    fn count(&mut self) -> Result<usize, MeshingError> {
+        let count = self.int()?;
+        usize::try_from(count).map_err(|_| malformed(format!("invalid count {}", count)))
+    }
+
+    fn real(&mut self) -> Result<f64, MeshingError> {
+        let raw = self.take(self.real_size)?;
+        Ok(match self.real_size {
+            4 => f32::from_le_bytes(raw.try_into().expect("4 bytes")) as f64,
+            _ => f64::from_le_bytes(raw.try_into().expect("8 bytes")),
+        })
+    }
+}
+
+fn parse_binary(bytes: &[u8]) -> Result<MeditMesh, MeshingError> {
+    let mut reader = Reader {
+        bytes,
+        position: 4,
+        real_size: 8,
+        int_size: 4,
+        position_size: 4,
+    };
+    let version = reader.sized_int(4)?;
+    match version {
+        1 => reader.real_size = 4,
+        2 => {}
+        3 => reader.position_size = 8,
+        4 => {
+            reader.position_size = 8;
+            reader.int_size = 8;
+        }
+        _ => return Err(malformed(format!("unsupported version {}", version))),
+    }
+
+    let mut mesh = MeditMesh::default();
+    let mut dimension = 3;
+    while reader.position < bytes.len() {
+        let code = reader.sized_int(4)?;
+        if code == MESHB_END as i64 {
+            break;
+        }
+        let next = reader.sized_int(reader.position_size)?;
+        match code as i32 {
+            MESHB_DIMENSION => {
+                dimension = reader.int()? as usize;
+                if dimension != 2 && dimension != 3 {
+                    return Err(malformed(format!("unsupported dimension {}", dimension)));
+                }
+            }
+            MESHB_VERTICES => {
+                for _ in 0..reader.count()? {
+                    let mut coords = [0.0; 3];
+                    for c in coords.iter_mut().take(dimension) {
+                        *c = reader.real()?;
+                    }
+                    let reference = reader.int()?;
+                    mesh.vertices.push(Point3D {
+                        index: mesh.vertices.len() as i64,
+                        x: coords[0],
+                        y: coords[1],
+                        z: coords[2],
+                    });
+                    mesh.vertex_refs.push(i32::try_from(reference).map_err(|_| {
+                        malformed(format!("reference {} does not fit in an i32", reference))
+                    })?);
+                }
+            }
+            MESHB_TRIANGLES => {
+                for _ in 0..reader.count()? {
+                    let indices = [reader.int()?, reader.int()?, reader.int()?];
+                    let reference = reader.int()?;
+                    push_element(
+                        &mut mesh.triangles,
+                        &mut mesh.triangle_refs,
+                        indices,
+                        reference,
+                        mesh.vertices.len(),
+                    )?;
+                }
+            }
+            MESHB_TETRAHEDRA => {
+                for _ in 0..reader.count()? {
+                    let indices = [reader.int()?, reader.int()?, reader.int()?, reader.int()?];
+                    let reference = reader.int()?;
+                    push_element(
+                        &mut mesh.tetrahedra,
+                        &mut mesh.tetrahedron_refs,
+                        indices,
+                        reference,
+                        mesh.vertices.len(),
+                    )?;
+                }
+            }
+            _ if next == 0 => break,
+            _ => {
+                reader.position = usize::try_from(next)
+                    .ok()
+                    .filter(|&next| next > reader.position && next <= bytes.len())
+                    .ok_or_else(|| malformed(format!("invalid keyword position {}", next)))?;
+            }
+        }
+    }
+    Ok(mesh)
+}
+
+/// Parses a Medit `.mesh` text or `.meshb` binary file.
+///
+/// Binary files are recognised by their leading little-endian `1` and may
+/// be of versions 1 to 4. Vertices, triangles and tetrahedra are kept with
+/// their references, so each `*_refs` vector has one entry per entity;
+/// vertices are numbered from 0 in file order (the [`Point3D::index`] of
+/// each vertex is its position in [`MeditMesh::vertices`]). Two-dimensional
+/// files get `z = 0`. Other sections such as `Edges`, `Corners` or
+/// `Normals` are skipped.
+///
+/// # Errors
+///
+/// Returns [`MeshingError::MalformedFile`] for unsupported versions or
+/// dimensions, big-endian files, unknown keywords in text files, truncated
+/// or unparsable sections, or elements referring to missing vertices.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::{tetrahedra_to_medit, MeditFormat};
+/// use meshing::import::medit_to_tetrahedra;
+/// use meshing::{Point3D, Tetrahedron};
+///
+/// let p = |index, x, y, z| Point3D { index, x, y, z };
+/// let tet = Tetrahedron {
+///     a: p(0, 0.0, 0.0, 0.0),
+///     b: p(1, 1.0, 0.0, 0.0),
+///     c: p(2, 0.0, 1.0, 0.0),
+///     d: p(3, 0.0, 0.0, 1.0),
+/// };
+/// let meshb = tetrahedra_to_medit(&[tet], MeditFormat::Binary);
+/// assert_eq!(medit_to_tetrahedra(&meshb).unwrap(), vec![tet]);
+/// ```
+pub fn medit_to_mesh(bytes: &[u8]) -> Result<MeditMesh, MeshingError> {
+    match bytes.get(..4) {
+        Some([1, 0, 0, 0]) => parse_binary(bytes),
+        Some([0, 0, 0, 1]) => Err(malformed("big-endian .meshb files are not supported")),
+        _ => {
+            let text =
+                std::str::from_utf8(bytes).map_err(|_| malformed("file is not valid text"))?;
+            parse_ascii(text)
+        }
+    }
+}
+
+/// Parses the tetrahedra of a Medit file, e.g. for
+/// [`refine_tetrahedra`](crate::pipeline::refine_tetrahedra).
+///
+/// # Errors
+///
+/// Same as [`medit_to_mesh`].
+pub fn medit_to_tetrahedra(bytes: &[u8]) -> Result<Vec<Tetrahedron>, MeshingError> {
+    Ok(medit_to_mesh(bytes)?.to_tetrahedra())
+}
+
+/// Parses the triangles of a Medit file as faces.
+///
+/// # Errors
+///
+/// Same as [`medit_to_mesh`].
+pub fn medit_to_faces(bytes: &[u8]) -> Result<Vec<Face>, MeshingError> {
+    Ok(medit_to_mesh(bytes)?.to_faces())
+}
+
+/// Reads a Medit file from `reader`, as [`medit_to_mesh`].
+///
+/// # Errors
+///
+/// Returns [`MeshingError::Io`] if reading fails, otherwise the errors of
+/// [`medit_to_mesh`].
+pub fn read_medit<R: Read>(mut reader: R) -> Result<MeditMesh, MeshingError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    medit_to_mesh(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{extract_surface_faces, mesh_to_medit, MeditFormat};
+    use crate::voxel_mesh::voxel_mesh_indexed;
+
+    fn p(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    fn labelled_block() -> MeditMesh {
+        let tets = voxel_mesh_indexed(p(0, 0.0, 0.0, 0.0), p(0, 2.0, 1.0, 1.0), 2, 1, 1, &|_| true);
+        let mut mesh = MeditMesh::from_tetrahedra(&tets.to_tetrahedra());
+        mesh.vertex_refs = (0..mesh.vertices.len() as i32).collect();
+        mesh.triangle_refs = mesh
+            .triangles
+            .iter()
+            .map(|t| {
+                if t.iter().all(|&v| mesh.vertices[v].x == 0.0) {
+                    7
+                } else {
+                    1
+                }
+            })
+            .collect();
+        mesh.tetrahedron_refs = mesh
+            .to_tetrahedra()
+            .iter()
+            .map(|t| {
+                if t.a.x + t.b.x + t.c.x + t.d.x < 4.0 {
+                    1
+                } else {
+                    2
+                }
+            })
+            .collect();
+        mesh
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mesh = labelled_block();
+        assert_eq!(
+            mesh.triangles.len(),
+            extract_surface_faces(&mesh.to_tetrahedra()).len()
+        );
+        for format in [MeditFormat::Ascii, MeditFormat::Binary] {
+            let parsed = medit_to_mesh(&mesh_to_medit(&mesh, format)).unwrap();
+            assert_eq!(parsed, mesh, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_handwritten_file_with_extra_sections() {
+        let text = "# written by hand\n\
+                    MeshVersionFormatted 1\n\
+                    Dimension\n3\n\
+                    Vertices\n4\n\
+                    0 0 0 1\n1 0 0 1\n0 1 0 2\n0 0 1 2\n\
+                    Edges\n1\n1 2 5\n\
+                    Corners 2 1 2\n\
+                    Triangles\n1\n1 3 2 4 # base\n\
+                    Normals\n1\n0 0 -1\n\
+                    NormalAtVertices\n1\n1 1\n\
+                    Tetrahedra\n1\n1 2 3 4 9\n\
+                    END\n";
+        let mesh = medit_to_mesh(text.as_bytes()).unwrap();
+        assert_eq!(mesh.vertex_refs, vec![1, 1, 2, 2]);
+        assert_eq!(mesh.triangles, vec![[0, 2, 1]]);
+        assert_eq!(mesh.triangle_refs, vec![4]);
+        assert_eq!(mesh.tetrahedra, vec![[0, 1, 2, 3]]);
+        assert_eq!(mesh.tetrahedron_refs, vec![9]);
+        assert_eq!(mesh.vertices[3], p(3, 0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_two_dimensional_file() {
+        let text = "MeshVersionFormatted 2\nDimension 2\nVertices 3\n0 0 0\n1 0 0\n0 1 0\n\
+                    Triangles 1\n1 2 3 0\nEnd\n";
+        let faces = medit_to_faces(text.as_bytes()).unwrap();
+        assert_eq!(faces.len(), 1);
+        assert_eq!(faces[0].c, p(2, 0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_binary_skips_unknown_keywords() {
+        // A version 3 file (64-bit positions) with an Edges section between
+        // the vertices and the tetrahedra.
+        let mut bytes = Vec::new();
+        let int = |bytes: &mut Vec<u8>, value: i32| bytes.extend_from_slice(&value.to_le_bytes());
+        let position = |bytes: &mut Vec<u8>, value: usize| {
+            bytes.extend_from_slice(&(value as i64).to_le_bytes())
+        };
+        int(&mut bytes, 1);
+        int(&mut bytes, 3);
+        int(&mut bytes, MESHB_VERTICES);
+        position(&mut bytes, 8 + 16 + 4 * 28);
+        int(&mut bytes, 4);
+        for (i, v) in [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ]
+        .iter()
+        .enumerate()
+        {
+            for c in v {
+                bytes.extend_from_slice(&f64::to_le_bytes(*c));
+            }
+            int(&mut bytes, i as i32);
+        }
+        int(&mut bytes, 5);
+        let next = bytes.len() + 8 + 4 + 12;
+        position(&mut bytes, next);
+        int(&mut bytes, 1);
+        for value in [1, 2, 0] {
+            int(&mut bytes, value);
+        }
+        int(&mut bytes, MESHB_TETRAHEDRA);
+        let next = bytes.len() + 8 + 4 + 20;
+        position(&mut bytes, next);
+        int(&mut bytes, 1);
+        for value in [1, 2, 3, 4, 3] {
+            int(&mut bytes, value);
+        }
+        int(&mut bytes, MESHB_END);
+
+        let mesh = medit_to_mesh(&bytes).unwrap();
+        assert_eq!(mesh.vertex_refs, vec![0, 1, 2, 3]);
+        assert_eq!(mesh.tetrahedra, vec![[0, 1, 2, 3]]);
+        assert_eq!(mesh.tetrahedron_refs, vec![3]);
+        assert!(mesh.triangles.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_files() {
+        let head = "MeshVersionFormatted 2\nDimension 3\nVertices 1\n0 0 0 0\n";
+        let cases = [
+            "Dimension 3\nEnd\n".to_string(),
+            "MeshVersionFormatted 9\nEnd\n".to_string(),
+            "MeshVersionFormatted 2\nDimension 4\nEnd\n".to_string(),
+            format!("{}Triangles 1\n1 1 2 0\nEnd\n", head),
+            format!("{}Tetrahedra 1\n1 1 1 0\n", head),
+            format!("{}Bananas 1\n1\nEnd\n", head),
+            format!("{}Vertices 1\n0 x 0 0\nEnd\n", head),
+        ];
+        for text in &cases {
+            assert!(
+                matches!(
+                    medit_to_mesh(text.as_bytes()),
+                    Err(MeshingError::MalformedFile(_))
+                ),
+                "{}",
+                text
+            );
+        }
+        let bytes = mesh_to_medit(&labelled_block(), MeditFormat::Binary);
+        assert!(matches!(
+            medit_to_mesh(&bytes[..bytes.len() / 2]),
+            Err(MeshingError::MalformedFile(_))
+        ));
+    }
+}
@@ -0,0 +1,17 @@
+mod gltf;
+mod medit;
+mod msh;
+mod obj;
+mod ply;
+mod stl;
+mod tetgen;
+mod vtk;
+
+pub use gltf::{gltf_to_faces, gltf_to_surface_mesh, read_gltf};
+pub use medit::{medit_to_faces, medit_to_mesh, medit_to_tetrahedra, read_medit};
+pub use msh::{msh_to_faces, msh_to_mesh, msh_to_tetrahedra, read_msh};
+pub use obj::{obj_to_faces, obj_to_mesh, obj_to_surface_mesh, read_obj, ObjMesh};
+pub use ply::{ply_to_faces, ply_to_mesh, ply_to_points, read_ply, PlyMesh};
+pub use stl::{read_stl, stl_to_faces, stl_to_surface_mesh};
+pub use tetgen::{tetgen_to_mesh, tetgen_to_tetrahedra};
+pub use vtk::{read_vtk, vtk_to_faces, vtk_to_mesh, vtk_to_tetrahedra, VtkMesh};
@@ -0,0 +1,629 @@
+use std::collections::HashMap;
+use std::io::Read;
+
+use crate::error::MeshingError;
+use crate::export::msh::{MSH_TETRAHEDRON, MSH_TRIANGLE};
+use crate::export::{MshMesh, MshPhysicalName};
+use crate::{Face, Point3D, Tetrahedron};
+
+fn malformed(message: impl Into<String>) -> MeshingError {
+    MeshingError::MalformedFile(format!("MSH: {}", message.into()))
+}
+
+/// Number of nodes of the Gmsh element types a reader may meet, so that
+/// unsupported elements (points, lines, quads, higher order, ...) can be
+/// skipped.
+fn nodes_per_element(element_type: i32) -> Option<usize> {
+    Some(match element_type {
+        15 => 1,
+        1 => 2,
+        2 | 8 => 3,
+        3 | 4 => 4,
+        7 => 5,
+        6 | 9 => 6,
+        5 | 16 => 8,
+        10 => 9,
+        11 => 10,
+        18 => 15,
+        19 => 13,
+        12 => 27,
+        13 => 18,
+        14 => 14,
+        17 => 20,
+        _ => return None,
+    })
+}
+
+/// Reads values from a section body, as text tokens or as little-endian
+/// binary.
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    binary: bool,
+    size_t: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn line(&mut self) -> Result<&'a str, MeshingError> {
+        let rest = &self.bytes[self.position..];
+        if rest.is_empty() {
+            return Err(malformed("unexpected end of file"));
+        }
+        let end = rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+        self.position += (end + 1).min(rest.len());
+        std::str::from_utf8(&rest[..end])
+            .map(|line| line.trim_end_matches('\r'))
+            .map_err(|_| malformed("section header is not valid text"))
+    }
+
+    fn token(&mut self) -> Result<&'a str, MeshingError> {
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+        if start == self.position {
+            return Err(malformed("unexpected end of file"));
+        }
+        std::str::from_utf8(&self.bytes[start..self.position])
+            .map_err(|_| malformed("invalid text value"))
+    }
+
+    fn parse<T: std::str::FromStr>(&mut self) -> Result<T, MeshingError> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| malformed(format!("invalid number '{}'", token)))
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], MeshingError> {
+        let raw = self
+            .bytes
+            .get(self.position..self.position + n)
+            .ok_or_else(|| malformed("unexpected end of binary data"))?;
+        self.position += n;
+        Ok(raw)
+    }
+
+    fn int(&mut self) -> Result<i32, MeshingError> {
+        if self.binary {
+            Ok(i32::from_le_bytes(
+                self.take(4)?.try_into().expect("4 bytes"),
+            ))
+        } else {
+            self.parse()
+        }
+    }
+
+    fn size(&mut self) -> Result<usize, MeshingError> {
+        if !self.binary {
+            return self.parse();
+        }
+        let raw = self.take(self.size_t)?;
+        let value = match self.size_t {
+            4 => u32::from_le_bytes(raw.try_into().expect("4 bytes")) as u64,
+            _ => u64::from_le_bytes(raw.try_into().expect("8 bytes")),
+        };
+        usize::try_from(value).map_err(|_| malformed(format!("size {} is too large", value)))
+    }
+
+    fn float(&mut self) -> Result<f64, MeshingError> {
+        if self.binary {
+            Ok(f64::from_le_bytes(
+                self.take(8)?.try_into().expect("8 bytes"),
+            ))
+        } else {
+            self.parse()
+        }
+    }
+
+    /// Checks that the section ends with `$End<name>` after its data.
+    fn end_section(&mut self, name: &str) -> Result<(), MeshingError> {
+        let token = self.token()?;
+        if token != format!("$End{}", name) {
+            return Err(malformed(format!(
+                "expected $End{} but found '{}'",
+                name, token
+            )));
+        }
+        Ok(())
+    }
+
+    /// Skips an unsupported section, which may hold binary data.
+    fn skip_section(&mut self, name: &str) -> Result<(), MeshingError> {
+        let end = format!("\n$End{}", name);
+        let rest = &self.bytes[self.position..];
+        let at = rest
+            .windows(end.len())
+            .position(|w| w == end.as_bytes())
+            .ok_or_else(|| malformed(format!("section {} is not terminated", name)))?;
+        self.position += at + end.len();
+        Ok(())
+    }
+}
+
+fn parse_mesh_format(cursor: &mut Cursor) -> Result<(), MeshingError> {
+    let line = cursor.line()?;
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let [version, file_type, data_size] = fields.as_slice() else {
+        return Err(malformed(format!("invalid format line '{}'", line)));
+    };
+    if !version.starts_with("4.1") {
+        return Err(malformed(format!(
+            "version {} is not supported, only 4.1",
+            version
+        )));
+    }
+    cursor.binary = match *file_type {
+        "0" => false,
+        "1" => true,
+        other => return Err(malformed(format!("invalid file type '{}'", other))),
+    };
+    cursor.size_t = match *data_size {
+        "4" => 4,
+        "8" => 8,
+        other => return Err(malformed(format!("unsupported data size '{}'", other))),
+    };
+    if cursor.binary {
+        let one = cursor.take(4)?;
+        if one != 1i32.to_le_bytes() {
+            return Err(malformed("only little-endian binary files are supported"));
+        }
+    }
+    cursor.end_section("MeshFormat")
+}
+
+fn parse_physical_names(cursor: &mut Cursor) -> Result<Vec<MshPhysicalName>, MeshingError> {
+    // This section is text even in binary files.
+    let binary = std::mem::replace(&mut cursor.binary, false);
+    let count: usize = cursor.parse()?;
+    let mut names = Vec::with_capacity(count);
+    for _ in 0..count {
+        let dimension = cursor.parse()?;
+        let tag = cursor.parse()?;
+        let rest = cursor.line()?.trim();
+        let name = rest
+            .strip_prefix('"')
+            .and_then(|r| r.strip_suffix('"'))
+            .ok_or_else(|| malformed(format!("physical name {} is not quoted", rest)))?;
+        names.push(MshPhysicalName {
+            dimension,
+            tag,
+            name: name.to_string(),
+        });
+    }
+    cursor.binary = binary;
+    cursor.end_section("PhysicalNames")?;
+    Ok(names)
+}
+
+/// Returns the first physical tag (or 0) of each entity, keyed by
+/// dimension and entity tag.
+fn parse_entities(cursor: &mut Cursor) -> Result<HashMap<(i32, i32), i32>, MeshingError> {
+    let mut counts = [0; 4];
+    for count in &mut counts {
+        *count = cursor.size()?;
+    }
+    let mut physical = HashMap::new();
+    for (dimension, &count) in counts.iter().enumerate() {
+        for _ in 0..count {
+            let tag = cursor.int()?;
+            // Points store one position, other entities a bounding box.
+            let coordinates = if dimension == 0 { 3 } else { 6 };
+            for _ in 0..coordinates {
+                cursor.float()?;
+            }
+            let num_physical = cursor.size()?;
+            let mut first = 0;
+            for k in 0..num_physical {
+                let physical_tag = cursor.int()?;
+                if k == 0 {
+                    first = physical_tag;
+                }
+            }
+            if dimension > 0 {
+                for _ in 0..cursor.size()? {
+                    cursor.int()?;
+                }
+            }
+            physical.insert((dimension as i32, tag), first.abs());
+        }
+    }
+    cursor.end_section("Entities")?;
+    Ok(physical)
+}
+
+fn parse_nodes(
+    cursor: &mut Cursor,
+    nodes: &mut Vec<Point3D>,
+    lookup: &mut HashMap<usize, usize>,
+) -> Result<(), MeshingError> {
+    let blocks = cursor.size()?;
+    let _num_nodes = cursor.size()?;
+    let _min_tag = cursor.size()?;
+    let _max_tag = cursor.size()?;
+    for _ in 0..blocks {
+        let dimension = cursor.int()?;
+        let _entity = cursor.int()?;
+        let parametric = cursor.int()?;
+        let count = cursor.size()?;
+        let mut tags = Vec::with_capacity(count.min(1 << 20));
+        for _ in 0..count {
+            tags.push(cursor.size()?);
+        }
+        for tag in tags {
+            let (x, y, z) = (cursor.float()?, cursor.float()?, cursor.float()?);
+            if parametric != 0 {
+                for _ in 0..dimension.max(0) {
+                    cursor.float()?;
+                }
+            }
+            if lookup.insert(tag, nodes.len()).is_some() {
+                return Err(malformed(format!("node {} is defined twice", tag)));
+            }
+            nodes.push(Point3D {
+                index: nodes.len() as i64,
+                x,
+                y,
+                z,
+            });
+        }
+    }
+    cursor.end_section("Nodes")
+}
+
+fn parse_elements(
+    cursor: &mut Cursor,
+    entities: &HashMap<(i32, i32), i32>,
+    node_tags: &mut Vec<(i32, Vec<usize>)>,
+) -> Result<(), MeshingError> {
+    let blocks = cursor.size()?;
+    let _num_elements = cursor.size()?;
+    let _min_tag = cursor.size()?;
+    let _max_tag = cursor.size()?;
+    for _ in 0..blocks {
+        let dimension = cursor.int()?;
+        let entity = cursor.int()?;
+        let element_type = cursor.int()?;
+        let count = cursor.size()?;
+        let nodes = nodes_per_element(element_type)
+            .ok_or_else(|| malformed(format!("unknown element type {}", element_type)))?;
+        let physical = entities.get(&(dimension, entity)).copied().unwrap_or(0);
+        for _ in 0..count {
+            let _tag = cursor.size()?;
+            let mut tags = Vec::with_capacity(nodes);
+            for _ in 0..nodes {
+                tags.push(cursor.size()?);
+            }
+            if element_type == MSH_TETRAHEDRON || element_type == MSH_TRIANGLE {
+                node_tags.push((physical, tags));
+            }
+        }
+    }
+    cursor.end_section("Elements")
+}
+
+/// Parses a Gmsh MSH 4.1 file (ASCII or little-endian binary).
+///
+/// Nodes are numbered from 0 in file order (the [`Point3D::index`] of each
+/// node is its position in [`MshMesh::nodes`]). Linear tetrahedra (type 4)
+/// and triangles (type 2) are kept with the first physical tag of their
+/// entity, or 0 if it has none; other element types and sections such as
+/// `$NodeData` are skipped.
+///
+/// # Errors
+///
+/// Returns [`MeshingError::MalformedFile`] for other MSH versions,
+/// big-endian files, truncated or unparsable sections, unknown element
+/// types, duplicate node tags, or elements referring to missing nodes.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::{tetrahedra_to_msh, MshFormat};
+/// use meshing::import::msh_to_tetrahedra;
+/// use meshing::{Point3D, Tetrahedron};
+///
+/// let p = |index, x, y, z| Point3D { index, x, y, z };
+/// let tet = Tetrahedron {
+///     a: p(0, 0.0, 0.0, 0.0),
+///     b: p(1, 1.0, 0.0, 0.0),
+///     c: p(2, 0.0, 1.0, 0.0),
+///     d: p(3, 0.0, 0.0, 1.0),
+/// };
+/// let msh = tetrahedra_to_msh(&[tet], MshFormat::Binary);
+/// assert_eq!(msh_to_tetrahedra(&msh).unwrap(), vec![tet]);
+/// ```
+pub fn msh_to_mesh(bytes: &[u8]) -> Result<MshMesh, MeshingError> {
+    let mut cursor = Cursor {
+        bytes,
+        position: 0,
+        binary: false,
+        size_t: 8,
+    };
+    let mut mesh = MshMesh::default();
+    let mut entities = HashMap::new();
+    let mut lookup = HashMap::new();
+    let mut elements = Vec::new();
+    let mut seen_format = false;
+
+    loop {
+        let section = match cursor.token() {
+            Ok(token) => token,
+            Err(_) if cursor.position >= bytes.len() => break,
+            Err(e) => return Err(e),
+        };
+        let name = section
+            .strip_prefix('$')
+            .ok_or_else(|| malformed(format!("expected a section but found '{}'", section)))?;
+        // Section headers end their line; data starts on the next one.
+        cursor.line()?;
+        if name != "MeshFormat" && !seen_format {
+            return Err(malformed("file does not start with $MeshFormat"));
+        }
+        match name {
+            "MeshFormat" => {
+                parse_mesh_format(&mut cursor)?;
+                seen_format = true;
+            }
+            "PhysicalNames" => mesh.physical_names = parse_physical_names(&mut cursor)?,
+            "Entities" => entities = parse_entities(&mut cursor)?,
+            "Nodes" => parse_nodes(&mut cursor, &mut mesh.nodes, &mut lookup)?,
+            "Elements" => parse_elements(&mut cursor, &entities, &mut elements)?,
+            other => cursor.skip_section(other)?,
+        }
+    }
+    if !seen_format {
+        return Err(malformed("missing $MeshFormat section"));
+    }
+
+    let resolve = |tag: usize| {
+        lookup
+            .get(&tag)
+            .copied()
+            .ok_or_else(|| malformed(format!("element refers to missing node {}", tag)))
+    };
+    for (physical, tags) in elements {
+        let indices = tags
+            .into_iter()
+            .map(resolve)
+            .collect::<Result<Vec<_>, _>>()?;
+        match *indices.as_slice() {
+            [a, b, c, d] => {
+                mesh.tetrahedra.push([a, b, c, d]);
+                mesh.tetrahedron_groups.push(physical);
+            }
+            [a, b, c] => {
+                mesh.triangles.push([a, b, c]);
+                mesh.triangle_groups.push(physical);
+            }
+            _ => unreachable!("only tetrahedra and triangles are kept"),
+        }
+    }
+    Ok(mesh)
+}
+
+/// Parses the tetrahedra of a Gmsh MSH 4.1 file, e.g. for
+/// [`refine_tetrahedra`](crate::pipeline::refine_tetrahedra).
+///
+/// # Errors
+///
+/// Same as [`msh_to_mesh`].
+pub fn msh_to_tetrahedra(bytes: &[u8]) -> Result<Vec<Tetrahedron>, MeshingError> {
+    Ok(msh_to_mesh(bytes)?.to_tetrahedra())
+}
+
+/// Parses the triangles of a Gmsh MSH 4.1 file as faces.
+///
+/// # Errors
+///
+/// Same as [`msh_to_mesh`].
+pub fn msh_to_faces(bytes: &[u8]) -> Result<Vec<Face>, MeshingError> {
+    Ok(msh_to_mesh(bytes)?.to_faces())
+}
+
+/// Reads a Gmsh MSH 4.1 file from `reader`, as [`msh_to_mesh`].
+///
+/// # Errors
+///
+/// Returns [`MeshingError::Io`] if reading fails, otherwise the errors of
+/// [`msh_to_mesh`].
+pub fn read_msh<R: Read>(mut reader: R) -> Result<MshMesh, MeshingError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    msh_to_mesh(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{faces_to_msh, mesh_to_msh, MshFormat};
+    use crate::pipeline::refine_tetrahedra;
+    use crate::voxel_mesh::voxel_mesh_indexed;
+    use crate::TetMesh;
+
+    fn p(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    fn grouped_cube() -> MshMesh {
+        let tets = voxel_mesh_indexed(p(0, 0.0, 0.0, 0.0), p(0, 2.0, 1.0, 1.0), 2, 1, 1, &|_| true);
+        let mut mesh = MshMesh::from_tet_mesh(&tets);
+        mesh.tetrahedron_groups = mesh
+            .to_tetrahedra()
+            .iter()
+            .map(|t| {
+                if t.a.x + t.b.x + t.c.x + t.d.x < 4.0 {
+                    1
+                } else {
+                    2
+                }
+            })
+            .collect();
+        let surface = tets.surface();
+        mesh.triangles = surface
+            .faces
+            .iter()
+            .map(|f| {
+                f.map(|v| {
+                    tets.vertices
+                        .iter()
+                        .position(|u| *u == surface.vertices[v])
+                        .unwrap()
+                })
+            })
+            .collect();
+        mesh.triangle_groups = vec![3; mesh.triangles.len()];
+        mesh.physical_names = vec![
+            MshPhysicalName {
+                dimension: 3,
+                tag: 1,
+                name: "left".to_string(),
+            },
+            MshPhysicalName {
+                dimension: 3,
+                tag: 2,
+                name: "right block".to_string(),
+            },
+            MshPhysicalName {
+                dimension: 2,
+                tag: 3,
+                name: "wall".to_string(),
+            },
+        ];
+        mesh
+    }
+
+    /// The reader returns elements grouped by entity, so compare sorted
+    /// (element, group) lists.
+    fn normalised(mesh: &MshMesh) -> Vec<(Vec<usize>, i32)> {
+        let tets = mesh.tetrahedra.iter().map(|t| t.to_vec());
+        let triangles = mesh.triangles.iter().map(|t| t.to_vec());
+        let groups = mesh.tetrahedron_groups.iter().chain(&mesh.triangle_groups);
+        let mut elements: Vec<_> = tets.chain(triangles).zip(groups.copied()).collect();
+        elements.sort_unstable();
+        elements
+    }
+
+    #[test]
+    fn test_round_trip_with_groups() {
+        let mesh = grouped_cube();
+        for format in [MshFormat::Ascii, MshFormat::Binary] {
+            let read = msh_to_mesh(&mesh_to_msh(&mesh, format)).unwrap();
+            assert_eq!(read.nodes, mesh.nodes);
+            assert_eq!(read.physical_names, mesh.physical_names);
+            assert_eq!(normalised(&read), normalised(&mesh));
+            assert_eq!(read.tetrahedra_in_group(1).len(), 5);
+            assert_eq!(read.faces_in_group(3).len(), mesh.triangles.len());
+        }
+    }
+
+    #[test]
+    fn test_imported_tetrahedra_can_be_refined() {
+        let mesh = grouped_cube();
+        let bytes = mesh_to_msh(&mesh, MshFormat::Binary);
+        let tetrahedra = read_msh(bytes.as_slice()).unwrap().to_tetrahedra();
+        let volume: f64 = tetrahedra.iter().map(|t| t.signed_volume().abs()).sum();
+        assert!((volume - 2.0).abs() < 1e-12);
+        assert!(!refine_tetrahedra(&tetrahedra, 2.0).is_empty());
+
+        let faces = TetMesh::from_tetrahedra(&tetrahedra).surface().to_faces();
+        assert_eq!(
+            msh_to_faces(&faces_to_msh(&faces, MshFormat::Ascii)).unwrap(),
+            faces
+        );
+    }
+
+    #[test]
+    fn test_gmsh_style_file() {
+        // Node tags that are not contiguous, a parametric node block,
+        // point and line elements to skip and an unknown section.
+        let msh = r#"$MeshFormat
+4.1 0 8
+$EndMeshFormat
+$PhysicalNames
+1
+3 7 "domain"
+$EndPhysicalNames
+$Entities
+1 1 0 1
+1 0 0 0 0
+1 0 0 0 1 0 0 0 2 1 -2
+1 0 0 0 1 1 1 1 7 0
+$EndEntities
+$Nodes
+3 4 10 40
+0 1 0 1
+10
+0 0 0
+1 1 1 1
+20
+1 0 0 0.5
+3 1 0 2
+30
+40
+0 1 0
+0 0 1
+$EndNodes
+$Elements
+3 3 1 3
+0 1 15 1
+1 10
+1 1 1 1
+2 10 20
+3 1 4 1
+3 10 20 30 40
+$EndElements
+$NodeData
+1
+"temperature"
+$EndNodeData
+"#;
+        let mesh = msh_to_mesh(msh.as_bytes()).unwrap();
+        assert_eq!(mesh.nodes.len(), 4);
+        assert_eq!(mesh.nodes[1], p(1, 1.0, 0.0, 0.0));
+        assert_eq!(mesh.tetrahedra, vec![[0, 1, 2, 3]]);
+        assert_eq!(mesh.tetrahedron_groups, vec![7]);
+        assert!(mesh.triangles.is_empty());
+        assert_eq!(mesh.physical_names[0].name, "domain");
+    }
+
+    #[test]
+    fn test_malformed_files() {
+        let good = String::from_utf8(mesh_to_msh(&grouped_cube(), MshFormat::Ascii)).unwrap();
+        let cases = [
+            "$MeshFormat\n2.2 0 8\n$EndMeshFormat\n".to_string(),
+            "$Nodes\n0 0 0 0\n$EndNodes\n".to_string(),
+            good.replacen("$EndNodes", "$EndNods", 1),
+            good.replacen("\n3 1 4 ", "\n3 1 99 ", 1),
+            good[..good.len() / 2].to_string(),
+            good.replacen("$Nodes\n1 ", "$Nodes\n0 ", 1),
+        ];
+        for msh in &cases {
+            assert!(
+                matches!(
+                    msh_to_mesh(msh.as_bytes()),
+                    Err(MeshingError::MalformedFile(_))
+                ),
+                "{}",
+                &msh[..msh.len().min(80)]
+            );
+        }
+
+        let binary = mesh_to_msh(&grouped_cube(), MshFormat::Binary);
+        assert!(matches!(
+            msh_to_mesh(&binary[..binary.len() - 40]),
+            Err(MeshingError::MalformedFile(_))
+        ));
+    }
+}
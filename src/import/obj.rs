@@ -0,0 +1,464 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+
+use crate::error::MeshingError;
+use crate::predicates::orient2d;
+use crate::{Face, Point2D, Point3D, SurfaceMesh};
+
+/// Group name given to faces that appear before any `g` or `o` statement.
+const DEFAULT_GROUP: &str = "default";
+
+/// Faces read from a Wavefront OBJ file together with their group tags.
+///
+/// `face_groups[i]` lists the positions in `groups` of the groups that
+/// `faces[i]` was declared in: one for an `o` statement, one per name of a
+/// `g` statement. Polygons are split into triangles, each of which inherits
+/// the polygon's groups.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObjMesh {
+    pub faces: Vec<Face>,
+    pub face_groups: Vec<Vec<usize>>,
+    pub groups: Vec<String>,
+}
+
+impl ObjMesh {
+    /// Returns the faces tagged with the group called `name`.
+    pub fn group_faces(&self, name: &str) -> Vec<Face> {
+        match self.groups.iter().position(|g| g == name) {
+            Some(group) => self
+                .faces
+                .iter()
+                .zip(&self.face_groups)
+                .filter(|(_, groups)| groups.contains(&group))
+                .map(|(f, _)| *f)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+fn malformed(line_no: usize, message: impl Into<String>) -> MeshingError {
+    MeshingError::MalformedFile(format!("OBJ: line {}: {}", line_no, message.into()))
+}
+
+/// Resolves the position part of a `v/vt/vn` reference to a 0-based vertex
+/// index. Negative indices count back from the last vertex read so far.
+fn resolve_index(token: &str, count: usize, line_no: usize) -> Result<usize, MeshingError> {
+    let position = token.split('/').next().unwrap_or("");
+    let index: i64 = position
+        .parse()
+        .map_err(|_| malformed(line_no, format!("invalid vertex reference '{}'", token)))?;
+    let resolved = match index {
+        i if i > 0 => i - 1,
+        i if i < 0 => count as i64 + i,
+        _ => return Err(malformed(line_no, "vertex index 0 is not allowed")),
+    };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(malformed(
+            line_no,
+            format!(
+                "vertex reference '{}' is out of range ({} vertices defined)",
+                token, count
+            ),
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+/// Splits a planar (or nearly planar) polygon into triangles by ear
+/// clipping in the plane of its Newell normal, keeping the polygon's
+/// winding. Falls back to a fan when the polygon is degenerate.
+pub(super) fn triangulate_polygon(polygon: &[Point3D]) -> Vec<[usize; 3]> {
+    let n = polygon.len();
+    let fan = |remaining: &[usize]| -> Vec<[usize; 3]> {
+        (1..remaining.len() - 1)
+            .map(|i| [remaining[0], remaining[i], remaining[i + 1]])
+            .collect()
+    };
+    if n == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    let mut normal = [0.0; 3];
+    for i in 0..n {
+        let (p, q) = (polygon[i], polygon[(i + 1) % n]);
+        normal[0] += (p.y - q.y) * (p.z + q.z);
+        normal[1] += (p.z - q.z) * (p.x + q.x);
+        normal[2] += (p.x - q.x) * (p.y + q.y);
+    }
+    let axis = (0..3)
+        .max_by(|&i, &j| normal[i].abs().total_cmp(&normal[j].abs()))
+        .expect("three axes");
+    if normal[axis] == 0.0 {
+        return fan(&(0..n).collect::<Vec<_>>());
+    }
+    // Cyclic projections keep the orientation of the dropped axis, so the
+    // polygon is counter-clockwise in 2D exactly when that component is
+    // positive.
+    let projected: Vec<Point2D> = polygon
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let (x, y) = match axis {
+                0 => (p.y, p.z),
+                1 => (p.z, p.x),
+                _ => (p.x, p.y),
+            };
+            Point2D {
+                index: i as i64,
+                x,
+                y,
+            }
+        })
+        .collect();
+    let ccw = normal[axis] > 0.0;
+    let orient = |a: usize, b: usize, c: usize| {
+        let o = orient2d(&projected[a], &projected[b], &projected[c]);
+        if ccw {
+            o
+        } else {
+            -o
+        }
+    };
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut triangles = Vec::with_capacity(n - 2);
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        // Starting at the second vertex makes convex polygons come out as
+        // a fan around the first one.
+        let ear = (1..=m).map(|i| i % m).find(|&i| {
+            let (a, b, c) = (
+                remaining[(i + m - 1) % m],
+                remaining[i],
+                remaining[(i + 1) % m],
+            );
+            orient(a, b, c) > 0.0
+                && remaining.iter().all(|&q| {
+                    let same = |v: usize| {
+                        projected[q].x == projected[v].x && projected[q].y == projected[v].y
+                    };
+                    same(a)
+                        || same(b)
+                        || same(c)
+                        || orient(a, b, q) < 0.0
+                        || orient(b, c, q) < 0.0
+                        || orient(c, a, q) < 0.0
+                })
+        });
+        match ear {
+            Some(i) => {
+                triangles.push([
+                    remaining[(i + m - 1) % m],
+                    remaining[i],
+                    remaining[(i + 1) % m],
+                ]);
+                remaining.remove(i);
+            }
+            None => {
+                triangles.extend(fan(&remaining));
+                return triangles;
+            }
+        }
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
+
+/// Parses a Wavefront OBJ file into triangles tagged with their groups.
+///
+/// Vertex `i` of the file (1-based) becomes a [`Point3D`] with index
+/// `i - 1`, so faces sharing a vertex in the file share it in the output.
+/// Face references may use the `v`, `v/vt`, `v//vn` or `v/vt/vn` forms and
+/// negative (relative) indices; texture coordinates and normals are
+/// ignored. Polygons with more than three vertices are triangulated by ear
+/// clipping, and triangles that repeat a vertex are dropped. Each `g` or
+/// `o` statement sets the groups of the faces that follow: `g a b` puts
+/// them in both `a` and `b`, while `o` takes the rest of the line as one
+/// object name. Faces before the first one belong to the group
+/// `"default"`. Other statements (`vt`, `vn`, `usemtl`, `s`, ...) are
+/// ignored.
+///
+/// # Errors
+///
+/// Returns [`MeshingError::MalformedFile`] for unparsable coordinates,
+/// faces with fewer than three vertices, or vertex references that are zero
+/// or out of range.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::import::obj_to_mesh;
+///
+/// let obj = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\ng quad\nf 1/1/1 2/2/1 -2 -1\n";
+/// let mesh = obj_to_mesh(obj).unwrap();
+/// assert_eq!(mesh.faces.len(), 2);
+/// assert_eq!(mesh.groups, vec!["quad".to_string()]);
+/// ```
+pub fn obj_to_mesh(text: &str) -> Result<ObjMesh, MeshingError> {
+    let mut vertices: Vec<Point3D> = Vec::new();
+    let mut mesh = ObjMesh::default();
+    let mut group_lookup: HashMap<String, usize> = HashMap::new();
+    let mut current_groups = vec![DEFAULT_GROUP.to_string()];
+
+    for (number, line) in text.lines().enumerate() {
+        let line_no = number + 1;
+        let line = line.split('#').next().unwrap_or("");
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(k) => k,
+            None => continue,
+        };
+        match keyword {
+            "v" => {
+                let mut coords = [0.0; 3];
+                for c in &mut coords {
+                    let token = tokens
+                        .next()
+                        .ok_or_else(|| malformed(line_no, "vertex needs three coordinates"))?;
+                    *c = token.parse().map_err(|_| {
+                        malformed(line_no, format!("invalid coordinate '{}'", token))
+                    })?;
+                }
+                vertices.push(Point3D {
+                    index: vertices.len() as i64,
+                    x: coords[0],
+                    y: coords[1],
+                    z: coords[2],
+                });
+            }
+            "f" => {
+                let polygon = tokens
+                    .map(|t| resolve_index(t, vertices.len(), line_no).map(|i| vertices[i]))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if polygon.len() < 3 {
+                    return Err(malformed(
+                        line_no,
+                        format!("face has {} vertices, expected at least 3", polygon.len()),
+                    ));
+                }
+                let groups: Vec<usize> = current_groups
+                    .iter()
+                    .map(|name| {
+                        let next = mesh.groups.len();
+                        *group_lookup.entry(name.clone()).or_insert_with(|| {
+                            mesh.groups.push(name.clone());
+                            next
+                        })
+                    })
+                    .collect();
+                for [a, b, c] in triangulate_polygon(&polygon) {
+                    let (a, b, c) = (polygon[a], polygon[b], polygon[c]);
+                    if a.index == b.index || b.index == c.index || c.index == a.index {
+                        continue;
+                    }
+                    mesh.faces.push(Face { a, b, c });
+                    mesh.face_groups.push(groups.clone());
+                }
+            }
+            "g" | "o" => {
+                let mut names: Vec<String> = if keyword == "g" {
+                    tokens.map(str::to_string).collect()
+                } else {
+                    vec![tokens.collect::<Vec<_>>().join(" ")]
+                };
+                let mut seen = HashSet::new();
+                names.retain(|name| !name.is_empty() && seen.insert(name.clone()));
+                current_groups = if names.is_empty() {
+                    vec![DEFAULT_GROUP.to_string()]
+                } else {
+                    names
+                };
+            }
+            _ => {}
+        }
+    }
+    Ok(mesh)
+}
+
+/// Parses a Wavefront OBJ file into faces, discarding group tags.
+///
+/// # Errors
+///
+/// Same as [`obj_to_mesh`].
+pub fn obj_to_faces(text: &str) -> Result<Vec<Face>, MeshingError> {
+    Ok(obj_to_mesh(text)?.faces)
+}
+
+/// Parses a Wavefront OBJ file into an indexed [`SurfaceMesh`].
+///
+/// Vertices not referenced by any face are dropped.
+///
+/// # Errors
+///
+/// Same as [`obj_to_mesh`].
+pub fn obj_to_surface_mesh(text: &str) -> Result<SurfaceMesh, MeshingError> {
+    Ok(SurfaceMesh::from_faces(&obj_to_faces(text)?))
+}
+
+/// Reads a Wavefront OBJ file from `reader`, as [`obj_to_mesh`].
+///
+/// # Errors
+///
+/// Returns [`MeshingError::Io`] if reading fails or the data is not valid
+/// UTF-8, otherwise the errors of [`obj_to_mesh`].
+pub fn read_obj<R: Read>(mut reader: R) -> Result<ObjMesh, MeshingError> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    obj_to_mesh(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{faces_to_obj, tetrahedra_to_obj};
+    use crate::marching_cubes::marching_cubes_indexed;
+    use crate::voxel_mesh::voxel_mesh;
+
+    fn p(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    fn area_vector(faces: &[Face]) -> [f64; 3] {
+        let mut sum = [0.0; 3];
+        for f in faces {
+            let u = [f.b.x - f.a.x, f.b.y - f.a.y, f.b.z - f.a.z];
+            let v = [f.c.x - f.a.x, f.c.y - f.a.y, f.c.z - f.a.z];
+            sum[0] += 0.5 * (u[1] * v[2] - u[2] * v[1]);
+            sum[1] += 0.5 * (u[2] * v[0] - u[0] * v[2]);
+            sum[2] += 0.5 * (u[0] * v[1] - u[1] * v[0]);
+        }
+        sum
+    }
+
+    #[test]
+    fn test_round_trip_preserves_shared_indices() {
+        let sphere = marching_cubes_indexed(
+            6,
+            6,
+            6,
+            p(0, -1.5, -1.5, -1.5),
+            p(0, 1.5, 1.5, 1.5),
+            &|x, y, z| x * x + y * y + z * z - 1.0,
+            0.0,
+        );
+        let faces = sphere.to_faces();
+        let read = obj_to_surface_mesh(&faces_to_obj(&faces)).unwrap();
+        assert_eq!(read.faces, SurfaceMesh::from_faces(&faces).faces);
+        assert_eq!(read.num_vertices(), sphere.num_vertices());
+        for (r, w) in read.vertices.iter().zip(&sphere.vertices) {
+            assert_eq!((r.x, r.y, r.z), (w.x, w.y, w.z));
+        }
+    }
+
+    #[test]
+    fn test_tetrahedra_surface_round_trip() {
+        let tets = voxel_mesh(p(0, 0.0, 0.0, 0.0), p(0, 1.0, 1.0, 1.0), 1, 1, 1, &|_| true);
+        let faces = obj_to_faces(&tetrahedra_to_obj(&tets)).unwrap();
+        let surface = crate::TetMesh::from_tetrahedra(&tets).surface();
+        assert_eq!(faces.len(), 12);
+        assert_eq!(SurfaceMesh::from_faces(&faces).faces, surface.faces);
+    }
+
+    #[test]
+    fn test_reference_forms_and_negative_indices() {
+        let obj = "\
+# a unit square split two ways
+v 0 0 0
+v 1 0 0
+v 1 1 0
+vt 0 0
+vn 0 0 1
+f 1/1 2/1/1 3//1
+v 0 1 0
+f -4 -2 -1
+";
+        let faces = obj_to_faces(obj).unwrap();
+        assert_eq!(faces.len(), 2);
+        let indices: Vec<[i64; 3]> = faces
+            .iter()
+            .map(|f| [f.a.index, f.b.index, f.c.index])
+            .collect();
+        assert_eq!(indices, vec![[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn test_concave_polygon_is_ear_clipped() {
+        // An L-shape in the plane x = 1 whose fan from vertex 0 would
+        // leave the polygon.
+        let obj = "\
+v 1 2 0
+v 1 2 2
+v 1 0 2
+v 1 0 1
+v 1 1 1
+v 1 1 0
+f 1 2 3 4 5 6
+";
+        let faces = obj_to_faces(obj).unwrap();
+        assert_eq!(faces.len(), 4);
+        let [x, y, z] = area_vector(&faces);
+        assert!((x - 3.0).abs() < 1e-12 && y == 0.0 && z == 0.0);
+        for f in &faces {
+            let u = [f.b.y - f.a.y, f.b.z - f.a.z];
+            let v = [f.c.y - f.a.y, f.c.z - f.a.z];
+            assert!(u[0] * v[1] - u[1] * v[0] > 0.0, "winding flipped");
+        }
+    }
+
+    #[test]
+    fn test_groups_become_face_tags() {
+        let obj = "\
+v 0 0 0
+v 1 0 0
+v 0 1 0
+v 0 0 1
+f 1 3 2
+o body part
+f 2 4 1
+g top side
+f 1 2 4
+f 2 3 4
+g
+f 3 1 4
+";
+        let mesh = obj_to_mesh(obj).unwrap();
+        assert_eq!(mesh.groups, vec!["default", "body part", "top", "side"]);
+        assert_eq!(
+            mesh.face_groups,
+            vec![vec![0], vec![1], vec![2, 3], vec![2, 3], vec![0]]
+        );
+        assert_eq!(mesh.group_faces("top").len(), 2);
+        assert_eq!(mesh.group_faces("side").len(), 2);
+        assert_eq!(mesh.group_faces("body part").len(), 1);
+        assert!(mesh.group_faces("top side").is_empty());
+
+        // Repeated names tag a face once, in order of first appearance.
+        let repeated = obj_to_mesh("v 0 0 0\nv 1 0 0\nv 0 1 0\ng a b a\nf 1 2 3\n").unwrap();
+        assert_eq!(repeated.groups, vec!["a", "b"]);
+        assert_eq!(repeated.face_groups, vec![vec![0, 1]]);
+        assert_eq!(repeated.group_faces("a").len(), 1);
+        let read = read_obj(obj.as_bytes()).unwrap();
+        assert_eq!(read, mesh);
+    }
+
+    #[test]
+    fn test_malformed_files() {
+        for (obj, line) in [
+            ("v 0 0\n", 1),
+            ("v 0 0 zero\n", 1),
+            ("v 0 0 0\nv 1 0 0\nf 1 2\n", 3),
+            ("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n", 4),
+            ("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 0 1 2\n", 4),
+            ("v 0 0 0\nv 1 0 0\nv 0 1 0\nf -4 1 2\n", 4),
+            ("v 0 0 0\nv 1 0 0\nv 0 1 0\nf a/1 1 2\n", 4),
+        ] {
+            match obj_to_mesh(obj) {
+                Err(MeshingError::MalformedFile(message)) => {
+                    assert!(message.contains(&format!("line {}", line)), "{}", message)
+                }
+                other => panic!("expected malformed file for {:?}, got {:?}", obj, other),
+            }
+        }
+    }
+}
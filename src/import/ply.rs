@@ -0,0 +1,600 @@
+use std::io::Read;
+
+use super::obj::triangulate_polygon;
+use crate::error::MeshingError;
+use crate::export::PlyAttributes;
+use crate::{Face, Point3D, SurfaceMesh};
+
+/// A surface or point cloud read from a PLY file.
+///
+/// `surface.vertices` holds every vertex of the file in order (with
+/// [`Point3D::index`] equal to its position), and `attributes` holds the
+/// per-vertex normals and any other numeric vertex properties.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlyMesh {
+    pub surface: SurfaceMesh,
+    pub attributes: PlyAttributes,
+}
+
+fn malformed(message: impl Into<String>) -> MeshingError {
+    MeshingError::MalformedFile(format!("PLY: {}", message.into()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Result<ScalarType, MeshingError> {
+        Ok(match name {
+            "char" | "int8" => ScalarType::I8,
+            "uchar" | "uint8" => ScalarType::U8,
+            "short" | "int16" => ScalarType::I16,
+            "ushort" | "uint16" => ScalarType::U16,
+            "int" | "int32" => ScalarType::I32,
+            "uint" | "uint32" => ScalarType::U32,
+            "float" | "float32" => ScalarType::F32,
+            "double" | "float64" => ScalarType::F64,
+            other => return Err(malformed(format!("unknown property type '{}'", other))),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::I8 | ScalarType::U8 => 1,
+            ScalarType::I16 | ScalarType::U16 => 2,
+            ScalarType::I32 | ScalarType::U32 | ScalarType::F32 => 4,
+            ScalarType::F64 => 8,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum PropertyKind {
+    Scalar(ScalarType),
+    List(ScalarType, ScalarType),
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Parses the header and returns the encoding, the declared elements and
+/// the offset of the body.
+fn parse_header(bytes: &[u8]) -> Result<(Encoding, Vec<Element>, usize), MeshingError> {
+    let mut offset = 0;
+    let mut next_line = || -> Result<&str, MeshingError> {
+        let rest = &bytes[offset..];
+        let end = rest
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| malformed("header is not terminated by end_header"))?;
+        offset += end + 1;
+        std::str::from_utf8(&rest[..end])
+            .map(|line| line.trim_end_matches('\r'))
+            .map_err(|_| malformed("header is not valid text"))
+    };
+
+    if next_line()?.trim() != "ply" {
+        return Err(malformed("missing 'ply' magic number"));
+    }
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+    loop {
+        let line = next_line()?;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            [] | ["comment", ..] | ["obj_info", ..] => {}
+            ["end_header"] => break,
+            ["format", kind, _version] => {
+                encoding = Some(match *kind {
+                    "ascii" => Encoding::Ascii,
+                    "binary_little_endian" => Encoding::BinaryLittleEndian,
+                    "binary_big_endian" => Encoding::BinaryBigEndian,
+                    other => return Err(malformed(format!("unknown format '{}'", other))),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| malformed(format!("invalid element count '{}'", count)))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count_type, item_type, name] => {
+                let kind = PropertyKind::List(
+                    ScalarType::parse(count_type)?,
+                    ScalarType::parse(item_type)?,
+                );
+                elements
+                    .last_mut()
+                    .ok_or_else(|| malformed("property declared before any element"))?
+                    .properties
+                    .push(Property {
+                        name: name.to_string(),
+                        kind,
+                    });
+            }
+            ["property", ty, name] => {
+                let kind = PropertyKind::Scalar(ScalarType::parse(ty)?);
+                elements
+                    .last_mut()
+                    .ok_or_else(|| malformed("property declared before any element"))?
+                    .properties
+                    .push(Property {
+                        name: name.to_string(),
+                        kind,
+                    });
+            }
+            _ => return Err(malformed(format!("unexpected header line '{}'", line))),
+        }
+    }
+    let encoding = encoding.ok_or_else(|| malformed("missing format line"))?;
+    Ok((encoding, elements, offset))
+}
+
+/// Sequential access to the values in the body of the file.
+enum Body<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary {
+        bytes: &'a [u8],
+        position: usize,
+        big_endian: bool,
+    },
+}
+
+impl Body<'_> {
+    fn read(&mut self, ty: ScalarType) -> Result<f64, MeshingError> {
+        match self {
+            Body::Ascii(tokens) => {
+                let token = tokens
+                    .next()
+                    .ok_or_else(|| malformed("unexpected end of data"))?;
+                token
+                    .parse()
+                    .map_err(|_| malformed(format!("invalid number '{}'", token)))
+            }
+            Body::Binary {
+                bytes,
+                position,
+                big_endian,
+            } => {
+                let size = ty.size();
+                let raw = bytes
+                    .get(*position..*position + size)
+                    .ok_or_else(|| malformed("unexpected end of data"))?;
+                *position += size;
+                let mut buffer = [0u8; 8];
+                buffer[..size].copy_from_slice(raw);
+                if *big_endian {
+                    buffer[..size].reverse();
+                }
+                let b = buffer;
+                Ok(match ty {
+                    ScalarType::I8 => b[0] as i8 as f64,
+                    ScalarType::U8 => b[0] as f64,
+                    ScalarType::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    ScalarType::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    ScalarType::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    ScalarType::U32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    ScalarType::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    ScalarType::F64 => f64::from_le_bytes(b),
+                })
+            }
+        }
+    }
+
+    fn read_index(&mut self, ty: ScalarType) -> Result<usize, MeshingError> {
+        let value = self.read(ty)?;
+        if value < 0.0 || value.fract() != 0.0 {
+            return Err(malformed(format!("invalid count or index {}", value)));
+        }
+        Ok(value as usize)
+    }
+
+    /// Reads one property value, returning list items as a vector.
+    fn read_property(&mut self, kind: &PropertyKind) -> Result<Vec<f64>, MeshingError> {
+        match *kind {
+            PropertyKind::Scalar(ty) => Ok(vec![self.read(ty)?]),
+            PropertyKind::List(count_type, item_type) => {
+                let count = self.read_index(count_type)?;
+                (0..count).map(|_| self.read(item_type)).collect()
+            }
+        }
+    }
+}
+
+/// Parses an ASCII or binary PLY file into a surface with per-vertex
+/// attributes.
+///
+/// The `vertex` element must have `x`, `y` and `z` properties; `nx`, `ny`
+/// and `nz` are read as normals and every other scalar vertex property
+/// becomes a named scalar attribute. Polygons of the `face` element
+/// (`vertex_indices` or `vertex_index`) are triangulated as in
+/// [`obj_to_mesh`](super::obj_to_mesh), dropping triangles that repeat a
+/// vertex. A file without a `face` element yields a point cloud, and other
+/// elements are skipped. Little- and big-endian binary files are accepted.
+///
+/// # Errors
+///
+/// Returns [`MeshingError::MalformedFile`] if the header is invalid, the
+/// body is truncated or unparsable, `x`/`y`/`z` are missing, or a face
+/// refers to a vertex that does not exist.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::import::ply_to_mesh;
+///
+/// let ply = "ply\nformat ascii 1.0\nelement vertex 4\nproperty float x\n\
+///            property float y\nproperty float z\nproperty float value\n\
+///            element face 1\nproperty list uchar int vertex_indices\nend_header\n\
+///            0 0 0 1\n1 0 0 2\n1 1 0 3\n0 1 0 4\n4 0 1 2 3\n";
+/// let mesh = ply_to_mesh(ply.as_bytes()).unwrap();
+/// assert_eq!(mesh.surface.faces, vec![[0, 1, 2], [0, 2, 3]]);
+/// assert_eq!(mesh.attributes.scalars[0].1, vec![1.0, 2.0, 3.0, 4.0]);
+/// ```
+pub fn ply_to_mesh(bytes: &[u8]) -> Result<PlyMesh, MeshingError> {
+    let (encoding, elements, offset) = parse_header(bytes)?;
+    let mut body = match encoding {
+        Encoding::Ascii => Body::Ascii(
+            std::str::from_utf8(&bytes[offset..])
+                .map_err(|_| malformed("ASCII body is not valid text"))?
+                .split_ascii_whitespace(),
+        ),
+        Encoding::BinaryLittleEndian | Encoding::BinaryBigEndian => Body::Binary {
+            bytes: &bytes[offset..],
+            position: 0,
+            big_endian: encoding == Encoding::BinaryBigEndian,
+        },
+    };
+
+    let mut vertices: Vec<Point3D> = Vec::new();
+    let mut normals: Option<Vec<[f64; 3]>> = None;
+    let mut scalars: Vec<(String, Vec<f64>)> = Vec::new();
+    let mut polygons: Vec<Vec<usize>> = Vec::new();
+
+    for element in &elements {
+        let find = |name: &str| {
+            element
+                .properties
+                .iter()
+                .position(|p| p.name == name && matches!(p.kind, PropertyKind::Scalar(_)))
+        };
+        match element.name.as_str() {
+            "vertex" => {
+                let position = ["x", "y", "z"].map(find);
+                let [Some(x), Some(y), Some(z)] = position else {
+                    return Err(malformed("vertex element needs x, y and z properties"));
+                };
+                let normal = match ["nx", "ny", "nz"].map(find) {
+                    [Some(nx), Some(ny), Some(nz)] => Some([nx, ny, nz]),
+                    _ => None,
+                };
+                let extra: Vec<usize> = (0..element.properties.len())
+                    .filter(|&i| {
+                        matches!(element.properties[i].kind, PropertyKind::Scalar(_))
+                            && ![x, y, z].contains(&i)
+                            && !normal.is_some_and(|n| n.contains(&i))
+                    })
+                    .collect();
+                scalars = extra
+                    .iter()
+                    .map(|&i| (element.properties[i].name.clone(), Vec::new()))
+                    .collect();
+                let mut read_normals = Vec::new();
+
+                for index in 0..element.count {
+                    let mut values = Vec::with_capacity(element.properties.len());
+                    for property in &element.properties {
+                        values.push(body.read_property(&property.kind)?);
+                    }
+                    let value = |i: usize| values[i][0];
+                    vertices.push(Point3D {
+                        index: index as i64,
+                        x: value(x),
+                        y: value(y),
+                        z: value(z),
+                    });
+                    if let Some(n) = normal {
+                        read_normals.push(n.map(value));
+                    }
+                    for (slot, &i) in scalars.iter_mut().zip(&extra) {
+                        slot.1.push(value(i));
+                    }
+                }
+                normals = normal.map(|_| read_normals);
+            }
+            "face" => {
+                let list = element.properties.iter().position(|p| {
+                    (p.name == "vertex_indices" || p.name == "vertex_index")
+                        && matches!(p.kind, PropertyKind::List(..))
+                });
+                let list = list.ok_or_else(|| {
+                    malformed("face element needs a vertex_indices list property")
+                })?;
+                for _ in 0..element.count {
+                    for (i, property) in element.properties.iter().enumerate() {
+                        if i != list {
+                            body.read_property(&property.kind)?;
+                            continue;
+                        }
+                        let indices = match property.kind {
+                            PropertyKind::List(count_type, item_type) => {
+                                let count = body.read_index(count_type)?;
+                                (0..count)
+                                    .map(|_| body.read_index(item_type))
+                                    .collect::<Result<Vec<_>, _>>()?
+                            }
+                            PropertyKind::Scalar(_) => unreachable!("checked to be a list"),
+                        };
+                        polygons.push(indices);
+                    }
+                }
+            }
+            _ => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        body.read_property(&property.kind)?;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut faces = Vec::new();
+    for polygon in &polygons {
+        if polygon.len() < 3 {
+            return Err(malformed(format!(
+                "face has {} vertices, expected at least 3",
+                polygon.len()
+            )));
+        }
+        if let Some(&i) = polygon.iter().find(|&&i| i >= vertices.len()) {
+            return Err(malformed(format!(
+                "face refers to vertex {} but there are {} vertices",
+                i,
+                vertices.len()
+            )));
+        }
+        let points: Vec<Point3D> = polygon.iter().map(|&i| vertices[i]).collect();
+        for [a, b, c] in triangulate_polygon(&points) {
+            let (a, b, c) = (polygon[a], polygon[b], polygon[c]);
+            if a != b && b != c && c != a {
+                faces.push([a, b, c]);
+            }
+        }
+    }
+
+    Ok(PlyMesh {
+        surface: SurfaceMesh { vertices, faces },
+        attributes: PlyAttributes { normals, scalars },
+    })
+}
+
+/// Parses a PLY file into faces, discarding vertex attributes.
+///
+/// # Errors
+///
+/// Same as [`ply_to_mesh`].
+pub fn ply_to_faces(bytes: &[u8]) -> Result<Vec<Face>, MeshingError> {
+    Ok(ply_to_mesh(bytes)?.surface.to_faces())
+}
+
+/// Parses the vertices of a PLY file as a point cloud, ignoring any faces.
+///
+/// # Errors
+///
+/// Same as [`ply_to_mesh`].
+pub fn ply_to_points(bytes: &[u8]) -> Result<Vec<Point3D>, MeshingError> {
+    Ok(ply_to_mesh(bytes)?.surface.vertices)
+}
+
+/// Reads a PLY file from `reader`, as [`ply_to_mesh`].
+///
+/// # Errors
+///
+/// Returns [`MeshingError::Io`] if reading fails, otherwise the errors of
+/// [`ply_to_mesh`].
+pub fn read_ply<R: Read>(mut reader: R) -> Result<PlyMesh, MeshingError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    ply_to_mesh(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{
+        faces_to_ply, points_to_ply, surface_mesh_to_ply_with_attributes, tetrahedra_to_ply,
+        PlyFormat,
+    };
+    use crate::marching_cubes::marching_cubes_indexed;
+    use crate::TetMesh;
+
+    fn p(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    fn sphere_with_attributes() -> (SurfaceMesh, PlyAttributes) {
+        let field = |x: f64, y: f64, z: f64| x * x + y * y + z * z - 1.0;
+        let mesh = marching_cubes_indexed(
+            8,
+            8,
+            8,
+            p(0, -1.5, -1.5, -1.5),
+            p(0, 1.5, 1.5, 1.5),
+            &field,
+            0.0,
+        );
+        let normals = mesh
+            .vertices
+            .iter()
+            .map(|v| {
+                let len = (v.x * v.x + v.y * v.y + v.z * v.z).sqrt();
+                [v.x / len, v.y / len, v.z / len]
+            })
+            .collect();
+        let values = mesh.vertices.iter().map(|v| field(v.x, v.y, v.z)).collect();
+        let attributes = PlyAttributes {
+            normals: Some(normals),
+            scalars: vec![
+                ("field".to_string(), values),
+                (
+                    "height".to_string(),
+                    mesh.vertices.iter().map(|v| v.z).collect(),
+                ),
+            ],
+        };
+        (mesh, attributes)
+    }
+
+    #[test]
+    fn test_round_trip_with_attributes() {
+        let (mesh, attributes) = sphere_with_attributes();
+        for format in [PlyFormat::Ascii, PlyFormat::BinaryLittleEndian] {
+            let bytes = surface_mesh_to_ply_with_attributes(&mesh, &attributes, format);
+            let read = ply_to_mesh(&bytes).unwrap();
+            assert_eq!(read.surface.faces, mesh.faces);
+            for (r, w) in read.surface.vertices.iter().zip(&mesh.vertices) {
+                assert_eq!((r.x, r.y, r.z), (w.x, w.y, w.z));
+            }
+            assert_eq!(read.attributes.scalars, attributes.scalars);
+            let normals = read.attributes.normals.unwrap();
+            for (r, w) in normals.iter().zip(attributes.normals.as_ref().unwrap()) {
+                for k in 0..3 {
+                    assert!((r[k] - w[k]).abs() < 1e-6);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_faces_and_tetrahedra_round_trip() {
+        let tet = crate::Tetrahedron {
+            a: p(0, 0.0, 0.0, 0.0),
+            b: p(1, 1.0, 0.0, 0.0),
+            c: p(2, 0.0, 1.0, 0.0),
+            d: p(3, 0.0, 0.0, 1.0),
+        };
+        let surface = TetMesh::from_tetrahedra(&[tet]).surface();
+        let bytes = tetrahedra_to_ply(&[tet], PlyFormat::BinaryLittleEndian);
+        assert_eq!(ply_to_mesh(&bytes).unwrap().surface, surface);
+
+        let faces = surface.to_faces();
+        let read = ply_to_faces(&faces_to_ply(&faces, PlyFormat::Ascii)).unwrap();
+        assert_eq!(read, faces);
+    }
+
+    #[test]
+    fn test_point_cloud() {
+        let points = vec![p(0, 1.0, 2.0, 3.0), p(1, -1.0, 0.5, 0.25)];
+        let bytes = points_to_ply(&points, &PlyAttributes::default(), PlyFormat::Ascii);
+        assert_eq!(ply_to_points(&bytes).unwrap(), points);
+        assert!(ply_to_faces(&bytes).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_foreign_binary_file() {
+        // Big-endian float positions, an extra per-face property before
+        // the index list, a quad, and an unknown trailing element.
+        let mut bytes = b"ply\r\nformat binary_big_endian 1.0\r\ncomment scanner\r\n\
+                          element vertex 4\r\nproperty float x\r\nproperty float y\r\n\
+                          property float z\r\nproperty uchar red\r\n\
+                          element face 1\r\nproperty int flags\r\n\
+                          property list uchar uint vertex_index\r\n\
+                          element edge 1\r\nproperty int vertex1\r\nproperty int vertex2\r\n\
+                          end_header\r\n"
+            .to_vec();
+        for (i, [x, y]) in [[0.0f32, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]
+            .iter()
+            .enumerate()
+        {
+            for c in [x, y, &0.0] {
+                bytes.extend(c.to_be_bytes());
+            }
+            bytes.push(10 * i as u8);
+        }
+        bytes.extend(7i32.to_be_bytes());
+        bytes.push(4);
+        for i in 0u32..4 {
+            bytes.extend(i.to_be_bytes());
+        }
+        bytes.extend(0i32.to_be_bytes());
+        bytes.extend(1i32.to_be_bytes());
+
+        let mesh = read_ply(bytes.as_slice()).unwrap();
+        assert_eq!(mesh.surface.faces, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(mesh.surface.vertices[2], p(2, 1.0, 1.0, 0.0));
+        assert_eq!(
+            mesh.attributes.scalars,
+            vec![("red".to_string(), vec![0.0, 10.0, 20.0, 30.0])]
+        );
+        assert!(mesh.attributes.normals.is_none());
+    }
+
+    #[test]
+    fn test_malformed_files() {
+        let header = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\n\
+                      property float y\nproperty float z\nelement face 1\n\
+                      property list uchar int vertex_indices\nend_header\n";
+        let cases = [
+            "solid x\n".to_string(),
+            "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\n".to_string(),
+            "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nend_header\n0\n"
+                .to_string(),
+            "ply\nformat ascii 1.0\nelement vertex 1\nproperty quad x\nend_header\n".to_string(),
+            format!("{}0 0 0\n1 0 0\n0 1 0\n3 0 1 3\n", header),
+            format!("{}0 0 0\n1 0 0\n0 1 0\n2 0 1\n", header),
+            format!("{}0 0 0\n1 0 0\n0 1 0\n3 0 1\n", header),
+            format!("{}0 0 0\n1 0 zero\n0 1 0\n3 0 1 2\n", header),
+        ];
+        for ply in &cases {
+            assert!(
+                matches!(
+                    ply_to_mesh(ply.as_bytes()),
+                    Err(MeshingError::MalformedFile(_))
+                ),
+                "{:?}",
+                ply
+            );
+        }
+
+        let binary = faces_to_ply(
+            &[Face {
+                a: p(0, 0.0, 0.0, 0.0),
+                b: p(1, 1.0, 0.0, 0.0),
+                c: p(2, 0.0, 1.0, 0.0),
+            }],
+            PlyFormat::BinaryLittleEndian,
+        );
+        assert!(matches!(
+            ply_to_mesh(&binary[..binary.len() - 1]),
+            Err(MeshingError::MalformedFile(_))
+        ));
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::io::Read;
+
+use crate::error::MeshingError;
+use crate::{Face, Point3D, SurfaceMesh};
+
+/// Assigns shared indices to coincident vertices.
+///
+/// STL stores every facet with its own copy of the vertex coordinates;
+/// welding restores the connectivity that algorithms such as
+/// [`advancing_front`](crate::advancing_front::advancing_front) rely on.
+#[derive(Default)]
+pub(super) struct Welder {
+    indices: HashMap<[u64; 3], i64>,
+}
+
+impl Welder {
+    fn weld(&mut self, x: f64, y: f64, z: f64) -> Point3D {
+        // Treat -0.0 and 0.0 as the same coordinate.
+        let key = [x + 0.0, y + 0.0, z + 0.0].map(f64::to_bits);
+        let next = self.indices.len() as i64;
+        let index = *self.indices.entry(key).or_insert(next);
+        Point3D { index, x, y, z }
+    }
+
+    /// Builds a face, dropping facets that collapse to a line or point.
+    pub(super) fn face(&mut self, v: [[f64; 3]; 3]) -> Option<Face> {
+        let [a, b, c] = v.map(|[x, y, z]| self.weld(x, y, z));
+        if a.index == b.index || b.index == c.index || c.index == a.index {
+            return None;
+        }
+        Some(Face { a, b, c })
+    }
+}
+
+fn malformed(message: impl Into<String>) -> MeshingError {
+    MeshingError::MalformedFile(format!("STL: {}", message.into()))
+}
+
+/// Returns `true` if `bytes` has the exact size of a binary STL announcing
+/// its facet count at offset 80.
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() < 84 {
+        return false;
+    }
+    let count = u32::from_le_bytes(bytes[80..84].try_into().expect("4 bytes")) as u64;
+    84 + 50 * count == bytes.len() as u64
+}
+
+fn parse_binary(bytes: &[u8]) -> Result<Vec<Face>, MeshingError> {
+    if bytes.len() < 84 {
+        return Err(malformed(format!(
+            "binary file is {} bytes, shorter than the 84-byte header",
+            bytes.len()
+        )));
+    }
+    let count = u32::from_le_bytes(bytes[80..84].try_into().expect("4 bytes")) as usize;
+    let expected = 84 + 50 * count as u64;
+    if bytes.len() as u64 != expected {
+        return Err(malformed(format!(
+            "binary header announces {} facets ({} bytes) but the file has {} bytes",
+            count,
+            expected,
+            bytes.len()
+        )));
+    }
+
+    let mut welder = Welder::default();
+    let mut faces = Vec::with_capacity(count);
+    for record in bytes[84..].chunks_exact(50) {
+        let value = |i: usize| {
+            f32::from_le_bytes(record[i * 4..i * 4 + 4].try_into().expect("4 bytes")) as f64
+        };
+        // Values 0..3 are the stored normal, which is recomputed on export.
+        let vertex = |v: usize| [value(3 + 3 * v), value(4 + 3 * v), value(5 + 3 * v)];
+        if let Some(face) = welder.face([vertex(0), vertex(1), vertex(2)]) {
+            faces.push(face);
+        }
+    }
+    Ok(faces)
+}
+
+fn parse_ascii(text: &str) -> Result<Vec<Face>, MeshingError> {
+    let mut welder = Welder::default();
+    let mut faces = Vec::new();
+    let mut in_solid = false;
+    let mut loop_vertices: Option<Vec<[f64; 3]>> = None;
+
+    for (number, line) in text.lines().enumerate() {
+        let line_no = number + 1;
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(k) => k,
+            None => continue,
+        };
+        match keyword {
+            "solid" => in_solid = true,
+            "endsolid" => in_solid = false,
+            "facet" | "endfacet" if in_solid => {}
+            "outer" if in_solid => {
+                if loop_vertices.is_some() {
+                    return Err(malformed(format!("line {}: nested outer loop", line_no)));
+                }
+                loop_vertices = Some(Vec::with_capacity(3));
+            }
+            "vertex" => {
+                let vertices = loop_vertices.as_mut().ok_or_else(|| {
+                    malformed(format!("line {}: vertex outside of a loop", line_no))
+                })?;
+                let mut coords = [0.0; 3];
+                for c in &mut coords {
+                    *c = tokens
+                        .next()
+                        .and_then(|t| t.parse::<f64>().ok())
+                        .ok_or_else(|| {
+                            malformed(format!("line {}: expected three coordinates", line_no))
+                        })?;
+                }
+                vertices.push(coords);
+            }
+            "endloop" => {
+                let vertices = loop_vertices.take().ok_or_else(|| {
+                    malformed(format!("line {}: endloop without outer loop", line_no))
+                })?;
+                let [a, b, c]: [[f64; 3]; 3] = vertices.try_into().map_err(|v: Vec<_>| {
+                    malformed(format!(
+                        "line {}: facet has {} vertices, expected 3",
+                        line_no,
+                        v.len()
+                    ))
+                })?;
+                faces.extend(welder.face([a, b, c]));
+            }
+            other => {
+                return Err(malformed(format!(
+                    "line {}: unexpected keyword '{}'",
+                    line_no, other
+                )))
+            }
+        }
+    }
+    if loop_vertices.is_some() {
+        return Err(malformed("unexpected end of file inside a facet"));
+    }
+    Ok(faces)
+}
+
+/// Parses an ASCII or binary STL file into faces with welded vertices.
+///
+/// The encoding is detected automatically: a file whose size matches the
+/// facet count in a binary header is read as binary (even if the header
+/// happens to start with `solid`, as some exporters write), otherwise it is
+/// read as ASCII. Vertices with identical coordinates share one
+/// [`Point3D::index`], numbered from 0 in order of first appearance. Facets
+/// that collapse after welding are dropped, and stored normals are ignored.
+///
+/// # Errors
+///
+/// Returns [`MeshingError::MalformedFile`] if the data is neither a
+/// consistent binary STL nor valid ASCII STL.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::faces_to_stl_binary;
+/// use meshing::import::stl_to_faces;
+/// use meshing::{Face, Point3D};
+///
+/// let p = |index, x, y, z| Point3D { index, x, y, z };
+/// let face = Face { a: p(7, 0.0, 0.0, 0.0), b: p(8, 1.0, 0.0, 0.0), c: p(9, 0.0, 1.0, 0.0) };
+/// let faces = stl_to_faces(&faces_to_stl_binary(&[face], "tri")).unwrap();
+/// assert_eq!(faces.len(), 1);
+/// assert_eq!([faces[0].a.index, faces[0].b.index, faces[0].c.index], [0, 1, 2]);
+/// ```
+pub fn stl_to_faces(bytes: &[u8]) -> Result<Vec<Face>, MeshingError> {
+    if is_binary(bytes) {
+        return parse_binary(bytes);
+    }
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    if bytes[start..].starts_with(b"solid") {
+        let text = std::str::from_utf8(bytes)
+            .map_err(|e| malformed(format!("ASCII file is not valid UTF-8: {}", e)))?;
+        parse_ascii(text)
+    } else {
+        parse_binary(bytes)
+    }
+}
+
+/// Parses an STL file into an indexed [`SurfaceMesh`].
+///
+/// # Errors
+///
+/// Same as [`stl_to_faces`].
+pub fn stl_to_surface_mesh(bytes: &[u8]) -> Result<SurfaceMesh, MeshingError> {
+    Ok(SurfaceMesh::from_faces(&stl_to_faces(bytes)?))
+}
+
+/// Reads an STL file from `reader`, as [`stl_to_faces`].
+///
+/// # Errors
+///
+/// Returns [`MeshingError::Io`] if reading fails, otherwise the errors of
+/// [`stl_to_faces`].
+pub fn read_stl<R: Read>(mut reader: R) -> Result<Vec<Face>, MeshingError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    stl_to_faces(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{faces_to_stl, faces_to_stl_binary, tetrahedra_to_stl_binary};
+    use crate::marching_cubes::marching_cubes;
+
+    fn p(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    fn sphere_faces() -> Vec<Face> {
+        marching_cubes(
+            8,
+            8,
+            8,
+            p(0, -1.5, -1.5, -1.5),
+            p(0, 1.5, 1.5, 1.5),
+            &|x, y, z| x * x + y * y + z * z - 1.0,
+            0.0,
+        )
+    }
+
+    fn assert_same_geometry(read: &[Face], written: &[Face]) {
+        assert_eq!(read.len(), written.len());
+        for (r, w) in read.iter().zip(written) {
+            for (a, b) in r.vertices().iter().zip(w.vertices()) {
+                assert!((a.x - b.x).abs() < 1e-6);
+                assert!((a.y - b.y).abs() < 1e-6);
+                assert!((a.z - b.z).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_binary_round_trip_welds_vertices() {
+        let faces = sphere_faces();
+        let read = stl_to_faces(&faces_to_stl_binary(&faces, "sphere")).unwrap();
+        assert_same_geometry(&read, &faces);
+
+        // Every edge of the closed surface is shared by exactly two faces.
+        let mut edges: HashMap<(i64, i64), usize> = HashMap::new();
+        for f in &read {
+            for (u, v) in [(f.a, f.b), (f.b, f.c), (f.c, f.a)] {
+                *edges
+                    .entry((u.index.min(v.index), u.index.max(v.index)))
+                    .or_default() += 1;
+            }
+        }
+        assert!(edges.values().all(|&n| n == 2));
+    }
+
+    #[test]
+    fn test_ascii_round_trip() {
+        let faces = sphere_faces();
+        let read = stl_to_faces(faces_to_stl(&faces, "sphere").as_bytes()).unwrap();
+        assert_same_geometry(&read, &faces);
+        let mesh = stl_to_surface_mesh(faces_to_stl(&faces, "sphere").as_bytes()).unwrap();
+        assert_eq!(mesh.num_faces(), faces.len());
+        assert!(mesh.num_vertices() < 3 * faces.len());
+    }
+
+    #[test]
+    fn test_binary_header_starting_with_solid() {
+        let face = Face {
+            a: p(0, 0.0, 0.0, 0.0),
+            b: p(1, 1.0, 0.0, 0.0),
+            c: p(2, 0.0, 1.0, 0.0),
+        };
+        let bytes = faces_to_stl_binary(&[face], "solid looks like ascii");
+        let read = stl_to_faces(&bytes).unwrap();
+        assert_eq!(read.len(), 1);
+    }
+
+    #[test]
+    fn test_imported_tet_surface_feeds_advancing_front() {
+        use crate::advancing_front::advancing_front;
+        use crate::voxel_mesh::voxel_mesh;
+
+        let tets = voxel_mesh(p(0, 0.0, 0.0, 0.0), p(0, 1.0, 1.0, 1.0), 1, 1, 1, &|_| true);
+        let faces = read_stl(tetrahedra_to_stl_binary(&tets, "cube").as_slice()).unwrap();
+        let mesh = SurfaceMesh::from_faces(&faces);
+        assert_eq!(mesh.num_vertices(), 8);
+        let result = advancing_front(faces, mesh.vertices.clone());
+        assert!(!result.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_files() {
+        let truncated = &faces_to_stl_binary(&sphere_faces(), "s")[..200];
+        assert!(matches!(
+            stl_to_faces(truncated),
+            Err(MeshingError::MalformedFile(_))
+        ));
+        assert!(matches!(
+            stl_to_faces(b"\x00\x01"),
+            Err(MeshingError::MalformedFile(_))
+        ));
+
+        let bad_number = "solid s\nfacet normal 0 0 1\nouter loop\nvertex 0 0 zero\n";
+        assert!(matches!(
+            stl_to_faces(bad_number.as_bytes()),
+            Err(MeshingError::MalformedFile(_))
+        ));
+
+        let two_vertices = "solid s\n facet normal 0 0 1\n  outer loop\n   vertex 0 0 0\n   \
+                            vertex 1 0 0\n  endloop\n endfacet\nendsolid s\n";
+        let err = stl_to_faces(two_vertices.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("line 6"), "{}", err);
+
+        let unterminated = "solid s\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\n";
+        assert!(matches!(
+            stl_to_faces(unterminated.as_bytes()),
+            Err(MeshingError::MalformedFile(_))
+        ));
+    }
+}
@@ -0,0 +1,320 @@
+use crate::error::MeshingError;
+use crate::export::medit::surface_indices;
+use crate::export::{extract_surface_faces, TetGenMesh};
+use crate::{Point3D, TetMesh, Tetrahedron};
+
+fn malformed(file: &str, line: usize, message: impl Into<String>) -> MeshingError {
+    MeshingError::MalformedFile(format!(
+        "TetGen .{}: line {}: {}",
+        file,
+        line,
+        message.into()
+    ))
+}
+
+/// A line number and the tokens of that line.
+type Record<'a> = (usize, Vec<&'a str>);
+
+/// The non-empty lines of a TetGen file with `#` comments removed, split
+/// into tokens and paired with their 1-based line numbers.
+fn records(text: &str) -> impl Iterator<Item = Record<'_>> {
+    text.lines().enumerate().filter_map(|(i, line)| {
+        let tokens: Vec<&str> = line
+            .split('#')
+            .next()
+            .unwrap_or("")
+            .split_whitespace()
+            .collect();
+        (!tokens.is_empty()).then_some((i + 1, tokens))
+    })
+}
+
+/// Parses the tokens of one record as numbers, failing if there are fewer
+/// than `min`.
+fn numbers<T: std::str::FromStr>(
+    file: &str,
+    (line, tokens): &Record,
+    min: usize,
+) -> Result<Vec<T>, MeshingError> {
+    if tokens.len() < min {
+        return Err(malformed(
+            file,
+            *line,
+            format!("expected at least {} values", min),
+        ));
+    }
+    tokens
+        .iter()
+        .map(|token| {
+            token
+                .parse()
+                .map_err(|_| malformed(file, *line, format!("invalid number '{}'", token)))
+        })
+        .collect()
+}
+
+/// Splits a file into its header record and the number of records the
+/// header announces.
+fn section<'a>(
+    file: &'static str,
+    text: &'a str,
+) -> Result<(Record<'a>, Vec<Record<'a>>), MeshingError> {
+    let mut records = records(text);
+    let header = records
+        .next()
+        .ok_or_else(|| malformed(file, 1, "missing header"))?;
+    let count: usize = header.1[0]
+        .parse()
+        .map_err(|_| malformed(file, header.0, format!("invalid count '{}'", header.1[0])))?;
+    let body: Vec<_> = records.take(count).collect();
+    if body.len() < count {
+        return Err(malformed(
+            file,
+            header.0,
+            format!(
+                "header announces {} entries but {} follow",
+                count,
+                body.len()
+            ),
+        ));
+    }
+    Ok((header, body))
+}
+
+/// Converts the 1-based or 0-based node ids of an element to positions in
+/// the node list.
+fn resolve<const N: usize>(
+    file: &str,
+    line: usize,
+    ids: &[i64],
+    base: i64,
+    node_count: usize,
+) -> Result<[usize; N], MeshingError> {
+    let mut element = [0; N];
+    for (slot, &id) in element.iter_mut().zip(ids) {
+        *slot = usize::try_from(id - base)
+            .ok()
+            .filter(|&i| i < node_count)
+            .ok_or_else(|| {
+                malformed(file, line, format!("element refers to missing node {}", id))
+            })?;
+    }
+    Ok(element)
+}
+
+/// Parses a TetGen mesh from the contents of its `.node` file and optional
+/// `.ele` and `.face` files.
+///
+/// Nodes may be numbered from 0 or 1, as decided by the first node, and
+/// must be numbered consecutively; their attributes are ignored. Quadratic
+/// (10-node) tetrahedra keep their corner nodes, and the first attribute of
+/// each tetrahedron becomes its region. Without a `.face` file the faces are
+/// the boundary of the tetrahedra, found with [`extract_surface_faces`].
+/// Nodes are given [`Point3D::index`] values matching their position in
+/// [`TetGenMesh::nodes`].
+///
+/// # Errors
+///
+/// Returns [`MeshingError::MalformedFile`] for missing or unparsable
+/// headers and records, files with fewer records than announced, nodes that
+/// are not 3D or not numbered consecutively, or elements referring to
+/// missing nodes.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::import::tetgen_to_mesh;
+///
+/// let node = "4 3 0 0\n1 0 0 0\n2 1 0 0\n3 0 1 0\n4 0 0 1\n";
+/// let ele = "1 4 0\n1 1 2 3 4\n";
+/// let mesh = tetgen_to_mesh(node, Some(ele), None).unwrap();
+/// assert_eq!(mesh.tetrahedra, vec![[0, 1, 2, 3]]);
+/// assert_eq!(mesh.faces.len(), 4);
+/// ```
+pub fn tetgen_to_mesh(
+    node: &str,
+    ele: Option<&str>,
+    face: Option<&str>,
+) -> Result<TetGenMesh, MeshingError> {
+    let mut mesh = TetGenMesh::default();
+
+    let (header, body) = section("node", node)?;
+    let values: Vec<usize> = numbers("node", &header, 1)?;
+    let (dimension, attributes, has_markers) = (
+        values.get(1).copied().unwrap_or(3),
+        values.get(2).copied().unwrap_or(0),
+        values.get(3).is_some_and(|&m| m != 0),
+    );
+    if dimension != 3 {
+        return Err(malformed(
+            "node",
+            header.0,
+            format!("unsupported dimension {}", dimension),
+        ));
+    }
+    let mut base = 0;
+    for (i, record) in body.iter().enumerate() {
+        let values: Vec<f64> = numbers("node", record, 4 + attributes + has_markers as usize)?;
+        if i == 0 {
+            base = values[0] as i64;
+        }
+        if values[0] as i64 != base + i as i64 {
+            return Err(malformed(
+                "node",
+                record.0,
+                format!("expected node {} but found {}", base + i as i64, values[0]),
+            ));
+        }
+        mesh.nodes.push(Point3D {
+            index: i as i64,
+            x: values[1],
+            y: values[2],
+            z: values[3],
+        });
+        if has_markers {
+            mesh.node_markers.push(values[4 + attributes] as i32);
+        }
+    }
+
+    if let Some(ele) = ele {
+        let (header, body) = section("ele", ele)?;
+        let values: Vec<usize> = numbers("ele", &header, 1)?;
+        let (nodes_per_tet, attributes) = (
+            values.get(1).copied().unwrap_or(4),
+            values.get(2).copied().unwrap_or(0),
+        );
+        if nodes_per_tet != 4 && nodes_per_tet != 10 {
+            return Err(malformed(
+                "ele",
+                header.0,
+                format!("unsupported {} nodes per tetrahedron", nodes_per_tet),
+            ));
+        }
+        for record in &body {
+            let values: Vec<f64> = numbers("ele", record, 1 + nodes_per_tet + attributes)?;
+            let ids: Vec<i64> = values[1..5].iter().map(|&id| id as i64).collect();
+            let tet = resolve("ele", record.0, &ids, base, mesh.nodes.len())?;
+            mesh.tetrahedra.push(tet);
+            if attributes > 0 {
+                mesh.tetrahedron_regions
+                    .push(values[1 + nodes_per_tet] as i32);
+            }
+        }
+    }
+
+    match face {
+        Some(face) => {
+            let (header, body) = section("face", face)?;
+            let values: Vec<usize> = numbers("face", &header, 1)?;
+            let has_markers = values.get(1).is_some_and(|&m| m != 0);
+            for record in &body {
+                let values: Vec<i64> = numbers("face", record, 4 + has_markers as usize)?;
+                let face = resolve("face", record.0, &values[1..4], base, mesh.nodes.len())?;
+                mesh.faces.push(face);
+                if has_markers {
+                    mesh.face_markers.push(values[4] as i32);
+                }
+            }
+        }
+        None if !mesh.tetrahedra.is_empty() => {
+            let tetrahedra = mesh.to_tetrahedra();
+            mesh.faces = surface_indices(
+                &TetMesh {
+                    vertices: mesh.nodes.clone(),
+                    tetrahedra: mesh.tetrahedra.clone(),
+                },
+                &extract_surface_faces(&tetrahedra),
+            );
+        }
+        None => {}
+    }
+    Ok(mesh)
+}
+
+/// Parses the tetrahedra of a TetGen `.node`/`.ele` pair, e.g. for
+/// [`refine_tetrahedra`](crate::pipeline::refine_tetrahedra).
+///
+/// # Errors
+///
+/// Same as [`tetgen_to_mesh`].
+pub fn tetgen_to_tetrahedra(node: &str, ele: &str) -> Result<Vec<Tetrahedron>, MeshingError> {
+    Ok(tetgen_to_mesh(node, Some(ele), None)?.to_tetrahedra())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::mesh_to_tetgen;
+    use crate::voxel_mesh::voxel_mesh_indexed;
+
+    fn p(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tets = voxel_mesh_indexed(p(0, 0.0, 0.0, 0.0), p(0, 2.0, 1.0, 1.0), 2, 1, 1, &|_| true);
+        let mut mesh = TetGenMesh::from_tetrahedra(&tets.to_tetrahedra());
+        mesh.node_markers = mesh.nodes.iter().map(|v| (v.x == 0.0) as i32).collect();
+        mesh.tetrahedron_regions = (0..mesh.tetrahedra.len() as i32).map(|i| i % 3).collect();
+        mesh.face_markers = vec![-1; mesh.faces.len()];
+        let files = mesh_to_tetgen(&mesh);
+        let parsed = tetgen_to_mesh(&files.node, Some(&files.ele), Some(&files.face)).unwrap();
+        assert_eq!(parsed, mesh);
+
+        // Without the .face file the boundary is recomputed.
+        let parsed = tetgen_to_mesh(&files.node, Some(&files.ele), None).unwrap();
+        assert_eq!(parsed.faces, mesh.faces);
+        assert!(parsed.face_markers.is_empty());
+    }
+
+    #[test]
+    fn test_zero_based_file_with_attributes() {
+        let node = "# Node count, 3 dim, 1 attribute, 1 marker\n\
+                    4  3  1  1\n\
+                    0  0.0 0.0 0.0  7.5  1\n\
+                    1  1.0 0.0 0.0  7.5  1\n\
+                    \n\
+                    2  0.0 1.0 0.0  7.5  0  # interior\n\
+                    3  0.0 0.0 1.0  7.5  2\n";
+        // Quadratic tetrahedron with a region attribute.
+        let ele = "1 10 1\n0  0 1 2 3  0 0 0 0 0 0  4\n";
+        let face = "1 0\n0 0 2 1\n";
+        let mesh = tetgen_to_mesh(node, Some(ele), Some(face)).unwrap();
+        assert_eq!(mesh.nodes[2], p(2, 0.0, 1.0, 0.0));
+        assert_eq!(mesh.node_markers, vec![1, 1, 0, 2]);
+        assert_eq!(mesh.tetrahedra, vec![[0, 1, 2, 3]]);
+        assert_eq!(mesh.tetrahedron_regions, vec![4]);
+        assert_eq!(mesh.faces, vec![[0, 2, 1]]);
+        assert!(mesh.face_markers.is_empty());
+        assert_eq!(tetgen_to_tetrahedra(node, ele).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_malformed_files() {
+        let node = "4 3 0 0\n1 0 0 0\n2 1 0 0\n3 0 1 0\n4 0 0 1\n";
+        let cases = [
+            ("", None, None),
+            ("2 3 0 0\n1 0 0 0\n", None, None),
+            ("2 2 0 0\n1 0 0\n2 1 0\n", None, None),
+            ("2 3 0 0\n1 0 0 0\n3 1 0 0\n", None, None),
+            ("1 3 0 0\n1 0 zero 0\n", None, None),
+            (node, Some("1 4 0\n1 1 2 3 5\n"), None),
+            (node, Some("1 4 0\n1 1 2 3\n"), None),
+            (node, Some("1 8 0\n1 1 2 3 4 1 2 3 4\n"), None),
+            (node, None, Some("1 0\n1 0 1 2\n")),
+        ];
+        for (node, ele, face) in cases {
+            assert!(
+                matches!(
+                    tetgen_to_mesh(node, ele, face),
+                    Err(MeshingError::MalformedFile(_))
+                ),
+                "{:?} {:?} {:?}",
+                node,
+                ele,
+                face
+            );
+        }
+    }
+}
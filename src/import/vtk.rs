@@ -0,0 +1,964 @@
+use std::io::Read;
+
+use super::obj::triangulate_polygon;
+use crate::error::MeshingError;
+use crate::export::{VtuArray, VtuAttributes, VtuValues};
+use crate::{Face, Point3D, Tetrahedron};
+
+/// A tetrahedral or surface mesh read from a legacy VTK file, with its
+/// point and cell data.
+///
+/// `vertices` holds every point of the file in order, with
+/// [`Point3D::index`] equal to its position. Cell arrays in `attributes`
+/// have one tuple per tetrahedron followed by one per triangle, each
+/// copied from the file cell it came from, so that they can be written
+/// back with [`faces_to_vtu`](crate::export::faces_to_vtu) or
+/// [`tet_mesh_to_vtu`](crate::export::tet_mesh_to_vtu).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VtkMesh {
+    pub vertices: Vec<Point3D>,
+    pub tetrahedra: Vec<[usize; 4]>,
+    pub triangles: Vec<[usize; 3]>,
+    pub attributes: VtuAttributes,
+}
+
+impl VtkMesh {
+    /// Expands the tetrahedra into [`Tetrahedron`]s.
+    pub fn to_tetrahedra(&self) -> Vec<Tetrahedron> {
+        self.tetrahedra
+            .iter()
+            .map(|&[a, b, c, d]| Tetrahedron {
+                a: self.vertices[a],
+                b: self.vertices[b],
+                c: self.vertices[c],
+                d: self.vertices[d],
+            })
+            .collect()
+    }
+
+    /// Expands the triangles into [`Face`]s.
+    pub fn to_faces(&self) -> Vec<Face> {
+        self.triangles
+            .iter()
+            .map(|&[a, b, c]| Face {
+                a: self.vertices[a],
+                b: self.vertices[b],
+                c: self.vertices[c],
+            })
+            .collect()
+    }
+}
+
+fn malformed(message: impl Into<String>) -> MeshingError {
+    MeshingError::MalformedFile(format!("VTK: {}", message.into()))
+}
+
+/// Component type of an array, and its size in binary files.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DataType {
+    Integer { size: usize, signed: bool },
+    Float32,
+    Float64,
+}
+
+impl DataType {
+    fn parse(name: &str) -> Result<DataType, MeshingError> {
+        let integer = |size, signed| DataType::Integer { size, signed };
+        Ok(match name.to_ascii_lowercase().as_str() {
+            "char" | "vtktypeint8" => integer(1, true),
+            "unsigned_char" | "vtktypeuint8" => integer(1, false),
+            "short" | "vtktypeint16" => integer(2, true),
+            "unsigned_short" | "vtktypeuint16" => integer(2, false),
+            // VTK writes vtkIdType arrays as 32-bit integers.
+            "int" | "vtkidtype" | "vtktypeint32" => integer(4, true),
+            "unsigned_int" | "vtktypeuint32" => integer(4, false),
+            "long" | "vtktypeint64" => integer(8, true),
+            "unsigned_long" | "vtktypeuint64" => integer(8, false),
+            "float" | "vtktypefloat32" => DataType::Float32,
+            "double" | "vtktypefloat64" => DataType::Float64,
+            other => return Err(malformed(format!("unsupported data type '{}'", other))),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            DataType::Integer { size, .. } => size,
+            DataType::Float32 => 4,
+            DataType::Float64 => 8,
+        }
+    }
+
+    /// Decodes one big-endian binary value.
+    fn decode(self, bytes: &[u8]) -> f64 {
+        match self {
+            DataType::Integer { signed: true, .. } => {
+                // Sign-extend from the top byte.
+                let first = if bytes[0] & 0x80 != 0 { -1i64 } else { 0 };
+                bytes
+                    .iter()
+                    .fold(first, |value, &b| (value << 8) | b as i64) as f64
+            }
+            DataType::Integer { signed: false, .. } => {
+                bytes.iter().fold(0u64, |value, &b| (value << 8) | b as u64) as f64
+            }
+            DataType::Float32 => f32::from_be_bytes(bytes.try_into().expect("4 bytes")) as f64,
+            DataType::Float64 => f64::from_be_bytes(bytes.try_into().expect("8 bytes")),
+        }
+    }
+}
+
+/// Reads the keywords and numbers of a legacy VTK file, and the raw
+/// big-endian blocks following header lines in binary files.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    binary: bool,
+}
+
+impl<'a> Reader<'a> {
+    /// The rest of the current line, without its line break.
+    fn line(&mut self) -> &'a [u8] {
+        let start = self.pos;
+        let end = self.bytes[start..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(self.bytes.len(), |i| start + i);
+        self.pos = (end + 1).min(self.bytes.len());
+        let line = &self.bytes[start..end];
+        line.strip_suffix(b"\r").unwrap_or(line)
+    }
+
+    fn token(&mut self) -> Result<Option<&'a str>, MeshingError> {
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+        let start = self.pos;
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Ok(None);
+        }
+        std::str::from_utf8(&self.bytes[start..self.pos])
+            .map(Some)
+            .map_err(|_| malformed(format!("byte {}: expected a keyword or number", start)))
+    }
+
+    fn expect(&mut self, what: &str) -> Result<&'a str, MeshingError> {
+        self.token()?
+            .ok_or_else(|| malformed(format!("unexpected end of file, expected {}", what)))
+    }
+
+    /// Reads the next token if it is `keyword`, case-insensitively.
+    fn accept(&mut self, keyword: &str) -> Result<bool, MeshingError> {
+        let pos = self.pos;
+        if self
+            .token()?
+            .is_some_and(|t| t.eq_ignore_ascii_case(keyword))
+        {
+            return Ok(true);
+        }
+        self.pos = pos;
+        Ok(false)
+    }
+
+    /// Number of bytes left, an upper bound on the number of items the rest
+    /// of the file can hold.
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn count(&mut self, what: &str) -> Result<usize, MeshingError> {
+        let token = self.expect(what)?;
+        token
+            .parse()
+            .map_err(|_| malformed(format!("invalid {} '{}'", what, token)))
+    }
+
+    /// Reads `count` values, as text or as a binary block starting on the
+    /// next line.
+    fn values(&mut self, count: usize, data_type: DataType) -> Result<Vec<f64>, MeshingError> {
+        if !self.binary {
+            return (0..count)
+                .map(|_| {
+                    let token = self.expect("a number")?;
+                    token
+                        .parse()
+                        .map_err(|_| malformed(format!("invalid number '{}'", token)))
+                })
+                .collect();
+        }
+        self.line();
+        let size = data_type.size();
+        let end = count
+            .checked_mul(size)
+            .and_then(|length| self.pos.checked_add(length))
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| malformed("binary data block exceeds the file"))?;
+        let values = self.bytes[self.pos..end]
+            .chunks_exact(size)
+            .map(|value| data_type.decode(value))
+            .collect();
+        self.pos = end;
+        Ok(values)
+    }
+
+    fn indices(&mut self, count: usize, data_type: DataType) -> Result<Vec<usize>, MeshingError> {
+        self.values(count, data_type)?
+            .into_iter()
+            .map(|v| {
+                if v >= 0.0 && v.fract() == 0.0 {
+                    Ok(v as usize)
+                } else {
+                    Err(malformed(format!("invalid index {}", v)))
+                }
+            })
+            .collect()
+    }
+
+    /// Skips a `METADATA` block, which ends at an empty line.
+    fn skip_metadata(&mut self) {
+        self.line();
+        while self.pos < self.bytes.len() && !self.line().iter().all(u8::is_ascii_whitespace) {}
+    }
+
+    /// Reads a cell list with `count` cells and `size` entries: counts
+    /// followed by point indices, or from version 5 on `OFFSETS` and
+    /// `CONNECTIVITY` arrays, in which case `count` is the number of offsets.
+    fn cells(&mut self, count: usize, size: usize) -> Result<Vec<Vec<usize>>, MeshingError> {
+        if self.accept("OFFSETS")? {
+            let data_type = DataType::parse(self.expect("a data type")?)?;
+            let offsets = self.indices(count, data_type)?;
+            if !self.accept("CONNECTIVITY")? {
+                return Err(malformed("expected CONNECTIVITY after OFFSETS"));
+            }
+            let data_type = DataType::parse(self.expect("a data type")?)?;
+            let connectivity = self.indices(size, data_type)?;
+            return offsets
+                .windows(2)
+                .map(|w| {
+                    connectivity
+                        .get(w[0]..w[1])
+                        .map(<[usize]>::to_vec)
+                        .ok_or_else(|| malformed("cell offsets exceed the connectivity array"))
+                })
+                .collect();
+        }
+        let entries = self.indices(
+            size,
+            DataType::Integer {
+                size: 4,
+                signed: true,
+            },
+        )?;
+        // Every cell takes at least its point count.
+        let mut cells = Vec::with_capacity(count.min(entries.len()));
+        let mut rest = entries.as_slice();
+        for _ in 0..count {
+            let (&n, tail) = rest
+                .split_first()
+                .ok_or_else(|| malformed("cell list is shorter than its size"))?;
+            if n > tail.len() {
+                return Err(malformed("cell list is shorter than its size"));
+            }
+            cells.push(tail[..n].to_vec());
+            rest = &tail[n..];
+        }
+        if !rest.is_empty() {
+            return Err(malformed("cell list is longer than its size"));
+        }
+        Ok(cells)
+    }
+}
+
+/// Number of values in `tuples` tuples of `components` components.
+fn num_values(tuples: usize, components: usize) -> Result<usize, MeshingError> {
+    tuples.checked_mul(components).ok_or_else(|| {
+        malformed(format!(
+            "{} tuples of {} components overflow",
+            tuples, components
+        ))
+    })
+}
+
+/// Decodes the `%XX` escapes VTK uses for spaces and other characters in
+/// array names.
+fn array_name(token: &str) -> String {
+    let bytes = token.as_bytes();
+    let mut name = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) if bytes[i] == b'%' => {
+                name.push(byte);
+                i += 3;
+            }
+            _ => {
+                name.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&name).into_owned()
+}
+
+/// A data array with `Int32` values if it has an integer type and every
+/// value fits.
+fn data_array(name: String, components: usize, values: Vec<f64>, data_type: DataType) -> VtuArray {
+    let integers = matches!(data_type, DataType::Integer { .. })
+        && values
+            .iter()
+            .all(|&v| v >= i32::MIN as f64 && v <= i32::MAX as f64);
+    VtuArray {
+        name,
+        components,
+        values: if integers {
+            VtuValues::Int32(values.into_iter().map(|v| v as i32).collect())
+        } else {
+            VtuValues::Float64(values)
+        },
+    }
+}
+
+/// Reads the attribute arrays of a `POINT_DATA` or `CELL_DATA` section of
+/// `count` tuples, up to the next section keyword, which is returned.
+fn read_data(
+    reader: &mut Reader,
+    count: usize,
+    arrays: &mut Vec<VtuArray>,
+) -> Result<Option<String>, MeshingError> {
+    while let Some(keyword) = reader.token()? {
+        let keyword = keyword.to_ascii_uppercase();
+        match keyword.as_str() {
+            "SCALARS" => {
+                let name = array_name(reader.expect("an array name")?);
+                let data_type = DataType::parse(reader.expect("a data type")?)?;
+                // The component count is optional and the lookup table
+                // required, but either may be the next token.
+                let pos = reader.pos;
+                let components = match reader.token()?.map(str::parse) {
+                    Some(Ok(components)) => components,
+                    _ => {
+                        reader.pos = pos;
+                        1
+                    }
+                };
+                if reader.accept("LOOKUP_TABLE")? {
+                    reader.expect("a lookup table name")?;
+                }
+                let values = reader.values(num_values(count, components)?, data_type)?;
+                arrays.push(data_array(name, components, values, data_type));
+            }
+            "COLOR_SCALARS" => {
+                let name = array_name(reader.expect("an array name")?);
+                let components = reader.count("a component count")?;
+                let values = if reader.binary {
+                    let bytes = DataType::Integer {
+                        size: 1,
+                        signed: false,
+                    };
+                    let values = reader.values(num_values(count, components)?, bytes)?;
+                    values.into_iter().map(|v| v / 255.0).collect()
+                } else {
+                    reader.values(num_values(count, components)?, DataType::Float32)?
+                };
+                arrays.push(data_array(name, components, values, DataType::Float32));
+            }
+            "VECTORS" | "NORMALS" | "TENSORS" | "TENSORS6" | "TEXTURE_COORDINATES" => {
+                let name = array_name(reader.expect("an array name")?);
+                let components = match keyword.as_str() {
+                    "TEXTURE_COORDINATES" => reader.count("a dimension")?,
+                    "TENSORS" => 9,
+                    "TENSORS6" => 6,
+                    _ => 3,
+                };
+                let data_type = DataType::parse(reader.expect("a data type")?)?;
+                let values = reader.values(num_values(count, components)?, data_type)?;
+                arrays.push(data_array(name, components, values, data_type));
+            }
+            "LOOKUP_TABLE" => {
+                reader.expect("a lookup table name")?;
+                let size = reader.count("a lookup table size")?;
+                let data_type = if reader.binary {
+                    DataType::Integer {
+                        size: 1,
+                        signed: false,
+                    }
+                } else {
+                    DataType::Float32
+                };
+                reader.values(num_values(size, 4)?, data_type)?;
+            }
+            "FIELD" => arrays.extend(read_field(reader, Some(count))?),
+            "METADATA" => reader.skip_metadata(),
+            _ => return Ok(Some(keyword)),
+        }
+    }
+    Ok(None)
+}
+
+/// Reads the arrays of a `FIELD`, checking that they have `count` tuples
+/// if given.
+fn read_field(reader: &mut Reader, count: Option<usize>) -> Result<Vec<VtuArray>, MeshingError> {
+    reader.expect("a field name")?;
+    let num_arrays = reader.count("an array count")?;
+    let mut arrays = Vec::with_capacity(num_arrays.min(reader.remaining()));
+    for _ in 0..num_arrays {
+        let name = array_name(reader.expect("an array name")?);
+        let components = reader.count("a component count")?;
+        let tuples = reader.count("a tuple count")?;
+        let data_type = DataType::parse(reader.expect("a data type")?)?;
+        let values = reader.values(num_values(tuples, components)?, data_type)?;
+        if count.is_some_and(|count| count != tuples) {
+            return Err(malformed(format!(
+                "field array '{}' has {} tuples, expected {}",
+                name,
+                tuples,
+                count.unwrap_or_default()
+            )));
+        }
+        if reader.accept("METADATA")? {
+            reader.skip_metadata();
+        }
+        arrays.push(data_array(name, components, values, data_type));
+    }
+    Ok(arrays)
+}
+
+/// Keeps the tuples of `array` for the given source cells, in order.
+fn select_tuples(array: &VtuArray, cells: &[usize]) -> VtuArray {
+    let n = array.components;
+    let values = match &array.values {
+        VtuValues::Float64(values) => VtuValues::Float64(
+            cells
+                .iter()
+                .flat_map(|&c| &values[c * n..(c + 1) * n])
+                .copied()
+                .collect(),
+        ),
+        VtuValues::Int32(values) => VtuValues::Int32(
+            cells
+                .iter()
+                .flat_map(|&c| &values[c * n..(c + 1) * n])
+                .copied()
+                .collect(),
+        ),
+    };
+    VtuArray {
+        name: array.name.clone(),
+        components: n,
+        values,
+    }
+}
+
+/// Parses a legacy VTK file (`.vtk`), ASCII or binary, holding an
+/// `UNSTRUCTURED_GRID` or `POLYDATA` data set.
+///
+/// Unstructured grids contribute their tetrahedra (cell type 10) and
+/// triangles (cell type 5); other cells are skipped. Polydata contributes
+/// its polygons, split into triangles, and triangle strips; vertices and
+/// lines are skipped. Cells lists in the version 5 `OFFSETS` and
+/// `CONNECTIVITY` layout written by recent ParaView releases are read as
+/// well.
+///
+/// `SCALARS`, `COLOR_SCALARS`, `VECTORS`, `NORMALS`, `TENSORS`,
+/// `TEXTURE_COORDINATES` and `FIELD` arrays of the `POINT_DATA` and
+/// `CELL_DATA` sections are returned in [`VtkMesh::attributes`], with
+/// integer arrays as [`VtuValues::Int32`] when their values fit.
+///
+/// # Errors
+///
+/// Returns [`MeshingError::MalformedFile`] if the header is not that of a
+/// legacy VTK file, the data set is of another type, a cell refers to a
+/// missing point, an array has the wrong number of tuples, or the data is
+/// truncated or not numeric.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::export::tetrahedra_to_vtk;
+/// use meshing::import::vtk_to_tetrahedra;
+/// use meshing::{Point3D, Tetrahedron};
+///
+/// let p = |index, x, y, z| Point3D { index, x, y, z };
+/// let tet = Tetrahedron {
+///     a: p(0, 0.0, 0.0, 0.0),
+///     b: p(1, 1.0, 0.0, 0.0),
+///     c: p(2, 0.0, 1.0, 0.0),
+///     d: p(3, 0.0, 0.0, 1.0),
+/// };
+/// let vtk = tetrahedra_to_vtk(&[tet], "one tet");
+/// assert_eq!(vtk_to_tetrahedra(vtk.as_bytes()).unwrap(), vec![tet]);
+/// ```
+pub fn vtk_to_mesh(bytes: &[u8]) -> Result<VtkMesh, MeshingError> {
+    let mut reader = Reader {
+        bytes,
+        pos: 0,
+        binary: false,
+    };
+    let header = String::from_utf8_lossy(reader.line()).to_ascii_lowercase();
+    if !header.starts_with("# vtk datafile version") {
+        return Err(malformed("missing '# vtk DataFile Version' header"));
+    }
+    reader.line(); // title
+    let encoding = String::from_utf8_lossy(reader.line())
+        .trim()
+        .to_ascii_uppercase();
+    reader.binary = match encoding.as_str() {
+        "ASCII" => false,
+        "BINARY" => true,
+        other => return Err(malformed(format!("unknown encoding '{}'", other))),
+    };
+    if !reader.accept("DATASET")? {
+        return Err(malformed("expected DATASET"));
+    }
+    let dataset = reader.expect("a data set type")?.to_ascii_uppercase();
+    if dataset != "UNSTRUCTURED_GRID" && dataset != "POLYDATA" {
+        return Err(malformed(format!(
+            "unsupported data set '{}', expected UNSTRUCTURED_GRID or POLYDATA",
+            dataset
+        )));
+    }
+
+    let mut points = Vec::new();
+    let mut cells = Vec::new();
+    let mut cell_types = None;
+    // Polydata cell lists, in the order VTK numbers their cells.
+    let mut poly_cells: [Vec<Vec<usize>>; 4] = Default::default();
+    let mut attributes = VtuAttributes::default();
+    let mut num_cell_tuples = None;
+    let mut keyword = reader.token()?.map(str::to_ascii_uppercase);
+    while let Some(current) = keyword.take() {
+        match current.as_str() {
+            "POINTS" => {
+                let n = reader.count("a point count")?;
+                let data_type = DataType::parse(reader.expect("a data type")?)?;
+                points = reader.values(num_values(n, 3)?, data_type)?;
+            }
+            "CELLS" | "VERTICES" | "LINES" | "POLYGONS" | "TRIANGLE_STRIPS" => {
+                let count = reader.count("a cell count")?;
+                let size = reader.count("a cell list size")?;
+                let list = reader.cells(count, size)?;
+                match current.as_str() {
+                    "CELLS" => cells = list,
+                    "VERTICES" => poly_cells[0] = list,
+                    "LINES" => poly_cells[1] = list,
+                    "POLYGONS" => poly_cells[2] = list,
+                    _ => poly_cells[3] = list,
+                }
+            }
+            "CELL_TYPES" => {
+                let n = reader.count("a cell count")?;
+                let data_type = DataType::Integer {
+                    size: 4,
+                    signed: true,
+                };
+                cell_types = Some(reader.indices(n, data_type)?);
+            }
+            "FIELD" => {
+                read_field(&mut reader, None)?;
+            }
+            "METADATA" => reader.skip_metadata(),
+            "POINT_DATA" => {
+                let n = reader.count("a point count")?;
+                if n != points.len() / 3 {
+                    return Err(malformed(format!(
+                        "POINT_DATA has {} tuples but there are {} points",
+                        n,
+                        points.len() / 3
+                    )));
+                }
+                keyword = read_data(&mut reader, n, &mut attributes.point_data)?;
+                continue;
+            }
+            "CELL_DATA" => {
+                let n = reader.count("a cell count")?;
+                num_cell_tuples = Some(n);
+                keyword = read_data(&mut reader, n, &mut attributes.cell_data)?;
+                continue;
+            }
+            other => return Err(malformed(format!("unexpected keyword '{}'", other))),
+        }
+        keyword = reader.token()?.map(str::to_ascii_uppercase);
+    }
+
+    let vertices: Vec<Point3D> = points
+        .chunks_exact(3)
+        .enumerate()
+        .map(|(i, p)| Point3D {
+            index: i as i64,
+            x: p[0],
+            y: p[1],
+            z: p[2],
+        })
+        .collect();
+    let all_cells = if dataset == "POLYDATA" {
+        poly_cells.concat()
+    } else {
+        cells
+    };
+    if let Some(&i) = all_cells.iter().flatten().find(|&&i| i >= vertices.len()) {
+        return Err(malformed(format!(
+            "cell refers to point {} but there are {} points",
+            i,
+            vertices.len()
+        )));
+    }
+
+    // Source cell of each tetrahedron and triangle.
+    let mut tetrahedra = Vec::new();
+    let mut tetrahedron_cells = Vec::new();
+    let mut triangles = Vec::new();
+    let mut triangle_cells = Vec::new();
+    if dataset == "POLYDATA" {
+        let polygons_start = poly_cells[0].len() + poly_cells[1].len();
+        let strips_start = polygons_start + poly_cells[2].len();
+        for (c, cell) in all_cells.iter().enumerate().skip(polygons_start) {
+            if cell.len() < 3 {
+                continue;
+            }
+            let new: Vec<[usize; 3]> = if c < strips_start {
+                let polygon: Vec<Point3D> = cell.iter().map(|&i| vertices[i]).collect();
+                triangulate_polygon(&polygon)
+                    .into_iter()
+                    .map(|t| t.map(|i| cell[i]))
+                    .collect()
+            } else {
+                (0..cell.len() - 2)
+                    .map(|i| {
+                        if i % 2 == 0 {
+                            [cell[i], cell[i + 1], cell[i + 2]]
+                        } else {
+                            [cell[i + 1], cell[i], cell[i + 2]]
+                        }
+                    })
+                    .collect()
+            };
+            triangle_cells.extend(std::iter::repeat_n(c, new.len()));
+            triangles.extend(new);
+        }
+    } else {
+        let types = cell_types.ok_or_else(|| malformed("CELLS without CELL_TYPES"))?;
+        if types.len() != all_cells.len() {
+            return Err(malformed(format!(
+                "{} cell types given for {} cells",
+                types.len(),
+                all_cells.len()
+            )));
+        }
+        for (c, (cell, cell_type)) in all_cells.iter().zip(types).enumerate() {
+            match (cell_type, cell.as_slice()) {
+                (10, &[a, b, d, e]) => {
+                    tetrahedra.push([a, b, d, e]);
+                    tetrahedron_cells.push(c);
+                }
+                (5, &[a, b, d]) => {
+                    triangles.push([a, b, d]);
+                    triangle_cells.push(c);
+                }
+                (10 | 5, _) => {
+                    return Err(malformed(format!(
+                        "cell {} of type {} has {} points",
+                        c,
+                        cell_type,
+                        cell.len()
+                    )))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(n) = num_cell_tuples {
+        if n != all_cells.len() {
+            return Err(malformed(format!(
+                "CELL_DATA has {} tuples but there are {} cells",
+                n,
+                all_cells.len()
+            )));
+        }
+    }
+    let sources: Vec<usize> = tetrahedron_cells
+        .into_iter()
+        .chain(triangle_cells)
+        .collect();
+    attributes.cell_data = attributes
+        .cell_data
+        .iter()
+        .map(|array| select_tuples(array, &sources))
+        .collect();
+
+    Ok(VtkMesh {
+        vertices,
+        tetrahedra,
+        triangles,
+        attributes,
+    })
+}
+
+/// Parses the tetrahedra of a legacy VTK file.
+///
+/// # Errors
+///
+/// Same as [`vtk_to_mesh`].
+pub fn vtk_to_tetrahedra(bytes: &[u8]) -> Result<Vec<Tetrahedron>, MeshingError> {
+    Ok(vtk_to_mesh(bytes)?.to_tetrahedra())
+}
+
+/// Parses the triangles of a legacy VTK file as faces.
+///
+/// # Errors
+///
+/// Same as [`vtk_to_mesh`].
+pub fn vtk_to_faces(bytes: &[u8]) -> Result<Vec<Face>, MeshingError> {
+    Ok(vtk_to_mesh(bytes)?.to_faces())
+}
+
+/// Reads a legacy VTK file from `reader`, as [`vtk_to_mesh`].
+///
+/// # Errors
+///
+/// Returns [`MeshingError::Io`] if reading fails, otherwise the errors of
+/// [`vtk_to_mesh`].
+pub fn read_vtk<R: Read>(mut reader: R) -> Result<VtkMesh, MeshingError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    vtk_to_mesh(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{tet_mesh_to_vtk, tetrahedra_to_vtk};
+    use crate::voxel_mesh::voxel_mesh_indexed;
+
+    fn p(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    #[test]
+    fn test_round_trip_with_writer() {
+        let mesh = voxel_mesh_indexed(p(0, 0.0, 0.0, 0.0), p(0, 2.0, 1.0, 1.5), 2, 1, 1, &|_| true);
+        let read = vtk_to_mesh(tet_mesh_to_vtk(&mesh, "block").as_bytes()).unwrap();
+        assert_eq!(read.vertices, mesh.vertices);
+        assert_eq!(read.tetrahedra, mesh.tetrahedra);
+        assert!(read.triangles.is_empty());
+
+        let tets = mesh.to_tetrahedra();
+        let from_soup = vtk_to_tetrahedra(tetrahedra_to_vtk(&tets, "soup").as_bytes()).unwrap();
+        assert_eq!(from_soup.len(), tets.len());
+        for (read, written) in from_soup.iter().zip(&tets) {
+            for (a, b) in read.vertices().iter().zip(written.vertices()) {
+                assert_eq!([a.x, a.y, a.z], [b.x, b.y, b.z]);
+            }
+        }
+
+        let empty = vtk_to_mesh(tetrahedra_to_vtk(&[], "empty").as_bytes()).unwrap();
+        assert_eq!(empty, VtkMesh::default());
+    }
+
+    const MIXED_GRID: &str = "# vtk DataFile Version 3.0
+mixed cells
+ASCII
+DATASET UNSTRUCTURED_GRID
+POINTS 5 float
+0 0 0  1 0 0  0 1 0
+0 0 1  1 1 1
+CELLS 4 17
+4 0 1 2 3
+2 0 4
+3 1 2 4
+4 1 2 3 4
+CELL_TYPES 4
+10
+3
+5
+10
+
+CELL_DATA 4
+SCALARS region int 1
+LOOKUP_TABLE default
+1 2 3 4
+FIELD extra 1
+quality%20ratio 2 4 double
+0.1 0.2 0.3 0.4 0.5 0.6 0.7 0.8
+POINT_DATA 5
+SCALARS temperature double
+LOOKUP_TABLE default
+10 20 30 40 50
+VECTORS velocity float
+1 0 0 0 1 0 0 0 1 1 1 1 0 0 0
+";
+
+    #[test]
+    fn test_cell_and_point_data() {
+        let mesh = vtk_to_mesh(MIXED_GRID.as_bytes()).unwrap();
+        assert_eq!(mesh.vertices.len(), 5);
+        assert_eq!(mesh.tetrahedra, vec![[0, 1, 2, 3], [1, 2, 3, 4]]);
+        assert_eq!(mesh.triangles, vec![[1, 2, 4]]);
+
+        // The line cell is dropped; tetrahedra come first, then triangles.
+        let cell = &mesh.attributes.cell_data;
+        assert_eq!(cell[0], VtuArray::labels("region", vec![1, 4, 3]));
+        assert_eq!(cell[1].name, "quality ratio");
+        assert_eq!(cell[1].components, 2);
+        assert_eq!(
+            cell[1].values,
+            VtuValues::Float64(vec![0.1, 0.2, 0.7, 0.8, 0.5, 0.6])
+        );
+
+        let point = &mesh.attributes.point_data;
+        assert_eq!(
+            point[0],
+            VtuArray::scalars("temperature", vec![10.0, 20.0, 30.0, 40.0, 50.0])
+        );
+        assert_eq!(point[1].name, "velocity");
+        assert_eq!(point[1].components, 3);
+    }
+
+    #[test]
+    fn test_polydata_polygons_and_strips() {
+        let text = "# vtk DataFile Version 4.2
+square and strip
+ASCII
+DATASET POLYDATA
+POINTS 6 double
+0 0 0 1 0 0 1 1 0 0 1 0 2 0 0 2 1 0
+VERTICES 1 2
+1 0
+POLYGONS 1 5
+4 0 1 2 3
+TRIANGLE_STRIPS 1 5
+4 1 4 2 5
+CELL_DATA 3
+SCALARS id int
+LOOKUP_TABLE default
+7 8 9
+";
+        let mesh = vtk_to_mesh(text.as_bytes()).unwrap();
+        assert_eq!(mesh.triangles.len(), 4);
+        assert!(mesh.tetrahedra.is_empty());
+        // Every triangle keeps the +z winding of its cell.
+        for face in mesh.to_faces() {
+            let [a, b, c] = face.vertices();
+            assert!((b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x) > 0.0);
+        }
+        assert_eq!(
+            mesh.attributes.cell_data[0].values,
+            VtuValues::Int32(vec![8, 8, 9, 9])
+        );
+        assert_eq!(vtk_to_faces(text.as_bytes()).unwrap().len(), 4);
+    }
+
+    /// Builds a binary file by appending big-endian blocks after text lines.
+    fn binary_grid(version5: bool) -> Vec<u8> {
+        let mut bytes =
+            b"# vtk DataFile Version 5.1\nbinary\nBINARY\nDATASET UNSTRUCTURED_GRID\n".to_vec();
+        bytes.extend_from_slice(b"POINTS 4 double\n");
+        for c in [
+            0.0f64, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0,
+        ] {
+            bytes.extend_from_slice(&c.to_be_bytes());
+        }
+        if version5 {
+            bytes.extend_from_slice(b"\nCELLS 3 7\nOFFSETS vtktypeint64\n");
+            for o in [0i64, 4, 7] {
+                bytes.extend_from_slice(&o.to_be_bytes());
+            }
+            bytes.extend_from_slice(b"\nCONNECTIVITY vtktypeint64\n");
+            for i in [0i64, 1, 2, 3, 0, 2, 1] {
+                bytes.extend_from_slice(&i.to_be_bytes());
+            }
+        } else {
+            bytes.extend_from_slice(b"\nCELLS 2 9\n");
+            for i in [4i32, 0, 1, 2, 3, 3, 0, 2, 1] {
+                bytes.extend_from_slice(&i.to_be_bytes());
+            }
+        }
+        bytes.extend_from_slice(b"\nCELL_TYPES 2\n");
+        for t in [10i32, 5] {
+            bytes.extend_from_slice(&t.to_be_bytes());
+        }
+        bytes.extend_from_slice(b"\nPOINT_DATA 4\nSCALARS level float 1\nLOOKUP_TABLE default\n");
+        for v in [0.5f32, 1.5, -2.0, 4.0] {
+            bytes.extend_from_slice(&v.to_be_bytes());
+        }
+        bytes.extend_from_slice(
+            b"\nCELL_DATA 2\nSCALARS mark unsigned_char\nLOOKUP_TABLE default\n",
+        );
+        bytes.extend_from_slice(&[200, 3]);
+        bytes.push(b'\n');
+        bytes
+    }
+
+    #[test]
+    fn test_binary_files() {
+        for version5 in [false, true] {
+            let mesh = read_vtk(binary_grid(version5).as_slice()).unwrap();
+            assert_eq!(mesh.vertices[3], p(3, 0.0, 0.0, 1.0));
+            assert_eq!(mesh.tetrahedra, vec![[0, 1, 2, 3]]);
+            assert_eq!(mesh.triangles, vec![[0, 2, 1]]);
+            assert_eq!(
+                mesh.attributes.point_data[0],
+                VtuArray::scalars("level", vec![0.5, 1.5, -2.0, 4.0])
+            );
+            assert_eq!(
+                mesh.attributes.cell_data[0],
+                VtuArray::labels("mark", vec![200, 3])
+            );
+        }
+    }
+
+    #[test]
+    fn test_malformed_files() {
+        assert!(matches!(
+            vtk_to_mesh(b"solid stl\n"),
+            Err(MeshingError::MalformedFile(_))
+        ));
+        let structured = "# vtk DataFile Version 3.0\nt\nASCII\nDATASET STRUCTURED_POINTS\n";
+        let err = vtk_to_mesh(structured.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("STRUCTURED_POINTS"), "{}", err);
+
+        let out_of_range = MIXED_GRID.replace("4 1 2 3 4", "4 1 2 3 9");
+        assert!(vtk_to_mesh(out_of_range.as_bytes()).is_err());
+        let short_data = MIXED_GRID.replace("1 2 3 4\n", "1 2 3\n");
+        assert!(vtk_to_mesh(short_data.as_bytes()).is_err());
+        let truncated = binary_grid(false);
+        assert!(vtk_to_mesh(&truncated[..120]).is_err());
+
+        // Counts whose sizes overflow or exceed the file are rejected.
+        let header = "# vtk DataFile Version 3.0\nhuge\nASCII\nDATASET UNSTRUCTURED_GRID\n";
+        for section in [
+            "POINTS 9223372036854775807 double\n0 0 0\n",
+            "CELLS 1000000000000000000 0\n",
+            "CELLS 1000000000000000000 3\n1 0 1\n",
+            "POINTS 1 float\n0 0 0\nPOINT_DATA 1\nSCALARS s float 9223372036854775807\n",
+            "POINTS 1 float\n0 0 0\nPOINT_DATA 1\nTEXTURE_COORDINATES t 9223372036854775807 float\n",
+            "POINTS 1 float\n0 0 0\nPOINT_DATA 1\nCOLOR_SCALARS c 9223372036854775807\n",
+            "POINTS 1 float\n0 0 0\nPOINT_DATA 1\nLOOKUP_TABLE t 9223372036854775807\n",
+            "FIELD f 1000000000000000000\na 9223372036854775807 3 double\n",
+        ] {
+            let file = format!("{}{}", header, section);
+            assert!(
+                matches!(vtk_to_mesh(file.as_bytes()), Err(MeshingError::MalformedFile(_))),
+                "{}",
+                section
+            );
+        }
+        let binary = b"# vtk DataFile Version 3.0\nhuge\nBINARY\nDATASET POLYDATA\n\
+            POINTS 9223372036854775807 double\n";
+        assert!(vtk_to_mesh(binary).is_err());
+    }
+}
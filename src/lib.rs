@@ -16,24 +16,34 @@
 //! ```
 
 use error::MeshingError;
-use geometry::{create_super_triangle, edge_is_shared_by_triangles, retriangulate};
-use geometry_3d::{create_super_tetrahedron, face_is_shared_by_tetrahedra, retetrahedralize};
-pub use model::{Circle, Edge, Face, Point2D, Point3D, Sphere, Tetrahedron, Triangle};
-use tetrahedron_utils::remove_tetrahedra_with_vertices_from_super_tetrahedron;
-use triangle_utils::remove_triangles_with_vertices_from_super_triangle;
+pub use model::{
+    Circle, Edge, Face, Point2D, Point3D, Sphere, SurfaceMesh, TetMesh, Tetrahedron, TriMesh2D,
+    Triangle,
+};
+use triangulation_2d::Triangulation2D;
+use triangulation_3d::Triangulation3D;
 
 pub mod advancing_front;
+pub mod constrained_delaunay;
 pub mod delaunay_refinement;
+pub mod delaunay_refinement_2d;
 pub mod error;
 pub mod export;
 mod geometry;
 mod geometry_3d;
+pub mod import;
 pub mod marching_cubes;
 mod model;
 pub mod octree;
 pub mod pipeline;
+pub mod polygon;
+pub mod predicates;
+mod spatial_sort;
 mod tetrahedron_utils;
 mod triangle_utils;
+mod triangulation_2d;
+mod triangulation_3d;
+pub mod voronoi;
 pub mod voxel_mesh;
 #[cfg(target_arch = "wasm32")]
 pub mod wasm;
@@ -41,7 +51,14 @@ pub mod wasm;
 /// Computes the Delaunay triangulation of a set of 2D points using the
 /// Bowyer-Watson incremental insertion algorithm.
 ///
-/// Returns a list of [`Triangle`]s forming the Delaunay triangulation.
+/// Points are inserted in a biased randomized insertion order (BRIO) sorted
+/// along a Hilbert curve. Each point is located by walking the triangle
+/// adjacency from the previously inserted one, and only the triangles whose
+/// circumcircle contains it are visited, so the expected cost per point is
+/// nearly constant. Points coinciding with an earlier point are skipped.
+///
+/// Returns a list of counter-clockwise [`Triangle`]s forming the Delaunay
+/// triangulation.
 ///
 /// # Errors
 ///
@@ -55,67 +72,21 @@ pub fn bowyer_watson(points: Vec<Point2D>) -> Result<Vec<Triangle>, MeshingError
         return Err(MeshingError::InsufficientPoints(points.len()));
     }
 
-    let mut triangulation: Vec<Triangle> = Vec::new();
-
-    // Step 1: Create a super-triangle large enough to contain all input points.
-    let super_triangle = create_super_triangle(&points);
-    triangulation.push(super_triangle);
-
-    // Step 2: Insert each point one at a time.
-    for point in points {
-        // Step 2a: Find all triangles whose circumcircle contains the new point.
-        // These are "bad" triangles that violate the Delaunay condition.
-        let mut bad_triangles: Vec<Triangle> = Vec::new();
-
-        for triangle in &triangulation {
-            let circumcircle = triangle.generate_circumcircle();
-            if circumcircle.point_in_circle(&point) {
-                bad_triangles.push(*triangle);
-            }
-        }
-
-        // Step 2b: Determine the boundary polygon of the "bad" region.
-        // An edge is on the boundary if it is not shared by any other bad triangle.
-        let mut polygon: Vec<Edge> = Vec::new();
-
-        for triangle in &bad_triangles {
-            let edges = triangle.edges();
-            let bad_triangles_without_triangle: Vec<Triangle> = bad_triangles
-                .iter()
-                .filter(|t| t != &triangle)
-                .cloned()
-                .collect();
-            for edge in edges {
-                if !edge_is_shared_by_triangles(&edge, &bad_triangles_without_triangle) {
-                    polygon.push(edge);
-                }
-            }
-        }
-
-        // Step 2c: Remove all bad triangles from the triangulation.
-        for bad_triangle in &bad_triangles {
-            triangulation.retain(|triangle| triangle != bad_triangle);
-        }
-
-        // Step 2d: Re-triangulate the polygonal hole by connecting each
-        // boundary edge to the new point.
-        for edge in &polygon {
-            let new_tri = retriangulate(edge, &point);
-            triangulation.push(new_tri);
-        }
-    }
-
-    // Step 3: Remove any triangles that share vertices with the super-triangle.
-    Ok(remove_triangles_with_vertices_from_super_triangle(
-        &triangulation,
-        &super_triangle,
-    ))
+    Ok(Triangulation2D::from_points(&points).triangles())
 }
 
 /// Computes the 3D Delaunay tetrahedralization of a set of points using
 /// the Bowyer-Watson incremental insertion algorithm.
 ///
-/// Returns a list of [`Tetrahedron`]s forming the Delaunay tetrahedralization.
+/// Uses the same BRIO/Hilbert insertion order, walking point location and
+/// neighbour-traversal cavity search as [`bowyer_watson`].
+///
+/// Returns a list of positively oriented [`Tetrahedron`]s (see
+/// [`Tetrahedron::signed_volume`]) forming the Delaunay tetrahedralization.
+///
+/// # Panics
+///
+/// Panics if `points` is empty.
 ///
 /// # Examples
 ///
@@ -132,47 +103,41 @@ pub fn bowyer_watson(points: Vec<Point2D>) -> Result<Vec<Triangle>, MeshingError
 /// assert_eq!(tetrahedra.len(), 1);
 /// ```
 pub fn bowyer_watson_3d(points: Vec<Point3D>) -> Vec<Tetrahedron> {
-    let mut tetrahedralization: Vec<Tetrahedron> = Vec::new();
-    let super_tetrahedron = create_super_tetrahedron(&points);
-    tetrahedralization.push(super_tetrahedron);
-
-    for point in points {
-        let mut bad_tetrahedra: Vec<Tetrahedron> = Vec::new();
-
-        for tet in &tetrahedralization {
-            let circumsphere = tet.circumsphere();
-            if circumsphere.point_in_sphere(&point) {
-                bad_tetrahedra.push(*tet);
-            }
-        }
-
-        let mut boundary_faces: Vec<Face> = Vec::new();
-
-        for tet in &bad_tetrahedra {
-            let faces = tet.faces();
-            let bad_tetrahedra_without_tet: Vec<Tetrahedron> = bad_tetrahedra
-                .iter()
-                .filter(|t| t != &tet)
-                .cloned()
-                .collect();
-            for face in faces {
-                if !face_is_shared_by_tetrahedra(&face, &bad_tetrahedra_without_tet) {
-                    boundary_faces.push(face);
-                }
-            }
-        }
-
-        for bad_tet in &bad_tetrahedra {
-            tetrahedralization.retain(|tet| tet != bad_tet);
-        }
+    Triangulation3D::from_points(&points).tetrahedra()
+}
 
-        for face in &boundary_faces {
-            let new_tet = retetrahedralize(face, &point);
-            tetrahedralization.push(new_tet);
-        }
+/// Computes the 2D Delaunay triangulation and returns it as an indexed
+/// [`TriMesh2D`].
+///
+/// Equivalent to [`bowyer_watson`] followed by [`TriMesh2D::from_triangles`],
+/// but reads the connectivity of the triangulation directly instead of
+/// building the [`Triangle`]s first.
+///
+/// # Errors
+///
+/// Same as [`bowyer_watson`].
+pub fn bowyer_watson_indexed(points: Vec<Point2D>) -> Result<TriMesh2D, MeshingError> {
+    if points.is_empty() {
+        return Err(MeshingError::EmptyInput);
+    }
+    if points.len() < 3 {
+        return Err(MeshingError::InsufficientPoints(points.len()));
     }
 
-    remove_tetrahedra_with_vertices_from_super_tetrahedron(&tetrahedralization, &super_tetrahedron)
+    Ok(Triangulation2D::from_points(&points).to_mesh())
+}
+
+/// Computes the 3D Delaunay tetrahedralization and returns it as an indexed
+/// [`TetMesh`].
+///
+/// Equivalent to [`bowyer_watson_3d`] followed by [`TetMesh::from_tetrahedra`],
+/// but reads the connectivity of the tetrahedralization directly.
+///
+/// # Panics
+///
+/// Panics if `points` is empty.
+pub fn bowyer_watson_3d_indexed(points: Vec<Point3D>) -> TetMesh {
+    Triangulation3D::from_points(&points).to_mesh()
 }
 
 #[cfg(test)]
@@ -332,6 +297,79 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_bowyer_watson_indexed_shares_vertices() {
+        let square = vec![
+            Point2D {
+                x: 0.0,
+                y: 0.0,
+                index: 0,
+            },
+            Point2D {
+                x: 1.0,
+                y: 0.0,
+                index: 1,
+            },
+            Point2D {
+                x: 0.0,
+                y: 1.0,
+                index: 2,
+            },
+            Point2D {
+                x: 1.0,
+                y: 1.0,
+                index: 3,
+            },
+        ];
+        let mesh = bowyer_watson_indexed(square.clone()).unwrap();
+        assert_eq!(mesh.num_vertices(), 4);
+        assert_eq!(mesh.num_triangles(), 2);
+        assert_eq!(mesh.to_triangles(), bowyer_watson(square).unwrap());
+    }
+
+    #[test]
+    fn test_tet_mesh_round_trip() {
+        let points = vec![
+            Point3D {
+                index: 0,
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            Point3D {
+                index: 1,
+                x: 1.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            Point3D {
+                index: 2,
+                x: 0.5,
+                y: 1.0,
+                z: 0.0,
+            },
+            Point3D {
+                index: 3,
+                x: 0.5,
+                y: 0.5,
+                z: 1.0,
+            },
+            Point3D {
+                index: 4,
+                x: 0.5,
+                y: 0.5,
+                z: -1.0,
+            },
+        ];
+        let tets = bowyer_watson_3d(points.clone());
+        let mesh = bowyer_watson_3d_indexed(points);
+        assert_eq!(mesh.num_vertices(), 5);
+        assert_eq!(mesh.to_tetrahedra(), tets);
+        // The surface of the mesh matches the element-based extraction.
+        let surface = mesh.surface();
+        assert_eq!(surface.to_faces(), export::extract_surface_faces(&tets));
+    }
+
     #[test]
     fn test_circumsphere() {
         let tet = Tetrahedron {
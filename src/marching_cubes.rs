@@ -1,4 +1,8 @@
-use crate::{Face, Point3D};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::error::MeshingError;
+use crate::{Face, Point3D, SurfaceMesh};
 
 /// Standard Marching Cubes edge table.
 /// For each of the 256 cube configurations, this table stores a 12-bit mask
@@ -318,6 +322,158 @@ const EDGE_VERTICES: [(usize, usize); 12] = [
     (3, 7), // Edge 11
 ];
 
+/// Grid offset of each of the 8 cube corners, in the standard ordering.
+const CORNER_OFFSETS: [[usize; 3]; 8] = [
+    [0, 0, 0],
+    [1, 0, 0],
+    [1, 1, 0],
+    [0, 1, 0],
+    [0, 0, 1],
+    [1, 0, 1],
+    [1, 1, 1],
+    [0, 1, 1],
+];
+
+/// Corners of each cube face, counter-clockwise seen from outside the cube.
+const FACE_CORNERS: [[usize; 4]; 6] = [
+    [0, 3, 2, 1], // z = 0
+    [4, 5, 6, 7], // z = 1
+    [0, 1, 5, 4], // y = 0
+    [3, 7, 6, 2], // y = 1
+    [0, 4, 7, 3], // x = 0
+    [1, 2, 6, 5], // x = 1
+];
+
+/// How [`marching_cubes_with_method`] and [`marching_cubes_volume`]
+/// triangulate cells whose corner signs allow more than one surface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MarchingCubesMethod {
+    /// The classic triangle table. A face with two diagonally opposite
+    /// corners above the iso-value is split by a fixed rule that looks only
+    /// at which corners are above, not at the field values, so
+    /// saddle-shaped fields such as the gyroid can come out with tunnels
+    /// cut or components split apart.
+    #[default]
+    Classic,
+    /// Resolves ambiguous faces with the asymptotic decider of Nielson and
+    /// Hamann: the opposite corners are joined when the bilinear
+    /// interpolant at the face saddle point is above the iso-value, so the
+    /// surface follows the topology of the field on the grid faces. Both
+    /// cells sharing a face make the same choice, and the surface is a
+    /// closed, consistently oriented 2-manifold away from the grid
+    /// boundary. Cells get one polygon per contour loop on their faces,
+    /// fanned into triangles, with a centre vertex when a fan would reuse
+    /// a diagonal of a neighbouring cell.
+    AsymptoticDecider,
+}
+
+/// Cube edge joining corners `a` and `b`.
+fn edge_between(a: usize, b: usize) -> usize {
+    EDGE_VERTICES
+        .iter()
+        .position(|&(u, v)| (u, v) == (a, b) || (u, v) == (b, a))
+        .expect("adjacent cube corners")
+}
+
+/// Bitmask of the cube faces containing `edge`.
+fn edge_faces(edge: usize) -> u8 {
+    let (u, v) = EDGE_VERTICES[edge];
+    (0..6)
+        .filter(|&f| FACE_CORNERS[f].contains(&u) && FACE_CORNERS[f].contains(&v))
+        .fold(0, |mask, f| mask | 1 << f)
+}
+
+/// Triangulates one cell with the asymptotic decider.
+///
+/// On each face, contour segments run from the edge where the boundary
+/// (counter-clockwise from outside) enters the region above the iso-value
+/// to the edge where it leaves, cutting off either the above or the below
+/// corners of an ambiguous face. Every intersected edge then starts one
+/// segment and ends another, and the segments form the contour loops of
+/// the cell.
+fn asymptotic_decider(
+    values: &[f64; 8],
+    iso_value: f64,
+    cube_vertices: &[usize; 12],
+    mesh: &mut SurfaceMesh,
+) {
+    let mut next = [usize::MAX; 12];
+    for corners in FACE_CORNERS {
+        let above = corners.map(|c| values[c] > iso_value);
+        let entry = |i: usize| edge_between(corners[(i + 3) % 4], corners[i]);
+        let exit = |i: usize| edge_between(corners[i], corners[(i + 1) % 4]);
+        let starts: Vec<usize> = (0..4)
+            .filter(|&i| above[i] && !above[(i + 3) % 4])
+            .collect();
+        match starts.as_slice() {
+            [] => {}
+            &[i] => {
+                let end = (i..i + 4).take_while(|&n| above[n % 4]).last().unwrap_or(i);
+                next[entry(i)] = exit(end % 4);
+            }
+            &[i, j] => {
+                // The saddle value of the bilinear interpolant,
+                // (ac - bd) / (a + c - b - d), has a positive denominator
+                // here, so only the sign of ac - bd matters.
+                let [a, b, c, d] = [i, i + 1, j, j + 1].map(|n| values[corners[n % 4]] - iso_value);
+                if a * c > b * d {
+                    next[entry(j)] = exit(i);
+                    next[entry(i)] = exit(j);
+                } else {
+                    next[entry(i)] = exit(i);
+                    next[entry(j)] = exit(j);
+                }
+            }
+            _ => unreachable!("a face has at most two runs of corners"),
+        }
+    }
+
+    let faces = std::array::from_fn::<u8, 12, _>(edge_faces);
+    for start in 0..12 {
+        if next[start] == usize::MAX {
+            continue;
+        }
+        let mut contour = Vec::new();
+        let mut edge = start;
+        while next[edge] != usize::MAX {
+            contour.push(edge);
+            edge = std::mem::replace(&mut next[edge], usize::MAX);
+        }
+        debug_assert_eq!(edge, start, "contour loops are closed");
+
+        let n = contour.len();
+        // A fan diagonal joining two edges of one face would also be drawn
+        // by the cell across that face.
+        let apex = (0..n)
+            .find(|&k| (2..n - 1).all(|m| faces[contour[k]] & faces[contour[(k + m) % n]] == 0));
+        let ring: Vec<usize> = contour.iter().map(|&e| cube_vertices[e]).collect();
+        match apex {
+            Some(k) => {
+                for m in 1..n - 1 {
+                    mesh.faces
+                        .push([ring[k], ring[(k + m + 1) % n], ring[(k + m) % n]]);
+                }
+            }
+            None => {
+                let centre = mesh.vertices.len();
+                let sum = ring.iter().fold([0.0; 3], |s, &v| {
+                    let p = mesh.vertices[v];
+                    [s[0] + p.x, s[1] + p.y, s[2] + p.z]
+                });
+                mesh.vertices.push(Point3D {
+                    index: centre as i64,
+                    x: sum[0] / n as f64,
+                    y: sum[1] / n as f64,
+                    z: sum[2] / n as f64,
+                });
+                for m in 0..n {
+                    mesh.faces.push([centre, ring[(m + 1) % n], ring[m]]);
+                }
+            }
+        }
+    }
+}
+
 /// Linearly interpolates between two points based on scalar values at each point
 /// relative to the iso-value.
 fn interpolate(
@@ -341,12 +497,171 @@ fn interpolate(
     )
 }
 
+/// Scalar samples on the `(nx + 1) * (ny + 1) * (nz + 1)` vertices of a
+/// regular grid of cells, stored with `x` varying fastest, and the samples
+/// cells may use if a mask is given.
+struct Grid<'a, T: Clone> {
+    cells: [usize; 3],
+    origin: [f64; 3],
+    spacing: [f64; 3],
+    values: Cow<'a, [T]>,
+    mask: Option<&'a [bool]>,
+}
+
+impl Grid<'_, f64> {
+    /// Samples `scalar_field` on the vertices of an `nx * ny * nz` grid
+    /// spanning `min` to `max`.
+    fn sample(
+        nx: usize,
+        ny: usize,
+        nz: usize,
+        min: Point3D,
+        max: Point3D,
+        scalar_field: &dyn Fn(f64, f64, f64) -> f64,
+    ) -> Self {
+        let mut grid = Grid {
+            cells: [nx, ny, nz],
+            origin: [min.x, min.y, min.z],
+            spacing: [
+                (max.x - min.x) / nx as f64,
+                (max.y - min.y) / ny as f64,
+                (max.z - min.z) / nz as f64,
+            ],
+            values: Cow::Owned(Vec::new()),
+            mask: None,
+        };
+        let values = (0..=nz)
+            .flat_map(|k| (0..=ny).flat_map(move |j| (0..=nx).map(move |i| [i, j, k])))
+            .map(|vertex| {
+                let (x, y, z) = grid.position(vertex);
+                scalar_field(x, y, z)
+            })
+            .collect();
+        grid.values = Cow::Owned(values);
+        grid
+    }
+}
+
+impl<T: Copy + Into<f64>> Grid<'_, T> {
+    /// Linear index of grid vertex `[i, j, k]`.
+    fn index(&self, [i, j, k]: [usize; 3]) -> usize {
+        i + (self.cells[0] + 1) * (j + (self.cells[1] + 1) * k)
+    }
+
+    fn position(&self, [i, j, k]: [usize; 3]) -> (f64, f64, f64) {
+        (
+            self.origin[0] + i as f64 * self.spacing[0],
+            self.origin[1] + j as f64 * self.spacing[1],
+            self.origin[2] + k as f64 * self.spacing[2],
+        )
+    }
+
+    /// Extracts the isosurface at `iso_value` as an indexed mesh.
+    ///
+    /// Each intersected grid edge contributes a single vertex, shared by
+    /// every triangle of the neighbouring cells that uses it, so the
+    /// surface is connected across cell boundaries. Cells with a corner
+    /// outside the mask are skipped.
+    fn polygonize(&self, iso_value: f64, method: MarchingCubesMethod) -> SurfaceMesh {
+        let [nx, ny, nz] = self.cells;
+        let mut mesh = SurfaceMesh::default();
+        // Vertex on the grid edge leaving grid vertex `n` along axis `a`,
+        // keyed by `3 * n + a`.
+        let mut edge_vertex: HashMap<usize, usize> = HashMap::new();
+
+        for k in 0..nz {
+            for j in 0..ny {
+                for i in 0..nx {
+                    let corners: [[usize; 3]; 8] =
+                        CORNER_OFFSETS.map(|[di, dj, dk]| [i + di, j + dj, k + dk]);
+                    let samples = corners.map(|c| self.index(c));
+                    if let Some(mask) = self.mask {
+                        if samples.iter().any(|&n| !mask[n]) {
+                            continue;
+                        }
+                    }
+                    let values = samples.map(|n| self.values[n].into());
+
+                    // Build cube index: bit n is set if corner n is above the iso-value
+                    let mut cube_index: usize = 0;
+                    for (n, val) in values.iter().enumerate() {
+                        if *val > iso_value {
+                            cube_index |= 1 << n;
+                        }
+                    }
+
+                    // Skip if cube is entirely inside or outside the surface
+                    let edges = EDGE_TABLE[cube_index];
+                    if edges == 0 {
+                        continue;
+                    }
+
+                    // Look up or create the vertex on each intersected edge,
+                    // always interpolating from the lower grid vertex so that
+                    // neighbouring cells agree on its position.
+                    let mut cube_vertices = [0; 12];
+                    for (edge, vertex) in cube_vertices.iter_mut().enumerate() {
+                        if edges & (1 << edge) == 0 {
+                            continue;
+                        }
+                        let (mut v1, mut v2) = EDGE_VERTICES[edge];
+                        if CORNER_OFFSETS[v1] > CORNER_OFFSETS[v2] {
+                            std::mem::swap(&mut v1, &mut v2);
+                        }
+                        let axis = (0..3)
+                            .find(|&a| CORNER_OFFSETS[v1][a] != CORNER_OFFSETS[v2][a])
+                            .expect("cube edges join distinct corners");
+                        let slot = 3 * samples[v1] + axis;
+                        *vertex = *edge_vertex.entry(slot).or_insert_with(|| {
+                            let (x, y, z) = interpolate(
+                                self.position(corners[v1]),
+                                self.position(corners[v2]),
+                                values[v1],
+                                values[v2],
+                                iso_value,
+                            );
+                            mesh.vertices.push(Point3D {
+                                index: mesh.vertices.len() as i64,
+                                x,
+                                y,
+                                z,
+                            });
+                            mesh.vertices.len() - 1
+                        });
+                    }
+
+                    if method == MarchingCubesMethod::AsymptoticDecider {
+                        asymptotic_decider(&values, iso_value, &cube_vertices, &mut mesh);
+                        continue;
+                    }
+
+                    // Generate triangles from the triangle table
+                    for triangle in TRI_TABLE[cube_index]
+                        .chunks_exact(3)
+                        .take_while(|t| t[0] != -1)
+                    {
+                        mesh.faces
+                            .push(std::array::from_fn(|n| cube_vertices[triangle[n] as usize]));
+                    }
+                }
+            }
+        }
+
+        mesh
+    }
+}
+
 /// Extracts an isosurface from a 3D scalar field using the Marching Cubes algorithm.
 ///
 /// Divides the bounding box defined by `min` and `max` into a regular grid of
 /// `nx * ny * nz` cells, evaluates the scalar field at each grid vertex, and
 /// generates triangles approximating the isosurface where the field equals `iso_value`.
 ///
+/// Triangles of neighbouring cells share the vertex of each grid edge they
+/// cross, and [`Point3D::index`] identifies it, so the faces form a
+/// connected surface that is closed wherever the isosurface stays inside
+/// the bounding box.
+///
 /// # Arguments
 ///
 /// * `nx`, `ny`, `nz` - Number of cells along each axis.
@@ -380,104 +695,147 @@ pub fn marching_cubes(
     scalar_field: &dyn Fn(f64, f64, f64) -> f64,
     iso_value: f64,
 ) -> Vec<Face> {
-    let dx = (max.x - min.x) / nx as f64;
-    let dy = (max.y - min.y) / ny as f64;
-    let dz = (max.z - min.z) / nz as f64;
-
-    let mut faces: Vec<Face> = Vec::new();
-    let mut point_index: i64 = 0;
-
-    for i in 0..nx {
-        for j in 0..ny {
-            for k in 0..nz {
-                let x = min.x + i as f64 * dx;
-                let y = min.y + j as f64 * dy;
-                let z = min.z + k as f64 * dz;
-
-                // 8 corner positions of this cube (standard ordering)
-                let corners = [
-                    (x, y, z),                // 0
-                    (x + dx, y, z),           // 1
-                    (x + dx, y + dy, z),      // 2
-                    (x, y + dy, z),           // 3
-                    (x, y, z + dz),           // 4
-                    (x + dx, y, z + dz),      // 5
-                    (x + dx, y + dy, z + dz), // 6
-                    (x, y + dy, z + dz),      // 7
-                ];
-
-                // Sample scalar field at each corner
-                let values: [f64; 8] =
-                    std::array::from_fn(|n| scalar_field(corners[n].0, corners[n].1, corners[n].2));
-
-                // Build cube index: bit n is set if corner n is above the iso-value
-                let mut cube_index: usize = 0;
-                for (n, val) in values.iter().enumerate() {
-                    if *val > iso_value {
-                        cube_index |= 1 << n;
-                    }
-                }
+    marching_cubes_indexed(nx, ny, nz, min, max, scalar_field, iso_value).to_faces()
+}
 
-                // Skip if cube is entirely inside or outside the surface
-                let edges = EDGE_TABLE[cube_index];
-                if edges == 0 {
-                    continue;
-                }
+/// Extracts an isosurface and returns it as an indexed [`SurfaceMesh`].
+///
+/// Produces the same triangles as [`marching_cubes`], with one vertex per
+/// intersected grid edge.
+pub fn marching_cubes_indexed(
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    min: Point3D,
+    max: Point3D,
+    scalar_field: &dyn Fn(f64, f64, f64) -> f64,
+    iso_value: f64,
+) -> SurfaceMesh {
+    Grid::sample(nx, ny, nz, min, max, scalar_field)
+        .polygonize(iso_value, MarchingCubesMethod::Classic)
+}
 
-                // Compute interpolated vertices on intersected edges
-                let mut edge_vertices = [(0.0, 0.0, 0.0); 12];
-                for edge in 0..12 {
-                    if edges & (1 << edge) != 0 {
-                        let (v1, v2) = EDGE_VERTICES[edge];
-                        edge_vertices[edge] = interpolate(
-                            corners[v1],
-                            corners[v2],
-                            values[v1],
-                            values[v2],
-                            iso_value,
-                        );
-                    }
-                }
+/// Extracts an isosurface as an indexed [`SurfaceMesh`], resolving
+/// ambiguous cells with `method`.
+///
+/// With [`MarchingCubesMethod::Classic`] this is [`marching_cubes_indexed`].
+///
+/// # Examples
+///
+/// ```
+/// use meshing::marching_cubes::{marching_cubes_with_method, MarchingCubesMethod};
+/// use meshing::Point3D;
+///
+/// let min = Point3D { index: 0, x: 0.0, y: 0.0, z: 0.0 };
+/// let max = Point3D { index: 0, x: 6.0, y: 6.0, z: 6.0 };
+/// let gyroid = |x: f64, y: f64, z: f64| {
+///     x.sin() * y.cos() + y.sin() * z.cos() + z.sin() * x.cos()
+/// };
+/// let mesh = marching_cubes_with_method(
+///     12, 12, 12, min, max, &gyroid, 0.0, MarchingCubesMethod::AsymptoticDecider,
+/// );
+/// assert!(!mesh.is_empty());
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn marching_cubes_with_method(
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    min: Point3D,
+    max: Point3D,
+    scalar_field: &dyn Fn(f64, f64, f64) -> f64,
+    iso_value: f64,
+    method: MarchingCubesMethod,
+) -> SurfaceMesh {
+    Grid::sample(nx, ny, nz, min, max, scalar_field).polygonize(iso_value, method)
+}
 
-                // Generate triangles from the triangle table
-                let mut t = 0;
-                while TRI_TABLE[cube_index][t] != -1 {
-                    let e0 = TRI_TABLE[cube_index][t] as usize;
-                    let e1 = TRI_TABLE[cube_index][t + 1] as usize;
-                    let e2 = TRI_TABLE[cube_index][t + 2] as usize;
-
-                    let v0 = edge_vertices[e0];
-                    let v1 = edge_vertices[e1];
-                    let v2 = edge_vertices[e2];
-
-                    faces.push(Face {
-                        a: Point3D {
-                            index: point_index,
-                            x: v0.0,
-                            y: v0.1,
-                            z: v0.2,
-                        },
-                        b: Point3D {
-                            index: point_index + 1,
-                            x: v1.0,
-                            y: v1.1,
-                            z: v1.2,
-                        },
-                        c: Point3D {
-                            index: point_index + 2,
-                            x: v2.0,
-                            y: v2.1,
-                            z: v2.2,
-                        },
-                    });
-                    point_index += 3;
-                    t += 3;
-                }
-            }
+/// Extracts an isosurface from a scalar volume sampled on a regular grid,
+/// such as a CT or MRI scan.
+///
+/// `values` holds `dims[0] * dims[1] * dims[2]` samples with `x` varying
+/// fastest, then `y`, then `z`, the layout of VTK image data, NRRD and
+/// NIfTI volumes. Sample `[i, j, k]` lies at `origin + [i, j, k] * spacing`.
+/// Samples may be `f32`, `f64` or any smaller numeric type.
+///
+/// If `mask` is given, it has one entry per sample and only cells whose
+/// eight corners are all `true` are polygonized, so the surface is open
+/// where it leaves the masked region.
+///
+/// The result matches [`marching_cubes_with_method`] run on the same
+/// samples, with one vertex per intersected grid edge.
+///
+/// # Errors
+///
+/// Returns [`MeshingError::InvalidParameter`] if `values` or `mask` does
+/// not have one entry per sample, or a spacing is not positive and finite.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::marching_cubes::{marching_cubes_volume, MarchingCubesMethod};
+/// use meshing::Point3D;
+///
+/// // A 3x3x3 volume with a single bright voxel in the middle.
+/// let mut values = vec![0.0f32; 27];
+/// values[13] = 100.0;
+/// let origin = Point3D { index: 0, x: 0.0, y: 0.0, z: 0.0 };
+/// let method = MarchingCubesMethod::Classic;
+/// let mesh = marching_cubes_volume(&values, [3, 3, 3], [0.5, 0.5, 2.0], origin, None, 50.0, method)
+///     .unwrap();
+/// assert_eq!(mesh.num_vertices(), 6);
+/// assert_eq!(mesh.num_faces(), 8);
+/// ```
+pub fn marching_cubes_volume<T: Copy + Into<f64>>(
+    values: &[T],
+    dims: [usize; 3],
+    spacing: [f64; 3],
+    origin: Point3D,
+    mask: Option<&[bool]>,
+    iso_value: f64,
+    method: MarchingCubesMethod,
+) -> Result<SurfaceMesh, MeshingError> {
+    let num_samples = dims
+        .iter()
+        .try_fold(1usize, |n, &d| n.checked_mul(d))
+        .ok_or_else(|| {
+            MeshingError::InvalidParameter(format!("volume dimensions {:?} overflow", dims))
+        })?;
+    if values.len() != num_samples {
+        return Err(MeshingError::InvalidParameter(format!(
+            "volume of dimensions {:?} needs {} samples, got {}",
+            dims,
+            num_samples,
+            values.len()
+        )));
+    }
+    if let Some(mask) = mask {
+        if mask.len() != num_samples {
+            return Err(MeshingError::InvalidParameter(format!(
+                "mask needs {} entries, got {}",
+                num_samples,
+                mask.len()
+            )));
         }
     }
+    if let Some(s) = spacing.iter().find(|s| !(s.is_finite() && **s > 0.0)) {
+        return Err(MeshingError::InvalidParameter(format!(
+            "volume spacing must be positive, got {}",
+            s
+        )));
+    }
+    if dims.contains(&0) {
+        return Ok(SurfaceMesh::default());
+    }
 
-    faces
+    let grid = Grid {
+        cells: dims.map(|d| d - 1),
+        origin: [origin.x, origin.y, origin.z],
+        spacing,
+        values: Cow::Borrowed(values),
+        mask,
+    };
+    Ok(grid.polygonize(iso_value, method))
 }
 
 #[cfg(test)]
@@ -501,6 +859,10 @@ mod tests {
         let field = |x: f64, y: f64, z: f64| x * x + y * y + z * z - 1.0;
         let faces = marching_cubes(10, 10, 10, min, max, &field, 0.0);
         assert!(!faces.is_empty(), "Sphere should produce faces");
+
+        // Faces carry the shared indices, forming a closed genus-0 surface.
+        let mesh = SurfaceMesh::from_faces(&faces);
+        assert_eq!(assert_closed_manifold(&mesh), 2);
     }
 
     #[test]
@@ -616,6 +978,7 @@ mod tests {
         };
         let faces = marching_cubes(15, 15, 8, min, max, &field, 0.0);
         assert!(!faces.is_empty(), "Torus should produce faces");
+        assert_eq!(assert_closed_manifold(&SurfaceMesh::from_faces(&faces)), 0);
     }
 
     #[test]
@@ -636,4 +999,433 @@ mod tests {
         let faces = marching_cubes(5, 5, 5, min, max, &field, 0.5);
         assert!(!faces.is_empty(), "Plane isosurface should produce faces");
     }
+
+    #[test]
+    fn test_marching_cubes_indexed_matches_faces() {
+        let min = Point3D {
+            index: 0,
+            x: -2.0,
+            y: -2.0,
+            z: -2.0,
+        };
+        let max = Point3D {
+            index: 0,
+            x: 2.0,
+            y: 2.0,
+            z: 2.0,
+        };
+        let field = |x: f64, y: f64, z: f64| x * x + y * y + z * z - 1.0;
+        let faces = marching_cubes(6, 6, 6, min, max, &field, 0.0);
+        let mesh = marching_cubes_indexed(6, 6, 6, min, max, &field, 0.0);
+        assert_eq!(mesh.num_faces(), faces.len());
+        assert_eq!(mesh.to_faces(), faces);
+    }
+
+    /// Asserts that every edge is shared by exactly two faces that use it
+    /// in opposite directions and that the faces around each vertex form a
+    /// single fan, and returns the Euler characteristic.
+    fn assert_closed_manifold(mesh: &SurfaceMesh) -> i64 {
+        let mut directed = std::collections::HashSet::new();
+        // Edge opposite each vertex in its faces, as `next[v][b] = c`.
+        let mut next = vec![HashMap::new(); mesh.num_vertices()];
+        for &[a, b, c] in &mesh.faces {
+            for edge in [(a, b), (b, c), (c, a)] {
+                assert!(directed.insert(edge), "edge {:?} used twice", edge);
+            }
+            next[a].insert(b, c);
+            next[b].insert(c, a);
+            next[c].insert(a, b);
+        }
+        for &(a, b) in &directed {
+            assert!(directed.contains(&(b, a)), "boundary edge {:?}", (a, b));
+        }
+        for (v, link) in next.iter().enumerate() {
+            let &start = link.keys().next().expect("every vertex is used");
+            let mut w = start;
+            for _ in 0..link.len() {
+                w = link[&w];
+            }
+            assert_eq!(w, start, "vertex {} has several fans", v);
+            let cycle_length = (1..=link.len())
+                .scan(start, |w, _| {
+                    *w = link[w];
+                    Some(*w)
+                })
+                .position(|w| w == start);
+            assert_eq!(
+                cycle_length,
+                Some(link.len() - 1),
+                "vertex {} has several fans",
+                v
+            );
+        }
+        mesh.num_vertices() as i64 - directed.len() as i64 / 2 + mesh.num_faces() as i64
+    }
+
+    #[test]
+    fn test_neighbouring_cells_share_vertices() {
+        let min = Point3D {
+            index: 0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let max = Point3D {
+            index: 0,
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        };
+        // A plane crossing a 5x5 column of cells has one vertex per
+        // vertical grid edge.
+        let field = |_x: f64, _y: f64, z: f64| z;
+        let mesh = marching_cubes_indexed(5, 5, 5, min, max, &field, 0.55);
+        assert_eq!(mesh.num_vertices(), 36);
+        assert_eq!(mesh.num_faces(), 50);
+        for (n, v) in mesh.vertices.iter().enumerate() {
+            assert_eq!(v.index, n as i64);
+        }
+    }
+
+    /// Samples `field` on a `dims` volume starting at `origin`.
+    fn sample_volume(
+        dims: [usize; 3],
+        spacing: [f64; 3],
+        origin: Point3D,
+        field: &dyn Fn(f64, f64, f64) -> f64,
+    ) -> Vec<f64> {
+        let mut values = Vec::new();
+        for k in 0..dims[2] {
+            for j in 0..dims[1] {
+                for i in 0..dims[0] {
+                    values.push(field(
+                        origin.x + i as f64 * spacing[0],
+                        origin.y + j as f64 * spacing[1],
+                        origin.z + k as f64 * spacing[2],
+                    ));
+                }
+            }
+        }
+        values
+    }
+
+    #[test]
+    fn test_volume_matches_scalar_field() {
+        let min = Point3D {
+            index: 0,
+            x: -2.0,
+            y: -1.5,
+            z: -1.2,
+        };
+        let max = Point3D {
+            index: 0,
+            x: 2.0,
+            y: 1.5,
+            z: 1.2,
+        };
+        let field = |x: f64, y: f64, z: f64| x * x / 2.0 + y * y + z * z - 1.0;
+        let spacing = [
+            (max.x - min.x) / 10.0,
+            (max.y - min.y) / 10.0,
+            (max.z - min.z) / 12.0,
+        ];
+        let values = sample_volume([11, 11, 13], spacing, min, &field);
+        let mesh = marching_cubes_volume(
+            &values,
+            [11, 11, 13],
+            spacing,
+            min,
+            None,
+            0.0,
+            MarchingCubesMethod::Classic,
+        )
+        .unwrap();
+        assert_eq!(
+            mesh,
+            marching_cubes_indexed(10, 10, 12, min, max, &field, 0.0)
+        );
+        assert_eq!(assert_closed_manifold(&mesh), 2);
+
+        // Single precision samples give the same connectivity.
+        let single: Vec<f32> = values.iter().map(|&v| v as f32).collect();
+        let from_single = marching_cubes_volume(
+            &single,
+            [11, 11, 13],
+            spacing,
+            min,
+            None,
+            0.0,
+            MarchingCubesMethod::Classic,
+        )
+        .unwrap();
+        assert_eq!(from_single.faces, mesh.faces);
+    }
+
+    #[test]
+    fn test_volume_mask_limits_cells() {
+        let origin = Point3D {
+            index: 0,
+            x: -2.0,
+            y: -2.0,
+            z: -2.0,
+        };
+        let field = |x: f64, y: f64, z: f64| x * x + y * y + z * z - 1.0;
+        let spacing = [0.25; 3];
+        let values = sample_volume([17, 17, 17], spacing, origin, &field);
+        // Keep the half space x >= 0, whose sample column is i >= 8.
+        let mask: Vec<bool> = (0..values.len()).map(|n| n % 17 >= 8).collect();
+        let full = marching_cubes_volume(
+            &values,
+            [17; 3],
+            spacing,
+            origin,
+            None,
+            0.0,
+            MarchingCubesMethod::Classic,
+        )
+        .unwrap();
+        let half = marching_cubes_volume(
+            &values,
+            [17; 3],
+            spacing,
+            origin,
+            Some(&mask),
+            0.0,
+            MarchingCubesMethod::Classic,
+        )
+        .unwrap();
+        assert!(half.num_faces() > 0 && half.num_faces() < full.num_faces());
+        assert!(half.vertices.iter().all(|v| v.x >= 0.0));
+    }
+
+    #[test]
+    fn test_volume_rejects_invalid_input() {
+        let origin = Point3D {
+            index: 0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let values = vec![0.0f64; 8];
+        let invalid = |result: Result<SurfaceMesh, MeshingError>| {
+            matches!(result, Err(MeshingError::InvalidParameter(_)))
+        };
+        assert!(invalid(marching_cubes_volume(
+            &values,
+            [2, 2, 3],
+            [1.0; 3],
+            origin,
+            None,
+            0.5,
+            MarchingCubesMethod::Classic
+        )));
+        assert!(invalid(marching_cubes_volume(
+            &values,
+            [2, 2, 2],
+            [1.0; 3],
+            origin,
+            Some(&[true; 4]),
+            0.5,
+            MarchingCubesMethod::Classic
+        )));
+        assert!(invalid(marching_cubes_volume(
+            &values,
+            [2, 2, 2],
+            [1.0, 0.0, 1.0],
+            origin,
+            None,
+            0.5,
+            MarchingCubesMethod::Classic
+        )));
+        let empty = marching_cubes_volume::<f32>(
+            &[],
+            [0, 4, 4],
+            [1.0; 3],
+            origin,
+            None,
+            0.5,
+            MarchingCubesMethod::Classic,
+        );
+        assert!(empty.unwrap().is_empty());
+    }
+
+    fn signed_volume(mesh: &SurfaceMesh) -> f64 {
+        mesh.to_faces()
+            .iter()
+            .map(|f| {
+                let [a, b, c] = f.vertices();
+                (a.x * (b.y * c.z - b.z * c.y) - a.y * (b.x * c.z - b.z * c.x)
+                    + a.z * (b.x * c.y - b.y * c.x))
+                    / 6.0
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_asymptotic_decider_matches_classic_on_sphere() {
+        let min = Point3D {
+            index: 0,
+            x: -2.0,
+            y: -2.0,
+            z: -2.0,
+        };
+        let max = Point3D {
+            index: 0,
+            x: 2.0,
+            y: 2.0,
+            z: 2.0,
+        };
+        let field = |x: f64, y: f64, z: f64| x * x + y * y + z * z - 1.0;
+        let classic = marching_cubes_indexed(10, 10, 10, min, max, &field, 0.0);
+        let decided = marching_cubes_with_method(
+            10,
+            10,
+            10,
+            min,
+            max,
+            &field,
+            0.0,
+            MarchingCubesMethod::AsymptoticDecider,
+        );
+        assert_eq!(assert_closed_manifold(&decided), 2);
+        // Same vertices and orientation, with only the polygons split
+        // differently.
+        assert_eq!(decided.vertices, classic.vertices);
+        let volume = signed_volume(&classic);
+        assert!((signed_volume(&decided) - volume).abs() < 1e-9 * volume.abs());
+        assert_eq!(
+            marching_cubes_with_method(10, 10, 10, min, max, &field, 0.0, Default::default()),
+            classic
+        );
+    }
+
+    #[test]
+    fn test_asymptotic_decider_closes_gyroid() {
+        let min = Point3D {
+            index: 0,
+            x: -1.0,
+            y: -1.0,
+            z: -1.0,
+        };
+        let max = Point3D {
+            index: 0,
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        };
+        // The gyroid of examples/3d_plot.rs, capped by a sphere so that the
+        // surface stays inside the grid.
+        let gyroid = |x: f64, y: f64, z: f64| {
+            let s = 3.0;
+            let g = (s * x).sin() * (s * y).cos()
+                + (s * y).sin() * (s * z).cos()
+                + (s * z).sin() * (s * x).cos();
+            g.max(x * x + y * y + z * z - 0.9)
+        };
+        for n in [17, 30, 31] {
+            let mesh = marching_cubes_with_method(
+                n,
+                n,
+                n,
+                min,
+                max,
+                &gyroid,
+                0.0,
+                MarchingCubesMethod::AsymptoticDecider,
+            );
+            assert!(mesh.num_faces() > 100);
+            assert_closed_manifold(&mesh);
+        }
+    }
+
+    #[test]
+    fn test_asymptotic_decider_closes_noise() {
+        use rand::{Rng, SeedableRng};
+
+        let origin = Point3D {
+            index: 0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let dims = [14, 12, 10];
+        for seed in 0..5 {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            // White noise, kept above the iso-value on the volume boundary.
+            let mut values = Vec::new();
+            for k in 0..dims[2] {
+                for j in 0..dims[1] {
+                    for i in 0..dims[0] {
+                        let boundary = [i, j, k]
+                            .iter()
+                            .zip(dims)
+                            .any(|(&c, d)| c == 0 || c == d - 1);
+                        values.push(if boundary {
+                            1.0f32
+                        } else {
+                            rng.gen_range(-1.0..1.0)
+                        });
+                    }
+                }
+            }
+            let method = MarchingCubesMethod::AsymptoticDecider;
+            let mesh =
+                marching_cubes_volume(&values, dims, [1.0; 3], origin, None, 0.0, method).unwrap();
+            assert_closed_manifold(&mesh);
+        }
+    }
+
+    #[test]
+    fn test_asymptotic_decider_joins_saddle() {
+        let min = Point3D {
+            index: 0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let max = Point3D {
+            index: 0,
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        };
+        // Corners (0, 0, 0) and (1, 1, 0) are far above the iso-value, the
+        // other two bottom corners barely below it, so the bilinear
+        // interpolant joins the two above corners across the bottom face.
+        let field = |x: f64, y: f64, z: f64| {
+            let diagonal = if (x - y).abs() < 0.5 { 4.0 } else { -0.5 };
+            if z < 0.5 {
+                diagonal
+            } else {
+                -1.0
+            }
+        };
+        let classic = marching_cubes_indexed(1, 1, 1, min, max, &field, 0.0);
+        assert_eq!((classic.num_vertices(), classic.num_faces()), (6, 2));
+        let decided = marching_cubes_with_method(
+            1,
+            1,
+            1,
+            min,
+            max,
+            &field,
+            0.0,
+            MarchingCubesMethod::AsymptoticDecider,
+        );
+        // One band around both corners instead of two separate caps.
+        assert_eq!(decided.vertices, classic.vertices);
+        assert_eq!(decided.num_faces(), 4);
+        let mut adjacent = std::collections::HashSet::new();
+        for &[a, b, c] in &decided.faces {
+            adjacent.extend([(a, b), (b, c), (c, a), (b, a), (c, b), (a, c)]);
+        }
+        let mut reached = vec![0];
+        while let Some(&v) = reached.last() {
+            match (0..6).find(|&w| adjacent.contains(&(v, w)) && !reached.contains(&w)) {
+                Some(w) => reached.push(w),
+                None => break,
+            }
+        }
+        assert_eq!(reached.len(), 6);
+    }
 }
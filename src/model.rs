@@ -21,3 +21,44 @@ pub use face::Face;
 
 mod sphere;
 pub use sphere::Sphere;
+
+mod tri_mesh_2d;
+pub use tri_mesh_2d::TriMesh2D;
+
+mod surface_mesh;
+pub use surface_mesh::SurfaceMesh;
+
+mod tet_mesh;
+pub use tet_mesh::TetMesh;
+
+/// Keeps the vertices used by `elements`, sorted by `index` with vertices of
+/// equal index merged into the first one, and renumbers `elements` to match.
+///
+/// This is the vertex order of every indexed mesh built from elements or
+/// from a triangulation.
+pub(crate) fn compact_vertices<P: Copy, const N: usize>(
+    vertices: &[P],
+    elements: &[[usize; N]],
+    index: impl Fn(&P) -> i64,
+) -> (Vec<P>, Vec<[usize; N]>) {
+    let mut used = vec![false; vertices.len()];
+    for &v in elements.iter().flatten() {
+        used[v] = true;
+    }
+    let mut order: Vec<usize> = (0..vertices.len()).filter(|&v| used[v]).collect();
+    order.sort_by_key(|&v| index(&vertices[v]));
+
+    let mut kept: Vec<P> = Vec::with_capacity(order.len());
+    let mut position = vec![usize::MAX; vertices.len()];
+    for v in order {
+        if kept
+            .last()
+            .is_none_or(|last| index(last) != index(&vertices[v]))
+        {
+            kept.push(vertices[v]);
+        }
+        position[v] = kept.len() - 1;
+    }
+    let renumbered = elements.iter().map(|e| e.map(|v| position[v])).collect();
+    (kept, renumbered)
+}
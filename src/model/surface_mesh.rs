@@ -0,0 +1,61 @@
+use crate::model::compact_vertices;
+use crate::model::face::Face;
+use crate::model::point_3d::Point3D;
+
+/// An indexed triangle surface mesh in 3D.
+///
+/// Vertices are stored once in `vertices` and each face is a triple of
+/// positions into that buffer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SurfaceMesh {
+    pub vertices: Vec<Point3D>,
+    pub faces: Vec<[usize; 3]>,
+}
+
+impl SurfaceMesh {
+    /// Builds an indexed surface from a slice of faces.
+    ///
+    /// Vertices are deduplicated by [`Point3D::index`] and stored sorted by
+    /// that index.
+    pub fn from_faces(faces: &[Face]) -> SurfaceMesh {
+        let vertices: Vec<Point3D> = faces.iter().flat_map(|f| f.vertices()).collect();
+        let elements: Vec<[usize; 3]> = (0..faces.len())
+            .map(|i| std::array::from_fn(|k| 3 * i + k))
+            .collect();
+        let (vertices, faces) = compact_vertices(&vertices, &elements, |v| v.index);
+        SurfaceMesh { vertices, faces }
+    }
+
+    /// Expands the mesh back into a vector of [`Face`]s.
+    pub fn to_faces(&self) -> Vec<Face> {
+        (0..self.faces.len()).map(|i| self.face(i)).collect()
+    }
+
+    /// Returns the face at position `i` as a [`Face`].
+    pub fn face(&self, i: usize) -> Face {
+        let [a, b, c] = self.faces[i];
+        Face {
+            a: self.vertices[a],
+            b: self.vertices[b],
+            c: self.vertices[c],
+        }
+    }
+
+    pub fn num_vertices(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn num_faces(&self) -> usize {
+        self.faces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.faces.is_empty()
+    }
+}
+
+impl From<&[Face]> for SurfaceMesh {
+    fn from(faces: &[Face]) -> Self {
+        SurfaceMesh::from_faces(faces)
+    }
+}
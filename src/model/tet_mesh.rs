@@ -0,0 +1,124 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::model::compact_vertices;
+use crate::model::point_3d::Point3D;
+use crate::model::surface_mesh::SurfaceMesh;
+use crate::model::tetrahedron::Tetrahedron;
+
+/// An indexed tetrahedral volume mesh.
+///
+/// Vertices are stored once in `vertices` and each tetrahedron is a set of
+/// four positions into that buffer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TetMesh {
+    pub vertices: Vec<Point3D>,
+    pub tetrahedra: Vec<[usize; 4]>,
+}
+
+impl TetMesh {
+    /// Builds an indexed mesh from a slice of tetrahedra.
+    ///
+    /// Vertices are deduplicated by [`Point3D::index`] and stored sorted by
+    /// that index.
+    pub fn from_tetrahedra(tetrahedra: &[Tetrahedron]) -> TetMesh {
+        let vertices: Vec<Point3D> = tetrahedra.iter().flat_map(|t| t.vertices()).collect();
+        let elements: Vec<[usize; 4]> = (0..tetrahedra.len())
+            .map(|i| std::array::from_fn(|k| 4 * i + k))
+            .collect();
+        let (vertices, tetrahedra) = compact_vertices(&vertices, &elements, |v| v.index);
+        TetMesh {
+            vertices,
+            tetrahedra,
+        }
+    }
+
+    /// Expands the mesh back into a vector of [`Tetrahedron`]s.
+    pub fn to_tetrahedra(&self) -> Vec<Tetrahedron> {
+        (0..self.tetrahedra.len())
+            .map(|i| self.tetrahedron(i))
+            .collect()
+    }
+
+    /// Returns the tetrahedron at position `i` as a [`Tetrahedron`].
+    pub fn tetrahedron(&self, i: usize) -> Tetrahedron {
+        let [a, b, c, d] = self.tetrahedra[i];
+        Tetrahedron {
+            a: self.vertices[a],
+            b: self.vertices[b],
+            c: self.vertices[c],
+            d: self.vertices[d],
+        }
+    }
+
+    /// Returns the boundary faces as index triples into `vertices`.
+    ///
+    /// A face is on the boundary if it belongs to exactly one tetrahedron.
+    /// Faces are visited in the same order as [`Tetrahedron::faces`], so the
+    /// result matches [`extract_surface_faces`](crate::export::extract_surface_faces)
+    /// on the equivalent element vector.
+    pub fn boundary_faces(&self) -> Vec<[usize; 3]> {
+        let local_faces = |&[a, b, c, d]: &[usize; 4]| [[a, b, c], [a, b, d], [a, c, d], [b, c, d]];
+
+        let mut counts: HashMap<[usize; 3], usize> = HashMap::new();
+        for tet in &self.tetrahedra {
+            for face in local_faces(tet) {
+                let mut key = face;
+                key.sort_unstable();
+                *counts.entry(key).or_insert(0) += 1;
+            }
+        }
+
+        let mut boundary = Vec::new();
+        for tet in &self.tetrahedra {
+            for face in local_faces(tet) {
+                let mut key = face;
+                key.sort_unstable();
+                if counts[&key] == 1 {
+                    boundary.push(face);
+                }
+            }
+        }
+        boundary
+    }
+
+    /// Extracts the boundary surface as a [`SurfaceMesh`].
+    ///
+    /// Only vertices referenced by boundary faces are kept.
+    pub fn surface(&self) -> SurfaceMesh {
+        let faces = self.boundary_faces();
+        let mut remap: BTreeMap<usize, usize> = BTreeMap::new();
+        for face in &faces {
+            for &v in face {
+                remap.insert(v, 0);
+            }
+        }
+        for (pos, new) in remap.values_mut().enumerate() {
+            *new = pos;
+        }
+        SurfaceMesh {
+            vertices: remap.keys().map(|&v| self.vertices[v]).collect(),
+            faces: faces
+                .iter()
+                .map(|f| [remap[&f[0]], remap[&f[1]], remap[&f[2]]])
+                .collect(),
+        }
+    }
+
+    pub fn num_vertices(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn num_tetrahedra(&self) -> usize {
+        self.tetrahedra.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tetrahedra.is_empty()
+    }
+}
+
+impl From<&[Tetrahedron]> for TetMesh {
+    fn from(tetrahedra: &[Tetrahedron]) -> Self {
+        TetMesh::from_tetrahedra(tetrahedra)
+    }
+}
@@ -97,6 +97,32 @@ impl Tetrahedron {
         (ux * (vy * wz - vz * wy) - uy * (vx * wz - vz * wx) + uz * (vx * wy - vy * wx)) / 6.0
     }
 
+    /// Normalized radius ratio `3 r / R` of the inscribed and circumscribed
+    /// spheres: 1 for a regular tetrahedron and 0 for a degenerate one.
+    pub fn radius_ratio(&self) -> f64 {
+        let volume = self.signed_volume().abs();
+        let area: f64 = self
+            .faces()
+            .iter()
+            .map(|f| {
+                let u = [f.b.x - f.a.x, f.b.y - f.a.y, f.b.z - f.a.z];
+                let v = [f.c.x - f.a.x, f.c.y - f.a.y, f.c.z - f.a.z];
+                let n = [
+                    u[1] * v[2] - u[2] * v[1],
+                    u[2] * v[0] - u[0] * v[2],
+                    u[0] * v[1] - u[1] * v[0],
+                ];
+                (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt() / 2.0
+            })
+            .sum();
+        let circumradius = self.circumsphere().radius;
+        if volume == 0.0 || !circumradius.is_finite() {
+            return 0.0;
+        }
+        let inradius = 3.0 * volume / area;
+        (3.0 * inradius / circumradius).min(1.0)
+    }
+
     pub fn contains_face(&self, face: &Face) -> bool {
         let verts = self.vertices();
         verts.contains(&face.a) && verts.contains(&face.b) && verts.contains(&face.c)
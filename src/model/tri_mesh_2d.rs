@@ -0,0 +1,67 @@
+use crate::model::compact_vertices;
+use crate::model::point_2d::Point2D;
+use crate::model::triangle::Triangle;
+
+/// An indexed 2D triangle mesh.
+///
+/// Stores each vertex once in `vertices` and describes every triangle as three
+/// positions into that buffer, instead of copying full [`Point2D`] values into
+/// each [`Triangle`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TriMesh2D {
+    pub vertices: Vec<Point2D>,
+    pub triangles: Vec<[usize; 3]>,
+}
+
+impl TriMesh2D {
+    /// Builds an indexed mesh from a slice of triangles.
+    ///
+    /// Vertices are deduplicated by [`Point2D::index`] and stored sorted by
+    /// that index, so the vertex order is independent of triangle order.
+    pub fn from_triangles(triangles: &[Triangle]) -> TriMesh2D {
+        let vertices: Vec<Point2D> = triangles.iter().flat_map(|t| t.vertices()).collect();
+        let elements: Vec<[usize; 3]> = (0..triangles.len())
+            .map(|i| std::array::from_fn(|k| 3 * i + k))
+            .collect();
+        let (vertices, triangles) = compact_vertices(&vertices, &elements, |v| v.index);
+        TriMesh2D {
+            vertices,
+            triangles,
+        }
+    }
+
+    /// Expands the mesh back into a vector of [`Triangle`]s.
+    pub fn to_triangles(&self) -> Vec<Triangle> {
+        (0..self.triangles.len())
+            .map(|i| self.triangle(i))
+            .collect()
+    }
+
+    /// Returns the triangle at position `i` as a [`Triangle`].
+    pub fn triangle(&self, i: usize) -> Triangle {
+        let [a, b, c] = self.triangles[i];
+        Triangle {
+            a: self.vertices[a],
+            b: self.vertices[b],
+            c: self.vertices[c],
+        }
+    }
+
+    pub fn num_vertices(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn num_triangles(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+}
+
+impl From<&[Triangle]> for TriMesh2D {
+    fn from(triangles: &[Triangle]) -> Self {
+        TriMesh2D::from_triangles(triangles)
+    }
+}
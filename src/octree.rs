@@ -1,4 +1,6 @@
-use crate::{Point3D, Tetrahedron};
+use std::collections::HashMap;
+
+use crate::{Point3D, TetMesh, Tetrahedron};
 
 #[derive(Clone, Copy)]
 struct Bounds {
@@ -10,13 +12,53 @@ struct Bounds {
     max_z: f64,
 }
 
+/// Corners of a hexahedral cell as offsets along x, y and z.
+const CORNERS: [[u64; 3]; 8] = [
+    [0, 0, 0],
+    [1, 0, 0],
+    [1, 1, 0],
+    [0, 1, 0],
+    [0, 0, 1],
+    [1, 0, 1],
+    [1, 1, 1],
+    [0, 1, 1],
+];
+
+/// Standard 5-tetrahedra decomposition of a hexahedron, as positions into
+/// [`CORNERS`].
+const CELL_TETRAHEDRA: [[usize; 4]; 5] = [
+    [0, 1, 3, 4],
+    [1, 2, 3, 6],
+    [1, 4, 5, 6],
+    [3, 4, 6, 7],
+    [1, 3, 4, 6],
+];
+
+/// [`CELL_TETRAHEDRA`] mirrored in x, with the first two vertices swapped to
+/// keep the orientation. Alternating the two by cell parity makes the face
+/// diagonals of neighbouring cells match.
+const MIRRORED_CELL_TETRAHEDRA: [[usize; 4]; 5] = [
+    [0, 1, 2, 5],
+    [3, 0, 2, 7],
+    [5, 0, 4, 7],
+    [5, 2, 7, 6],
+    [2, 0, 5, 7],
+];
+
+/// A leaf cell whose center is inside the domain, with the integer position
+/// of its minimum corner on the lattice of the finest level.
+struct Leaf {
+    bounds: Bounds,
+    cell: [u64; 3],
+}
+
 fn subdivide(
     b: Bounds,
+    cell: [u64; 3],
     depth: usize,
     max_depth: usize,
     is_inside: &dyn Fn(&Point3D) -> bool,
-    next_index: &mut i64,
-    tetrahedra: &mut Vec<Tetrahedron>,
+    leaves: &mut Vec<Leaf>,
 ) {
     if depth >= max_depth {
         let center = Point3D {
@@ -25,97 +67,11 @@ fn subdivide(
             y: (b.min_y + b.max_y) / 2.0,
             z: (b.min_z + b.max_z) / 2.0,
         };
-        if !is_inside(&center) {
-            return;
+        if is_inside(&center) {
+            leaves.push(Leaf { bounds: b, cell });
         }
-
-        let idx = *next_index;
-        *next_index += 8;
-        let p = [
-            Point3D {
-                index: idx,
-                x: b.min_x,
-                y: b.min_y,
-                z: b.min_z,
-            },
-            Point3D {
-                index: idx + 1,
-                x: b.max_x,
-                y: b.min_y,
-                z: b.min_z,
-            },
-            Point3D {
-                index: idx + 2,
-                x: b.max_x,
-                y: b.max_y,
-                z: b.min_z,
-            },
-            Point3D {
-                index: idx + 3,
-                x: b.min_x,
-                y: b.max_y,
-                z: b.min_z,
-            },
-            Point3D {
-                index: idx + 4,
-                x: b.min_x,
-                y: b.min_y,
-                z: b.max_z,
-            },
-            Point3D {
-                index: idx + 5,
-                x: b.max_x,
-                y: b.min_y,
-                z: b.max_z,
-            },
-            Point3D {
-                index: idx + 6,
-                x: b.max_x,
-                y: b.max_y,
-                z: b.max_z,
-            },
-            Point3D {
-                index: idx + 7,
-                x: b.min_x,
-                y: b.max_y,
-                z: b.max_z,
-            },
-        ];
-
-        // Standard 5-tetrahedra decomposition of a hexahedron
-        tetrahedra.push(Tetrahedron {
-            a: p[0],
-            b: p[1],
-            c: p[3],
-            d: p[4],
-        });
-        tetrahedra.push(Tetrahedron {
-            a: p[1],
-            b: p[2],
-            c: p[3],
-            d: p[6],
-        });
-        tetrahedra.push(Tetrahedron {
-            a: p[1],
-            b: p[4],
-            c: p[5],
-            d: p[6],
-        });
-        tetrahedra.push(Tetrahedron {
-            a: p[3],
-            b: p[4],
-            c: p[6],
-            d: p[7],
-        });
-        tetrahedra.push(Tetrahedron {
-            a: p[1],
-            b: p[3],
-            c: p[4],
-            d: p[6],
-        });
         return;
     }
-
     let mid_x = (b.min_x + b.max_x) / 2.0;
     let mid_y = (b.min_y + b.max_y) / 2.0;
     let mid_z = (b.min_z + b.max_z) / 2.0;
@@ -187,15 +143,10 @@ fn subdivide(
         },
     ];
 
-    for octant in &octants {
-        subdivide(
-            *octant,
-            depth + 1,
-            max_depth,
-            is_inside,
-            next_index,
-            tetrahedra,
-        );
+    for (i, octant) in octants.iter().enumerate() {
+        let offset = [i as u64 & 1, (i as u64 >> 1) & 1, i as u64 >> 2];
+        let child = [0, 1, 2].map(|k| 2 * cell[k] + offset[k]);
+        subdivide(*octant, child, depth + 1, max_depth, is_inside, leaves);
     }
 }
 
@@ -234,7 +185,36 @@ pub fn octree_mesh(
     is_inside: &dyn Fn(&Point3D) -> bool,
 ) -> Vec<Tetrahedron> {
     let mut tetrahedra = Vec::new();
-    let mut next_index: i64 = 0;
+    for (i, leaf) in leaves(min, max, max_depth, is_inside).iter().enumerate() {
+        let bounds = leaf.bounds;
+        // Every leaf gets its own eight vertex indices.
+        let p: [Point3D; 8] = std::array::from_fn(|k| {
+            let [cx, cy, cz] = CORNERS[k];
+            Point3D {
+                index: (8 * i + k) as i64,
+                x: if cx == 0 { bounds.min_x } else { bounds.max_x },
+                y: if cy == 0 { bounds.min_y } else { bounds.max_y },
+                z: if cz == 0 { bounds.min_z } else { bounds.max_z },
+            }
+        });
+        for [a, b, c, d] in CELL_TETRAHEDRA {
+            tetrahedra.push(Tetrahedron {
+                a: p[a],
+                b: p[b],
+                c: p[c],
+                d: p[d],
+            });
+        }
+    }
+    tetrahedra
+}
+
+fn leaves(
+    min: Point3D,
+    max: Point3D,
+    max_depth: usize,
+    is_inside: &dyn Fn(&Point3D) -> bool,
+) -> Vec<Leaf> {
     let bounds = Bounds {
         min_x: min.x,
         min_y: min.y,
@@ -243,17 +223,53 @@ pub fn octree_mesh(
         max_y: max.y,
         max_z: max.z,
     };
-    subdivide(
-        bounds,
-        0,
-        max_depth,
-        is_inside,
-        &mut next_index,
-        &mut tetrahedra,
-    );
-    tetrahedra
+    let mut leaves = Vec::new();
+    subdivide(bounds, [0; 3], 0, max_depth, is_inside, &mut leaves);
+    leaves
 }
 
+/// Generates an octree mesh and returns it as an indexed [`TetMesh`].
+///
+/// Unlike [`octree_mesh`], neighbouring leaves share their corner vertices,
+/// which are identified by their position on the lattice of the finest
+/// level, and the leaves alternate between mirrored 5-tetrahedra
+/// decompositions so that the shared faces match. The result is a conforming
+/// mesh whose [`TetMesh::boundary_faces`] are only the faces on the boundary
+/// of the domain.
+pub fn octree_mesh_indexed(
+    min: Point3D,
+    max: Point3D,
+    max_depth: usize,
+    is_inside: &dyn Fn(&Point3D) -> bool,
+) -> TetMesh {
+    let n = (1u64 << max_depth) as f64;
+    let mut mesh = TetMesh::default();
+    let mut lookup: HashMap<[u64; 3], usize> = HashMap::new();
+    for leaf in leaves(min, max, max_depth, is_inside) {
+        let corners = CORNERS.map(|offset| {
+            let key = [0, 1, 2].map(|k| leaf.cell[k] + offset[k]);
+            *lookup.entry(key).or_insert_with(|| {
+                let position = mesh.vertices.len();
+                mesh.vertices.push(Point3D {
+                    index: position as i64,
+                    x: min.x + (max.x - min.x) * key[0] as f64 / n,
+                    y: min.y + (max.y - min.y) * key[1] as f64 / n,
+                    z: min.z + (max.z - min.z) * key[2] as f64 / n,
+                });
+                position
+            })
+        });
+        let decomposition = if leaf.cell.iter().sum::<u64>() % 2 == 0 {
+            CELL_TETRAHEDRA
+        } else {
+            MIRRORED_CELL_TETRAHEDRA
+        };
+        for tet in decomposition {
+            mesh.tetrahedra.push(tet.map(|c| corners[c]));
+        }
+    }
+    mesh
+}
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -392,4 +408,45 @@ mod tests {
         let result = octree_mesh(min, max, 2, &|_| false);
         assert!(result.is_empty());
     }
+
+    #[test]
+    fn test_octree_mesh_indexed() {
+        let min = Point3D {
+            index: 0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let max = Point3D {
+            index: 0,
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        };
+        let mesh = octree_mesh_indexed(min, max, 1, &|_| true);
+        assert_eq!(mesh.num_tetrahedra(), 40);
+        assert_eq!(mesh.num_vertices(), 27);
+        assert_boundary_is_closed(&mesh, 48);
+        for i in 0..mesh.num_tetrahedra() {
+            assert!(mesh.tetrahedron(i).signed_volume() > 0.0);
+        }
+
+        let mesh = octree_mesh_indexed(min, max, 2, &|_| true);
+        assert_eq!(mesh.num_vertices(), 125);
+        assert_boundary_is_closed(&mesh, 192);
+    }
+
+    /// Asserts that the boundary has `count` triangles and that every edge
+    /// is shared by exactly two of them.
+    fn assert_boundary_is_closed(mesh: &TetMesh, count: usize) {
+        let faces = mesh.boundary_faces();
+        assert_eq!(faces.len(), count);
+        let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+        for [a, b, c] in faces {
+            for (u, v) in [(a, b), (b, c), (c, a)] {
+                *edges.entry((u.min(v), u.max(v))).or_default() += 1;
+            }
+        }
+        assert!(edges.values().all(|&n| n == 2));
+    }
 }
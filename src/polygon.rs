@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+
+use crate::constrained_delaunay::Cdt;
+use crate::error::MeshingError;
+use crate::{Point2D, Triangle};
+
+/// Twice the signed area of a closed polygon; positive for counter-clockwise
+/// vertex order.
+fn signed_area(polygon: &[Point2D]) -> f64 {
+    let n = polygon.len();
+    (0..n)
+        .map(|i| {
+            let p = &polygon[i];
+            let q = &polygon[(i + 1) % n];
+            p.x * q.y - q.x * p.y
+        })
+        .sum()
+}
+
+/// Inserts the closed `polygon` as constrained segments and returns its
+/// directed edges, oriented counter-clockwise, as vertex positions.
+fn insert_loop(
+    cdt: &mut Cdt,
+    polygon: &[Point2D],
+    position: &HashMap<i64, usize>,
+) -> Result<Vec<(usize, usize)>, MeshingError> {
+    let mut ring: Vec<usize> = polygon.iter().map(|p| position[&p.index]).collect();
+    if signed_area(polygon) < 0.0 {
+        ring.reverse();
+    }
+    let n = ring.len();
+    let edges: Vec<(usize, usize)> = (0..n).map(|i| (ring[i], ring[(i + 1) % n])).collect();
+    for &(u, v) in &edges {
+        cdt.insert_constraint(u, v)?;
+    }
+    Ok(edges)
+}
+
+/// Expands `seeds` across every unconstrained edge and flags the reached
+/// triangles as exterior.
+fn flood_fill(cdt: &mut Cdt, seeds: Vec<usize>) {
+    let mut stack = seeds;
+    while let Some(t) = stack.pop() {
+        if cdt.exterior[t] {
+            continue;
+        }
+        cdt.exterior[t] = true;
+        let [a, b, c] = cdt.triangles[t];
+        for (u, v) in [(a, b), (b, c), (c, a)] {
+            if cdt.is_constrained(u, v) {
+                continue;
+            }
+            if let Some(n) = cdt.owner(v, u) {
+                if !cdt.exterior[n] {
+                    stack.push(n);
+                }
+            }
+        }
+    }
+}
+
+/// Builds the constrained triangulation of a planar straight-line graph and
+/// flags the triangles lying outside `outer` or inside any of `holes` as
+/// exterior.
+pub(crate) fn build_domain(
+    outer: &[Point2D],
+    holes: &[Vec<Point2D>],
+    interior_points: &[Point2D],
+) -> Result<Cdt, MeshingError> {
+    if outer.is_empty() {
+        return Err(MeshingError::EmptyInput);
+    }
+    if outer.len() < 3 {
+        return Err(MeshingError::InsufficientPoints(outer.len()));
+    }
+    if let Some(hole) = holes.iter().find(|h| h.len() < 3) {
+        return Err(MeshingError::InvalidPolygon(format!(
+            "hole needs at least 3 vertices, got {}",
+            hole.len()
+        )));
+    }
+
+    let mut points: Vec<Point2D> = outer.to_vec();
+    for hole in holes {
+        points.extend_from_slice(hole);
+    }
+    points.extend_from_slice(interior_points);
+
+    let mut position: HashMap<i64, usize> = HashMap::new();
+    for (pos, p) in points.iter().enumerate() {
+        if position.insert(p.index, pos).is_some() {
+            return Err(MeshingError::InvalidPolygon(format!(
+                "duplicate point index {}",
+                p.index
+            )));
+        }
+    }
+
+    let mut cdt = Cdt::new(points)?;
+    let outer_edges = insert_loop(&mut cdt, outer, &position)?;
+    let mut hole_edges = Vec::new();
+    for hole in holes {
+        hole_edges.push(insert_loop(&mut cdt, hole, &position)?);
+    }
+
+    // Everything reachable from the convex hull without crossing the outer
+    // boundary lies outside the domain, as does the right side of every
+    // counter-clockwise outer edge.
+    let mut seeds: Vec<usize> = outer_edges
+        .iter()
+        .filter_map(|&(u, v)| cdt.owner(v, u))
+        .collect();
+    for (t, tri) in cdt.triangles.iter().enumerate() {
+        let [a, b, c] = *tri;
+        let on_hull = [(a, b), (b, c), (c, a)]
+            .iter()
+            .any(|&(u, v)| cdt.owner(v, u).is_none() && !cdt.is_constrained(u, v));
+        if on_hull {
+            seeds.push(t);
+        }
+    }
+    flood_fill(&mut cdt, seeds);
+
+    // Each counter-clockwise hole edge has the hole interior on its left.
+    let hole_seeds: Vec<usize> = hole_edges
+        .iter()
+        .flatten()
+        .filter_map(|&(u, v)| cdt.owner(u, v))
+        .collect();
+    flood_fill(&mut cdt, hole_seeds);
+
+    Ok(cdt)
+}
+
+/// Triangulates a polygonal domain with holes.
+///
+/// The domain is described as a planar straight-line graph: a closed `outer`
+/// boundary, any number of closed `holes`, and optional `interior_points`
+/// that must appear as mesh vertices. Polygons are given as vertex lists
+/// without repeating the first vertex and may use either orientation.
+///
+/// All polygon edges are inserted as constraints into the
+/// [`bowyer_watson`](crate::bowyer_watson) triangulation, then triangles
+/// outside the outer boundary or inside a hole are removed by flood fill from
+/// the convex hull and from seeds on the inner side of each hole edge.
+///
+/// # Errors
+///
+/// Returns [`MeshingError::EmptyInput`] or [`MeshingError::InsufficientPoints`]
+/// for a degenerate outer polygon, [`MeshingError::InvalidPolygon`] for
+/// degenerate holes or duplicate point indices, and
+/// [`MeshingError::InvalidConstraint`] if polygon edges intersect.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::polygon::triangulate_polygon;
+/// use meshing::Point2D;
+///
+/// let p = |index, x, y| Point2D { index, x, y };
+/// let outer = vec![p(0, 0.0, 0.0), p(1, 3.0, 0.0), p(2, 3.0, 3.0), p(3, 0.0, 3.0)];
+/// let hole = vec![p(4, 1.0, 1.0), p(5, 2.0, 1.0), p(6, 2.0, 2.0), p(7, 1.0, 2.0)];
+/// let triangles = triangulate_polygon(outer, vec![hole], vec![]).unwrap();
+/// assert_eq!(triangles.len(), 8);
+/// ```
+pub fn triangulate_polygon(
+    outer: Vec<Point2D>,
+    holes: Vec<Vec<Point2D>>,
+    interior_points: Vec<Point2D>,
+) -> Result<Vec<Triangle>, MeshingError> {
+    let cdt = build_domain(&outer, &holes, &interior_points)?;
+    Ok((0..cdt.triangles.len())
+        .filter(|&t| !cdt.exterior[t])
+        .map(|t| cdt.triangle(t))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(index: i64, x: f64, y: f64) -> Point2D {
+        Point2D { index, x, y }
+    }
+
+    fn area(triangles: &[Triangle]) -> f64 {
+        triangles
+            .iter()
+            .map(|t| {
+                ((t.b.x - t.a.x) * (t.c.y - t.a.y) - (t.b.y - t.a.y) * (t.c.x - t.a.x)).abs() / 2.0
+            })
+            .sum()
+    }
+
+    fn centroid(t: &Triangle) -> (f64, f64) {
+        ((t.a.x + t.b.x + t.c.x) / 3.0, (t.a.y + t.b.y + t.c.y) / 3.0)
+    }
+
+    #[test]
+    fn test_convex_polygon() {
+        let outer = vec![
+            p(0, 0.0, 0.0),
+            p(1, 1.0, 0.0),
+            p(2, 1.0, 1.0),
+            p(3, 0.0, 1.0),
+        ];
+        let result = triangulate_polygon(outer, vec![], vec![]).unwrap();
+        assert_eq!(result.len(), 2);
+        assert!((area(&result) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_concave_polygon_removes_notch() {
+        // L-shaped domain: the bounding square minus its top-right quadrant.
+        let outer = vec![
+            p(0, 0.0, 0.0),
+            p(1, 2.0, 0.0),
+            p(2, 2.0, 1.0),
+            p(3, 1.0, 1.0),
+            p(4, 1.0, 2.0),
+            p(5, 0.0, 2.0),
+        ];
+        let result = triangulate_polygon(outer, vec![], vec![]).unwrap();
+        assert!((area(&result) - 3.0).abs() < 1e-12);
+        for t in &result {
+            let (cx, cy) = centroid(t);
+            assert!(!(cx > 1.0 && cy > 1.0));
+        }
+    }
+
+    #[test]
+    fn test_square_with_hole() {
+        let outer = vec![
+            p(0, 0.0, 0.0),
+            p(1, 3.0, 0.0),
+            p(2, 3.0, 3.0),
+            p(3, 0.0, 3.0),
+        ];
+        // Clockwise hole to exercise orientation normalisation.
+        let hole = vec![
+            p(4, 1.0, 1.0),
+            p(5, 1.0, 2.0),
+            p(6, 2.0, 2.0),
+            p(7, 2.0, 1.0),
+        ];
+        let result = triangulate_polygon(outer, vec![hole], vec![]).unwrap();
+        assert!((area(&result) - 8.0).abs() < 1e-12);
+        for t in &result {
+            let (cx, cy) = centroid(t);
+            assert!(!(cx > 1.0 && cx < 2.0 && cy > 1.0 && cy < 2.0));
+        }
+    }
+
+    #[test]
+    fn test_interior_points_are_used() {
+        let outer = vec![
+            p(0, 0.0, 0.0),
+            p(1, 2.0, 0.0),
+            p(2, 2.0, 2.0),
+            p(3, 0.0, 2.0),
+        ];
+        let interior = vec![p(4, 1.0, 1.0)];
+        let result = triangulate_polygon(outer, vec![], interior).unwrap();
+        assert_eq!(result.len(), 4);
+        assert!(result
+            .iter()
+            .all(|t| t.vertices().iter().any(|v| v.index == 4)));
+    }
+
+    #[test]
+    fn test_two_holes() {
+        let outer = vec![
+            p(0, 0.0, 0.0),
+            p(1, 5.0, 0.0),
+            p(2, 5.0, 2.0),
+            p(3, 0.0, 2.0),
+        ];
+        let hole1 = vec![
+            p(4, 1.0, 0.5),
+            p(5, 2.0, 0.5),
+            p(6, 2.0, 1.5),
+            p(7, 1.0, 1.5),
+        ];
+        let hole2 = vec![
+            p(8, 3.0, 0.5),
+            p(9, 4.0, 0.5),
+            p(10, 4.0, 1.5),
+            p(11, 3.0, 1.5),
+        ];
+        let result = triangulate_polygon(outer, vec![hole1, hole2], vec![]).unwrap();
+        assert!((area(&result) - 8.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_degenerate_hole_rejected() {
+        let outer = vec![p(0, 0.0, 0.0), p(1, 1.0, 0.0), p(2, 1.0, 1.0)];
+        let hole = vec![p(3, 0.5, 0.2), p(4, 0.6, 0.3)];
+        let result = triangulate_polygon(outer, vec![hole], vec![]);
+        assert!(matches!(result, Err(MeshingError::InvalidPolygon(_))));
+    }
+
+    #[test]
+    fn test_duplicate_indices_rejected() {
+        let outer = vec![p(0, 0.0, 0.0), p(1, 1.0, 0.0), p(1, 1.0, 1.0)];
+        let result = triangulate_polygon(outer, vec![], vec![]);
+        assert!(matches!(result, Err(MeshingError::InvalidPolygon(_))));
+    }
+}
@@ -0,0 +1,464 @@
+//! Robust geometric predicates.
+//!
+//! Orientation and in-circle/in-sphere tests in the style of Shewchuk's
+//! adaptive predicates: each test is first evaluated in plain `f64` together
+//! with a forward error bound, and only when the result is too close to zero
+//! to be trusted is it re-evaluated exactly with floating-point expansion
+//! arithmetic. The sign of the returned value is therefore always correct,
+//! including for cocircular, cospherical and collinear inputs; its magnitude
+//! is only an approximation of the determinant.
+//!
+//! # Examples
+//!
+//! ```
+//! use meshing::predicates::{incircle, orient2d};
+//! use meshing::Point2D;
+//!
+//! let p = |x, y| Point2D { index: 0, x, y };
+//! assert!(orient2d(&p(0.0, 0.0), &p(1.0, 0.0), &p(0.0, 1.0)) > 0.0);
+//! // Four cocircular points.
+//! assert_eq!(incircle(&p(1.0, 0.0), &p(0.0, 1.0), &p(-1.0, 0.0), &p(0.0, -1.0)), 0.0);
+//! ```
+
+use crate::{Point2D, Point3D};
+
+/// Half an ulp of 1.0, the unit roundoff of `f64`.
+const EPSILON: f64 = f64::EPSILON / 2.0;
+const ORIENT2D_BOUND: f64 = (3.0 + 16.0 * EPSILON) * EPSILON;
+const ORIENT3D_BOUND: f64 = (7.0 + 56.0 * EPSILON) * EPSILON;
+const INCIRCLE_BOUND: f64 = (10.0 + 96.0 * EPSILON) * EPSILON;
+const INSPHERE_BOUND: f64 = (16.0 + 224.0 * EPSILON) * EPSILON;
+
+/// Returns a positive value if `a`, `b`, `c` are in counter-clockwise order,
+/// a negative value if clockwise and zero if they are collinear.
+///
+/// The value approximates twice the signed area of the triangle.
+pub fn orient2d(a: &Point2D, b: &Point2D, c: &Point2D) -> f64 {
+    let left = (a.x - c.x) * (b.y - c.y);
+    let right = (a.y - c.y) * (b.x - c.x);
+    let det = left - right;
+    let bound = ORIENT2D_BOUND * (left.abs() + right.abs());
+    if det > bound || -det > bound {
+        return det;
+    }
+    exact::orient2d(a, b, c)
+}
+
+/// Returns a positive value if `d` lies strictly inside the circle through
+/// `a`, `b`, `c`, a negative value if outside and zero if the four points
+/// are cocircular. The points `a`, `b`, `c` must be in counter-clockwise
+/// order; otherwise the sign is reversed.
+pub fn incircle(a: &Point2D, b: &Point2D, c: &Point2D, d: &Point2D) -> f64 {
+    let (adx, ady) = (a.x - d.x, a.y - d.y);
+    let (bdx, bdy) = (b.x - d.x, b.y - d.y);
+    let (cdx, cdy) = (c.x - d.x, c.y - d.y);
+    let alift = adx * adx + ady * ady;
+    let blift = bdx * bdx + bdy * bdy;
+    let clift = cdx * cdx + cdy * cdy;
+
+    let det = alift * (bdx * cdy - cdx * bdy)
+        + blift * (cdx * ady - adx * cdy)
+        + clift * (adx * bdy - bdx * ady);
+    let permanent = ((bdx * cdy).abs() + (cdx * bdy).abs()) * alift
+        + ((cdx * ady).abs() + (adx * cdy).abs()) * blift
+        + ((adx * bdy).abs() + (bdx * ady).abs()) * clift;
+    let bound = INCIRCLE_BOUND * permanent;
+    if det > bound || -det > bound {
+        return det;
+    }
+    exact::incircle(a, b, c, d)
+}
+
+/// Returns a positive value if the tetrahedron `(a, b, c, d)` is positively
+/// oriented, i.e. `d` lies above the plane through `a`, `b`, `c` when those
+/// appear counter-clockwise from above, a negative value if below and zero
+/// if the four points are coplanar.
+///
+/// This follows the sign convention of [`Tetrahedron::signed_volume`](crate::Tetrahedron::signed_volume),
+/// which is the opposite of Shewchuk's `orient3d`. The value approximates six
+/// times the signed volume.
+pub fn orient3d(a: &Point3D, b: &Point3D, c: &Point3D, d: &Point3D) -> f64 {
+    let (ux, uy, uz) = (b.x - a.x, b.y - a.y, b.z - a.z);
+    let (vx, vy, vz) = (c.x - a.x, c.y - a.y, c.z - a.z);
+    let (wx, wy, wz) = (d.x - a.x, d.y - a.y, d.z - a.z);
+
+    let det = ux * (vy * wz - vz * wy) - uy * (vx * wz - vz * wx) + uz * (vx * wy - vy * wx);
+    let permanent = ux.abs() * ((vy * wz).abs() + (vz * wy).abs())
+        + uy.abs() * ((vx * wz).abs() + (vz * wx).abs())
+        + uz.abs() * ((vx * wy).abs() + (vy * wx).abs());
+    let bound = ORIENT3D_BOUND * permanent;
+    if det > bound || -det > bound {
+        return det;
+    }
+    exact::orient3d(a, b, c, d)
+}
+
+/// Returns a positive value if `e` lies strictly inside the sphere through
+/// `a`, `b`, `c`, `d`, a negative value if outside and zero if the five
+/// points are cospherical. The tetrahedron `(a, b, c, d)` must be positively
+/// oriented according to [`orient3d`]; otherwise the sign is reversed.
+pub fn insphere(a: &Point3D, b: &Point3D, c: &Point3D, d: &Point3D, e: &Point3D) -> f64 {
+    let (aex, aey, aez) = (a.x - e.x, a.y - e.y, a.z - e.z);
+    let (bex, bey, bez) = (b.x - e.x, b.y - e.y, b.z - e.z);
+    let (cex, cey, cez) = (c.x - e.x, c.y - e.y, c.z - e.z);
+    let (dex, dey, dez) = (d.x - e.x, d.y - e.y, d.z - e.z);
+
+    let ab = aex * bey - bex * aey;
+    let bc = bex * cey - cex * bey;
+    let cd = cex * dey - dex * cey;
+    let da = dex * aey - aex * dey;
+    let ac = aex * cey - cex * aey;
+    let bd = bex * dey - dex * bey;
+
+    let abc = aez * bc - bez * ac + cez * ab;
+    let bcd = bez * cd - cez * bd + dez * bc;
+    let cda = cez * da + dez * ac + aez * cd;
+    let dab = dez * ab + aez * bd + bez * da;
+
+    let alift = aex * aex + aey * aey + aez * aez;
+    let blift = bex * bex + bey * bey + bez * bez;
+    let clift = cex * cex + cey * cey + cez * cez;
+    let dlift = dex * dex + dey * dey + dez * dez;
+
+    // Shewchuk's determinant is positive for points inside the sphere of a
+    // tetrahedron with negative orientation in our convention.
+    let det = -((dlift * abc - clift * dab) + (blift * cda - alift * bcd));
+
+    let (aezp, bezp, cezp, dezp) = (aez.abs(), bez.abs(), cez.abs(), dez.abs());
+    let aexbey = (aex * bey).abs();
+    let bexaey = (bex * aey).abs();
+    let bexcey = (bex * cey).abs();
+    let cexbey = (cex * bey).abs();
+    let cexdey = (cex * dey).abs();
+    let dexcey = (dex * cey).abs();
+    let dexaey = (dex * aey).abs();
+    let aexdey = (aex * dey).abs();
+    let aexcey = (aex * cey).abs();
+    let cexaey = (cex * aey).abs();
+    let bexdey = (bex * dey).abs();
+    let dexbey = (dex * bey).abs();
+    let permanent = ((cexdey + dexcey) * bezp
+        + (dexbey + bexdey) * cezp
+        + (bexcey + cexbey) * dezp)
+        * alift
+        + ((dexaey + aexdey) * cezp + (aexcey + cexaey) * dezp + (cexdey + dexcey) * aezp) * blift
+        + ((aexbey + bexaey) * dezp + (bexdey + dexbey) * aezp + (dexaey + aexdey) * bezp) * clift
+        + ((bexcey + cexbey) * aezp + (cexaey + aexcey) * bezp + (aexbey + bexaey) * cezp) * dlift;
+    let bound = INSPHERE_BOUND * permanent;
+    if det > bound || -det > bound {
+        return det;
+    }
+    exact::insphere(a, b, c, d, e)
+}
+
+/// Exact evaluation with floating-point expansions: a value is represented
+/// as a sequence of non-overlapping `f64` components in increasing order of
+/// magnitude whose sum is exact, so the last component carries the sign.
+mod exact {
+    use crate::{Point2D, Point3D};
+
+    type Expansion = Vec<f64>;
+
+    fn two_sum(a: f64, b: f64) -> (f64, f64) {
+        let x = a + b;
+        let bv = x - a;
+        let av = x - bv;
+        (x, (a - av) + (b - bv))
+    }
+
+    fn two_product(a: f64, b: f64) -> (f64, f64) {
+        let x = a * b;
+        (x, a.mul_add(b, -x))
+    }
+
+    /// The exact difference `a - b`.
+    fn diff(a: f64, b: f64) -> Expansion {
+        let (x, y) = two_sum(a, -b);
+        [y, x].into_iter().filter(|&c| c != 0.0).collect()
+    }
+
+    /// Sums two expansions, eliminating zero components.
+    fn sum(e: &[f64], f: &[f64]) -> Expansion {
+        let mut merged = Vec::with_capacity(e.len() + f.len());
+        let (mut i, mut j) = (0, 0);
+        while i < e.len() && j < f.len() {
+            if e[i].abs() <= f[j].abs() {
+                merged.push(e[i]);
+                i += 1;
+            } else {
+                merged.push(f[j]);
+                j += 1;
+            }
+        }
+        merged.extend_from_slice(&e[i..]);
+        merged.extend_from_slice(&f[j..]);
+
+        let mut components = merged.into_iter();
+        let mut q = match components.next() {
+            Some(q) => q,
+            None => return Vec::new(),
+        };
+        let mut h = Vec::new();
+        for c in components {
+            let (x, y) = two_sum(q, c);
+            if y != 0.0 {
+                h.push(y);
+            }
+            q = x;
+        }
+        if q != 0.0 || h.is_empty() {
+            h.push(q);
+        }
+        h
+    }
+
+    /// Multiplies an expansion by a single `f64`, eliminating zero components.
+    fn scale(e: &[f64], b: f64) -> Expansion {
+        let mut h = Vec::with_capacity(2 * e.len());
+        let Some((&first, rest)) = e.split_first() else {
+            return h;
+        };
+        let (mut q, low) = two_product(first, b);
+        if low != 0.0 {
+            h.push(low);
+        }
+        for &c in rest {
+            let (product, low) = two_product(c, b);
+            let (x, y) = two_sum(q, low);
+            if y != 0.0 {
+                h.push(y);
+            }
+            let (x, y) = two_sum(product, x);
+            if y != 0.0 {
+                h.push(y);
+            }
+            q = x;
+        }
+        if q != 0.0 || h.is_empty() {
+            h.push(q);
+        }
+        h
+    }
+
+    fn product(e: &[f64], f: &[f64]) -> Expansion {
+        f.iter().fold(Vec::new(), |acc, &c| sum(&acc, &scale(e, c)))
+    }
+
+    fn neg(e: &[f64]) -> Expansion {
+        e.iter().map(|c| -c).collect()
+    }
+
+    fn sub(e: &[f64], f: &[f64]) -> Expansion {
+        sum(e, &neg(f))
+    }
+
+    /// `a * d - b * c`.
+    fn det2(a: &[f64], b: &[f64], c: &[f64], d: &[f64]) -> Expansion {
+        sub(&product(a, d), &product(b, c))
+    }
+
+    fn sign(e: &[f64]) -> f64 {
+        e.last().copied().unwrap_or(0.0)
+    }
+
+    pub fn orient2d(a: &Point2D, b: &Point2D, c: &Point2D) -> f64 {
+        let (acx, acy) = (diff(a.x, c.x), diff(a.y, c.y));
+        let (bcx, bcy) = (diff(b.x, c.x), diff(b.y, c.y));
+        sign(&det2(&acx, &acy, &bcx, &bcy))
+    }
+
+    pub fn incircle(a: &Point2D, b: &Point2D, c: &Point2D, d: &Point2D) -> f64 {
+        let (adx, ady) = (diff(a.x, d.x), diff(a.y, d.y));
+        let (bdx, bdy) = (diff(b.x, d.x), diff(b.y, d.y));
+        let (cdx, cdy) = (diff(c.x, d.x), diff(c.y, d.y));
+        let lift = |x: &[f64], y: &[f64]| sum(&product(x, x), &product(y, y));
+
+        let a_term = product(&lift(&adx, &ady), &det2(&bdx, &bdy, &cdx, &cdy));
+        let b_term = product(&lift(&bdx, &bdy), &det2(&cdx, &cdy, &adx, &ady));
+        let c_term = product(&lift(&cdx, &cdy), &det2(&adx, &ady, &bdx, &bdy));
+        sign(&sum(&sum(&a_term, &b_term), &c_term))
+    }
+
+    pub fn orient3d(a: &Point3D, b: &Point3D, c: &Point3D, d: &Point3D) -> f64 {
+        let (ux, uy, uz) = (diff(b.x, a.x), diff(b.y, a.y), diff(b.z, a.z));
+        let (vx, vy, vz) = (diff(c.x, a.x), diff(c.y, a.y), diff(c.z, a.z));
+        let (wx, wy, wz) = (diff(d.x, a.x), diff(d.y, a.y), diff(d.z, a.z));
+
+        let x_term = product(&ux, &det2(&vy, &vz, &wy, &wz));
+        let y_term = product(&uy, &det2(&vx, &vz, &wx, &wz));
+        let z_term = product(&uz, &det2(&vx, &vy, &wx, &wy));
+        sign(&sum(&sub(&x_term, &y_term), &z_term))
+    }
+
+    pub fn insphere(a: &Point3D, b: &Point3D, c: &Point3D, d: &Point3D, e: &Point3D) -> f64 {
+        let rel = |p: &Point3D| [diff(p.x, e.x), diff(p.y, e.y), diff(p.z, e.z)];
+        let [ae, be, ce, de] = [rel(a), rel(b), rel(c), rel(d)];
+        let minor = |p: &[Expansion; 3], q: &[Expansion; 3]| det2(&p[0], &p[1], &q[0], &q[1]);
+
+        let ab = minor(&ae, &be);
+        let bc = minor(&be, &ce);
+        let cd = minor(&ce, &de);
+        let da = minor(&de, &ae);
+        let ac = minor(&ae, &ce);
+        let bd = minor(&be, &de);
+
+        let abc = sum(
+            &sub(&product(&ae[2], &bc), &product(&be[2], &ac)),
+            &product(&ce[2], &ab),
+        );
+        let bcd = sum(
+            &sub(&product(&be[2], &cd), &product(&ce[2], &bd)),
+            &product(&de[2], &bc),
+        );
+        let cda = sum(
+            &sum(&product(&ce[2], &da), &product(&de[2], &ac)),
+            &product(&ae[2], &cd),
+        );
+        let dab = sum(
+            &sum(&product(&de[2], &ab), &product(&ae[2], &bd)),
+            &product(&be[2], &da),
+        );
+
+        let lift = |p: &[Expansion; 3]| {
+            sum(
+                &sum(&product(&p[0], &p[0]), &product(&p[1], &p[1])),
+                &product(&p[2], &p[2]),
+            )
+        };
+        let left = sub(&product(&lift(&de), &abc), &product(&lift(&ce), &dab));
+        let right = sub(&product(&lift(&be), &cda), &product(&lift(&ae), &bcd));
+        -sign(&sum(&left, &right))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p2(x: f64, y: f64) -> Point2D {
+        Point2D { index: 0, x, y }
+    }
+
+    fn p3(x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index: 0, x, y, z }
+    }
+
+    fn sign(value: f64) -> i32 {
+        value.partial_cmp(&0.0).unwrap() as i32
+    }
+
+    #[test]
+    fn test_orient2d_near_collinear_grid() {
+        // Points (0.5 + i*u, 0.5 + j*u) against the line through (12, 12) and
+        // (24, 24): the exact determinant is 12 u (j - i), which plain f64
+        // evaluation gets wrong for many (i, j).
+        let u = f64::EPSILON / 2.0;
+        let (q, r) = (p2(12.0, 12.0), p2(24.0, 24.0));
+        for i in 0..64i32 {
+            for j in 0..64i32 {
+                let p = p2(0.5 + i as f64 * u, 0.5 + j as f64 * u);
+                let expected = (j - i).signum();
+                assert_eq!(sign(orient2d(&p, &q, &r)), expected, "i={i} j={j}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_incircle_cocircular_and_perturbed() {
+        let (a, b, c) = (p2(1.0, 0.0), p2(0.0, 1.0), p2(-1.0, 0.0));
+        assert_eq!(incircle(&a, &b, &c, &p2(0.0, -1.0)), 0.0);
+        let tiny = f64::EPSILON / 2.0;
+        assert!(incircle(&a, &b, &c, &p2(0.0, -1.0 + tiny)) > 0.0);
+        assert!(incircle(&a, &b, &c, &p2(0.0, -1.0 - 2.0 * tiny)) < 0.0);
+        // Cocircular lattice points on a large offset grid.
+        let o = 1e8;
+        let (a, b, c, d) = (
+            p2(o, o),
+            p2(o + 3.0, o),
+            p2(o + 3.0, o + 3.0),
+            p2(o, o + 3.0),
+        );
+        assert_eq!(incircle(&a, &b, &c, &d), 0.0);
+    }
+
+    #[test]
+    fn test_orient3d_coplanar_and_sign() {
+        let (a, b, c) = (p3(0.0, 0.0, 0.0), p3(1.0, 0.0, 0.0), p3(0.0, 1.0, 0.0));
+        assert!(orient3d(&a, &b, &c, &p3(0.0, 0.0, 1.0)) > 0.0);
+        assert!(orient3d(&a, &b, &c, &p3(0.0, 0.0, -1.0)) < 0.0);
+        // Points near a tilted plane through large coordinates: the sign
+        // must be consistent under permutations of the first three points.
+        let o = 1e6;
+        let (a, b, c) = (
+            p3(o, o, o),
+            p3(o + 0.1, o + 0.3, o + 0.7),
+            p3(o + 0.2, o + 0.5, o + 0.9),
+        );
+        for k in -20..20 {
+            let t = k as f64 * 0.37;
+            let d = p3(
+                a.x + t * (b.x - a.x) - 2.0 * (c.x - a.x),
+                a.y + t * (b.y - a.y) - 2.0 * (c.y - a.y),
+                a.z + t * (b.z - a.z) - 2.0 * (c.z - a.z),
+            );
+            let s = sign(orient3d(&a, &b, &c, &d));
+            assert_eq!(sign(orient3d(&b, &c, &a, &d)), s);
+            assert_eq!(sign(orient3d(&c, &a, &b, &d)), s);
+            assert_eq!(sign(orient3d(&b, &a, &c, &d)), -s);
+        }
+        assert_eq!(orient3d(&a, &a, &c, &b), 0.0);
+    }
+
+    #[test]
+    fn test_insphere_cube_corners() {
+        let (a, b, c, d) = (
+            p3(0.0, 0.0, 0.0),
+            p3(1.0, 0.0, 0.0),
+            p3(0.0, 1.0, 0.0),
+            p3(0.0, 0.0, 1.0),
+        );
+        assert!(orient3d(&a, &b, &c, &d) > 0.0);
+        // All cube corners lie on the circumsphere of this corner tetrahedron.
+        for corner in [
+            p3(1.0, 1.0, 0.0),
+            p3(1.0, 0.0, 1.0),
+            p3(0.0, 1.0, 1.0),
+            p3(1.0, 1.0, 1.0),
+        ] {
+            assert_eq!(insphere(&a, &b, &c, &d, &corner), 0.0);
+        }
+        assert!(insphere(&a, &b, &c, &d, &p3(0.5, 0.5, 0.5)) > 0.0);
+        assert!(insphere(&a, &b, &c, &d, &p3(2.0, 2.0, 2.0)) < 0.0);
+        let tiny = f64::EPSILON;
+        assert!(insphere(&a, &b, &c, &d, &p3(1.0 - tiny, 1.0, 1.0)) > 0.0);
+        assert!(insphere(&a, &b, &c, &d, &p3(1.0 + 2.0 * tiny, 1.0, 1.0)) < 0.0);
+    }
+
+    #[test]
+    fn test_exact_agrees_with_filter_on_generic_input() {
+        let (a, b, c, d, e) = (
+            p3(0.1, 0.2, 0.3),
+            p3(1.7, 0.4, -0.2),
+            p3(0.3, 1.9, 0.5),
+            p3(0.2, 0.1, 1.3),
+            p3(0.6, 0.7, 0.4),
+        );
+        assert_eq!(
+            sign(orient3d(&a, &b, &c, &d)),
+            sign(exact::orient3d(&a, &b, &c, &d))
+        );
+        assert_eq!(
+            sign(insphere(&a, &b, &c, &d, &e)),
+            sign(exact::insphere(&a, &b, &c, &d, &e))
+        );
+        let (a2, b2, c2, d2) = (p2(0.1, 0.2), p2(1.7, 0.4), p2(0.3, 1.9), p2(0.6, 0.7));
+        assert_eq!(
+            sign(orient2d(&a2, &b2, &c2)),
+            sign(exact::orient2d(&a2, &b2, &c2))
+        );
+        assert_eq!(
+            sign(incircle(&a2, &b2, &c2, &d2)),
+            sign(exact::incircle(&a2, &b2, &c2, &d2))
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{Point2D, Point3D};
+
+/// Number of bits per axis used to quantise coordinates onto the Hilbert grid.
+const HILBERT_BITS: u32 = 16;
+
+/// Returns the position of `(x, y)` along a 2D Hilbert curve on a
+/// `2^HILBERT_BITS` grid.
+fn hilbert_index_2d(mut x: u32, mut y: u32) -> u64 {
+    let n = 1u32 << HILBERT_BITS;
+    let mut d = 0u64;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = (x & s) > 0;
+        let ry = (y & s) > 0;
+        d += (s as u64) * (s as u64) * ((3 * rx as u64) ^ ry as u64);
+        if !ry {
+            if rx {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    d
+}
+
+/// Returns the position of `coords` along a 3D Hilbert curve on a
+/// `2^HILBERT_BITS` grid (Skilling's transpose algorithm).
+fn hilbert_index_3d(coords: [u32; 3]) -> u64 {
+    let mut x = coords;
+    let m = 1u32 << (HILBERT_BITS - 1);
+
+    // Inverse undo of the excess work.
+    let mut q = m;
+    while q > 1 {
+        let p = q - 1;
+        for i in 0..3 {
+            if x[i] & q != 0 {
+                x[0] ^= p;
+            } else {
+                let t = (x[0] ^ x[i]) & p;
+                x[0] ^= t;
+                x[i] ^= t;
+            }
+        }
+        q >>= 1;
+    }
+
+    // Gray encode.
+    for i in 1..3 {
+        x[i] ^= x[i - 1];
+    }
+    let mut t = 0;
+    q = m;
+    while q > 1 {
+        if x[2] & q != 0 {
+            t ^= q - 1;
+        }
+        q >>= 1;
+    }
+    for v in &mut x {
+        *v ^= t;
+    }
+
+    // Interleave the transposed bits into a single key.
+    let mut key = 0u64;
+    for bit in (0..HILBERT_BITS).rev() {
+        for v in &x {
+            key = (key << 1) | ((v >> bit) & 1) as u64;
+        }
+    }
+    key
+}
+
+/// Maps `value` in `[min, min + extent]` onto the Hilbert grid.
+fn quantise(value: f64, min: f64, extent: f64) -> u32 {
+    let max = ((1u32 << HILBERT_BITS) - 1) as f64;
+    if extent <= 0.0 {
+        return 0;
+    }
+    ((value - min) / extent * max).clamp(0.0, max) as u32
+}
+
+/// Assigns every item to a BRIO round: roughly half of the items go to the
+/// last round, a quarter to the one before, and so on. The assignment is
+/// pseudo-random but deterministic so triangulations are reproducible.
+fn brio_rounds(len: usize) -> Vec<u32> {
+    let last = usize::BITS - len.leading_zeros();
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    (0..len)
+        .map(|_| {
+            // xorshift64*
+            state ^= state >> 12;
+            state ^= state << 25;
+            state ^= state >> 27;
+            let r = state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+            last.saturating_sub(r.trailing_zeros().min(last))
+        })
+        .collect()
+}
+
+/// Sorts `keys` by BRIO round first and Hilbert index second, returning the
+/// resulting insertion order.
+fn brio_order(keys: Vec<u64>) -> Vec<usize> {
+    let rounds = brio_rounds(keys.len());
+    let mut order: Vec<usize> = (0..keys.len()).collect();
+    order.sort_by_key(|&i| (rounds[i], keys[i]));
+    order
+}
+
+/// Returns an insertion order for `points` following a Biased Randomized
+/// Insertion Order (BRIO) whose rounds are each sorted along a Hilbert curve.
+///
+/// Consecutive points are close in space, which keeps point-location walks
+/// short, while the random rounds avoid the worst cases of a purely
+/// space-filling order.
+pub fn brio_order_2d(points: &[Point2D]) -> Vec<usize> {
+    let (mut min_x, mut min_y) = (f64::MAX, f64::MAX);
+    let (mut max_x, mut max_y) = (f64::MIN, f64::MIN);
+    for p in points {
+        min_x = min_x.min(p.x);
+        min_y = min_y.min(p.y);
+        max_x = max_x.max(p.x);
+        max_y = max_y.max(p.y);
+    }
+    let extent = (max_x - min_x).max(max_y - min_y);
+    let keys = points
+        .iter()
+        .map(|p| hilbert_index_2d(quantise(p.x, min_x, extent), quantise(p.y, min_y, extent)))
+        .collect();
+    brio_order(keys)
+}
+
+/// Returns a BRIO/Hilbert insertion order for `points`, as [`brio_order_2d`].
+pub fn brio_order_3d(points: &[Point3D]) -> Vec<usize> {
+    let mut min = [f64::MAX; 3];
+    let mut max = [f64::MIN; 3];
+    for p in points {
+        for (axis, value) in [p.x, p.y, p.z].into_iter().enumerate() {
+            min[axis] = min[axis].min(value);
+            max[axis] = max[axis].max(value);
+        }
+    }
+    let extent = (0..3).map(|i| max[i] - min[i]).fold(0.0, f64::max);
+    let keys = points
+        .iter()
+        .map(|p| {
+            hilbert_index_3d([
+                quantise(p.x, min[0], extent),
+                quantise(p.y, min[1], extent),
+                quantise(p.z, min[2], extent),
+            ])
+        })
+        .collect();
+    brio_order(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_permutation(order: &[usize], len: usize) -> bool {
+        let mut sorted = order.to_vec();
+        sorted.sort_unstable();
+        sorted == (0..len).collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn test_hilbert_2d_visits_neighbouring_cells() {
+        // On the full grid, consecutive Hilbert indices are adjacent cells;
+        // check the first few cells of the curve.
+        let mut cells: Vec<(u32, u32)> = (0..4).flat_map(|x| (0..4).map(move |y| (x, y))).collect();
+        cells.sort_by_key(|&(x, y)| hilbert_index_2d(x, y));
+        for pair in cells.windows(2) {
+            let d = pair[0].0.abs_diff(pair[1].0) + pair[0].1.abs_diff(pair[1].1);
+            assert_eq!(d, 1, "{:?}", pair);
+        }
+    }
+
+    #[test]
+    fn test_hilbert_3d_visits_neighbouring_cells() {
+        let mut cells: Vec<[u32; 3]> = (0..8)
+            .flat_map(|x| (0..8).flat_map(move |y| (0..8).map(move |z| [x, y, z])))
+            .collect();
+        cells.sort_by_key(|&c| hilbert_index_3d(c));
+        for pair in cells.windows(2) {
+            let d: u32 = (0..3).map(|i| pair[0][i].abs_diff(pair[1][i])).sum();
+            assert_eq!(d, 1, "{:?}", pair);
+        }
+    }
+
+    #[test]
+    fn test_brio_order_is_permutation() {
+        let points: Vec<Point2D> = (0..1000)
+            .map(|i| Point2D {
+                index: i,
+                x: (i as f64 * 0.37).sin(),
+                y: (i as f64 * 0.91).cos(),
+            })
+            .collect();
+        assert!(is_permutation(&brio_order_2d(&points), points.len()));
+
+        let points: Vec<Point3D> = (0..1000)
+            .map(|i| Point3D {
+                index: i,
+                x: (i as f64 * 0.37).sin(),
+                y: (i as f64 * 0.91).cos(),
+                z: (i as f64 * 0.13).sin(),
+            })
+            .collect();
+        assert!(is_permutation(&brio_order_3d(&points), points.len()));
+    }
+
+    #[test]
+    fn test_brio_order_degenerate_inputs() {
+        assert!(brio_order_2d(&[]).is_empty());
+        let same = vec![
+            Point2D {
+                index: 0,
+                x: 1.0,
+                y: 1.0
+            };
+            5
+        ];
+        assert!(is_permutation(&brio_order_2d(&same), 5));
+    }
+}
@@ -0,0 +1,347 @@
+use crate::geometry::create_super_triangle;
+use crate::model::compact_vertices;
+use crate::predicates::{incircle, orient2d};
+use crate::spatial_sort::brio_order_2d;
+use crate::triangle_utils::remove_triangles_with_vertices_from_super_triangle;
+use crate::{Point2D, TriMesh2D, Triangle};
+
+/// Marker for a missing neighbour (an edge of the super-triangle).
+const NONE: usize = usize::MAX;
+
+/// Incremental 2D Delaunay triangulation with triangle adjacency.
+///
+/// Triangles are stored counter-clockwise; `neighbours[t][i]` is the triangle
+/// across the edge opposite `triangles[t][i]`. Points are located by walking
+/// from the most recently created triangle and the cavity of a new point is
+/// grown by neighbour traversal, so each insertion only touches the
+/// triangles around it.
+pub struct Triangulation2D {
+    vertices: Vec<Point2D>,
+    triangles: Vec<[usize; 3]>,
+    neighbours: Vec<[usize; 3]>,
+    alive: Vec<bool>,
+    in_cavity: Vec<bool>,
+    visited: Vec<usize>,
+    epoch: usize,
+    free: Vec<usize>,
+    last: usize,
+    super_triangle: Triangle,
+}
+
+impl Triangulation2D {
+    /// Creates a triangulation holding only a super-triangle enclosing `points`.
+    pub fn new(points: &[Point2D]) -> Self {
+        let super_triangle = create_super_triangle(points);
+        debug_assert!(orient2d(&super_triangle.a, &super_triangle.b, &super_triangle.c) > 0.0);
+        Triangulation2D {
+            vertices: super_triangle.vertices().to_vec(),
+            triangles: vec![[0, 1, 2]],
+            neighbours: vec![[NONE; 3]],
+            alive: vec![true],
+            in_cavity: vec![false],
+            visited: vec![0],
+            epoch: 0,
+            free: Vec::new(),
+            last: 0,
+            super_triangle,
+        }
+    }
+
+    /// Triangulates `points`, inserting them in BRIO/Hilbert order.
+    pub fn from_points(points: &[Point2D]) -> Self {
+        let mut triangulation = Triangulation2D::new(points);
+        for i in brio_order_2d(points) {
+            triangulation.insert(points[i]);
+        }
+        triangulation
+    }
+
+    fn point(&self, v: usize) -> &Point2D {
+        &self.vertices[v]
+    }
+
+    fn contains(&self, t: usize, p: &Point2D) -> bool {
+        let [a, b, c] = self.triangles[t];
+        orient2d(self.point(a), self.point(b), p) >= 0.0
+            && orient2d(self.point(b), self.point(c), p) >= 0.0
+            && orient2d(self.point(c), self.point(a), p) >= 0.0
+    }
+
+    /// Finds a triangle containing `p` by a visibility walk from the last
+    /// created triangle. With exact predicates the walk always terminates on
+    /// a Delaunay triangulation; the linear scan is only a safeguard.
+    fn locate(&self, p: &Point2D) -> usize {
+        let mut t = self.last;
+        let mut offset = 0;
+        'walk: for _ in 0..self.triangles.len() {
+            let tri = self.triangles[t];
+            offset = (offset + 1) % 3;
+            for k in 0..3 {
+                let i = (k + offset) % 3;
+                let n = self.neighbours[t][i];
+                let (a, b) = (tri[(i + 1) % 3], tri[(i + 2) % 3]);
+                if n != NONE && orient2d(self.point(a), self.point(b), p) < 0.0 {
+                    t = n;
+                    continue 'walk;
+                }
+            }
+            return t;
+        }
+        (0..self.triangles.len())
+            .find(|&t| self.alive[t] && self.contains(t, p))
+            .unwrap_or(t)
+    }
+
+    fn in_conflict(&self, t: usize, p: &Point2D) -> bool {
+        let [a, b, c] = self.triangles[t];
+        incircle(self.point(a), self.point(b), self.point(c), p) > 0.0
+    }
+
+    fn allocate(&mut self, triangle: [usize; 3], neighbours: [usize; 3]) -> usize {
+        if let Some(t) = self.free.pop() {
+            self.triangles[t] = triangle;
+            self.neighbours[t] = neighbours;
+            self.alive[t] = true;
+            t
+        } else {
+            self.triangles.push(triangle);
+            self.neighbours.push(neighbours);
+            self.alive.push(true);
+            self.in_cavity.push(false);
+            self.visited.push(0);
+            self.triangles.len() - 1
+        }
+    }
+
+    /// Inserts `point` and restores the Delaunay property. Points coinciding
+    /// with an existing vertex are ignored.
+    pub fn insert(&mut self, point: Point2D) {
+        let start = self.locate(&point);
+        if self.triangles[start]
+            .iter()
+            .any(|&v| self.point(v).x == point.x && self.point(v).y == point.y)
+        {
+            return;
+        }
+
+        // Grow the cavity of triangles whose circumcircle contains the point.
+        self.epoch += 1;
+        self.visited[start] = self.epoch;
+        let mut stack = vec![start];
+        let mut cavity = Vec::new();
+        while let Some(t) = stack.pop() {
+            cavity.push(t);
+            self.in_cavity[t] = true;
+            for n in self.neighbours[t] {
+                if n != NONE && self.visited[n] != self.epoch {
+                    self.visited[n] = self.epoch;
+                    if self.in_conflict(n, &point) {
+                        stack.push(n);
+                    }
+                }
+            }
+        }
+
+        // Collect the cavity boundary as (a, b, outside triangle, index of
+        // the shared edge in the outside triangle), counter-clockwise.
+        let mut boundary = Vec::new();
+        for &t in &cavity {
+            let tri = self.triangles[t];
+            for i in 0..3 {
+                let n = self.neighbours[t][i];
+                if n == NONE || !self.in_cavity[n] {
+                    let k = if n == NONE {
+                        NONE
+                    } else {
+                        (0..3)
+                            .find(|&k| self.neighbours[n][k] == t)
+                            .expect("adjacency is symmetric")
+                    };
+                    boundary.push((tri[(i + 1) % 3], tri[(i + 2) % 3], n, k));
+                }
+            }
+        }
+        for &t in &cavity {
+            self.in_cavity[t] = false;
+            self.alive[t] = false;
+            self.free.push(t);
+        }
+
+        // Connect every boundary edge to the new point.
+        let p = self.vertices.len();
+        self.vertices.push(point);
+        let mut created: Vec<(usize, usize)> = Vec::with_capacity(boundary.len());
+        for &(a, b, n, k) in &boundary {
+            let t = self.allocate([a, b, p], [NONE, NONE, n]);
+            if n != NONE {
+                self.neighbours[n][k] = t;
+            }
+            created.push((a, t));
+        }
+        for &(_, t) in &created {
+            let b = self.triangles[t][1];
+            let next = created
+                .iter()
+                .find(|&&(a, _)| a == b)
+                .map(|&(_, t)| t)
+                .expect("cavity boundary is closed");
+            self.neighbours[t][0] = next;
+            self.neighbours[next][1] = t;
+        }
+        self.last = created[0].1;
+    }
+
+    /// Returns the triangles that do not touch the super-triangle.
+    pub fn triangles(&self) -> Vec<Triangle> {
+        let all: Vec<Triangle> = (0..self.triangles.len())
+            .filter(|&t| self.alive[t])
+            .map(|t| {
+                let [a, b, c] = self.triangles[t];
+                Triangle {
+                    a: self.vertices[a],
+                    b: self.vertices[b],
+                    c: self.vertices[c],
+                }
+            })
+            .collect();
+        remove_triangles_with_vertices_from_super_triangle(&all, &self.super_triangle)
+    }
+
+    /// Returns the same triangles as [`Triangulation2D::triangles`] as an
+    /// indexed mesh, with the vertices sorted by [`Point2D::index`] as in
+    /// [`TriMesh2D::from_triangles`].
+    pub fn to_mesh(&self) -> TriMesh2D {
+        // The super-triangle occupies the first three vertex positions.
+        let triangles: Vec<[usize; 3]> = (0..self.triangles.len())
+            .filter(|&t| self.alive[t] && self.triangles[t].iter().all(|&v| v >= 3))
+            .map(|t| self.triangles[t])
+            .collect();
+        let (vertices, triangles) = compact_vertices(&self.vertices, &triangles, |v| v.index);
+        TriMesh2D {
+            vertices,
+            triangles,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    fn random_points(n: usize, seed: u64) -> Vec<Point2D> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        (0..n)
+            .map(|i| Point2D {
+                index: i as i64,
+                x: rng.gen_range(0.0..1.0),
+                y: rng.gen_range(0.0..1.0),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_adjacency_is_consistent() {
+        let triangulation = Triangulation2D::from_points(&random_points(500, 1));
+        for t in 0..triangulation.triangles.len() {
+            if !triangulation.alive[t] {
+                continue;
+            }
+            let tri = triangulation.triangles[t];
+            let [a, b, c] = tri.map(|v| triangulation.vertices[v]);
+            assert!(orient2d(&a, &b, &c) > 0.0);
+            for i in 0..3 {
+                let n = triangulation.neighbours[t][i];
+                if n == NONE {
+                    continue;
+                }
+                assert!(triangulation.alive[n]);
+                let (u, v) = (tri[(i + 1) % 3], tri[(i + 2) % 3]);
+                let other = triangulation.triangles[n];
+                assert!((0..3).any(|k| other[k] == v && other[(k + 1) % 3] == u));
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_mesh_matches_triangles() {
+        let triangulation = Triangulation2D::from_points(&random_points(300, 4));
+        let mesh = triangulation.to_mesh();
+        assert_eq!(mesh, TriMesh2D::from_triangles(&triangulation.triangles()));
+    }
+
+    #[test]
+    fn test_empty_circumcircles() {
+        let points = random_points(1000, 2);
+        let triangles = Triangulation2D::from_points(&points).triangles();
+        for t in &triangles {
+            for p in &points {
+                assert!(incircle(&t.a, &t.b, &t.c, p) <= 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_duplicate_points_are_ignored() {
+        let mut points = random_points(100, 3);
+        points.extend(points.clone());
+        let unique = Triangulation2D::from_points(&points[..100]).triangles();
+        let doubled = Triangulation2D::from_points(&points).triangles();
+        assert_eq!(unique.len(), doubled.len());
+    }
+
+    #[test]
+    fn test_square_lattice() {
+        // Every cell of the lattice has four cocircular corners.
+        let n = 20;
+        let points: Vec<Point2D> = (0..n * n)
+            .map(|i| Point2D {
+                index: i as i64,
+                x: (i % n) as f64 * 0.1,
+                y: (i / n) as f64 * 0.1,
+            })
+            .collect();
+        let triangles = Triangulation2D::from_points(&points).triangles();
+        assert_eq!(triangles.len(), 2 * (n - 1) * (n - 1));
+        for t in &triangles {
+            assert!(orient2d(&t.a, &t.b, &t.c) > 0.0);
+            for p in &points {
+                assert!(incircle(&t.a, &t.b, &t.c, p) <= 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_near_collinear_points() {
+        // Points on a line perturbed by a few ulps, plus two points off it.
+        let mut points: Vec<Point2D> = (0..50)
+            .map(|i| {
+                let x = 0.5 + i as f64 * 0.01;
+                let wiggle = if i % 2 == 0 { f64::EPSILON } else { 0.0 };
+                Point2D {
+                    index: i,
+                    x,
+                    y: x * 3.0 + wiggle,
+                }
+            })
+            .collect();
+        points.push(Point2D {
+            index: 50,
+            x: 0.0,
+            y: 3.0,
+        });
+        points.push(Point2D {
+            index: 51,
+            x: 1.5,
+            y: 0.0,
+        });
+        let triangles = Triangulation2D::from_points(&points).triangles();
+        assert!(!triangles.is_empty());
+        for t in &triangles {
+            assert!(orient2d(&t.a, &t.b, &t.c) > 0.0);
+            for p in &points {
+                assert!(incircle(&t.a, &t.b, &t.c, p) <= 0.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+
+use crate::geometry_3d::create_super_tetrahedron;
+use crate::model::compact_vertices;
+use crate::predicates::{insphere, orient3d};
+use crate::spatial_sort::brio_order_3d;
+use crate::tetrahedron_utils::remove_tetrahedra_with_vertices_from_super_tetrahedron;
+use crate::{Point3D, TetMesh, Tetrahedron};
+
+/// Marker for a missing neighbour (a face of the super-tetrahedron).
+const NONE: usize = usize::MAX;
+
+/// Incremental 3D Delaunay tetrahedralization with tetrahedron adjacency.
+///
+/// Tetrahedra are stored positively oriented; `neighbours[t][i]` is the
+/// tetrahedron across the face opposite `tetrahedra[t][i]`. Point location
+/// and cavity search work as in
+/// [`Triangulation2D`](crate::triangulation_2d::Triangulation2D).
+pub struct Triangulation3D {
+    vertices: Vec<Point3D>,
+    tetrahedra: Vec<[usize; 4]>,
+    neighbours: Vec<[usize; 4]>,
+    alive: Vec<bool>,
+    in_cavity: Vec<bool>,
+    visited: Vec<usize>,
+    epoch: usize,
+    free: Vec<usize>,
+    last: usize,
+    super_tetrahedron: Tetrahedron,
+}
+
+impl Triangulation3D {
+    /// Creates a tetrahedralization holding only a super-tetrahedron
+    /// enclosing `points`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` is empty.
+    pub fn new(points: &[Point3D]) -> Self {
+        let super_tetrahedron = create_super_tetrahedron(&points.to_vec());
+        let [a, b, c, d] = super_tetrahedron.vertices();
+        debug_assert!(orient3d(&a, &b, &c, &d) > 0.0);
+        Triangulation3D {
+            vertices: vec![a, b, c, d],
+            tetrahedra: vec![[0, 1, 2, 3]],
+            neighbours: vec![[NONE; 4]],
+            alive: vec![true],
+            in_cavity: vec![false],
+            visited: vec![0],
+            epoch: 0,
+            free: Vec::new(),
+            last: 0,
+            super_tetrahedron,
+        }
+    }
+
+    /// Tetrahedralizes `points`, inserting them in BRIO/Hilbert order.
+    pub fn from_points(points: &[Point3D]) -> Self {
+        let mut triangulation = Triangulation3D::new(points);
+        for i in brio_order_3d(points) {
+            triangulation.insert(points[i]);
+        }
+        triangulation
+    }
+
+    fn point(&self, v: usize) -> &Point3D {
+        &self.vertices[v]
+    }
+
+    /// Orientation of tetrahedron `t` with its `i`-th vertex replaced by `p`.
+    /// Negative if `p` lies beyond the face opposite that vertex.
+    fn orient_replacing(&self, t: usize, i: usize, p: &Point3D) -> f64 {
+        let mut v = self.tetrahedra[t].map(|v| self.vertices[v]);
+        v[i] = *p;
+        orient3d(&v[0], &v[1], &v[2], &v[3])
+    }
+
+    fn contains(&self, t: usize, p: &Point3D) -> bool {
+        (0..4).all(|i| self.orient_replacing(t, i, p) >= 0.0)
+    }
+
+    /// Finds a tetrahedron containing `p` by a visibility walk from the last
+    /// created tetrahedron, with a linear scan as a safeguard.
+    fn locate(&self, p: &Point3D) -> usize {
+        let mut t = self.last;
+        let mut offset = 0;
+        'walk: for _ in 0..self.tetrahedra.len() {
+            offset = (offset + 1) % 4;
+            for k in 0..4 {
+                let i = (k + offset) % 4;
+                let n = self.neighbours[t][i];
+                if n != NONE && self.orient_replacing(t, i, p) < 0.0 {
+                    t = n;
+                    continue 'walk;
+                }
+            }
+            return t;
+        }
+        (0..self.tetrahedra.len())
+            .find(|&t| self.alive[t] && self.contains(t, p))
+            .unwrap_or(t)
+    }
+
+    fn in_conflict(&self, t: usize, p: &Point3D) -> bool {
+        let [a, b, c, d] = self.tetrahedra[t];
+        insphere(
+            self.point(a),
+            self.point(b),
+            self.point(c),
+            self.point(d),
+            p,
+        ) > 0.0
+    }
+
+    fn allocate(&mut self, tetrahedron: [usize; 4], neighbours: [usize; 4]) -> usize {
+        if let Some(t) = self.free.pop() {
+            self.tetrahedra[t] = tetrahedron;
+            self.neighbours[t] = neighbours;
+            self.alive[t] = true;
+            t
+        } else {
+            self.tetrahedra.push(tetrahedron);
+            self.neighbours.push(neighbours);
+            self.alive.push(true);
+            self.in_cavity.push(false);
+            self.visited.push(0);
+            self.tetrahedra.len() - 1
+        }
+    }
+
+    /// Inserts `point` and restores the Delaunay property. Points coinciding
+    /// with an existing vertex are ignored.
+    pub fn insert(&mut self, point: Point3D) {
+        let start = self.locate(&point);
+        if self.tetrahedra[start].iter().any(|&v| {
+            let q = self.point(v);
+            q.x == point.x && q.y == point.y && q.z == point.z
+        }) {
+            return;
+        }
+
+        // Grow the cavity of tetrahedra whose circumsphere contains the point.
+        self.epoch += 1;
+        self.visited[start] = self.epoch;
+        let mut stack = vec![start];
+        let mut cavity = Vec::new();
+        while let Some(t) = stack.pop() {
+            cavity.push(t);
+            self.in_cavity[t] = true;
+            for n in self.neighbours[t] {
+                if n != NONE && self.visited[n] != self.epoch {
+                    self.visited[n] = self.epoch;
+                    if self.in_conflict(n, &point) {
+                        stack.push(n);
+                    }
+                }
+            }
+        }
+
+        // Each boundary face yields a new tetrahedron: the cavity tetrahedron
+        // with the vertex opposite that face replaced by the new point, which
+        // keeps it positively oriented.
+        let mut boundary = Vec::new();
+        for &t in &cavity {
+            for i in 0..4 {
+                let n = self.neighbours[t][i];
+                if n == NONE || !self.in_cavity[n] {
+                    let k = if n == NONE {
+                        NONE
+                    } else {
+                        (0..4)
+                            .find(|&k| self.neighbours[n][k] == t)
+                            .expect("adjacency is symmetric")
+                    };
+                    boundary.push((self.tetrahedra[t], i, n, k));
+                }
+            }
+        }
+        for &t in &cavity {
+            self.in_cavity[t] = false;
+            self.alive[t] = false;
+            self.free.push(t);
+        }
+
+        let p = self.vertices.len();
+        self.vertices.push(point);
+        // Faces through the new point, keyed by their other two vertices.
+        let mut open_faces: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        for &(mut tet, i, n, k) in &boundary {
+            tet[i] = p;
+            let mut neighbours = [NONE; 4];
+            neighbours[i] = n;
+            let t = self.allocate(tet, neighbours);
+            if n != NONE {
+                self.neighbours[n][k] = t;
+            }
+            for j in (0..4).filter(|&j| j != i) {
+                let mut edge = (0..4).filter(|&m| m != i && m != j).map(|m| tet[m]);
+                let (u, v) = (edge.next().unwrap(), edge.next().unwrap());
+                let key = (u.min(v), u.max(v));
+                match open_faces.remove(&key) {
+                    Some((other, m)) => {
+                        self.neighbours[t][j] = other;
+                        self.neighbours[other][m] = t;
+                    }
+                    None => {
+                        open_faces.insert(key, (t, j));
+                    }
+                }
+            }
+            self.last = t;
+        }
+        debug_assert!(open_faces.is_empty(), "cavity boundary is closed");
+    }
+
+    /// Returns the tetrahedra that do not touch the super-tetrahedron.
+    pub fn tetrahedra(&self) -> Vec<Tetrahedron> {
+        let all: Vec<Tetrahedron> = (0..self.tetrahedra.len())
+            .filter(|&t| self.alive[t])
+            .map(|t| {
+                let [a, b, c, d] = self.tetrahedra[t];
+                Tetrahedron {
+                    a: self.vertices[a],
+                    b: self.vertices[b],
+                    c: self.vertices[c],
+                    d: self.vertices[d],
+                }
+            })
+            .collect();
+        remove_tetrahedra_with_vertices_from_super_tetrahedron(&all, &self.super_tetrahedron)
+    }
+
+    /// Returns the same tetrahedra as [`Triangulation3D::tetrahedra`] as an
+    /// indexed mesh, with the vertices sorted by [`Point3D::index`] as in
+    /// [`TetMesh::from_tetrahedra`].
+    pub fn to_mesh(&self) -> TetMesh {
+        // The super-tetrahedron occupies the first four vertex positions.
+        let tetrahedra: Vec<[usize; 4]> = (0..self.tetrahedra.len())
+            .filter(|&t| self.alive[t] && self.tetrahedra[t].iter().all(|&v| v >= 4))
+            .map(|t| self.tetrahedra[t])
+            .collect();
+        let (vertices, tetrahedra) = compact_vertices(&self.vertices, &tetrahedra, |v| v.index);
+        TetMesh {
+            vertices,
+            tetrahedra,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    fn random_points(n: usize, seed: u64) -> Vec<Point3D> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        (0..n)
+            .map(|i| Point3D {
+                index: i as i64,
+                x: rng.gen_range(0.0..1.0),
+                y: rng.gen_range(0.0..1.0),
+                z: rng.gen_range(0.0..1.0),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_adjacency_is_consistent() {
+        let triangulation = Triangulation3D::from_points(&random_points(300, 1));
+        for t in 0..triangulation.tetrahedra.len() {
+            if !triangulation.alive[t] {
+                continue;
+            }
+            let tet = triangulation.tetrahedra[t];
+            let [a, b, c, d] = tet.map(|v| triangulation.vertices[v]);
+            assert!(orient3d(&a, &b, &c, &d) > 0.0);
+            for i in 0..4 {
+                let n = triangulation.neighbours[t][i];
+                if n == NONE {
+                    continue;
+                }
+                assert!(triangulation.alive[n]);
+                let shared = triangulation.tetrahedra[n]
+                    .iter()
+                    .filter(|v| tet.contains(v))
+                    .count();
+                assert_eq!(shared, 3);
+                assert!(triangulation.neighbours[n].contains(&t));
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty_circumspheres() {
+        let points = random_points(300, 2);
+        let tetrahedra = Triangulation3D::from_points(&points).tetrahedra();
+        assert!(!tetrahedra.is_empty());
+        for t in &tetrahedra {
+            for p in &points {
+                assert!(insphere(&t.a, &t.b, &t.c, &t.d, p) <= 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_mesh_matches_tetrahedra() {
+        let triangulation = Triangulation3D::from_points(&random_points(200, 4));
+        let mesh = triangulation.to_mesh();
+        assert_eq!(mesh, TetMesh::from_tetrahedra(&triangulation.tetrahedra()));
+    }
+
+    #[test]
+    fn test_cube_lattice() {
+        // The lattice of `cube_points` in the benchmarks: every cell has
+        // eight cospherical corners.
+        let n = 5;
+        let step = 1.0 / n as f64;
+        let mut points = Vec::new();
+        for i in 0..=n {
+            for j in 0..=n {
+                for k in 0..=n {
+                    points.push(Point3D {
+                        index: points.len() as i64,
+                        x: i as f64 * step,
+                        y: j as f64 * step,
+                        z: k as f64 * step,
+                    });
+                }
+            }
+        }
+        let tetrahedra = Triangulation3D::from_points(&points).tetrahedra();
+        let volume: f64 = tetrahedra.iter().map(|t| t.signed_volume()).sum();
+        assert!((volume - 1.0).abs() < 1e-9, "volume {}", volume);
+        for t in &tetrahedra {
+            assert!(orient3d(&t.a, &t.b, &t.c, &t.d) > 0.0);
+            for p in &points {
+                assert!(insphere(&t.a, &t.b, &t.c, &t.d, p) <= 0.0);
+            }
+        }
+    }
+}
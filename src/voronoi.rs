@@ -0,0 +1,482 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::error::MeshingError;
+use crate::{Point2D, Point3D, Tetrahedron, Triangle};
+
+/// A Voronoi cell of a 2D Delaunay triangulation, clipped to a bounding box.
+#[derive(Debug, Clone)]
+pub struct VoronoiCell2D {
+    /// The input point owning the cell.
+    pub site: Point2D,
+    /// Counter-clockwise cell polygon. Vertices are derived points and use
+    /// `i64::MAX` as their index, as [`Triangle::circumcenter`] does.
+    pub vertices: Vec<Point2D>,
+    /// Area of the clipped cell.
+    pub area: f64,
+}
+
+/// A Voronoi cell of a 3D Delaunay tetrahedralization, clipped to a bounding
+/// box.
+#[derive(Debug, Clone)]
+pub struct VoronoiCell3D {
+    /// The input point owning the cell.
+    pub site: Point3D,
+    /// Planar faces of the convex cell, each counter-clockwise when seen from
+    /// outside. Vertices use `i64::MAX` as their index.
+    pub faces: Vec<Vec<Point3D>>,
+    /// Volume of the clipped cell.
+    pub volume: f64,
+}
+
+/// Collects every site and its Delaunay neighbours, keyed by point index.
+fn neighbourhoods<P: Copy, const N: usize>(
+    elements: impl Iterator<Item = [P; N]>,
+    index: impl Fn(&P) -> i64,
+) -> BTreeMap<i64, (P, BTreeSet<i64>)> {
+    let mut sites: BTreeMap<i64, (P, BTreeSet<i64>)> = BTreeMap::new();
+    for vertices in elements {
+        for a in &vertices {
+            let entry = sites.entry(index(a)).or_insert((*a, BTreeSet::new()));
+            for b in &vertices {
+                if index(a) != index(b) {
+                    entry.1.insert(index(b));
+                }
+            }
+        }
+    }
+    sites
+}
+
+fn check_box(min: &[f64], max: &[f64]) -> Result<(), MeshingError> {
+    if min
+        .iter()
+        .zip(max)
+        .any(|(lo, hi)| lo.is_nan() || hi.is_nan() || lo >= hi)
+    {
+        return Err(MeshingError::InvalidParameter(format!(
+            "bounding box min {:?} must be below max {:?} on every axis",
+            min, max
+        )));
+    }
+    Ok(())
+}
+
+fn derived_2d(x: f64, y: f64) -> Point2D {
+    Point2D {
+        index: i64::MAX,
+        x,
+        y,
+    }
+}
+
+fn derived_3d(p: [f64; 3]) -> Point3D {
+    Point3D {
+        index: i64::MAX,
+        x: p[0],
+        y: p[1],
+        z: p[2],
+    }
+}
+
+/// Clips a convex polygon to the half-plane of points closer to `site` than
+/// to `other` (Sutherland-Hodgman against the perpendicular bisector).
+fn clip_polygon(polygon: &[Point2D], site: &Point2D, other: &Point2D) -> Vec<Point2D> {
+    let (nx, ny) = (other.x - site.x, other.y - site.y);
+    let (mx, my) = ((site.x + other.x) / 2.0, (site.y + other.y) / 2.0);
+    let side = |p: &Point2D| (p.x - mx) * nx + (p.y - my) * ny;
+
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for (i, p) in polygon.iter().enumerate() {
+        let q = &polygon[(i + 1) % polygon.len()];
+        let (sp, sq) = (side(p), side(q));
+        if sp <= 0.0 {
+            clipped.push(*p);
+        }
+        if (sp < 0.0 && sq > 0.0) || (sp > 0.0 && sq < 0.0) {
+            let t = sp / (sp - sq);
+            clipped.push(derived_2d(p.x + t * (q.x - p.x), p.y + t * (q.y - p.y)));
+        }
+    }
+    clipped
+}
+
+fn polygon_area(polygon: &[Point2D]) -> f64 {
+    (0..polygon.len())
+        .map(|i| {
+            let (p, q) = (&polygon[i], &polygon[(i + 1) % polygon.len()]);
+            p.x * q.y - q.x * p.y
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Computes the Voronoi cells of the sites of a 2D Delaunay triangulation,
+/// such as the output of [`bowyer_watson`](crate::bowyer_watson).
+///
+/// The cell of a site is the bounding box clipped by the perpendicular
+/// bisectors to each of its Delaunay neighbours. Interior cell vertices are
+/// therefore the [`Triangle::circumcenter`]s of the incident triangles, and
+/// unbounded hull cells are cut off at the box.
+///
+/// # Arguments
+///
+/// * `triangles` - A Delaunay triangulation.
+/// * `min` / `max` - Corners of the clipping box.
+///
+/// # Returns
+///
+/// One cell per distinct point index, sorted by index. Cells of sites lying
+/// outside the box may be empty.
+///
+/// # Errors
+///
+/// Returns [`MeshingError::EmptyInput`] if `triangles` is empty and
+/// [`MeshingError::InvalidParameter`] if the box is empty or inverted.
+///
+/// # Examples
+///
+/// ```
+/// use meshing::voronoi::voronoi_2d;
+/// use meshing::{bowyer_watson, Point2D};
+///
+/// let p = |index, x, y| Point2D { index, x, y };
+/// let points = vec![p(0, 0.0, 0.0), p(1, 1.0, 0.0), p(2, 0.0, 1.0), p(3, 1.0, 1.0)];
+/// let triangles = bowyer_watson(points).unwrap();
+/// let cells = voronoi_2d(&triangles, p(0, 0.0, 0.0), p(0, 1.0, 1.0)).unwrap();
+/// assert_eq!(cells.len(), 4);
+/// assert!(cells.iter().all(|c| (c.area - 0.25).abs() < 1e-12));
+/// ```
+pub fn voronoi_2d(
+    triangles: &[Triangle],
+    min: Point2D,
+    max: Point2D,
+) -> Result<Vec<VoronoiCell2D>, MeshingError> {
+    if triangles.is_empty() {
+        return Err(MeshingError::EmptyInput);
+    }
+    check_box(&[min.x, min.y], &[max.x, max.y])?;
+
+    let sites = neighbourhoods(triangles.iter().map(Triangle::vertices), |p| p.index);
+    let bounding_box = vec![
+        derived_2d(min.x, min.y),
+        derived_2d(max.x, min.y),
+        derived_2d(max.x, max.y),
+        derived_2d(min.x, max.y),
+    ];
+
+    Ok(sites
+        .values()
+        .map(|(site, neighbours)| {
+            let mut polygon = bounding_box.clone();
+            for n in neighbours {
+                if polygon.is_empty() {
+                    break;
+                }
+                polygon = clip_polygon(&polygon, site, &sites[n].0);
+            }
+            VoronoiCell2D {
+                site: *site,
+                area: polygon_area(&polygon),
+                vertices: polygon,
+            }
+        })
+        .collect())
+}
+
+type Vec3 = [f64; 3];
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: Vec3, b: Vec3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn coords(p: &Point3D) -> Vec3 {
+    [p.x, p.y, p.z]
+}
+
+/// The six faces of an axis-aligned box, counter-clockwise from outside.
+fn box_faces(lo: Vec3, hi: Vec3) -> Vec<Vec<Vec3>> {
+    let corner = |i: usize| {
+        [
+            if i & 1 == 0 { lo[0] } else { hi[0] },
+            if i & 2 == 0 { lo[1] } else { hi[1] },
+            if i & 4 == 0 { lo[2] } else { hi[2] },
+        ]
+    };
+    [
+        [0, 2, 3, 1],
+        [4, 5, 7, 6],
+        [0, 1, 5, 4],
+        [2, 6, 7, 3],
+        [0, 4, 6, 2],
+        [1, 3, 7, 5],
+    ]
+    .iter()
+    .map(|face| face.iter().map(|&i| corner(i)).collect())
+    .collect()
+}
+
+/// Clips a convex polyhedron, given as outward counter-clockwise faces, to
+/// the half-space `dot(normal, x) <= offset`.
+fn clip_polyhedron(faces: &[Vec<Vec3>], normal: Vec3, offset: f64) -> Vec<Vec<Vec3>> {
+    let side = |p: Vec3| dot(normal, p) - offset;
+    let mut clipped = Vec::with_capacity(faces.len() + 1);
+    let mut cap: Vec<Vec3> = Vec::new();
+
+    for face in faces {
+        let mut polygon = Vec::with_capacity(face.len() + 1);
+        for (i, &p) in face.iter().enumerate() {
+            let q = face[(i + 1) % face.len()];
+            let (sp, sq) = (side(p), side(q));
+            if sp <= 0.0 {
+                polygon.push(p);
+                if sp == 0.0 {
+                    cap.push(p);
+                }
+            }
+            if (sp < 0.0 && sq > 0.0) || (sp > 0.0 && sq < 0.0) {
+                // Interpolate from the lexicographically smaller endpoint so
+                // that both faces sharing the edge produce the same point.
+                let ((a, sa), (b, sb)) = if p < q {
+                    ((p, sp), (q, sq))
+                } else {
+                    ((q, sq), (p, sp))
+                };
+                let t = sa / (sa - sb);
+                let x = [
+                    a[0] + t * (b[0] - a[0]),
+                    a[1] + t * (b[1] - a[1]),
+                    a[2] + t * (b[2] - a[2]),
+                ];
+                polygon.push(x);
+                cap.push(x);
+            }
+        }
+        if polygon.len() >= 3 {
+            clipped.push(polygon);
+        }
+    }
+
+    // Close the polyhedron with the cut face, ordered counter-clockwise
+    // around `normal`, which points out of the clipped polyhedron.
+    cap.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    cap.dedup();
+    if cap.len() >= 3 {
+        let n = cap.len() as f64;
+        let centre = cap.iter().fold([0.0; 3], |acc, p| {
+            [acc[0] + p[0] / n, acc[1] + p[1] / n, acc[2] + p[2] / n]
+        });
+        let u = sub(cap[0], centre);
+        let v = cross(normal, u);
+        cap.sort_by(|a, b| {
+            let angle = |p: &Vec3| dot(sub(*p, centre), v).atan2(dot(sub(*p, centre), u));
+            angle(a)
+                .partial_cmp(&angle(b))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        clipped.push(cap);
+    }
+    clipped
+}
+
+fn polyhedron_volume(faces: &[Vec<Vec3>]) -> f64 {
+    let origin = match faces.first().and_then(|f| f.first()) {
+        Some(&p) => p,
+        None => return 0.0,
+    };
+    faces
+        .iter()
+        .flat_map(|face| {
+            (1..face.len() - 1).map(move |i| {
+                dot(
+                    sub(face[0], origin),
+                    cross(sub(face[i], origin), sub(face[i + 1], origin)),
+                )
+            })
+        })
+        .sum::<f64>()
+        / 6.0
+}
+
+/// Computes the Voronoi cells of the sites of a 3D Delaunay
+/// tetrahedralization, such as the output of
+/// [`bowyer_watson_3d`](crate::bowyer_watson_3d).
+///
+/// The cell of a site is the bounding box clipped by the bisector planes to
+/// each of its Delaunay neighbours, so interior cell vertices are the
+/// centers of the incident tetrahedra's [`Tetrahedron::circumsphere`]s.
+///
+/// # Returns
+///
+/// One cell per distinct point index, sorted by index.
+///
+/// # Errors
+///
+/// Returns [`MeshingError::EmptyInput`] if `tetrahedra` is empty and
+/// [`MeshingError::InvalidParameter`] if the box is empty or inverted.
+pub fn voronoi_3d(
+    tetrahedra: &[Tetrahedron],
+    min: Point3D,
+    max: Point3D,
+) -> Result<Vec<VoronoiCell3D>, MeshingError> {
+    if tetrahedra.is_empty() {
+        return Err(MeshingError::EmptyInput);
+    }
+    let (lo, hi) = (coords(&min), coords(&max));
+    check_box(&lo, &hi)?;
+
+    let sites = neighbourhoods(tetrahedra.iter().map(Tetrahedron::vertices), |p| p.index);
+    let bounding_box = box_faces(lo, hi);
+
+    Ok(sites
+        .values()
+        .map(|(site, neighbours)| {
+            let s = coords(site);
+            let mut faces = bounding_box.clone();
+            for n in neighbours {
+                if faces.is_empty() {
+                    break;
+                }
+                let o = coords(&sites[n].0);
+                let normal = sub(o, s);
+                let offset = (dot(o, o) - dot(s, s)) / 2.0;
+                faces = clip_polyhedron(&faces, normal, offset);
+            }
+            VoronoiCell3D {
+                site: *site,
+                volume: polyhedron_volume(&faces),
+                faces: faces
+                    .into_iter()
+                    .map(|face| face.into_iter().map(derived_3d).collect())
+                    .collect(),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bowyer_watson, bowyer_watson_3d};
+    use rand::{Rng, SeedableRng};
+
+    fn p2(index: i64, x: f64, y: f64) -> Point2D {
+        Point2D { index, x, y }
+    }
+
+    fn p3(index: i64, x: f64, y: f64, z: f64) -> Point3D {
+        Point3D { index, x, y, z }
+    }
+
+    #[test]
+    fn test_voronoi_2d_areas_tile_the_box() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let points: Vec<Point2D> = (0..200)
+            .map(|i| p2(i, rng.gen_range(0.0..2.0), rng.gen_range(0.0..1.0)))
+            .collect();
+        let triangles = bowyer_watson(points.clone()).unwrap();
+        let cells = voronoi_2d(&triangles, p2(0, 0.0, 0.0), p2(0, 2.0, 1.0)).unwrap();
+        assert_eq!(cells.len(), points.len());
+        let total: f64 = cells.iter().map(|c| c.area).sum();
+        assert!((total - 2.0).abs() < 1e-9, "total {}", total);
+        for (cell, point) in cells.iter().zip(&points) {
+            assert_eq!(cell.site.index, point.index);
+            assert!(cell.area > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_voronoi_2d_interior_vertices_are_circumcenters() {
+        let mut points = vec![p2(0, 0.5, 0.5)];
+        for k in 0..6 {
+            let angle = k as f64 * std::f64::consts::PI / 3.0;
+            points.push(p2(k + 1, 0.5 + 0.3 * angle.cos(), 0.5 + 0.3 * angle.sin()));
+        }
+        let triangles = bowyer_watson(points).unwrap();
+        let cells = voronoi_2d(&triangles, p2(0, -1.0, -1.0), p2(0, 2.0, 2.0)).unwrap();
+        let centre_cell = &cells[0];
+        assert_eq!(centre_cell.vertices.len(), 6);
+        let incident: Vec<Point2D> = triangles
+            .iter()
+            .filter(|t| t.vertices().iter().any(|v| v.index == 0))
+            .map(Triangle::circumcenter)
+            .collect();
+        for v in &centre_cell.vertices {
+            assert!(incident.iter().any(|c| c.distance(v) < 1e-9));
+        }
+        // A regular hexagon with apothem 0.15.
+        let expected = 6.0 * 0.15 * 0.15 * (std::f64::consts::PI / 6.0).tan();
+        assert!((centre_cell.area - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_voronoi_3d_volumes_tile_the_box() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        let points: Vec<Point3D> = (0..60)
+            .map(|i| {
+                p3(
+                    i,
+                    rng.gen_range(0.0..1.0),
+                    rng.gen_range(0.0..1.0),
+                    rng.gen_range(0.0..1.0),
+                )
+            })
+            .collect();
+        let tetrahedra = bowyer_watson_3d(points.clone());
+        let cells = voronoi_3d(&tetrahedra, p3(0, 0.0, 0.0, 0.0), p3(0, 1.0, 1.0, 1.0)).unwrap();
+        assert_eq!(cells.len(), points.len());
+        let total: f64 = cells.iter().map(|c| c.volume).sum();
+        assert!((total - 1.0).abs() < 1e-9, "total {}", total);
+        for cell in &cells {
+            assert!(cell.volume > 0.0);
+            assert!(cell.faces.len() >= 4);
+        }
+    }
+
+    #[test]
+    fn test_voronoi_3d_lattice_cells_are_cubes() {
+        let mut points = Vec::new();
+        for i in 0..3 {
+            for j in 0..3 {
+                for k in 0..3 {
+                    points.push(p3(points.len() as i64, i as f64, j as f64, k as f64));
+                }
+            }
+        }
+        let tetrahedra = bowyer_watson_3d(points);
+        let cells = voronoi_3d(&tetrahedra, p3(0, -0.5, -0.5, -0.5), p3(0, 2.5, 2.5, 2.5)).unwrap();
+        for cell in &cells {
+            assert!((cell.volume - 1.0).abs() < 1e-9, "volume {}", cell.volume);
+        }
+        // The centre cell is bounded by its six face neighbours only.
+        assert_eq!(cells[13].faces.len(), 6);
+    }
+
+    #[test]
+    fn test_voronoi_invalid_input() {
+        assert!(matches!(
+            voronoi_2d(&[], p2(0, 0.0, 0.0), p2(0, 1.0, 1.0)),
+            Err(MeshingError::EmptyInput)
+        ));
+        let triangle = Triangle {
+            a: p2(0, 0.0, 0.0),
+            b: p2(1, 1.0, 0.0),
+            c: p2(2, 0.0, 1.0),
+        };
+        assert!(matches!(
+            voronoi_2d(&[triangle], p2(0, 1.0, 0.0), p2(0, 0.0, 1.0)),
+            Err(MeshingError::InvalidParameter(_))
+        ));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Point3D, Tetrahedron};
+use crate::{Point3D, TetMesh, Tetrahedron};
 
 /// Generates a tetrahedral volume mesh from a uniform voxel grid.
 ///
@@ -148,6 +148,21 @@ pub fn voxel_mesh(
     tetrahedra
 }
 
+/// Generates a voxel mesh and returns it as an indexed [`TetMesh`].
+///
+/// Grid vertices are shared between neighbouring cells, so each vertex of the
+/// voxel grid appears at most once in the vertex buffer.
+pub fn voxel_mesh_indexed(
+    min: Point3D,
+    max: Point3D,
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    is_inside: &dyn Fn(&Point3D) -> bool,
+) -> TetMesh {
+    TetMesh::from_tetrahedra(&voxel_mesh(min, max, nx, ny, nz, is_inside))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -296,4 +311,24 @@ mod tests {
         // 2x2x2 grid has 3x3x3 = 27 unique vertices
         assert_eq!(indices.len(), 27);
     }
+
+    #[test]
+    fn test_voxel_mesh_indexed_shares_grid_vertices() {
+        let min = Point3D {
+            index: 0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let max = Point3D {
+            index: 0,
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        };
+        let mesh = voxel_mesh_indexed(min, max, 2, 2, 2, &|_| true);
+        assert_eq!(mesh.num_tetrahedra(), 40);
+        // 3 x 3 x 3 grid vertices
+        assert_eq!(mesh.num_vertices(), 27);
+    }
 }